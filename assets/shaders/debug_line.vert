@@ -0,0 +1,34 @@
+#version 450
+
+#extension GL_EXT_nonuniform_qualifier: require
+
+#include "math/color.glsl"
+#include "uniforms/globals.glsl"
+#include "uniforms/bindless.glsl"
+
+layout (push_constant) uniform PushConstant {
+    uint vertex_buffer_index;
+    uint decode_srgb;
+} push_constant;
+
+struct DebugVertex {
+    vec3 position;
+    uint color;
+};
+
+BINDLESS_SBO_RO(std430, DebugVertex, u_debug_vertices);
+
+layout (location = 0) out vec4 out_color;
+
+void main() {
+    DebugVertex vertex = u_debug_vertices[push_constant.vertex_buffer_index].items[gl_VertexIndex];
+
+    // NOTE: Colors are sRGB encoded, the target encodes them again on write
+    vec4 color = unpackUnorm4x8(vertex.color);
+    if (push_constant.decode_srgb != 0) {
+        color.rgb = srgb_to_linear(color.rgb);
+    }
+
+    out_color = color;
+    gl_Position = CAMERA_PROJECTION * CAMERA_VIEW * vec4(vertex.position, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 450
+
+layout (set = 0, binding = 0) uniform sampler2DMS u_depth;
+
+void main() {
+    ivec2 position = ivec2(gl_FragCoord.xy);
+
+    // NOTE: The farthest sample keeps occlusion culling conservative
+    float depth = 0.0;
+    int sample_count = textureSamples(u_depth);
+    for (int i = 0; i < sample_count; ++i) {
+        depth = max(depth, texelFetch(u_depth, position, i).r);
+    }
+    gl_FragDepth = depth;
+}
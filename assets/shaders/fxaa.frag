@@ -0,0 +1,159 @@
+#version 450
+
+#include "math/color.glsl"
+
+// Tonemapped and sRGB encoded scene color.
+layout (set = 0, binding = 0) uniform sampler2D u_color;
+
+layout (push_constant) uniform PushConstant {
+    // Whether the target format applies the sRGB transfer function itself.
+    uint decode_srgb;
+} push_constant;
+
+layout (location = 0) out vec4 out_frag_color;
+
+// Edges with a smaller local contrast are skipped
+#define EDGE_THRESHOLD_MIN 0.0312
+#define EDGE_THRESHOLD_MAX 0.125
+// Amount of sub-pixel aliasing removal
+#define SUBPIXEL_QUALITY 0.75
+#define ITERATIONS 12
+
+const float STEPS[ITERATIONS] = float[](1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0);
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+float sample_luma(vec2 uv) {
+    return luma(textureLod(u_color, uv, 0.0).rgb);
+}
+
+float sample_luma(vec2 uv, ivec2 offset) {
+    return luma(textureLodOffset(u_color, uv, 0.0, offset).rgb);
+}
+
+vec3 fxaa(vec2 uv, vec2 texel_size) {
+    vec3 color_center = textureLod(u_color, uv, 0.0).rgb;
+
+    float luma_center = luma(color_center);
+    float luma_up = sample_luma(uv, ivec2(0, -1));
+    float luma_down = sample_luma(uv, ivec2(0, 1));
+    float luma_left = sample_luma(uv, ivec2(-1, 0));
+    float luma_right = sample_luma(uv, ivec2(1, 0));
+
+    float luma_min = min(luma_center, min(min(luma_up, luma_down), min(luma_left, luma_right)));
+    float luma_max = max(luma_center, max(max(luma_up, luma_down), max(luma_left, luma_right)));
+    float luma_range = luma_max - luma_min;
+    if (luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX)) {
+        return color_center;
+    }
+
+    float luma_up_left = sample_luma(uv, ivec2(-1, -1));
+    float luma_up_right = sample_luma(uv, ivec2(1, -1));
+    float luma_down_left = sample_luma(uv, ivec2(-1, 1));
+    float luma_down_right = sample_luma(uv, ivec2(1, 1));
+
+    float luma_up_down = luma_up + luma_down;
+    float luma_left_right = luma_left + luma_right;
+    float luma_left_corners = luma_up_left + luma_down_left;
+    float luma_right_corners = luma_up_right + luma_down_right;
+    float luma_up_corners = luma_up_left + luma_up_right;
+    float luma_down_corners = luma_down_left + luma_down_right;
+
+    // Estimate the edge direction from the local gradients
+    float edge_horizontal = abs(-2.0 * luma_left + luma_left_corners)
+        + abs(-2.0 * luma_center + luma_up_down) * 2.0
+        + abs(-2.0 * luma_right + luma_right_corners);
+    float edge_vertical = abs(-2.0 * luma_up + luma_up_corners)
+        + abs(-2.0 * luma_center + luma_left_right) * 2.0
+        + abs(-2.0 * luma_down + luma_down_corners);
+    bool is_horizontal = edge_horizontal >= edge_vertical;
+
+    // Choose the side of the edge with the steepest gradient
+    float luma_1 = is_horizontal ? luma_up : luma_left;
+    float luma_2 = is_horizontal ? luma_down : luma_right;
+    float gradient_1 = luma_1 - luma_center;
+    float gradient_2 = luma_2 - luma_center;
+    bool is_1_steepest = abs(gradient_1) >= abs(gradient_2);
+    float gradient_scaled = 0.25 * max(abs(gradient_1), abs(gradient_2));
+
+    float step_length = is_horizontal ? texel_size.y : texel_size.x;
+    float luma_local_average;
+    if (is_1_steepest) {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma_1 + luma_center);
+    } else {
+        luma_local_average = 0.5 * (luma_2 + luma_center);
+    }
+
+    // Walk along the edge in both directions until its end is found
+    vec2 edge_uv = uv;
+    if (is_horizontal) {
+        edge_uv.y += step_length * 0.5;
+    } else {
+        edge_uv.x += step_length * 0.5;
+    }
+    vec2 offset = is_horizontal ? vec2(texel_size.x, 0.0) : vec2(0.0, texel_size.y);
+
+    vec2 uv_1 = edge_uv - offset * STEPS[0];
+    vec2 uv_2 = edge_uv + offset * STEPS[0];
+    float luma_end_1 = 0.0;
+    float luma_end_2 = 0.0;
+    bool reached_1 = false;
+    bool reached_2 = false;
+    for (int i = 0; i < ITERATIONS && !(reached_1 && reached_2); ++i) {
+        if (!reached_1) {
+            luma_end_1 = sample_luma(uv_1) - luma_local_average;
+            reached_1 = abs(luma_end_1) >= gradient_scaled;
+        }
+        if (!reached_2) {
+            luma_end_2 = sample_luma(uv_2) - luma_local_average;
+            reached_2 = abs(luma_end_2) >= gradient_scaled;
+        }
+        if (i + 1 < ITERATIONS) {
+            if (!reached_1) {
+                uv_1 -= offset * STEPS[i + 1];
+            }
+            if (!reached_2) {
+                uv_2 += offset * STEPS[i + 1];
+            }
+        }
+    }
+
+    float distance_1 = is_horizontal ? (uv.x - uv_1.x) : (uv.y - uv_1.y);
+    float distance_2 = is_horizontal ? (uv_2.x - uv.x) : (uv_2.y - uv.y);
+    bool is_direction_1 = distance_1 < distance_2;
+    float distance_final = min(distance_1, distance_2);
+    float edge_length = distance_1 + distance_2;
+
+    // NOTE: Only the pixels on the side of the edge whose variation matches the center are shifted
+    bool is_luma_center_smaller = luma_center < luma_local_average;
+    bool correct_variation = ((is_direction_1 ? luma_end_1 : luma_end_2) < 0.0) != is_luma_center_smaller;
+    float pixel_offset = correct_variation ? 0.5 - distance_final / edge_length : 0.0;
+
+    // Sub-pixel aliasing of thin lines and single pixels
+    float luma_average = (1.0 / 12.0) * (2.0 * (luma_up_down + luma_left_right) + luma_left_corners + luma_right_corners);
+    float subpixel_offset = saturate(abs(luma_average - luma_center) / luma_range);
+    subpixel_offset = (-2.0 * subpixel_offset + 3.0) * subpixel_offset * subpixel_offset;
+    pixel_offset = max(pixel_offset, subpixel_offset * subpixel_offset * SUBPIXEL_QUALITY);
+
+    vec2 final_uv = uv;
+    if (is_horizontal) {
+        final_uv.y += pixel_offset * step_length;
+    } else {
+        final_uv.x += pixel_offset * step_length;
+    }
+    return textureLod(u_color, final_uv, 0.0).rgb;
+}
+
+void main() {
+    vec2 texel_size = 1.0 / vec2(textureSize(u_color, 0));
+    vec3 color = fxaa(gl_FragCoord.xy * texel_size, texel_size);
+
+    if (push_constant.decode_srgb != 0) {
+        color = srgb_to_linear(color);
+    }
+
+    out_frag_color = vec4(color, 1.0);
+}
@@ -0,0 +1,28 @@
+#ifndef MATH_AABB_GLSL
+#define MATH_AABB_GLSL
+
+// Axis-aligned bounding box, `w` components are unused.
+struct Aabb {
+    vec4 min;
+    vec4 max;
+};
+
+vec3 aabb_center(Aabb aabb) {
+    return (aabb.min.xyz + aabb.max.xyz) * 0.5;
+}
+
+vec3 aabb_half_extents(Aabb aabb) {
+    return (aabb.max.xyz - aabb.min.xyz) * 0.5;
+}
+
+Aabb aabb_transform_by_mat4(Aabb aabb, mat4 transform) {
+    vec3 center = (transform * vec4(aabb_center(aabb), 1.0)).xyz;
+    vec3 half_extents = aabb_half_extents(aabb);
+    half_extents = abs(transform[0].xyz) * half_extents.x
+        + abs(transform[1].xyz) * half_extents.y
+        + abs(transform[2].xyz) * half_extents.z;
+
+    return Aabb(vec4(center - half_extents, 0.0), vec4(center + half_extents, 0.0));
+}
+
+#endif  // MATH_AABB_GLSL
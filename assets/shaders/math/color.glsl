@@ -5,4 +5,26 @@ float saturate(float v) {
     return clamp(v, 0.0, 1.0);
 }
 
+// Relative luminance of a linear Rec.709 color.
+float luminance(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    vec3 lo = color * 12.92;
+    vec3 hi = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(hi, lo, lessThanEqual(color, vec3(0.0031308)));
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    vec3 lo = color / 12.92;
+    vec3 hi = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(hi, lo, lessThanEqual(color, vec3(0.04045)));
+}
+
+// Exposure multiplier for the scene luminance of the specified EV100.
+float ev100_to_exposure(float ev100) {
+    return 1.0 / (1.2 * exp2(ev100));
+}
+
 #endif  // MATH_COLOR_GLSL
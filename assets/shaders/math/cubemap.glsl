@@ -0,0 +1,35 @@
+#ifndef MATH_CUBEMAP_GLSL
+#define MATH_CUBEMAP_GLSL
+
+#include "const.glsl"
+
+// Direction towards the center of a cube face texel, faces are ordered as +X, -X, +Y, -Y, +Z, -Z.
+vec3 cube_direction(uint face, vec2 uv) {
+    vec2 st = uv * 2.0 - 1.0;
+    vec3 direction;
+    switch (face) {
+        case 0: direction = vec3(1.0, -st.y, -st.x); break;
+        case 1: direction = vec3(-1.0, -st.y, st.x); break;
+        case 2: direction = vec3(st.x, 1.0, st.y); break;
+        case 3: direction = vec3(st.x, -1.0, -st.y); break;
+        case 4: direction = vec3(st.x, -st.y, 1.0); break;
+        default: direction = vec3(-st.x, -st.y, -1.0); break;
+    }
+    return normalize(direction);
+}
+
+// Solid angle covered by a cube face texel at `uv`, relative to the area of the texel on the face.
+float cube_texel_solid_angle(vec2 uv) {
+    vec2 st = uv * 2.0 - 1.0;
+    float distance_squared = 1.0 + dot(st, st);
+    return 1.0 / (distance_squared * sqrt(distance_squared));
+}
+
+// Texture coordinates of a direction in an equirectangular panorama with +Y up.
+vec2 equirect_uv(vec3 direction) {
+    float phi = atan(direction.z, direction.x);
+    float theta = acos(clamp(direction.y, -1.0, 1.0));
+    return vec2(phi / (2.0 * PI) + 0.5, theta / PI);
+}
+
+#endif  // MATH_CUBEMAP_GLSL
@@ -0,0 +1,51 @@
+#ifndef MATH_DEBUG_VIEW_GLSL
+#define MATH_DEBUG_VIEW_GLSL
+
+// Values of `DebugView::shader_index`.
+#define DEBUG_VIEW_NONE 0
+#define DEBUG_VIEW_WIREFRAME 1
+#define DEBUG_VIEW_NORMALS 2
+#define DEBUG_VIEW_UV_CHECKERBOARD 3
+#define DEBUG_VIEW_OVERDRAW 4
+#define DEBUG_VIEW_OBJECT_COLOR 5
+#define DEBUG_VIEW_CULLING_STATUS 6
+#define DEBUG_VIEW_MATERIAL_SLOT 7
+
+// Values of `DEBUG_CULLING_STATUS` in the culling status view.
+#define DEBUG_CULLING_EARLY 0
+#define DEBUG_CULLING_LATE 1
+#define DEBUG_CULLING_NONE 2
+
+// Number of overlapping fragments shown as red in the overdraw heatmap.
+#define DEBUG_OVERDRAW_MAX 8.0
+
+// Integer hash by Chris Wellons (lowbias32).
+uint debug_hash(uint value) {
+    value ^= value >> 16;
+    value *= 0x7feb352du;
+    value ^= value >> 15;
+    value *= 0x846ca68bu;
+    value ^= value >> 16;
+    return value;
+}
+
+// Saturated color which is stable for the same id.
+vec3 debug_random_color(uint id) {
+    uint hash = debug_hash(id);
+    float hue = float(hash & 0xffffu) / 65535.0;
+    vec3 rgb = clamp(abs(mod(hue * 6.0 + vec3(0.0, 4.0, 2.0), 6.0) - 3.0) - 1.0, 0.0, 1.0);
+    float value = 0.6 + 0.4 * float(hash >> 24) / 255.0;
+    return rgb * value;
+}
+
+// Maps `0..=1` to blue, cyan, green, yellow and red.
+vec3 debug_heatmap(float value) {
+    value = clamp(value, 0.0, 1.0);
+    return clamp(vec3(
+        4.0 * value - 2.0,
+        value < 0.5 ? 4.0 * value : 4.0 - 4.0 * value,
+        2.0 - 4.0 * value
+    ), 0.0, 1.0);
+}
+
+#endif  // MATH_DEBUG_VIEW_GLSL
@@ -1,6 +1,7 @@
 #ifndef MATH_FRUSTUM_GLSL
 #define MATH_FRUSTUM_GLSL
 
+#include "./aabb.glsl"
 #include "./sphere.glsl"
 
 struct Plane {
@@ -28,4 +29,24 @@ bool frustum_contains_sphere(Frustum frustum, Sphere sphere) {
     plane_distance_to_point(frustum.near, sphere.data.xyz) >= neg_radius;
 }
 
+// Signed distance from the plane to the farthest corner of the box along its normal.
+float plane_distance_to_aabb(Plane plane, vec3 center, vec3 half_extents) {
+    return plane_distance_to_point(plane, center) + dot(abs(plane.inner.xyz), half_extents);
+}
+
+bool frustum_intersects_aabb(Frustum frustum, Aabb aabb) {
+    vec3 center = aabb_center(aabb);
+    vec3 half_extents = aabb_half_extents(aabb);
+    return plane_distance_to_aabb(frustum.left, center, half_extents) >= 0.0 &&
+    plane_distance_to_aabb(frustum.right, center, half_extents) >= 0.0 &&
+    plane_distance_to_aabb(frustum.top, center, half_extents) >= 0.0 &&
+    plane_distance_to_aabb(frustum.bottom, center, half_extents) >= 0.0 &&
+    plane_distance_to_aabb(frustum.near, center, half_extents) >= 0.0;
+}
+
+// Checks the bounding sphere first and then the tighter bounding box.
+bool frustum_intersects_bounds(Frustum frustum, Sphere sphere, Aabb aabb) {
+    return frustum_contains_sphere(frustum, sphere) && frustum_intersects_aabb(frustum, aabb);
+}
+
 #endif  // MATH_FRUSTUM_GLSL
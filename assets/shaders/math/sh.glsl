@@ -0,0 +1,30 @@
+#ifndef MATH_SH_GLSL
+#define MATH_SH_GLSL
+
+#define SH_COEFFICIENT_COUNT 9
+
+// Real spherical harmonics basis up to the second band.
+void sh_basis(vec3 direction, out float basis[SH_COEFFICIENT_COUNT]) {
+    float x = direction.x;
+    float y = direction.y;
+    float z = direction.z;
+
+    basis[0] = 0.282095;
+    basis[1] = 0.488603 * y;
+    basis[2] = 0.488603 * z;
+    basis[3] = 0.488603 * x;
+    basis[4] = 1.092548 * x * y;
+    basis[5] = 1.092548 * y * z;
+    basis[6] = 0.315392 * (3.0 * z * z - 1.0);
+    basis[7] = 1.092548 * x * z;
+    basis[8] = 0.546274 * (x * x - y * y);
+}
+
+// Convolution of each band with the clamped cosine lobe, divided by PI.
+const float SH_COSINE_BANDS[SH_COEFFICIENT_COUNT] = float[](
+    1.0,
+    2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0,
+    0.25, 0.25, 0.25, 0.25, 0.25
+);
+
+#endif  // MATH_SH_GLSL
@@ -0,0 +1,78 @@
+#ifndef MATH_TONEMAP_GLSL
+#define MATH_TONEMAP_GLSL
+
+#include "./color.glsl"
+
+#define TONEMAPPING_NONE 0
+#define TONEMAPPING_ACES 1
+#define TONEMAPPING_AGX 2
+#define TONEMAPPING_REINHARD 3
+
+// Stephen Hill's fit of the ACES RRT and ODT.
+vec3 tonemap_aces(vec3 color) {
+    const mat3 input_mat = mat3(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777
+    );
+    const mat3 output_mat = mat3(
+        1.60475, -0.10208, -0.00327,
+        -0.53108, 1.10813, -0.07276,
+        -0.07367, -0.00605, 1.07602
+    );
+
+    color = input_mat * color;
+    vec3 a = color * (color + 0.0245786) - 0.000090537;
+    vec3 b = color * (0.983729 * color + 0.4329510) + 0.238081;
+    return clamp(output_mat * (a / b), 0.0, 1.0);
+}
+
+// Minimal AgX by Benjamin Wrensch, with the default look.
+vec3 tonemap_agx(vec3 color) {
+    const mat3 inset_mat = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    const mat3 outset_mat = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    const float min_ev = -12.47393;
+    const float max_ev = 4.026069;
+
+    color = inset_mat * color;
+    color = clamp(log2(max(color, vec3(1e-10))), min_ev, max_ev);
+    color = (color - min_ev) / (max_ev - min_ev);
+
+    // Sigmoid contrast approximation
+    vec3 x2 = color * color;
+    vec3 x4 = x2 * x2;
+    color = 15.5 * x4 * x2 - 40.14 * x4 * color + 31.96 * x4
+        - 6.868 * x2 * color + 0.4298 * x2 + 0.1191 * color - 0.00232;
+
+    color = outset_mat * color;
+
+    // NOTE: AgX produces display encoded values, linearize them back
+    return pow(clamp(color, 0.0, 1.0), vec3(2.2));
+}
+
+vec3 tonemap_reinhard(vec3 color) {
+    return clamp(color / (1.0 + luminance(color)), 0.0, 1.0);
+}
+
+vec3 tonemap(vec3 color, uint tonemapping) {
+    switch (tonemapping) {
+        case TONEMAPPING_ACES:
+            return tonemap_aces(color);
+        case TONEMAPPING_AGX:
+            return tonemap_agx(color);
+        case TONEMAPPING_REINHARD:
+            return tonemap_reinhard(color);
+        default:
+            return clamp(color, 0.0, 1.0);
+    }
+}
+
+#endif  // MATH_TONEMAP_GLSL
@@ -0,0 +1,21 @@
+#ifndef MATH_VELOCITY_GLSL
+#define MATH_VELOCITY_GLSL
+
+// Converts NDC into texture coordinates of a target rendered with a flipped viewport.
+vec2 ndc_to_uv(vec2 ndc) {
+    return vec2(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+}
+
+// Inverse of `ndc_to_uv`.
+vec2 uv_to_ndc(vec2 uv) {
+    return vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+}
+
+// Screen space motion in texture coordinates from the previous frame to the current one.
+vec2 compute_velocity(vec4 clip_position, vec4 previous_clip_position) {
+    vec2 uv = ndc_to_uv(clip_position.xy / clip_position.w);
+    vec2 previous_uv = ndc_to_uv(previous_clip_position.xy / previous_clip_position.w);
+    return uv - previous_uv;
+}
+
+#endif  // MATH_VELOCITY_GLSL
@@ -1,14 +1,96 @@
 #version 450
 
+#include "math/velocity.glsl"
+#include "uniforms/globals.glsl"
+#include "uniforms/bindless.glsl"
+#include "uniforms/environment.glsl"
+
 layout (location = 0) in vec3 in_color;
 layout (location = 1) in vec3 in_normal;
+layout (location = 2) in vec4 in_clip_position;
+layout (location = 3) in vec4 in_previous_clip_position;
+layout (location = 4) in vec3 in_world_position;
 
 layout (location = 0) out vec4 out_frag_color;
+layout (location = 1) out vec2 out_velocity;
+
+#ifdef DEBUG_VIEW
+#include "math/debug_view.glsl"
+
+layout (location = 5) in vec2 in_uv;
+layout (location = 6) flat in uint in_debug_id;
+
+// Color of the selected debug view, displayed without tonemapping.
+vec3 debug_view_color() {
+#if DEBUG_VIEW == DEBUG_VIEW_WIREFRAME
+    return vec3(1.0, 0.6, 0.1);
+#elif DEBUG_VIEW == DEBUG_VIEW_NORMALS
+    return normalize(in_normal) * 0.5 + 0.5;
+#elif DEBUG_VIEW == DEBUG_VIEW_UV_CHECKERBOARD
+    ivec2 cell = ivec2(floor(in_uv * 8.0));
+    float checker = ((cell.x + cell.y) & 1) == 0 ? 1.0 : 0.25;
+    // NOTE: Tinted by the coordinates to show their direction
+    return checker * vec3(fract(in_uv), 1.0);
+#elif DEBUG_VIEW == DEBUG_VIEW_OVERDRAW
+    return vec3(1.0);
+#elif DEBUG_VIEW == DEBUG_VIEW_CULLING_STATUS
+#if DEBUG_CULLING_STATUS == DEBUG_CULLING_EARLY
+    return vec3(0.1, 0.8, 0.1);
+#elif DEBUG_CULLING_STATUS == DEBUG_CULLING_LATE
+    return vec3(0.9, 0.1, 0.1);
+#else
+    return vec3(0.1, 0.3, 0.9);
+#endif
+#else
+    return debug_random_color(in_debug_id);
+#endif
+}
+#endif
+
+// Occlusion computed in the previous frame at the reprojected position of the fragment.
+float ambient_occlusion() {
+    if (AMBIENT_OCCLUSION_TEXTURE == NO_RESOURCE) {
+        return 1.0;
+    }
+
+    vec2 uv = ndc_to_uv(in_previous_clip_position.xy / in_previous_clip_position.w);
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        return 1.0;
+    }
+
+    // NOTE: Surfaces which were hidden in the previous frame have a different `w`
+    vec2 occlusion = textureLod(u_global_textures[AMBIENT_OCCLUSION_TEXTURE], uv, 0.0).rg;
+    float w = in_previous_clip_position.w;
+    return abs(occlusion.y - w) <= 0.05 * w ? occlusion.x : 1.0;
+}
 
 void main() {
+#ifdef DEBUG_VIEW
+    out_frag_color = vec4(debug_view_color(), 1.0);
+    out_velocity = compute_velocity(in_clip_position, in_previous_clip_position);
+    return;
+#endif
+
     const vec3 light_direction = normalize(vec3(-0.5, -0.5, -0.5));
+    const float ambient = 0.2;
+    // NOTE: The debug material is shaded as a rough dielectric
+    const float roughness = 0.6;
+    const vec3 f0 = vec3(0.04);
+
+    vec3 normal = normalize(in_normal);
+    float diffuse = clamp(dot(-light_direction, normal), 0.0, 1.0);
+    float occlusion = ambient_occlusion();
 
-    vec3 color = clamp(dot(-light_direction, normalize(in_normal)), 0.0, 1.0) * in_color;
+    vec3 color = diffuse * in_color;
+    if (has_environment()) {
+        vec3 view = normalize(CAMERA_VIEW_INVERSE[3].xyz - in_world_position);
+        vec3 indirect = environment_diffuse(normal) * in_color
+            + environment_specular(normal, view, f0, roughness);
+        color += indirect * occlusion;
+    } else {
+        color += ambient * occlusion * in_color;
+    }
 
     out_frag_color = vec4(color, 1.0f);
+    out_velocity = compute_velocity(in_clip_position, in_previous_clip_position);
 }
@@ -3,16 +3,13 @@
 #extension GL_EXT_nonuniform_qualifier: require
 #extension GL_ARB_shader_draw_parameters: require
 
-#define VERTEX_POSITION 0
-#define VERTEX_NORMAL 1
-#define VERTEX_TANGENT 2
-#define VERTEX_UV0 3
-#define VERTEX_COLOR 4
-#define VERTEX_ATTR_COUNT 5
+// NOTE: `VERTEX_*` attribute indices are defined by the material
 
 #include "uniforms/globals.glsl"
 #include "uniforms/bindless.glsl"
 #include "uniforms/object.glsl"
+#include "uniforms/morph.glsl"
+#include "uniforms/skin.glsl"
 
 layout (push_constant) uniform PushConstant {
     uint mesh_buffer_index;
@@ -32,14 +29,72 @@ MaterialData material_data_read(uint buffer_index, uint slot) {
 
 layout (location = 0) out vec3 out_color;
 layout (location = 1) out vec3 out_normal;
+layout (location = 2) out vec4 out_clip_position;
+layout (location = 3) out vec4 out_previous_clip_position;
+layout (location = 4) out vec3 out_world_position;
+
+#ifdef DEBUG_VIEW
+#include "math/debug_view.glsl"
+
+layout (location = 5) out vec2 out_uv;
+layout (location = 6) flat out uint out_debug_id;
+#endif
+
+#ifdef PICKING
+layout (location = 5) flat out uint out_object_id;
+#endif
 
 void main() {
     ObjectData object_data = object_data_read(push_constant.object_buffer_index);
     MaterialData material_data = material_data_read(push_constant.material_buffer_index, object_data.data.z);
 
     Vertex vertex = vertex_read(push_constant.mesh_buffer_index, object_data.offsets);
+    apply_morph_targets(object_data, push_constant.mesh_buffer_index, vertex);
+
+    mat4 skin = skin_matrix(object_data, vertex);
+    vec4 position = skin * vec4(vertex.position, 1.0f);
+    vec3 normal = mat3(skin) * vertex.normal;
+
+    vec4 world_position = object_data.transform * position;
+    vec4 clip_position = CAMERA_PROJECTION * CAMERA_VIEW * world_position;
+    // NOTE: Joints and morph weights of the previous frame are not tracked
+    out_previous_clip_position = CAMERA_PREVIOUS_PROJECTION * CAMERA_PREVIOUS_VIEW * object_data.previous_transform * position;
+    out_clip_position = clip_position;
+    out_world_position = world_position.xyz;
 
-    gl_Position = CAMERA_PROJECTION * CAMERA_VIEW * object_data.transform * vec4(vertex.position, 1.0f);
+    gl_Position = clip_position;
+    gl_Position.xy += CAMERA_JITTER * clip_position.w;
     out_color = material_data.color;
-    out_normal = (object_data.transform_inverse_transpose * vec4(vertex.normal, 1.0)).xyz;
+    out_normal = (object_data.transform_inverse_transpose * vec4(normal, 1.0)).xyz;
+
+#ifdef DEBUG_VIEW
+#ifdef VERTEX_UV0
+    out_uv = vertex.uv0;
+#else
+    out_uv = vec2(0.0);
+#endif
+
+#if DEBUG_VIEW == DEBUG_VIEW_MATERIAL_SLOT
+    out_debug_id = object_data.data.z;
+#else
+    // NOTE: Instance indices change when objects are added or culled, the picking id is stable
+    out_debug_id = object_data.id;
+#endif
+
+#if DEBUG_VIEW == DEBUG_VIEW_WIREFRAME
+    // NOTE: Edges are pulled towards the camera to pass the depth test against the shaded surface
+    gl_Position.z -= 1e-5 * gl_Position.w;
+#endif
+#endif
+
+#ifdef PICKING
+    // NOTE: Not jittered, so the picked depth matches the position computed on the CPU
+    gl_Position = clip_position;
+    out_object_id = object_data.id;
+    if ((object_flags(object_data) & OBJECT_FLAG_EXCLUDE_FROM_PICKING) != 0u
+        || !object_is_visible_in(object_data, PICKING_RENDER_LAYERS)) {
+        // Primitives outside of the clip volume are discarded
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+    }
+#endif
 }
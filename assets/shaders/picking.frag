@@ -0,0 +1,9 @@
+#version 450
+
+layout (location = 5) flat in uint in_object_id;
+
+layout (location = 0) out uint out_object_id;
+
+void main() {
+    out_object_id = in_object_id;
+}
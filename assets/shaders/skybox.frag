@@ -0,0 +1,24 @@
+#version 450
+
+#include "math/velocity.glsl"
+#include "uniforms/globals.glsl"
+#include "uniforms/bindless.glsl"
+
+layout (location = 0) in vec2 in_ndc;
+
+layout (location = 0) out vec4 out_frag_color;
+layout (location = 1) out vec2 out_velocity;
+
+void main() {
+    vec4 view_position = CAMERA_PROJECTION_INVERSE * vec4(in_ndc, 0.5, 1.0);
+    vec3 direction = normalize(mat3(CAMERA_VIEW_INVERSE) * (view_position.xyz / view_position.w));
+
+    vec3 color = textureLod(u_global_textures_cube[ENVIRONMENT_SKYBOX], direction, 0.0).rgb;
+
+    // NOTE: Directions are infinitely far away, so only the camera rotation moves them
+    vec4 clip_position = CAMERA_PROJECTION * vec4(mat3(CAMERA_VIEW) * direction, 0.0);
+    vec4 previous_clip_position = CAMERA_PREVIOUS_PROJECTION * vec4(mat3(CAMERA_PREVIOUS_VIEW) * direction, 0.0);
+
+    out_frag_color = vec4(color, 1.0);
+    out_velocity = compute_velocity(clip_position, previous_clip_position);
+}
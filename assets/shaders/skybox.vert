@@ -0,0 +1,10 @@
+#version 450
+
+layout (location = 0) out vec2 out_ndc;
+
+// Covers the whole viewport with a single triangle on the far plane.
+void main() {
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+    out_ndc = position;
+    gl_Position = vec4(position, 1.0, 1.0);
+}
@@ -0,0 +1,48 @@
+#version 450
+
+#include "math/color.glsl"
+
+// Tonemapped and sRGB encoded scene color.
+layout (set = 0, binding = 0) uniform sampler2D u_color;
+// Blending weights from `smaa_weights.frag`.
+layout (set = 0, binding = 1) uniform sampler2D u_weights;
+
+layout (push_constant) uniform PushConstant {
+    // Whether the target format applies the sRGB transfer function itself.
+    uint decode_srgb;
+} push_constant;
+
+layout (location = 0) out vec4 out_frag_color;
+
+// Blends each pixel with its neighbors according to the weights of the surrounding edges.
+vec3 neighborhood_blend(vec2 uv, vec2 texel_size) {
+    vec4 a;
+    a.x = textureLodOffset(u_weights, uv, 0.0, ivec2(1, 0)).a; // Right
+    a.y = textureLodOffset(u_weights, uv, 0.0, ivec2(0, 1)).g; // Bottom
+    a.wz = textureLod(u_weights, uv, 0.0).xz; // Top / Left
+
+    if (dot(a, vec4(1.0)) < 1e-5) {
+        return textureLod(u_color, uv, 0.0).rgb;
+    }
+
+    // NOTE: Only the direction with the strongest weights is blended
+    bool horizontal = max(a.x, a.z) > max(a.y, a.w);
+    vec4 blending_offset = horizontal ? vec4(a.x, 0.0, -a.z, 0.0) : vec4(0.0, a.y, 0.0, -a.w);
+    vec2 blending_weight = horizontal ? a.xz : a.yw;
+    blending_weight /= dot(blending_weight, vec2(1.0));
+
+    vec4 blending_uv = blending_offset * texel_size.xyxy + uv.xyxy;
+    return blending_weight.x * textureLod(u_color, blending_uv.xy, 0.0).rgb
+        + blending_weight.y * textureLod(u_color, blending_uv.zw, 0.0).rgb;
+}
+
+void main() {
+    vec2 texel_size = 1.0 / vec2(textureSize(u_color, 0));
+    vec3 color = neighborhood_blend(gl_FragCoord.xy * texel_size, texel_size);
+
+    if (push_constant.decode_srgb != 0) {
+        color = srgb_to_linear(color);
+    }
+
+    out_frag_color = vec4(color, 1.0);
+}
@@ -0,0 +1,49 @@
+#version 450
+
+// Tonemapped and sRGB encoded scene color.
+layout (set = 0, binding = 0) uniform sampler2D u_color;
+
+layout (location = 0) out vec2 out_edges;
+
+// Minimal local contrast of an edge
+#define SMAA_THRESHOLD 0.1
+// Edges are discarded if their neighbors have a much higher contrast
+#define SMAA_LOCAL_CONTRAST_ADAPTATION_FACTOR 2.0
+
+float luma(vec2 uv, ivec2 offset) {
+    return dot(textureLodOffset(u_color, uv, 0.0, offset).rgb, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// Luma edge detection, `r` is set for edges on the left and `g` for edges on the top.
+void main() {
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(u_color, 0));
+
+    float l = luma(uv, ivec2(0, 0));
+    float l_left = luma(uv, ivec2(-1, 0));
+    float l_top = luma(uv, ivec2(0, -1));
+
+    vec4 delta;
+    delta.xy = abs(l - vec2(l_left, l_top));
+    vec2 edges = step(SMAA_THRESHOLD, delta.xy);
+    if (dot(edges, vec2(1.0)) == 0.0) {
+        out_edges = vec2(0.0);
+        return;
+    }
+
+    // Maximum contrast of the surrounding edges
+    float l_right = luma(uv, ivec2(1, 0));
+    float l_bottom = luma(uv, ivec2(0, 1));
+    delta.zw = abs(l - vec2(l_right, l_bottom));
+    vec2 max_delta = max(delta.xy, delta.zw);
+
+    float l_left_left = luma(uv, ivec2(-2, 0));
+    float l_top_top = luma(uv, ivec2(0, -2));
+    delta.zw = abs(vec2(l_left, l_top) - vec2(l_left_left, l_top_top));
+    max_delta = max(max_delta.xy, delta.zw);
+    float final_delta = max(max_delta.x, max_delta.y);
+
+    // NOTE: Local contrast adaptation
+    edges *= step(final_delta, SMAA_LOCAL_CONTRAST_ADAPTATION_FACTOR * delta.xy);
+
+    out_edges = edges;
+}
@@ -0,0 +1,125 @@
+#version 450
+
+// Bilinearly filtered edges from `smaa_edges.frag`.
+layout (set = 0, binding = 0) uniform sampler2D u_edges;
+// Precomputed areas of the orthogonal patterns, see `assets/textures/smaa`.
+layout (set = 0, binding = 1) uniform sampler2D u_area;
+// Distance corrections of the last search step, see `assets/textures/smaa`.
+layout (set = 0, binding = 2) uniform sampler2D u_search;
+
+layout (location = 0) out vec4 out_weights;
+
+// Maximum number of steps of each search, two pixels are checked per step
+#define SMAA_MAX_SEARCH_STEPS 8
+// Maximum (compressed) distance and size of a single pattern in the area texture
+#define SMAA_AREATEX_MAX_DISTANCE 16.0
+#define SMAA_AREATEX_PIXEL_SIZE (1.0 / vec2(80.0))
+// Number of texels used by each search direction in the search texture
+#define SMAA_SEARCHTEX_DIRECTION_SIZE 33
+
+vec4 rt_metrics;
+
+// Correction of the distance for the edges fetched in the last search step.
+float search_length(vec2 e, int direction) {
+    ivec2 texel = ivec2(round(e * 32.0)) + ivec2(direction * SMAA_SEARCHTEX_DIRECTION_SIZE, 0);
+    return texelFetch(u_search, texel, 0).r;
+}
+
+float search_x_left(vec2 uv, float end) {
+    // NOTE: The coordinates are offset by (-0.25, -0.125) to fetch four edges at once,
+    // which are then told apart by the bilinear weights.
+    vec2 e = vec2(0.0, 1.0);
+    while (uv.x > end && e.g > 0.8281 && e.r == 0.0) {
+        e = textureLod(u_edges, uv, 0.0).rg;
+        uv -= vec2(2.0, 0.0) * rt_metrics.xy;
+    }
+    float offset = -(255.0 / 127.0) * search_length(e, 0) + 3.25;
+    return rt_metrics.x * offset + uv.x;
+}
+
+float search_x_right(vec2 uv, float end) {
+    vec2 e = vec2(0.0, 1.0);
+    while (uv.x < end && e.g > 0.8281 && e.r == 0.0) {
+        e = textureLod(u_edges, uv, 0.0).rg;
+        uv += vec2(2.0, 0.0) * rt_metrics.xy;
+    }
+    float offset = -(255.0 / 127.0) * search_length(e, 1) + 3.25;
+    return -rt_metrics.x * offset + uv.x;
+}
+
+float search_y_up(vec2 uv, float end) {
+    vec2 e = vec2(1.0, 0.0);
+    while (uv.y > end && e.r > 0.8281 && e.g == 0.0) {
+        e = textureLod(u_edges, uv, 0.0).rg;
+        uv -= vec2(0.0, 2.0) * rt_metrics.xy;
+    }
+    float offset = -(255.0 / 127.0) * search_length(e.gr, 0) + 3.25;
+    return rt_metrics.y * offset + uv.y;
+}
+
+float search_y_down(vec2 uv, float end) {
+    vec2 e = vec2(1.0, 0.0);
+    while (uv.y < end && e.r > 0.8281 && e.g == 0.0) {
+        e = textureLod(u_edges, uv, 0.0).rg;
+        uv += vec2(0.0, 2.0) * rt_metrics.xy;
+    }
+    float offset = -(255.0 / 127.0) * search_length(e.gr, 1) + 3.25;
+    return -rt_metrics.y * offset + uv.y;
+}
+
+// Coverage of the current pixel for the pattern formed by the crossing edges
+// `e1` and `e2` at distances `dist` (square roots of the distances in pixels).
+vec2 area(vec2 dist, float e1, float e2) {
+    // NOTE: Rounding prevents precision errors of bilinear filtering
+    vec2 uv = SMAA_AREATEX_MAX_DISTANCE * round(4.0 * vec2(e1, e2)) + dist;
+    uv = SMAA_AREATEX_PIXEL_SIZE * uv + 0.5 * SMAA_AREATEX_PIXEL_SIZE;
+    return textureLod(u_area, uv, 0.0).rg;
+}
+
+// Blending weights for the orthogonal patterns (SMAA 1x without diagonal and corner detection).
+void main() {
+    vec2 size = vec2(textureSize(u_edges, 0));
+    rt_metrics = vec4(1.0 / size, size);
+
+    vec2 uv = gl_FragCoord.xy * rt_metrics.xy;
+    vec2 pixel = gl_FragCoord.xy;
+
+    vec4 offsets[3];
+    offsets[0] = rt_metrics.xyxy * vec4(-0.25, -0.125, 1.25, -0.125) + uv.xyxy;
+    offsets[1] = rt_metrics.xyxy * vec4(-0.125, -0.25, -0.125, 1.25) + uv.xyxy;
+    offsets[2] = rt_metrics.xxyy * vec4(-2.0, 2.0, -2.0, 2.0) * float(SMAA_MAX_SEARCH_STEPS)
+        + vec4(offsets[0].xz, offsets[1].yw);
+
+    vec4 weights = vec4(0.0);
+    vec2 e = textureLod(u_edges, uv, 0.0).rg;
+
+    // Edge at the top
+    if (e.g > 0.0) {
+        vec3 coords;
+        coords.x = search_x_left(offsets[0].xy, offsets[2].x);
+        coords.y = offsets[1].y;
+        float e1 = textureLod(u_edges, coords.xy, 0.0).r;
+
+        coords.z = search_x_right(offsets[0].zw, offsets[2].y);
+        float e2 = textureLodOffset(u_edges, coords.zy, 0.0, ivec2(1, 0)).r;
+
+        vec2 d = abs(round(rt_metrics.zz * vec2(coords.x, coords.z) - pixel.xx));
+        weights.rg = area(sqrt(d), e1, e2);
+    }
+
+    // Edge at the left
+    if (e.r > 0.0) {
+        vec3 coords;
+        coords.y = search_y_up(offsets[1].xy, offsets[2].z);
+        coords.x = offsets[0].x;
+        float e1 = textureLod(u_edges, coords.xy, 0.0).g;
+
+        coords.z = search_y_down(offsets[1].zw, offsets[2].w);
+        float e2 = textureLodOffset(u_edges, coords.xz, 0.0, ivec2(0, 1)).g;
+
+        vec2 d = abs(round(rt_metrics.ww * vec2(coords.y, coords.z) - pixel.yy));
+        weights.ba = area(sqrt(d), e1, e2);
+    }
+
+    out_weights = weights;
+}
@@ -0,0 +1,51 @@
+#version 450
+
+#include "math/debug_view.glsl"
+#include "math/tonemap.glsl"
+
+layout (set = 0, binding = 0) uniform sampler2D u_hdr_target;
+
+layout (std430, set = 0, binding = 1) readonly buffer Exposure {
+    float ev100;
+} exposure;
+
+layout (set = 0, binding = 2) uniform sampler2D u_bloom;
+
+layout (push_constant) uniform PushConstant {
+    uint tonemapping;
+    // Whether the target format does not apply the sRGB transfer function itself.
+    uint encode_srgb;
+    // Zero if bloom is disabled.
+    float bloom_intensity;
+    // Debug views are displayed without exposure and tonemapping.
+    uint debug_view;
+} push_constant;
+
+layout (location = 0) out vec4 out_frag_color;
+
+void main() {
+    vec3 color = texelFetch(u_hdr_target, ivec2(gl_FragCoord.xy), 0).rgb;
+
+    if (push_constant.debug_view == DEBUG_VIEW_OVERDRAW) {
+        // NOTE: The clear color is below a half of a fragment
+        float count = round(color.r);
+        color = count < 1.0 ? vec3(0.0) : debug_heatmap((count - 1.0) / (DEBUG_OVERDRAW_MAX - 1.0));
+    } else if (push_constant.debug_view != DEBUG_VIEW_NONE) {
+        color = clamp(color, 0.0, 1.0);
+    } else {
+        color *= ev100_to_exposure(exposure.ev100);
+
+        if (push_constant.bloom_intensity > 0.0) {
+            vec2 uv = gl_FragCoord.xy / vec2(textureSize(u_hdr_target, 0));
+            color += textureLod(u_bloom, uv, 0.0).rgb * push_constant.bloom_intensity;
+        }
+
+        color = tonemap(color, push_constant.tonemapping);
+    }
+
+    if (push_constant.encode_srgb != 0) {
+        color = linear_to_srgb(color);
+    }
+
+    out_frag_color = vec4(color, 1.0);
+}
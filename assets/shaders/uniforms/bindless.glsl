@@ -19,6 +19,7 @@ BINDLESS_TEX(sampler2D, u_global_textures);
 BINDLESS_TEX(usampler2D, u_global_textures_uint);
 BINDLESS_TEX(sampler3D, u_global_textures_3d);
 BINDLESS_TEX(usampler3D, u_global_textures_3d_uint);
+BINDLESS_TEX(samplerCube, u_global_textures_cube);
 
 #define BINDLESS_UBO(ty, name) \
 layout (set = BINDLESS_SET, binding = BINDLESS_UBO_BINDING) uniform ty##Buffer { \
@@ -0,0 +1,50 @@
+#ifndef UNIFORMS_ENVIRONMENT_GLSL
+#define UNIFORMS_ENVIRONMENT_GLSL
+
+#include "../math/sh.glsl"
+#include "bindless.glsl"
+#include "globals.glsl"
+
+// Must match `SPECULAR_MIP_LEVELS` of the renderer.
+#define ENVIRONMENT_SPECULAR_MIPS 5
+
+BINDLESS_SBO_RO(std430, vec4, u_environment_irradiance);
+
+bool has_environment() {
+    return ENVIRONMENT_IRRADIANCE != NO_RESOURCE;
+}
+
+// Irradiance around the normal divided by PI, i.e. the radiance reflected by a white diffuse surface.
+vec3 environment_diffuse(vec3 normal) {
+    float basis[SH_COEFFICIENT_COUNT];
+    sh_basis(normal, basis);
+
+    vec3 result = vec3(0.0);
+    for (uint i = 0; i < SH_COEFFICIENT_COUNT; ++i) {
+        result += u_environment_irradiance[ENVIRONMENT_IRRADIANCE].items[i].rgb * basis[i];
+    }
+    return max(result, vec3(0.0));
+}
+
+// Radiance along the direction, prefiltered with the GGX lobe of the roughness.
+vec3 environment_radiance(vec3 direction, float roughness) {
+    float lod = roughness * float(ENVIRONMENT_SPECULAR_MIPS - 1);
+    return textureLod(u_global_textures_cube[ENVIRONMENT_SPECULAR], direction, lod).rgb;
+}
+
+// Specular reflection of the environment, using an analytic approximation of the
+// split sum BRDF (Karis, "Physically Based Shading on Mobile").
+vec3 environment_specular(vec3 normal, vec3 view, vec3 f0, float roughness) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+
+    float n_dot_v = max(dot(normal, view), 1e-4);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    vec2 scale_bias = vec2(-1.04, 1.04) * a004 + r.zw;
+
+    vec3 radiance = environment_radiance(reflect(-view, normal), roughness);
+    return radiance * (f0 * scale_bias.x + scale_bias.y);
+}
+
+#endif  // UNIFORMS_ENVIRONMENT_GLSL
@@ -18,6 +18,13 @@ layout (set = GLOBALS_SET, binding = GLOBALS_BINDING, std140) uniform GlobalUnif
     float time;
     float delta_time;
     uint frame_index;
+    uint camera_render_layers;
+    vec2 camera_jitter;
+    uint ambient_occlusion_texture;
+    uint environment_skybox;
+    uint environment_specular;
+    uint environment_irradiance;
+    uint picking_render_layers;
 }
 globals;
 
@@ -31,5 +38,14 @@ globals;
 #define TIME globals.time
 #define DELTA_TIME globals.delta_time
 #define FRAME_INDEX globals.frame_index
+#define CAMERA_RENDER_LAYERS globals.camera_render_layers
+#define CAMERA_JITTER globals.camera_jitter
+#define AMBIENT_OCCLUSION_TEXTURE globals.ambient_occlusion_texture
+#define ENVIRONMENT_SKYBOX globals.environment_skybox
+#define ENVIRONMENT_SPECULAR globals.environment_specular
+#define ENVIRONMENT_IRRADIANCE globals.environment_irradiance
+#define PICKING_RENDER_LAYERS globals.picking_render_layers
+
+#define NO_RESOURCE 0xffffffffu
 
 #endif  // UNIFORMS_GLOBALS_GLSL
@@ -0,0 +1,51 @@
+#ifndef UNIFORMS_MORPH_GLSL
+#define UNIFORMS_MORPH_GLSL
+
+#include "./bindless.glsl"
+#include "./object.glsl"
+
+BINDLESS_SBO_RO(std430, float, u_morph_weights);
+
+#ifdef VERTEX_MORPH_POSITIONS
+// NOTE: `morph.x` is a weights buffer index, `morph.y` is the first weight
+// of the object, `morph.z` is the number of weights and `morph.w` is
+// the number of mesh vertices (the stride between morph targets).
+void apply_morph_targets(ObjectData object_data, uint buffer_index, inout Vertex vertex) {
+    uint weight_count = object_data.morph.z;
+    if (weight_count == 0 || object_data.offsets[VERTEX_MORPH_POSITIONS] == VERTEX_ATTR_MISSING) {
+        return;
+    }
+
+    uint weights_buffer_index = object_data.morph.x;
+    uint first_weight = object_data.morph.y;
+    uint vertex_count = object_data.morph.w;
+
+    #ifdef VERTEX_MORPH_NORMALS
+    bool has_normals = object_data.offsets[VERTEX_MORPH_NORMALS] != VERTEX_ATTR_MISSING;
+    #endif
+
+    for (uint i = 0; i < weight_count; ++i) {
+        float weight = u_morph_weights[weights_buffer_index].items[first_weight + i];
+        if (weight == 0.0) {
+            continue;
+        }
+
+        uint vertex_index = i * vertex_count + gl_VertexIndex;
+        vertex.position += weight * vertex_data_read_vec3_at(
+            buffer_index, object_data.offsets[VERTEX_MORPH_POSITIONS], vertex_index);
+
+        #if defined(VERTEX_MORPH_NORMALS) && defined(VERTEX_NORMAL)
+        if (has_normals) {
+            vertex.normal += weight * vertex_data_read_vec3_at(
+                buffer_index, object_data.offsets[VERTEX_MORPH_NORMALS], vertex_index);
+        }
+        #endif
+    }
+
+    #ifdef VERTEX_NORMAL
+    vertex.normal = normalize(vertex.normal);
+    #endif
+}
+#endif
+
+#endif // UNIFORMS_MORPH_GLSL
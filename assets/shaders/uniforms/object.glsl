@@ -1,19 +1,46 @@
 #ifndef UNIFORMS_OBJECT_GLSL
 #define UNIFORMS_OBJECT_GLSL
 
+#include "../math/aabb.glsl"
 #include "../math/sphere.glsl"
 #include "./bindless.glsl"
 
 struct ObjectData {
     mat4 transform;
     mat4 transform_inverse_transpose;
+    // Transform of the previous frame, used for motion vectors.
+    mat4 previous_transform;
     Sphere bounding_sphere;
+    Aabb bounding_box;
     uvec4 data;
+    uvec4 skin;
+    uvec4 morph;
+    // Handle of the object written into the picking buffer, zero is reserved for the background.
+    uint id;
     #ifdef VERTEX_ATTR_COUNT
     uint offsets[VERTEX_ATTR_COUNT];
     #endif
 };
 
+// `data.w` contains object flags in the lower half and render layers in the upper half.
+#define OBJECT_FLAG_VISIBLE 0x1u
+#define OBJECT_FLAG_CAST_SHADOWS 0x2u
+#define OBJECT_FLAG_RECEIVE_SHADOWS 0x4u
+#define OBJECT_FLAG_EXCLUDE_FROM_PICKING 0x8u
+
+uint object_flags(ObjectData object) {
+    return object.data.w & 0xffffu;
+}
+
+uint object_render_layers(ObjectData object) {
+    return object.data.w >> 16;
+}
+
+bool object_is_visible_in(ObjectData object, uint render_layers) {
+    return (object_flags(object) & OBJECT_FLAG_VISIBLE) != 0u
+        && (object_render_layers(object) & render_layers) != 0u;
+}
+
 BINDLESS_SBO_RO(std430, ObjectData, u_object_data);
 
 ObjectData object_data_read(uint buffer_index) {
@@ -23,27 +50,27 @@ ObjectData object_data_read(uint buffer_index) {
 BINDLESS_SBO_RO(std430, float, u_vertex_buffer_float);
 
 #ifdef VERTEX_ATTR_COUNT
-struct Vertex {
-    #ifdef VERTEX_POSITION
-    vec3 position;
-    #endif
+#define VERTEX_ATTR_MISSING 0xffffffffu
 
-    #ifdef VERTEX_NORMAL
-    vec3 normal;
-    #endif
+// Attribute encoding is stored in the lowest bits of its offset.
+#define VERTEX_ENCODING_MASK 0xfu
+#define VERTEX_ENCODING_FLOAT 0u
+#define VERTEX_ENCODING_HALF 1u
+#define VERTEX_ENCODING_UNORM8 2u
+#define VERTEX_ENCODING_OCTAHEDRAL 3u
+#define VERTEX_ENCODING_QUANTIZED 4u
 
-    #ifdef VERTEX_TANGENT
-    vec3 tangent;
-    #endif
+#define VERTEX_ENCODING_ENABLED(encodings, encoding) (((encodings) & (1u << (encoding))) != 0u)
 
-    #ifdef VERTEX_UV0
-    vec2 uv0;
-    #endif
-
-    #ifdef VERTEX_COLOR
-    vec4 color;
-    #endif
-};
+uvec4 vertex_data_read_uvec4(uint buffer_index, uint byte_offset) {
+    uint offset = byte_offset / 4 + gl_VertexIndex * 4;
+    return uvec4(
+        floatBitsToUint(u_vertex_buffer_float[buffer_index].items[offset]),
+        floatBitsToUint(u_vertex_buffer_float[buffer_index].items[offset + 1]),
+        floatBitsToUint(u_vertex_buffer_float[buffer_index].items[offset + 2]),
+        floatBitsToUint(u_vertex_buffer_float[buffer_index].items[offset + 3])
+    );
+}
 
 vec4 vertex_data_read_vec4(uint buffer_index, uint byte_offset) {
     uint offset = byte_offset / 4 + gl_VertexIndex * 4;
@@ -55,8 +82,8 @@ vec4 vertex_data_read_vec4(uint buffer_index, uint byte_offset) {
     );
 }
 
-vec3 vertex_data_read_vec3(uint buffer_index, uint byte_offset) {
-    uint offset = byte_offset / 4 + gl_VertexIndex * 3;
+vec3 vertex_data_read_vec3_at(uint buffer_index, uint byte_offset, uint vertex_index) {
+    uint offset = byte_offset / 4 + vertex_index * 3;
     return vec3(
         u_vertex_buffer_float[buffer_index].items[offset],
         u_vertex_buffer_float[buffer_index].items[offset + 1],
@@ -64,6 +91,10 @@ vec3 vertex_data_read_vec3(uint buffer_index, uint byte_offset) {
     );
 }
 
+vec3 vertex_data_read_vec3(uint buffer_index, uint byte_offset) {
+    return vertex_data_read_vec3_at(buffer_index, byte_offset, gl_VertexIndex);
+}
+
 vec2 vertex_data_read_vec2(uint buffer_index, uint byte_offset) {
     uint offset = byte_offset / 4 + gl_VertexIndex * 2;
     return vec2(
@@ -72,28 +103,80 @@ vec2 vertex_data_read_vec2(uint buffer_index, uint byte_offset) {
     );
 }
 
-Vertex vertex_read(uint buffer_index, uint[VERTEX_ATTR_COUNT] offsets) {
-    Vertex result;
+uint vertex_data_read_uint_at(uint buffer_index, uint byte_offset, uint index) {
+    return floatBitsToUint(u_vertex_buffer_float[buffer_index].items[byte_offset / 4 + index]);
+}
 
-    #ifdef VERTEX_POSITION
-    result.position = vertex_data_read_vec3(buffer_index, offsets[VERTEX_POSITION]);
-    #endif
-    #ifdef VERTEX_NORMAL
-    result.normal = vertex_data_read_vec3(buffer_index, offsets[VERTEX_NORMAL]);
-    #endif
-    #ifdef VERTEX_TANGENT
-    result.tangent = vertex_data_read_vec3(buffer_index, offsets[VERTEX_TANGENT]);
-    #endif
-    #ifdef VERTEX_UV0
-    result.uv0 = vertex_data_read_vec2(buffer_index, offsets[VERTEX_UV0]);
-    #endif
-    #ifdef VERTEX_COLOR
-    result.color = vertex_data_read_vec4(buffer_index, offsets[VERTEX_COLOR]);
-    #endif
+float vertex_data_read_float(uint buffer_index, uint byte_offset) {
+    return u_vertex_buffer_float[buffer_index].items[byte_offset / 4 + gl_VertexIndex];
+}
+
+uint vertex_data_read_uint(uint buffer_index, uint byte_offset) {
+    return vertex_data_read_uint_at(buffer_index, byte_offset, gl_VertexIndex);
+}
+
+vec3 octahedral_decode(vec2 e) {
+    vec3 v = vec3(e, 1.0 - abs(e.x) - abs(e.y));
+    if (v.z < 0.0) {
+        vec2 sign_not_zero = vec2(e.x >= 0.0 ? 1.0 : -1.0, e.y >= 0.0 ? 1.0 : -1.0);
+        v.xy = (1.0 - abs(v.yx)) * sign_not_zero;
+    }
+    return normalize(v);
+}
 
-    return result;
+vec3 vertex_read_position(uint buffer_index, uint offset, uint encodings) {
+    uint encoding = offset & VERTEX_ENCODING_MASK;
+    uint byte_offset = offset & ~VERTEX_ENCODING_MASK;
+
+    if (VERTEX_ENCODING_ENABLED(encodings, VERTEX_ENCODING_QUANTIZED)
+        && encoding == VERTEX_ENCODING_QUANTIZED) {
+        // 32-byte header with bounds followed by 3 x unorm16 per vertex
+        vec3 origin = vertex_data_read_vec3_at(buffer_index, byte_offset, 0);
+        vec3 extent = vertex_data_read_vec3_at(buffer_index, byte_offset + 16, 0);
+        uint xy = vertex_data_read_uint_at(buffer_index, byte_offset + 32, gl_VertexIndex * 2);
+        uint z = vertex_data_read_uint_at(buffer_index, byte_offset + 32, gl_VertexIndex * 2 + 1);
+        return origin + extent * vec3(unpackUnorm2x16(xy), unpackUnorm2x16(z).x);
+    }
+    return vertex_data_read_vec3(buffer_index, byte_offset);
 }
 
+vec3 vertex_read_direction(uint buffer_index, uint offset, uint encodings) {
+    uint encoding = offset & VERTEX_ENCODING_MASK;
+    uint byte_offset = offset & ~VERTEX_ENCODING_MASK;
+
+    if (VERTEX_ENCODING_ENABLED(encodings, VERTEX_ENCODING_OCTAHEDRAL)
+        && encoding == VERTEX_ENCODING_OCTAHEDRAL) {
+        uint value = vertex_data_read_uint_at(buffer_index, byte_offset, gl_VertexIndex);
+        return octahedral_decode(unpackSnorm2x16(value));
+    }
+    return vertex_data_read_vec3(buffer_index, byte_offset);
+}
+
+vec2 vertex_read_uv(uint buffer_index, uint offset, uint encodings) {
+    uint encoding = offset & VERTEX_ENCODING_MASK;
+    uint byte_offset = offset & ~VERTEX_ENCODING_MASK;
+
+    if (VERTEX_ENCODING_ENABLED(encodings, VERTEX_ENCODING_HALF)
+        && encoding == VERTEX_ENCODING_HALF) {
+        return unpackHalf2x16(vertex_data_read_uint_at(buffer_index, byte_offset, gl_VertexIndex));
+    }
+    return vertex_data_read_vec2(buffer_index, byte_offset);
+}
+
+vec4 vertex_read_color(uint buffer_index, uint offset, uint encodings) {
+    uint encoding = offset & VERTEX_ENCODING_MASK;
+    uint byte_offset = offset & ~VERTEX_ENCODING_MASK;
+
+    if (VERTEX_ENCODING_ENABLED(encodings, VERTEX_ENCODING_UNORM8)
+        && encoding == VERTEX_ENCODING_UNORM8) {
+        return unpackUnorm4x8(vertex_data_read_uint_at(buffer_index, byte_offset, gl_VertexIndex));
+    }
+    return vertex_data_read_vec4(buffer_index, byte_offset);
+}
+
+// Generated `Vertex` struct and `vertex_read` for all attribute kinds
+#include "./vertex.glsl"
+
 #endif // VERTEX_ATTR_COUNT
 
 #endif // UNIFORMS_OBJECT_GLSL
@@ -0,0 +1,29 @@
+#ifndef UNIFORMS_SKIN_GLSL
+#define UNIFORMS_SKIN_GLSL
+
+#include "./bindless.glsl"
+#include "./object.glsl"
+
+BINDLESS_SBO_RO(std430, mat4, u_joint_matrices);
+
+#if defined(VERTEX_JOINTS) && defined(VERTEX_WEIGHTS)
+// NOTE: `skin.x` is a joint buffer index, `skin.y` is the first joint
+// of the object and `skin.z` is the number of joints.
+mat4 skin_matrix(ObjectData object_data, Vertex vertex) {
+    uint joint_count = object_data.skin.z;
+    if (joint_count == 0 || object_data.offsets[VERTEX_WEIGHTS] == VERTEX_ATTR_MISSING) {
+        return mat4(1.0);
+    }
+
+    uint buffer_index = object_data.skin.x;
+    uint first_joint = object_data.skin.y;
+    uvec4 joints = min(vertex.joints, uvec4(joint_count - 1)) + first_joint;
+
+    return vertex.weights.x * u_joint_matrices[buffer_index].items[joints.x]
+        + vertex.weights.y * u_joint_matrices[buffer_index].items[joints.y]
+        + vertex.weights.z * u_joint_matrices[buffer_index].items[joints.z]
+        + vertex.weights.w * u_joint_matrices[buffer_index].items[joints.w];
+}
+#endif
+
+#endif // UNIFORMS_SKIN_GLSL
@@ -0,0 +1,178 @@
+#!/usr/bin/env python3
+"""Generates the SMAA lookup textures as raw texel data.
+
+Ported from the reference generators by Jorge Jimenez et al.
+(`Scripts/AreaTex.py` and `Scripts/SearchTex.py` of https://github.com/iryoku/smaa).
+
+Differences from the reference textures:
+- Only the orthogonal areas without subsample offsets are generated (SMAA 1x
+  with diagonal detection disabled), so the area texture is 80x80.
+- The search texture is neither cropped nor flipped, it is read with
+  `texelFetch` using the fetched edges scaled by 32 as coordinates.
+
+Usage: python3 generate.py
+"""
+
+import math
+import os
+
+# Maximum (compressed) distance stored per pattern of the area texture
+SIZE_ORTHO = 16
+# Number of distinct bilinearly fetched edge values per side of the area texture
+ORTHO_SLOTS = 5
+# Maximum distance for smoothing u-shapes
+SMOOTH_MAX_DISTANCE = 32
+
+# Slot of the crossing edges for each pattern: `left = 3 * bottom + top`
+EDGES_ORTHO = [
+    (0, 0), (3, 0), (0, 3), (3, 3), (1, 0), (4, 0), (1, 3), (4, 3),
+    (0, 1), (3, 1), (0, 4), (3, 4), (1, 1), (4, 1), (1, 4), (4, 4),
+]
+
+
+def lerp(a, b, p):
+    return a + (b - a) * p
+
+
+def saturate(a):
+    return min(max(a, 0.0), 1.0)
+
+
+def smootharea(d, a1, a2):
+    """Smooths u-shaped patterns, which are otherwise revectorized into triangles."""
+    b1 = tuple(math.sqrt(a * 2.0) * 0.5 for a in a1)
+    b2 = tuple(math.sqrt(a * 2.0) * 0.5 for a in a2)
+    p = saturate(d / float(SMOOTH_MAX_DISTANCE))
+    return tuple(lerp(b1[i], a1[i], p) + lerp(b2[i], a2[i], p) for i in range(2))
+
+
+def area(p1, p2, x):
+    """Area under the line `p1 -> p2` for the pixel `x..x + 1`, split by the line sign."""
+    d = (p2[0] - p1[0], p2[1] - p1[1])
+    x1 = float(x)
+    x2 = x + 1.0
+    y1 = p1[1] + d[1] * (x1 - p1[0]) / d[0]
+    y2 = p1[1] + d[1] * (x2 - p1[0]) / d[0]
+
+    inside = (p1[0] <= x1 < p2[0]) or (p1[0] < x2 <= p2[0])
+    if not inside:
+        return (0.0, 0.0)
+
+    is_trapezoid = (
+        math.copysign(1.0, y1) == math.copysign(1.0, y2) or abs(y1) < 1e-4 or abs(y2) < 1e-4
+    )
+    if is_trapezoid:
+        a = (y1 + y2) / 2.0
+        return (abs(a), 0.0) if a < 0.0 else (0.0, abs(a))
+
+    # The line crosses the pixel, so there are two triangles
+    x = -p1[1] * d[0] / d[1] + p1[0]
+    a1 = y1 * math.modf(x)[0] / 2.0 if x > p1[0] else 0.0
+    a2 = y2 * (1.0 - math.modf(x)[0]) / 2.0 if x < p2[0] else 0.0
+    a = a1 if abs(a1) > abs(a2) else -a2
+    return (abs(a1), abs(a2)) if a < 0.0 else (abs(a2), abs(a1))
+
+
+def areaortho(pattern, left, right):
+    """Area for a pattern and the distances to both of its ends."""
+    d = left + right + 1
+    o1 = 0.5
+    o2 = -0.5
+
+    if pattern == 1:
+        return area((0.0, o2), (d / 2.0, 0.0), left) if left <= right else (0.0, 0.0)
+    if pattern == 2:
+        return area((d / 2.0, 0.0), (d, o2), left) if left >= right else (0.0, 0.0)
+    if pattern == 3:
+        a1 = area((0.0, o2), (d / 2.0, 0.0), left)
+        a2 = area((d / 2.0, 0.0), (d, o2), left)
+        return smootharea(d, a1, a2)
+    if pattern == 4:
+        return area((0.0, o1), (d / 2.0, 0.0), left) if left <= right else (0.0, 0.0)
+    if pattern in (6, 7, 14):
+        return area((0.0, o1), (d, o2), left)
+    if pattern == 8:
+        return area((d / 2.0, 0.0), (d, o1), left) if left >= right else (0.0, 0.0)
+    if pattern in (9, 11, 13):
+        return area((0.0, o2), (d, o1), left)
+    if pattern == 12:
+        a1 = area((0.0, o1), (d / 2.0, 0.0), left)
+        a2 = area((d / 2.0, 0.0), (d, o1), left)
+        return smootharea(d, a1, a2)
+    # Straight lines (0) and crossings on both sides (5, 10, 15) are not filtered
+    return (0.0, 0.0)
+
+
+def area_texture():
+    width = SIZE_ORTHO * ORTHO_SLOTS
+    data = bytearray(width * width * 2)
+    for pattern, (e1, e2) in enumerate(EDGES_ORTHO):
+        for y in range(SIZE_ORTHO):
+            for x in range(SIZE_ORTHO):
+                # NOTE: Distances are stored sqrt-compressed
+                a = areaortho(pattern, x * x, y * y)
+                offset = ((e2 * SIZE_ORTHO + y) * width + e1 * SIZE_ORTHO + x) * 2
+                data[offset] = round(saturate(a[0]) * 255.0)
+                data[offset + 1] = round(saturate(a[1]) * 255.0)
+    return data
+
+
+def bilinear(e):
+    """Value of a bilinear fetch at (-0.25, -0.125) between four edges.
+
+    e[0]       e[1]
+
+             x <-------- Sample position:    (-0.25,-0.125)
+    e[2]       e[3] <--- Current pixel [3]:  (  0.0, 0.0  )
+    """
+    a = lerp(e[0], e[1], 1.0 - 0.25)
+    b = lerp(e[2], e[3], 1.0 - 0.25)
+    return lerp(a, b, 1.0 - 0.125)
+
+
+def delta_left(left, top):
+    d = 0
+    # If there is an edge, continue
+    if top[3] == 1:
+        d += 1
+    # If we previously found an edge, there is another edge and no crossing edges, continue
+    if d == 1 and top[2] == 1 and left[1] != 1 and left[3] != 1:
+        d += 1
+    return d
+
+
+def delta_right(left, top):
+    d = 0
+    # If there is an edge and no crossing edges, continue
+    if top[3] == 1 and left[1] != 1 and left[3] != 1:
+        d += 1
+    # If we previously found an edge, there is another edge and no crossing edges, continue
+    if d == 1 and top[2] == 1 and left[0] != 1 and left[2] != 1:
+        d += 1
+    return d
+
+
+def search_texture():
+    # Reverse lookup of the bilinear fetch, values are multiples of 1/32
+    edges = {}
+    for i in range(16):
+        e = [(i >> bit) & 1 for bit in range(4)]
+        edges[round(bilinear(e) * 32.0)] = e
+
+    # Left searches take the first 33 columns, right searches the rest
+    width = 66
+    data = bytearray(width * 33)
+    for y in range(33):
+        for x in range(33):
+            if x in edges and y in edges:
+                data[y * width + x] = 127 * delta_left(edges[x], edges[y])
+                data[y * width + x + 33] = 127 * delta_right(edges[x], edges[y])
+    return data
+
+
+if __name__ == "__main__":
+    directory = os.path.dirname(os.path.abspath(__file__))
+    with open(os.path.join(directory, "area.bin"), "wb") as file:
+        file.write(area_texture())
+    with open(os.path.join(directory, "search.bin"), "wb") as file:
+        file.write(search_texture())
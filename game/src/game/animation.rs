@@ -0,0 +1,272 @@
+use ecs::components::Transform;
+use glam::{Quat, Vec3};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    Step,
+    /// Keyframe values are stored as `[in_tangent, value, out_tangent]` triples.
+    CubicSpline,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChannelValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationChannel {
+    /// Index of the animated joint in the skeleton.
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    /// Keyframe times in seconds (sorted).
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+impl AnimationChannel {
+    /// Writes the channel value at the specified time into the joint pose.
+    pub fn sample(&self, time: f32, pose: &mut Transform) {
+        let Some(keyframe) = Keyframe::find(&self.times, time) else {
+            return;
+        };
+
+        match &self.values {
+            ChannelValues::Translation(values) => {
+                if let Some(value) = self.sample_values(values, &keyframe, Vec3::lerp) {
+                    pose.translation = value;
+                }
+            }
+            ChannelValues::Rotation(values) => {
+                if let Some(value) = self.sample_values(values, &keyframe, Quat::slerp) {
+                    pose.rotation = value.normalize();
+                }
+            }
+            ChannelValues::Scale(values) => {
+                if let Some(value) = self.sample_values(values, &keyframe, Vec3::lerp) {
+                    pose.scale = value;
+                }
+            }
+        }
+    }
+
+    fn sample_values<T>(
+        &self,
+        values: &[T],
+        keyframe: &Keyframe,
+        lerp: fn(T, T, f32) -> T,
+    ) -> Option<T>
+    where
+        T: Copy + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+    {
+        let Keyframe { prev, next, t, dt } = *keyframe;
+        match self.interpolation {
+            Interpolation::Step => values.get(prev).copied(),
+            Interpolation::Linear => Some(lerp(*values.get(prev)?, *values.get(next)?, t)),
+            Interpolation::CubicSpline => {
+                let value = |i: usize, offset: usize| values.get(i * 3 + offset).copied();
+                let p0 = value(prev, 1)?;
+                if prev == next {
+                    return Some(p0);
+                }
+                let m0 = value(prev, 2)? * dt;
+                let p1 = value(next, 1)?;
+                let m1 = value(next, 0)? * dt;
+
+                // Hermite spline
+                let t2 = t * t;
+                let t3 = t2 * t;
+                Some(
+                    p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+                        + m0 * (t3 - 2.0 * t2 + t)
+                        + p1 * (-2.0 * t3 + 3.0 * t2)
+                        + m1 * (t3 - t2),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    /// Clip duration in seconds.
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: Option<String>, channels: Vec<AnimationChannel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+
+        Self {
+            name,
+            duration,
+            channels,
+        }
+    }
+
+    /// Applies all channels at the specified time to the pose.
+    ///
+    /// Joints which are not animated by this clip are left untouched.
+    pub fn sample(&self, time: f32, pose: &mut [Transform]) {
+        for channel in &self.channels {
+            if let Some(joint) = pose.get_mut(channel.joint) {
+                channel.sample(time, joint);
+            }
+        }
+    }
+}
+
+/// Blends `other` pose into `pose` with the specified weight.
+pub fn blend_poses(pose: &mut [Transform], other: &[Transform], weight: f32) {
+    for (joint, other) in std::iter::zip(pose, other) {
+        joint.translation = joint.translation.lerp(other.translation, weight);
+        joint.scale = joint.scale.lerp(other.scale, weight);
+
+        // Take the shortest path
+        let mut rotation = other.rotation;
+        if joint.rotation.dot(rotation) < 0.0 {
+            rotation = -rotation;
+        }
+        joint.rotation = joint.rotation.lerp(rotation, weight).normalize();
+    }
+}
+
+struct Keyframe {
+    prev: usize,
+    next: usize,
+    /// Normalized time between keyframes.
+    t: f32,
+    /// Duration between keyframes.
+    dt: f32,
+}
+
+impl Keyframe {
+    fn find(times: &[f32], time: f32) -> Option<Self> {
+        let last = times.len().checked_sub(1)?;
+
+        let next = times.partition_point(|&t| t <= time);
+        Some(if next == 0 {
+            Self::exact(0)
+        } else if next > last {
+            Self::exact(last)
+        } else {
+            let prev = next - 1;
+            let dt = times[next] - times[prev];
+            Self {
+                prev,
+                next,
+                t: if dt > 0.0 {
+                    (time - times[prev]) / dt
+                } else {
+                    0.0
+                },
+                dt,
+            }
+        })
+    }
+
+    fn exact(index: usize) -> Self {
+        Self {
+            prev: index,
+            next: index,
+            t: 0.0,
+            dt: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation_channel(interpolation: Interpolation, values: Vec<Vec3>) -> AnimationChannel {
+        AnimationChannel {
+            joint: 0,
+            interpolation,
+            times: vec![0.0, 1.0, 3.0],
+            values: ChannelValues::Translation(values),
+        }
+    }
+
+    fn sample_translation(channel: &AnimationChannel, time: f32) -> Vec3 {
+        let mut pose = Transform::IDENTITY;
+        channel.sample(time, &mut pose);
+        pose.translation
+    }
+
+    #[test]
+    fn linear_and_step_sampling() {
+        let values = vec![Vec3::ZERO, Vec3::X, Vec3::new(3.0, 0.0, 0.0)];
+
+        let linear = translation_channel(Interpolation::Linear, values.clone());
+        assert_eq!(sample_translation(&linear, -1.0), Vec3::ZERO);
+        assert_eq!(sample_translation(&linear, 0.5), Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(sample_translation(&linear, 2.0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(sample_translation(&linear, 10.0), Vec3::new(3.0, 0.0, 0.0));
+
+        let step = translation_channel(Interpolation::Step, values);
+        assert_eq!(sample_translation(&step, 0.5), Vec3::ZERO);
+        assert_eq!(sample_translation(&step, 1.0), Vec3::X);
+        assert_eq!(sample_translation(&step, 2.9), Vec3::X);
+    }
+
+    #[test]
+    fn cubic_spline_passes_through_keyframes() {
+        let mut values = Vec::new();
+        for value in [Vec3::ZERO, Vec3::X, Vec3::Y] {
+            values.extend([Vec3::ZERO, value, Vec3::Z]);
+        }
+        let channel = translation_channel(Interpolation::CubicSpline, values);
+
+        assert!(sample_translation(&channel, 0.0).abs_diff_eq(Vec3::ZERO, 1e-6));
+        assert!(sample_translation(&channel, 1.0).abs_diff_eq(Vec3::X, 1e-6));
+        assert!(sample_translation(&channel, 3.0).abs_diff_eq(Vec3::Y, 1e-6));
+
+        // Tangents affect the value between keyframes
+        let middle = sample_translation(&channel, 0.5);
+        assert!((middle.x - 0.5).abs() < 1e-6);
+        assert!(middle.z > 0.0);
+    }
+
+    #[test]
+    fn clip_duration_and_blending() {
+        let clip = AnimationClip::new(
+            None,
+            vec![AnimationChannel {
+                joint: 1,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, 2.0],
+                values: ChannelValues::Rotation(vec![
+                    Quat::IDENTITY,
+                    Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+                ]),
+            }],
+        );
+        assert_eq!(clip.duration, 2.0);
+
+        let mut pose = vec![Transform::IDENTITY; 2];
+        clip.sample(1.0, &mut pose);
+        assert_eq!(pose[0], Transform::IDENTITY);
+        assert!(pose[1]
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4), 1e-5));
+
+        let mut blended = vec![Transform::IDENTITY; 2];
+        blend_poses(&mut blended, &pose, 1.0);
+        assert!(blended[1].rotation.abs_diff_eq(pose[1].rotation, 1e-5));
+
+        let other = vec![Transform::from_translation(Vec3::X); 2];
+        blend_poses(&mut blended, &other, 0.25);
+        assert!(blended[0]
+            .translation
+            .abs_diff_eq(Vec3::new(0.25, 0.0, 0.0), 1e-6));
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use bevy_ecs::component::Component;
+use ecs::components::Transform;
+use glam::Mat4;
+
+use crate::game::animation::{blend_poses, AnimationClip};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkeletonJoint {
+    /// Index of the parent joint in the skeleton.
+    pub parent: Option<usize>,
+    /// Local transform which is used when the joint is not animated.
+    pub rest: Transform,
+    pub inverse_bind: Mat4,
+    /// Transform of the joint parent space relative to the skinned mesh.
+    /// Only used for joints without a parent joint.
+    pub base: Mat4,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct Skeleton {
+    joints: Vec<SkeletonJoint>,
+    /// Joint indices sorted so that parents are evaluated before children.
+    order: Vec<usize>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<SkeletonJoint>) -> Self {
+        let mut order = Vec::with_capacity(joints.len());
+        let mut visited = vec![false; joints.len()];
+        for i in 0..joints.len() {
+            let mut path = Vec::new();
+            let mut joint = Some(i);
+            while let Some(j) = joint {
+                if visited[j] {
+                    break;
+                }
+                visited[j] = true;
+                path.push(j);
+                joint = joints[j].parent;
+            }
+            order.extend(path.into_iter().rev());
+        }
+
+        Self { joints, order }
+    }
+
+    pub fn rest_pose(&self) -> Vec<Transform> {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+    /// Computes skinning matrices for the specified local joint poses.
+    pub fn compute_joint_matrices(&self, pose: &[Transform]) -> Vec<Mat4> {
+        let mut globals = vec![Mat4::IDENTITY; self.joints.len()];
+        for &i in &self.order {
+            let joint = &self.joints[i];
+            let parent = match joint.parent {
+                Some(parent) => globals[parent],
+                None => joint.base,
+            };
+            let local = pose.get(i).unwrap_or(&joint.rest);
+            globals[i] = parent * local.to_matrix();
+        }
+
+        std::iter::zip(globals, &self.joints)
+            .map(|(global, joint)| global * joint.inverse_bind)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationLayer {
+    pub clip: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub weight: f32,
+    pub looping: bool,
+    /// Weight change per second.
+    fade_speed: f32,
+}
+
+#[derive(Debug, Default, Clone, Component)]
+pub struct AnimationPlayer {
+    pub clips: Vec<Arc<AnimationClip>>,
+    layers: Vec<AnimationLayer>,
+}
+
+impl AnimationPlayer {
+    pub fn new(clips: Vec<Arc<AnimationClip>>) -> Self {
+        Self {
+            clips,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn layers(&self) -> &[AnimationLayer] {
+        &self.layers
+    }
+
+    /// Index of the clip which is currently fading in.
+    pub fn current_clip(&self) -> Option<usize> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|layer| layer.fade_speed >= 0.0)
+            .map(|layer| layer.clip)
+    }
+
+    /// Starts playing the clip, crossfading from all other clips
+    /// during the specified duration (in seconds).
+    pub fn play(&mut self, clip: usize, fade_duration: f32) {
+        if clip >= self.clips.len() {
+            tracing::warn!(clip, "animation clip not found");
+            return;
+        }
+        tracing::debug!(name = ?self.clips[clip].name, "play animation clip");
+
+        let fade_speed = if fade_duration > 0.0 {
+            1.0 / fade_duration
+        } else {
+            f32::INFINITY
+        };
+
+        for layer in &mut self.layers {
+            layer.fade_speed = -fade_speed;
+        }
+
+        match self.layers.iter_mut().find(|layer| layer.clip == clip) {
+            Some(layer) => layer.fade_speed = fade_speed,
+            None => self.layers.push(AnimationLayer {
+                clip,
+                time: 0.0,
+                speed: 1.0,
+                weight: if self.layers.is_empty() { 1.0 } else { 0.0 },
+                looping: true,
+                fade_speed,
+            }),
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            let duration = self.clips[layer.clip].duration;
+            layer.time += dt * layer.speed;
+            if layer.looping && duration > 0.0 {
+                layer.time = layer.time.rem_euclid(duration);
+            } else {
+                layer.time = layer.time.clamp(0.0, duration);
+            }
+
+            layer.weight = (layer.weight + layer.fade_speed * dt).clamp(0.0, 1.0);
+        }
+
+        // Remove faded out layers
+        self.layers
+            .retain(|layer| layer.fade_speed >= 0.0 || layer.weight > 0.0);
+    }
+
+    /// Samples and blends all active clips.
+    pub fn sample(&self, skeleton: &Skeleton) -> Vec<Transform> {
+        let rest_pose = skeleton.rest_pose();
+
+        let mut pose = rest_pose.clone();
+        let mut layer_pose = rest_pose.clone();
+        let mut total_weight = 0.0;
+        for layer in &self.layers {
+            if layer.weight <= 0.0 {
+                continue;
+            }
+            total_weight += layer.weight;
+
+            layer_pose.copy_from_slice(&rest_pose);
+            self.clips[layer.clip].sample(layer.time, &mut layer_pose);
+            blend_poses(&mut pose, &layer_pose, layer.weight / total_weight);
+        }
+
+        pose
+    }
+}
@@ -1,5 +1,11 @@
+pub use self::animation::{AnimationPlayer, Skeleton, SkeletonJoint};
 pub use self::camera::Camera;
 pub use self::mesh_instance::{DynamicMeshInstance, StaticMeshInstance};
+pub use self::morph_weights::MorphWeights;
+pub use self::terrain::{Terrain, TerrainSettings};
 
+mod animation;
 mod camera;
 mod mesh_instance;
+mod morph_weights;
+mod terrain;
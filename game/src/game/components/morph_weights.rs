@@ -0,0 +1,7 @@
+use bevy_ecs::component::Component;
+
+/// Morph target weights of a dynamic mesh instance.
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct MorphWeights {
+    pub weights: Vec<f32>,
+}
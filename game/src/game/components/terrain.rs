@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use bevy_ecs::component::Component;
+use glam::{Mat4, Vec2, Vec3};
+use renderer::{
+    Heightmap, HeightmapMeshGenerator, MaterialInstanceHandle, Mesh, MeshHandle, RendererState,
+    StaticObjectHandle,
+};
+
+#[derive(Debug, Clone)]
+pub struct TerrainSettings {
+    /// Chunk size in heightmap samples intervals.
+    pub chunk_size: u32,
+    /// Distance between samples along X and Z.
+    pub spacing: Vec2,
+    pub height_scale: f32,
+    /// Camera distances at which each next LOD is used.
+    /// LOD `i` uses every `2^i`-th sample of the heightmap.
+    pub lod_distances: Vec<f32>,
+    pub skirt_depth: f32,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 32,
+            spacing: Vec2::ONE,
+            height_scale: 1.0,
+            lod_distances: vec![48.0, 96.0, 192.0],
+            skirt_depth: 1.0,
+        }
+    }
+}
+
+pub struct TerrainChunk {
+    /// Chunk center in the terrain space.
+    center: Vec3,
+    lods: Vec<MeshHandle>,
+    lod: usize,
+    handle: StaticObjectHandle,
+}
+
+/// Heightmap terrain split into chunks with distance-based LOD.
+///
+/// Each chunk is a separate static object. Gaps between chunks with
+/// different LODs are hidden by the chunk skirts.
+#[derive(Component)]
+pub struct Terrain {
+    material: MaterialInstanceHandle,
+    lod_distances: Vec<f32>,
+    chunks: Vec<TerrainChunk>,
+}
+
+impl Terrain {
+    pub fn new(
+        renderer: &Arc<RendererState>,
+        heightmap: &Heightmap,
+        settings: &TerrainSettings,
+        material: MaterialInstanceHandle,
+        transform: &Mat4,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            settings.chunk_size > 0,
+            "terrain chunk size must not be zero"
+        );
+
+        let lod_count = settings.lod_distances.len() + 1;
+        let size = settings.chunk_size;
+
+        let mut chunks = Vec::new();
+        for z in (0..heightmap.depth() - 1).step_by(size as usize) {
+            for x in (0..heightmap.width() - 1).step_by(size as usize) {
+                let generator = HeightmapMeshGenerator {
+                    offset: [x, z],
+                    size: [size, size],
+                    spacing: settings.spacing,
+                    height_scale: settings.height_scale,
+                    skirt_depth: settings.skirt_depth,
+                    ..HeightmapMeshGenerator::new(heightmap)
+                };
+
+                let lods = (0..lod_count)
+                    .map(|lod| {
+                        let mesh = Mesh::builder(HeightmapMeshGenerator {
+                            step: 1 << lod,
+                            ..generator
+                        })
+                        .build()?;
+                        renderer.add_mesh(&mesh)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                let (center_x, center_z) = (
+                    (x + size / 2).min(heightmap.width() - 1),
+                    (z + size / 2).min(heightmap.depth() - 1),
+                );
+                let center = Vec3::new(
+                    center_x as f32 * settings.spacing.x,
+                    heightmap.get(center_x as i64, center_z as i64) * settings.height_scale,
+                    center_z as f32 * settings.spacing.y,
+                );
+
+                // Start with the coarsest LOD until the camera is known
+                let lod = lod_count - 1;
+                let handle =
+                    renderer.add_static_object(lods[lod].clone(), material.clone(), transform);
+
+                chunks.push(TerrainChunk {
+                    center,
+                    lods,
+                    lod,
+                    handle,
+                });
+            }
+        }
+
+        tracing::debug!(chunks = chunks.len(), lod_count, "created terrain");
+
+        Ok(Self {
+            material,
+            lod_distances: settings.lod_distances.clone(),
+            chunks,
+        })
+    }
+
+    /// Replaces chunk objects whose LOD changed for the specified camera position.
+    pub fn update_lods(
+        &mut self,
+        renderer: &Arc<RendererState>,
+        transform: &Mat4,
+        camera_position: Vec3,
+    ) {
+        for chunk in &mut self.chunks {
+            let distance = transform
+                .transform_point3(chunk.center)
+                .distance(camera_position);
+
+            let lod = select_lod(&self.lod_distances, distance);
+            if lod != chunk.lod {
+                // NOTE: The previous object is removed when its handle is dropped
+                chunk.handle = renderer.add_static_object(
+                    chunk.lods[lod].clone(),
+                    self.material.clone(),
+                    transform,
+                );
+                chunk.lod = lod;
+            }
+        }
+    }
+
+    pub fn update_transform(&self, renderer: &Arc<RendererState>, transform: &Mat4) {
+        for chunk in &self.chunks {
+            renderer.update_static_object(&chunk.handle, *transform);
+        }
+    }
+}
+
+fn select_lod(lod_distances: &[f32], distance: f32) -> usize {
+    lod_distances.partition_point(|&lod_distance| lod_distance <= distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lod_selection() {
+        let lod_distances = [10.0, 20.0, 40.0];
+        assert_eq!(select_lod(&lod_distances, 0.0), 0);
+        assert_eq!(select_lod(&lod_distances, 9.9), 0);
+        assert_eq!(select_lod(&lod_distances, 10.0), 1);
+        assert_eq!(select_lod(&lod_distances, 39.0), 2);
+        assert_eq!(select_lod(&lod_distances, 1000.0), 3);
+        assert_eq!(select_lod(&[], 1000.0), 0);
+    }
+}
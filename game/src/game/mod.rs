@@ -1,20 +1,28 @@
 use std::path::Path;
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use bevy_ecs::prelude::*;
 use bevy_ecs::schedule::ScheduleLabel;
 use ecs::components::Transform;
-use glam::{Mat4, Vec2, Vec3};
+use glam::{Mat4, Quat, UVec4, Vec2, Vec3, Vec4};
 use rand::Rng;
 use renderer::materials::DebugMaterialInstance;
-use renderer::RendererState;
-use winit::event::WindowEvent;
-
-use self::components::{Camera, DynamicMeshInstance, StaticMeshInstance};
+use renderer::{
+    DebugView, EnvironmentMap, Heightmap, ObjScene, PickRequest, PickedObject, RendererState,
+};
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+use self::animation::{AnimationChannel, AnimationClip, ChannelValues, Interpolation};
+use self::components::{
+    AnimationPlayer, Camera, DynamicMeshInstance, MorphWeights, Skeleton, SkeletonJoint,
+    StaticMeshInstance, Terrain, TerrainSettings,
+};
 use self::resources::{Graphics, MainCamera, Time};
 
+mod animation;
 mod components;
 mod resources;
 
@@ -23,6 +31,9 @@ pub struct Game {
     fixed_update_schedule: Schedule,
     draw_schedule: Schedule,
     minimized: bool,
+    /// Last cursor position in physical pixels.
+    cursor_position: Option<(u32, u32)>,
+    pick_requests: Vec<PickRequest>,
 }
 
 impl Game {
@@ -39,12 +50,18 @@ impl Game {
         world.insert_resource(Graphics::new(renderer)?);
 
         let mut fixed_update_schedule = FixedUpdateSchedule::base_schedule();
-        fixed_update_schedule.add_systems(rotate_objects_system.in_set(FixedUpdateSet::OnUpdate));
+        fixed_update_schedule.add_systems(
+            (rotate_objects_system, advance_animation_players_system)
+                .in_set(FixedUpdateSet::OnUpdate),
+        );
         fixed_update_schedule.add_systems(
             (
                 (
                     apply_static_objects_transform_system,
                     apply_dynamic_objects_transform_system,
+                    apply_skinned_objects_joints_system,
+                    apply_morph_weights_system,
+                    update_terrain_lods_system,
                 ),
                 sync_fixed_update_system,
             )
@@ -71,6 +88,8 @@ impl Game {
             fixed_update_schedule,
             draw_schedule,
             minimized: false,
+            cursor_position: None,
+            pick_requests: Vec::new(),
         })
     }
 
@@ -104,6 +123,22 @@ impl Game {
                         .set_running(false);
                     elwt.exit();
                 }
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.cursor_position = Some((position.x as u32, position.y as u32));
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    self.cursor_position = None;
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    if let Some((x, y)) = self.cursor_position {
+                        let renderer = &self.world.resource::<Graphics>().renderer;
+                        self.pick_requests.push(renderer.pick(x, y));
+                    }
+                }
                 WindowEvent::KeyboardInput { event, .. } => {
                     use winit::keyboard::{KeyCode, PhysicalKey};
 
@@ -117,6 +152,22 @@ impl Game {
                             self.spawn_cube();
                             tracing::info!("added test object");
                         }
+                        KeyCode::KeyN => {
+                            self.play_next_animation();
+                        }
+                        KeyCode::F1 => self.set_debug_view(DebugView::None),
+                        KeyCode::F2 => self.set_debug_view(DebugView::Wireframe),
+                        KeyCode::F3 => self.set_debug_view(DebugView::Normals),
+                        KeyCode::F4 => self.set_debug_view(DebugView::UvCheckerboard),
+                        KeyCode::F5 => self.set_debug_view(DebugView::Overdraw),
+                        KeyCode::F6 => self.set_debug_view(DebugView::ObjectColor),
+                        KeyCode::F7 => self.set_debug_view(DebugView::CullingStatus),
+                        KeyCode::F8 => self.set_debug_view(DebugView::MaterialSlot),
+                        KeyCode::KeyV => {
+                            let debug_view =
+                                self.world.resource::<Graphics>().renderer.next_debug_view();
+                            self.set_debug_view(debug_view);
+                        }
                         _ => {}
                     }
                 }
@@ -141,6 +192,8 @@ impl Game {
             self.fixed_update_schedule.run(&mut self.world);
         }
 
+        self.poll_pick_requests();
+
         if redraw_requested {
             self.draw_schedule.run(&mut self.world);
             self.world.resource::<Graphics>().renderer.notify_draw();
@@ -156,25 +209,142 @@ impl Game {
 
         let renderer = self.world.resource::<Graphics>().renderer.clone();
 
+        // Collect global transforms and parents of all scene nodes first,
+        // since skins can reference nodes from other branches.
+        let mut nodes = GltfNodes {
+            globals: vec![Mat4::IDENTITY; gltf.nodes().len()],
+            parents: vec![None; gltf.nodes().len()],
+        };
+        let mut scene_nodes = Vec::new();
+
         let mut stack = Vec::new();
         for node in scene.nodes() {
-            stack.push((node.children(), Mat4::IDENTITY, Some(node)));
+            nodes.globals[node.index()] = Mat4::from_cols_array_2d(&node.transform().matrix());
+            stack.push((node.children(), node.index(), Some(node)));
 
-            while let Some((children, transform, node)) = stack.last_mut() {
+            while let Some((children, index, node)) = stack.last_mut() {
                 if let Some(node) = node.take() {
-                    process_gltf_node(node, &buffers, transform, &mut self.world, &renderer)?;
+                    scene_nodes.push(node);
                 }
 
                 if let Some(child) = children.next() {
-                    let child_transform =
-                        transform.mul_mat4(&Mat4::from_cols_array_2d(&child.transform().matrix()));
-                    stack.push((child.children(), child_transform, Some(child)));
+                    let parent = *index;
+                    nodes.globals[child.index()] = nodes.globals[parent]
+                        .mul_mat4(&Mat4::from_cols_array_2d(&child.transform().matrix()));
+                    nodes.parents[child.index()] = Some(parent);
+                    stack.push((child.children(), child.index(), Some(child)));
                 } else {
                     stack.pop();
                 }
             }
         }
 
+        for node in scene_nodes {
+            let global_transform = nodes.globals[node.index()];
+            process_gltf_node(
+                &gltf,
+                node,
+                &buffers,
+                &nodes,
+                &global_transform,
+                &mut self.world,
+                &renderer,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // TEMP
+    pub fn load_obj(&mut self, path: &Path) -> Result<()> {
+        let scene = ObjScene::load(path)?;
+
+        let renderer = self.world.resource::<Graphics>().renderer.clone();
+
+        let materials = scene
+            .materials
+            .iter()
+            .map(|material| renderer.add_material_instance(DebugMaterialInstance::from(material)))
+            .collect::<Vec<_>>();
+        let default_material =
+            renderer.add_material_instance(DebugMaterialInstance { color: Vec3::ONE });
+
+        let transform = Transform::IDENTITY;
+        for obj_mesh in scene.meshes {
+            let mesh = renderer.add_mesh(&obj_mesh.mesh)?;
+            let material = match obj_mesh.material {
+                Some(material) => materials[material].clone(),
+                None => default_material.clone(),
+            };
+
+            let handle =
+                renderer.add_static_object(mesh.clone(), material.clone(), &transform.to_matrix());
+
+            self.world.spawn((
+                transform,
+                StaticMeshInstance {
+                    mesh,
+                    material,
+                    handle,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    // TEMP
+    pub fn load_heightmap(&mut self, path: &Path) -> Result<()> {
+        let image = image::open(path)
+            .with_context(|| format!("failed to open heightmap {}", path.display()))?
+            .into_luma16();
+        let heightmap = Heightmap::from_luma16(image.width(), image.height(), image.as_raw())?;
+
+        let settings = TerrainSettings {
+            height_scale: 16.0,
+            ..Default::default()
+        };
+
+        // Center the terrain below the origin
+        let extent = Vec2::new(
+            (heightmap.width() - 1) as f32 * settings.spacing.x,
+            (heightmap.depth() - 1) as f32 * settings.spacing.y,
+        );
+        let transform = Transform::from_translation(Vec3::new(
+            -extent.x * 0.5,
+            -settings.height_scale - 1.0,
+            -extent.y * 0.5,
+        ));
+
+        let graphics = self.world.resource::<Graphics>();
+        let material = graphics
+            .renderer
+            .add_material_instance(DebugMaterialInstance {
+                color: Vec3::new(0.4, 0.6, 0.3),
+            });
+
+        let terrain = Terrain::new(
+            &graphics.renderer,
+            &heightmap,
+            &settings,
+            material,
+            &transform.to_matrix(),
+        )?;
+
+        self.world.spawn((transform, terrain));
+        Ok(())
+    }
+
+    // TEMP
+    pub fn load_environment(&mut self, path: &Path) -> Result<()> {
+        let image = image::open(path)
+            .with_context(|| format!("failed to open environment {}", path.display()))?
+            .into_rgb32f();
+        let environment =
+            EnvironmentMap::from_rgb32f(image.width(), image.height(), image.as_raw())?;
+
+        let graphics = self.world.resource::<Graphics>();
+        graphics.renderer.set_environment(Some(environment));
         Ok(())
     }
 
@@ -218,6 +388,71 @@ impl Game {
             },
         });
     }
+
+    pub fn set_debug_view(&mut self, debug_view: DebugView) {
+        let renderer = &self.world.resource::<Graphics>().renderer;
+        if !renderer.is_debug_view_supported(debug_view) {
+            tracing::warn!(?debug_view, "debug view is not supported");
+            return;
+        }
+        renderer.set_debug_view(debug_view);
+        tracing::info!(?debug_view, "debug view changed");
+    }
+
+    /// Logs entities of the resolved pick requests.
+    fn poll_pick_requests(&mut self) {
+        let mut pick_requests = std::mem::take(&mut self.pick_requests);
+        pick_requests.retain(|request| match request.poll() {
+            Poll::Ready(Some(result)) => {
+                let entity = self.find_entity(&result.object);
+                tracing::info!(
+                    ?entity,
+                    object = ?result.object,
+                    position = ?result.position,
+                    "picked object"
+                );
+                false
+            }
+            Poll::Ready(None) => {
+                tracing::info!("nothing picked");
+                false
+            }
+            Poll::Pending => true,
+        });
+        self.pick_requests = pick_requests;
+    }
+
+    fn find_entity(&mut self, object: &PickedObject) -> Option<Entity> {
+        match object {
+            PickedObject::Static(handle) => self
+                .world
+                .query::<(Entity, &StaticMeshInstance)>()
+                .iter(&self.world)
+                .find(|(_, instance)| instance.handle == *handle)
+                .map(|(entity, _)| entity),
+            PickedObject::Dynamic(handle) => self
+                .world
+                .query::<(Entity, &DynamicMeshInstance)>()
+                .iter(&self.world)
+                .find(|(_, instance)| instance.handle == *handle)
+                .map(|(entity, _)| entity),
+        }
+    }
+
+    // TEMP
+    pub fn play_next_animation(&mut self) {
+        let mut query = self.world.query::<&mut AnimationPlayer>();
+        for mut player in query.iter_mut(&mut self.world) {
+            if player.clips.is_empty() {
+                continue;
+            }
+            let next = player
+                .current_clip()
+                .map(|clip| (clip + 1) % player.clips.len())
+                .unwrap_or_default();
+            player.play(next, 0.5);
+        }
+    }
 }
 
 #[derive(Debug, ScheduleLabel, Hash, PartialEq, Eq, Clone)]
@@ -263,9 +498,16 @@ pub enum DrawSet {
     AfterDraw,
 }
 
+struct GltfNodes {
+    globals: Vec<Mat4>,
+    parents: Vec<Option<usize>>,
+}
+
 fn process_gltf_node(
+    gltf: &gltf::Document,
     node: gltf::Node,
     buffers: &[gltf::buffer::Data],
+    nodes: &GltfNodes,
     global_transform: &Mat4,
     ecs_world: &mut World,
     renderer: &Arc<RendererState>,
@@ -274,6 +516,23 @@ fn process_gltf_node(
         return Ok(());
     };
 
+    let mesh_weights = node
+        .weights()
+        .or_else(|| mesh.weights())
+        .map(ToOwned::to_owned)
+        .unwrap_or_default();
+
+    let skin = match node.skin() {
+        Some(skin) => Some(load_gltf_skin(
+            gltf,
+            skin,
+            buffers,
+            nodes,
+            global_transform,
+        )?),
+        None => None,
+    };
+
     for primitive in mesh.primitives() {
         let reader =
             primitive.reader(|buffer| buffers.get(buffer.index()).map(std::ops::Deref::deref));
@@ -303,6 +562,18 @@ fn process_gltf_node(
             reader.read_tex_coords(0).map(|iter| iter.into_f32()),
             vertex_count,
         )?;
+        let uv1 = optional_iter(
+            reader.read_tex_coords(1).map(|iter| iter.into_f32()),
+            vertex_count,
+        )?;
+        let joints = optional_iter(
+            reader.read_joints(0).map(|iter| iter.into_u16()),
+            vertex_count,
+        )?;
+        let weights = optional_iter(
+            reader.read_weights(0).map(|iter| iter.into_f32()),
+            vertex_count,
+        )?;
 
         let mesh = {
             let mut builder = renderer::Mesh::builder(
@@ -334,30 +605,223 @@ fn process_gltf_node(
                         .collect::<Vec<_>>(),
                 );
             }
+            if let Some(uv1) = uv1 {
+                builder = builder.with_uv1(
+                    uv1.map(|[x, y]| renderer::UV1(Vec2::new(x, y)))
+                        .collect::<Vec<_>>(),
+                );
+            }
+
+            if let (Some(joints), Some(weights), Some(_)) = (joints, weights, &skin) {
+                builder = builder
+                    .with_joints(
+                        joints
+                            .map(|[x, y, z, w]| {
+                                renderer::Joints(UVec4::new(x as _, y as _, z as _, w as _))
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .with_weights(
+                        weights
+                            .map(|weights| renderer::Weights(Vec4::from_array(weights)))
+                            .collect::<Vec<_>>(),
+                    );
+            }
+
+            let morph_targets = reader
+                .read_morph_targets()
+                .map(|(positions, normals, _)| {
+                    let positions = positions
+                        .map(|iter| iter.map(Vec3::from_array).collect::<Vec<_>>())
+                        .unwrap_or_else(|| vec![Vec3::ZERO; vertex_count]);
+                    let normals = normals.map(|iter| iter.map(Vec3::from_array).collect());
+                    renderer::MorphTarget { positions, normals }
+                })
+                .collect::<Vec<_>>();
+            if !morph_targets.is_empty() {
+                builder = builder.with_morph_targets(morph_targets);
+            }
 
-            builder.with_indices(indices.into_u32().collect()).build()?
+            builder
+                .with_indices(indices.into_u32().collect())
+                .optimize()
+                .quantized()
+                .build()?
         };
+        if let Some(stats) = mesh.optimization_stats() {
+            tracing::info!(
+                node = node.name(),
+                vertices_before = stats.vertex_count_before,
+                vertices_after = stats.vertex_count_after,
+                acmr_before = stats.acmr_before,
+                acmr_after = stats.acmr_after,
+                "optimized glTF mesh"
+            );
+        }
+        let morph_target_count = mesh.morph_target_count() as usize;
 
         let mesh = renderer.add_mesh(&mesh)?;
         let material = renderer.add_material_instance(renderer::materials::DebugMaterialInstance {
             color: glam::vec3(1.0, 1.0, 1.0),
         });
 
-        let handle = renderer.add_dynamic_object(mesh.clone(), material.clone(), global_transform);
+        // Default weights are applied by the `apply_morph_weights_system`
+        let morph_weights = (morph_target_count > 0).then(|| {
+            let mut weights = mesh_weights.clone();
+            weights.resize(morph_target_count, 0.0);
+            MorphWeights { weights }
+        });
 
-        ecs_world.spawn(SceneObjectBundle {
-            transform: Transform::from_matrix(*global_transform),
-            mesh_instance: DynamicMeshInstance {
-                mesh,
-                material,
-                handle,
+        let Some((skeleton, clips)) = &skin else {
+            let handle =
+                renderer.add_dynamic_object(mesh.clone(), material.clone(), global_transform);
+
+            let mut entity = ecs_world.spawn(SceneObjectBundle {
+                transform: Transform::from_matrix(*global_transform),
+                mesh_instance: DynamicMeshInstance {
+                    mesh,
+                    material,
+                    handle,
+                },
+            });
+            if let Some(morph_weights) = morph_weights {
+                entity.insert(morph_weights);
+            }
+            continue;
+        };
+
+        let handle = renderer.add_skinned_object(
+            mesh.clone(),
+            material.clone(),
+            global_transform,
+            skeleton.compute_joint_matrices(&skeleton.rest_pose()),
+        );
+
+        let mut player = AnimationPlayer::new(clips.clone());
+        if !clips.is_empty() {
+            player.play(0, 0.0);
+        }
+
+        let mut entity = ecs_world.spawn((
+            SceneObjectBundle {
+                transform: Transform::from_matrix(*global_transform),
+                mesh_instance: DynamicMeshInstance {
+                    mesh,
+                    material,
+                    handle,
+                },
             },
-        });
+            skeleton.clone(),
+            player,
+        ));
+        if let Some(morph_weights) = morph_weights {
+            entity.insert(morph_weights);
+        }
     }
 
     Ok(())
 }
 
+fn load_gltf_skin(
+    gltf: &gltf::Document,
+    skin: gltf::Skin,
+    buffers: &[gltf::buffer::Data],
+    nodes: &GltfNodes,
+    mesh_global_transform: &Mat4,
+) -> Result<(Skeleton, Vec<Arc<AnimationClip>>)> {
+    let joint_nodes = skin.joints().map(|node| node.index()).collect::<Vec<_>>();
+    let joint_index = |node: usize| joint_nodes.iter().position(|&joint| joint == node);
+
+    let inverse_bind_matrices = skin
+        .reader(|buffer| buffers.get(buffer.index()).map(std::ops::Deref::deref))
+        .read_inverse_bind_matrices()
+        .map(|iter| {
+            iter.map(|m| Mat4::from_cols_array_2d(&m))
+                .collect::<Vec<_>>()
+        });
+    if let Some(matrices) = &inverse_bind_matrices {
+        anyhow::ensure!(
+            matrices.len() == joint_nodes.len(),
+            "inverse bind matrices length mismatch"
+        );
+    }
+
+    let mesh_inverse = mesh_global_transform.inverse();
+
+    let joints = skin
+        .joints()
+        .enumerate()
+        .map(|(i, node)| {
+            let parent_node = nodes.parents[node.index()];
+            let parent = parent_node.and_then(joint_index);
+            let base = match parent_node {
+                Some(parent_node) if parent.is_none() => mesh_inverse * nodes.globals[parent_node],
+                _ => mesh_inverse,
+            };
+
+            SkeletonJoint {
+                parent,
+                rest: Transform::from_matrix(Mat4::from_cols_array_2d(&node.transform().matrix())),
+                inverse_bind: inverse_bind_matrices
+                    .as_ref()
+                    .map(|matrices| matrices[i])
+                    .unwrap_or(Mat4::IDENTITY),
+                base,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut clips = Vec::new();
+    for animation in gltf.animations() {
+        let mut channels = Vec::new();
+        for channel in animation.channels() {
+            let Some(joint) = joint_index(channel.target().node().index()) else {
+                continue;
+            };
+
+            let reader =
+                channel.reader(|buffer| buffers.get(buffer.index()).map(std::ops::Deref::deref));
+            let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs())
+            else {
+                continue;
+            };
+
+            use gltf::animation::util::ReadOutputs;
+
+            let values = match outputs {
+                ReadOutputs::Translations(iter) => {
+                    ChannelValues::Translation(iter.map(Vec3::from_array).collect())
+                }
+                ReadOutputs::Rotations(iter) => {
+                    ChannelValues::Rotation(iter.into_f32().map(Quat::from_array).collect())
+                }
+                ReadOutputs::Scales(iter) => {
+                    ChannelValues::Scale(iter.map(Vec3::from_array).collect())
+                }
+                ReadOutputs::MorphTargetWeights(_) => continue,
+            };
+
+            channels.push(AnimationChannel {
+                joint,
+                interpolation: match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                    gltf::animation::Interpolation::Step => Interpolation::Step,
+                    gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                },
+                times: inputs.collect(),
+                values,
+            });
+        }
+
+        if !channels.is_empty() {
+            let name = animation.name().map(ToOwned::to_owned);
+            clips.push(Arc::new(AnimationClip::new(name, channels)));
+        }
+    }
+
+    Ok((Skeleton::new(joints), clips))
+}
+
 #[derive(Bundle)]
 struct SceneObjectBundle {
     transform: Transform,
@@ -396,6 +860,60 @@ fn apply_dynamic_objects_transform_system(
     }
 }
 
+fn update_terrain_lods_system(
+    graphics: Res<Graphics>,
+    main_camera: Res<MainCamera>,
+    cameras: Query<&Transform, With<Camera>>,
+    mut terrains: Query<(Ref<Transform>, &mut Terrain), Without<Camera>>,
+) {
+    let Some(camera) = main_camera
+        .entity
+        .and_then(|entity| cameras.get(entity).ok())
+    else {
+        return;
+    };
+
+    for (transform, mut terrain) in &mut terrains {
+        let matrix = transform.to_matrix();
+        if transform.is_changed() && !transform.is_added() {
+            terrain.update_transform(&graphics.renderer, &matrix);
+        }
+        terrain.update_lods(&graphics.renderer, &matrix, camera.translation);
+    }
+}
+
+fn advance_animation_players_system(time: Res<Time>, mut query: Query<&mut AnimationPlayer>) {
+    let dt = time.step.as_secs_f32();
+    for mut player in &mut query {
+        if !player.layers().is_empty() {
+            player.advance(dt);
+        }
+    }
+}
+
+fn apply_skinned_objects_joints_system(
+    graphics: Res<Graphics>,
+    query: Query<(&AnimationPlayer, &Skeleton, &DynamicMeshInstance), Changed<AnimationPlayer>>,
+) {
+    for (player, skeleton, object) in &query {
+        let pose = player.sample(skeleton);
+        graphics
+            .renderer
+            .update_dynamic_object_joints(&object.handle, skeleton.compute_joint_matrices(&pose));
+    }
+}
+
+fn apply_morph_weights_system(
+    graphics: Res<Graphics>,
+    query: Query<(&MorphWeights, &DynamicMeshInstance), Changed<MorphWeights>>,
+) {
+    for (morph_weights, object) in &query {
+        graphics
+            .renderer
+            .update_dynamic_object_morph_weights(&object.handle, morph_weights.weights.clone());
+    }
+}
+
 fn sync_fixed_update_system(time: Res<Time>, graphics: Res<Graphics>) {
     graphics.renderer.finish_fixed_update(time.now, time.step);
 }
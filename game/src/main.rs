@@ -27,9 +27,19 @@ fn main() -> Result<()> {
 #[derive(FromArgs)]
 struct App {
     // TEMP
-    /// glTF file to load
+    /// glTF or OBJ file to load
     #[argh(positional)]
-    gltf_scene: Option<String>,
+    scene: Option<String>,
+
+    // TEMP
+    /// 16-bit grayscale heightmap image to load as a terrain
+    #[argh(option)]
+    heightmap: Option<String>,
+
+    // TEMP
+    /// equirectangular HDR image to use as the skybox and ambient lighting
+    #[argh(option)]
+    environment: Option<String>,
 
     /// enable profiling server
     #[argh(switch)]
@@ -122,8 +132,18 @@ impl App {
 
         let mut game = Box::new(Game::new(renderer.state().clone())?);
 
-        if let Some(gltf_scene_path) = self.gltf_scene {
-            game.load_gltf(gltf_scene_path.as_ref())?;
+        if let Some(scene_path) = self.scene {
+            let scene_path = std::path::Path::new(&scene_path);
+            match scene_path.extension() {
+                Some(ext) if ext.eq_ignore_ascii_case("obj") => game.load_obj(scene_path)?,
+                _ => game.load_gltf(scene_path)?,
+            }
+        }
+        if let Some(heightmap_path) = self.heightmap {
+            game.load_heightmap(heightmap_path.as_ref())?;
+        }
+        if let Some(environment_path) = self.environment {
+            game.load_environment(environment_path.as_ref())?;
         }
 
         tracing::debug!("event loop started");
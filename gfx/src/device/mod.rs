@@ -1,4 +1,5 @@
 use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, Weak};
 
 use bumpalo::Bump;
@@ -25,8 +26,8 @@ use crate::resources::{
     DescriptorSetSize, DescriptorSlice, DescriptorType, Fence, FenceState, Framebuffer,
     FramebufferInfo, GraphicsPipeline, GraphicsPipelineInfo, Image, ImageInfo, ImageView,
     ImageViewInfo, ImageViewType, MemoryBlockMut, MemoryUsage, PipelineLayout, PipelineLayoutInfo,
-    RenderPass, RenderPassInfo, Sampler, SamplerInfo, Semaphore, ShaderModule, ShaderModuleInfo,
-    StencilTest, UpdateDescriptorSet,
+    RenderPass, RenderPassInfo, Sampler, SamplerInfo, Samples, Semaphore, ShaderModule,
+    ShaderModuleInfo, StencilTest, UpdateDescriptorSet,
 };
 use crate::surface::{CreateSurfaceError, Surface, Window};
 use crate::types::{DeviceAddress, DeviceLost, OutOfDeviceMemory, State};
@@ -102,6 +103,7 @@ impl Device {
                 descriptors,
                 samplers_cache: Default::default(),
                 epochs: Epochs::new(queues),
+                live_objects: Default::default(),
             }),
         }
     }
@@ -142,6 +144,16 @@ impl Device {
         self.inner.wait_idle()
     }
 
+    /// Returns the number of objects created by this device which are not destroyed yet.
+    pub fn live_object_counts(&self) -> DeviceObjectCounts {
+        let counters = &self.inner.live_objects;
+        DeviceObjectCounts {
+            buffers: counters.buffers.load(Ordering::Relaxed),
+            images: counters.images.load(Ordering::Relaxed),
+            pipelines: counters.pipelines.load(Ordering::Relaxed),
+        }
+    }
+
     pub fn map_memory(
         &self,
         memory_block: &mut MemoryBlockMut,
@@ -183,6 +195,28 @@ impl Device {
         Ok(())
     }
 
+    /// Reads memory written by the device, invalidating it first if it is not coherent.
+    ///
+    /// Commands which write to the memory must be completed.
+    pub fn download_from_memory<T>(
+        &self,
+        memory_block: &mut MemoryBlockMut,
+        offset: usize,
+        data: &mut [T],
+    ) -> Result<(), MapError>
+    where
+        T: bytemuck::Pod,
+    {
+        unsafe {
+            memory_block.read_bytes(
+                self.logical().as_memory_device(),
+                offset as u64,
+                bytemuck::cast_slice_mut(data),
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn create_semaphore(&self) -> Result<Semaphore, OutOfDeviceMemory> {
         let logical = &self.inner.logical;
 
@@ -440,6 +474,10 @@ impl Device {
         };
 
         tracing::debug!(buffer = ?*handle, "created buffer");
+        self.inner
+            .live_objects
+            .buffers
+            .fetch_add(1, Ordering::Relaxed);
 
         Ok(Buffer::new(
             handle.disarm(),
@@ -462,6 +500,10 @@ impl Device {
             .dealloc(self.logical().as_memory_device(), block);
 
         self.logical().destroy_buffer(handle, None);
+        self.inner
+            .live_objects
+            .buffers
+            .fetch_sub(1, Ordering::Relaxed);
     }
 
     pub fn create_buffer_view(
@@ -504,6 +546,7 @@ impl Device {
 
         let handle = {
             let info = vk::ImageCreateInfo::builder()
+                .flags(info.flags.to_vk())
                 .image_type(info.extent.to_vk())
                 .format(info.format.to_vk())
                 .extent(vk::Extent3D::from_gfx(info.extent))
@@ -567,6 +610,10 @@ impl Device {
             .map_err(OutOfDeviceMemory::on_creation)?;
 
         tracing::debug!(image = ?*handle, "created image");
+        self.inner
+            .live_objects
+            .images
+            .fetch_add(1, Ordering::Relaxed);
 
         Ok(Image::new(handle.disarm(), info, self.downgrade(), block))
     }
@@ -582,7 +629,11 @@ impl Device {
             .unwrap()
             .dealloc(self.logical().as_memory_device(), block);
 
-        self.logical().destroy_image(handle, None)
+        self.logical().destroy_image(handle, None);
+        self.inner
+            .live_objects
+            .images
+            .fetch_sub(1, Ordering::Relaxed);
     }
 
     pub fn create_image_view(&self, info: ImageViewInfo) -> Result<ImageView, OutOfDeviceMemory> {
@@ -700,7 +751,9 @@ impl Device {
         let mut subpasses = SmallVec::<[_; 4]>::with_capacity(info.subpasses.len());
         for (subpass_index, subpass) in info.subpasses.iter().enumerate() {
             let color_offset = subpass_attachments.len();
-            subpass_attachments.reserve(subpass.colors.len() + subpass.depth.is_some() as usize);
+            subpass_attachments.reserve(
+                subpass.colors.len() + subpass.depth.is_some() as usize + subpass.resolves.len(),
+            );
 
             for (color_index, &(i, layout)) in subpass.colors.iter().enumerate() {
                 if i as usize >= info.attachments.len() {
@@ -734,21 +787,52 @@ impl Device {
                 );
             }
 
-            subpasses.push((color_offset, depths_offset));
+            let resolves_offset = subpass_attachments.len();
+            if !subpass.resolves.is_empty() && subpass.resolves.len() != subpass.colors.len() {
+                return Err(CreateRenderPassError::ResolveAttachmentCountMismatch {
+                    resolve_count: subpass.resolves.len(),
+                    color_count: subpass.colors.len(),
+                    subpass_index,
+                });
+            }
+            for (resolve_index, &(i, layout)) in subpass.resolves.iter().enumerate() {
+                if i as usize >= info.attachments.len() {
+                    return Err(CreateRenderPassError::ResolveAttachmentOutOfBounds {
+                        attachment_index: i,
+                        resolve_index,
+                        subpass_index,
+                    });
+                }
+
+                subpass_attachments.push(
+                    vk::AttachmentReference::builder()
+                        .attachment(i)
+                        .layout(layout.to_vk()),
+                );
+            }
+
+            subpasses.push((color_offset, depths_offset, resolves_offset));
         }
         let subpasses = info
             .subpasses
             .iter()
             .zip(subpasses)
-            .map(|(subpass, (color_offset, depths_offset))| {
-                let descr = vk::SubpassDescription::builder()
-                    .color_attachments(&subpass_attachments[color_offset..depths_offset]);
-                if subpass.depth.is_some() {
-                    descr.depth_stencil_attachment(&subpass_attachments[depths_offset])
-                } else {
+            .map(
+                |(subpass, (color_offset, depths_offset, resolves_offset))| {
+                    let mut descr = vk::SubpassDescription::builder()
+                        .color_attachments(&subpass_attachments[color_offset..depths_offset]);
+                    if subpass.depth.is_some() {
+                        descr = descr.depth_stencil_attachment(&subpass_attachments[depths_offset]);
+                    }
+                    if !subpass.resolves.is_empty() {
+                        descr = descr.resolve_attachments(
+                            &subpass_attachments
+                                [resolves_offset..resolves_offset + subpass.resolves.len()],
+                        );
+                    }
                     descr
-                }
-            })
+                },
+            )
             .collect::<Vec<_>>();
 
         let attachments = info
@@ -761,7 +845,7 @@ impl Device {
                     .store_op(info.store_op.to_vk())
                     .initial_layout(info.initial_layout.to_vk())
                     .final_layout(info.final_layout.to_vk())
-                    .samples(vk::SampleCountFlags::_1)
+                    .samples(info.samples.to_vk())
             })
             .collect::<Vec<_>>();
 
@@ -1193,12 +1277,11 @@ impl Device {
 
         let mut create_info = vk::GraphicsPipelineCreateInfo::builder();
 
-        let color_count = {
+        let (color_count, samples) = {
             let r = &info.rendering;
 
-            let subpass = r
-                .render_pass
-                .info()
+            let render_pass_info = r.render_pass.info();
+            let subpass = render_pass_info
                 .subpasses
                 .get(r.subpass as usize)
                 .expect("subpass index is out of bounds");
@@ -1207,7 +1290,16 @@ impl Device {
                 .render_pass(r.render_pass.handle())
                 .subpass(r.subpass);
 
-            subpass.colors.len()
+            // NOTE: All color and depth attachments of a subpass have the same sample count.
+            let samples = subpass
+                .colors
+                .first()
+                .or(subpass.depth.as_ref())
+                .map_or(Samples::_1, |&(i, _)| {
+                    render_pass_info.attachments[i as usize].samples
+                });
+
+            (subpass.colors.len(), samples)
         };
 
         let mut shader_stages = Vec::with_capacity(2);
@@ -1282,8 +1374,7 @@ impl Device {
                 }
 
                 // Multisample state
-                multisample_state =
-                    multisample_state.rasterization_samples(vk::SampleCountFlags::_1);
+                multisample_state = multisample_state.rasterization_samples(samples.to_vk());
 
                 // Depth/stencil state
                 if let Some(depth_test) = rasterizer.depth_test {
@@ -1483,6 +1574,10 @@ impl Device {
         };
 
         tracing::debug!(graphics_pipeline = ?handle, "created graphics pipeline");
+        self.inner
+            .live_objects
+            .pipelines
+            .fetch_add(1, Ordering::Relaxed);
 
         Ok(GraphicsPipeline::new(handle, info, self.downgrade()))
     }
@@ -1518,12 +1613,20 @@ impl Device {
         };
 
         tracing::debug!(compute_pipeline = ?handle, "created compute pipeline");
+        self.inner
+            .live_objects
+            .pipelines
+            .fetch_add(1, Ordering::Relaxed);
 
         Ok(ComputePipeline::new(handle, info, self.downgrade()))
     }
 
     pub(crate) unsafe fn destroy_pipeline(&self, handle: vk::Pipeline) {
-        self.logical().destroy_pipeline(handle, None)
+        self.logical().destroy_pipeline(handle, None);
+        self.inner
+            .live_objects
+            .pipelines
+            .fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -1567,6 +1670,30 @@ struct Inner {
     descriptors: Mutex<DescriptorAlloc>,
     samplers_cache: FastDashMap<SamplerInfo, Sampler>,
     epochs: Epochs,
+    live_objects: LiveObjectCounters,
+}
+
+#[derive(Default)]
+struct LiveObjectCounters {
+    buffers: AtomicUsize,
+    images: AtomicUsize,
+    pipelines: AtomicUsize,
+}
+
+/// Number of live objects of each kind created by a [`Device`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceObjectCounts {
+    pub buffers: usize,
+    /// Images created by the device (surface images are not included).
+    pub images: usize,
+    /// Graphics and compute pipelines.
+    pub pipelines: usize,
+}
+
+impl DeviceObjectCounts {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 impl Inner {
@@ -1698,4 +1825,24 @@ pub enum CreateRenderPassError {
         attachment_index: u32,
         subpass_index: usize,
     },
+
+    #[error(
+        "attachment index {attachment_index} is out of bounds for the resolve output \
+        {resolve_index} in the subpass {subpass_index}"
+    )]
+    ResolveAttachmentOutOfBounds {
+        attachment_index: u32,
+        resolve_index: usize,
+        subpass_index: usize,
+    },
+
+    #[error(
+        "subpass {subpass_index} has {resolve_count} resolve attachments \
+        but {color_count} color attachments"
+    )]
+    ResolveAttachmentCountMismatch {
+        resolve_count: usize,
+        color_count: usize,
+        subpass_index: usize,
+    },
 }
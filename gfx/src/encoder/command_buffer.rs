@@ -347,6 +347,29 @@ impl CommandBuffer {
         }
     }
 
+    pub(crate) fn draw_indexed_indirect(
+        &mut self,
+        buffer: &Buffer,
+        offset: usize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        let inner = self.inner.as_mut();
+        if let Some(device) = inner.state.device_from_full() {
+            inner.references.buffers.insert(buffer.clone());
+
+            unsafe {
+                device.logical().cmd_draw_indexed_indirect(
+                    inner.handle,
+                    buffer.handle(),
+                    offset as u64,
+                    draw_count,
+                    stride,
+                )
+            }
+        }
+    }
+
     pub(crate) fn update_buffer(&mut self, buffer: &Buffer, offset: usize, data: &[u8]) {
         let inner = self.inner.as_mut();
         if let Some(device) = inner.state.device_from_full() {
@@ -492,6 +515,35 @@ impl CommandBuffer {
         }
     }
 
+    pub(crate) fn copy_image_to_buffer(
+        &mut self,
+        src_image: &Image,
+        src_layout: ImageLayout,
+        dst_buffer: &Buffer,
+        regions: &[BufferImageCopy],
+    ) {
+        let inner = self.inner.as_mut();
+        if let Some(device) = inner.state.device_from_full() {
+            inner.references.images.push(src_image.clone());
+            inner.references.buffers.insert(dst_buffer.clone());
+
+            let alloc = DeallocOnDrop(&mut inner.alloc);
+
+            let regions = alloc
+                .alloc_slice_fill_iter(regions.iter().map(|r| vk::BufferImageCopy::from_gfx(*r)));
+
+            unsafe {
+                device.logical().cmd_copy_image_to_buffer(
+                    inner.handle,
+                    src_image.handle(),
+                    src_layout.to_vk(),
+                    dst_buffer.handle(),
+                    regions,
+                )
+            }
+        }
+    }
+
     pub(crate) fn blit_image(
         &mut self,
         src_image: &Image,
@@ -696,6 +748,17 @@ impl References {
     }
 }
 
+/// Structure specifying an indexed indirect drawing command.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DrawIndexedIndirectCommand {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+    pub first_instance: u32,
+}
+
 /// Structure specifying a buffer copy operation.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct BufferCopy {
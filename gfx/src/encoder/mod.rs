@@ -203,6 +203,18 @@ impl Encoder {
             .copy_buffer_to_image(src_buffer, dst_image, dst_layout, regions);
     }
 
+    /// Copy data from an image into a buffer
+    pub fn copy_image_to_buffer(
+        &mut self,
+        src_image: &Image,
+        src_layout: ImageLayout,
+        dst_buffer: &Buffer,
+        regions: &[BufferImageCopy],
+    ) {
+        self.command_buffer
+            .copy_image_to_buffer(src_image, src_layout, dst_buffer, regions);
+    }
+
     /// Copy regions of an image, potentially performing format conversion,
     pub fn blit_image(
         &mut self,
@@ -423,6 +435,25 @@ impl<'a, 'b> RenderPassEncoder<'a, 'b> {
             .command_buffer
             .draw_indexed(indices, vertex_offset, instances);
     }
+
+    /// Draw indexed primitives with parameters read from a buffer.
+    ///
+    /// The buffer must contain `draw_count` tightly packed or `stride` bytes apart
+    /// [`DrawIndexedIndirectCommand`] structures starting at `offset`.
+    /// Drawing more than one command requires the [`MultiDrawIndirect`] feature.
+    ///
+    /// [`MultiDrawIndirect`]: crate::DeviceFeature::MultiDrawIndirect
+    pub fn draw_indexed_indirect(
+        &mut self,
+        buffer: &Buffer,
+        offset: usize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        self.inner
+            .command_buffer
+            .draw_indexed_indirect(buffer, offset, draw_count, stride);
+    }
 }
 
 impl std::ops::Deref for RenderPassEncoder<'_, '_> {
@@ -2,11 +2,13 @@ extern crate self as gfx;
 
 use vulkanalia::vk;
 
-pub use self::device::{CreateRenderPassError, DescriptorAllocError, Device, MapError, WeakDevice};
+pub use self::device::{
+    CreateRenderPassError, DescriptorAllocError, Device, DeviceObjectCounts, MapError, WeakDevice,
+};
 pub use self::encoder::{
     AccessFlags, BufferCopy, BufferImageCopy, BufferMemoryBarrier, CommandBuffer,
-    CommandBufferLevel, Encoder, EncoderCommon, ImageBlit, ImageCopy, ImageLayoutTransition,
-    ImageMemoryBarrier, MemoryBarrier, PrimaryEncoder, RenderPassEncoder,
+    CommandBufferLevel, DrawIndexedIndirectCommand, Encoder, EncoderCommon, ImageBlit, ImageCopy,
+    ImageLayoutTransition, ImageMemoryBarrier, MemoryBarrier, PrimaryEncoder, RenderPassEncoder,
 };
 pub use self::graphics::{Graphics, InitGraphicsError, InstanceConfig};
 pub use self::layout::{AsStd140, AsStd430, Padded, Padding, Std140, Std430};
@@ -28,19 +30,20 @@ pub use self::resources::{
     DescriptorSetSize, DescriptorSetWrite, DescriptorSlice, DescriptorType, Fence, FenceState,
     Filter, Format, FormatChannels, FormatDescription, FormatType, FragmentShader, Framebuffer,
     FramebufferInfo, FrontFace, GraphicsPipeline, GraphicsPipelineDescr, GraphicsPipelineInfo,
-    GraphicsPipelineRenderingInfo, Image, ImageAspectFlags, ImageExtent, ImageInfo, ImageLayout,
-    ImageSubresource, ImageSubresourceLayers, ImageSubresourceRange, ImageUsageFlags, ImageView,
-    ImageViewInfo, ImageViewType, IndexType, LoadOp, LogicOp, MakeImageView, MemoryBlockMut,
-    MemoryUsage, MipmapMode, Pipeline, PipelineBindPoint, PipelineLayout, PipelineLayoutInfo,
-    PipelineStageFlags, PolygonMode, PrimitiveTopology, PushConstant, Rasterizer, Rect,
-    ReductionMode, RenderPass, RenderPassInfo, Sampler, SamplerAddressMode, SamplerInfo, Samples,
-    Semaphore, ShaderModule, ShaderModuleInfo, ShaderStageFlags, ShaderType, StencilOp,
-    StencilTest, StencilTests, StoreOp, Subpass, SubpassDependency, Swizzle, UpdateDescriptorSet,
-    VertexFormat, VertexInputAttribute, VertexInputBinding, VertexInputRate, VertexShader,
-    Viewport,
+    GraphicsPipelineRenderingInfo, Image, ImageAspectFlags, ImageExtent, ImageFlags, ImageInfo,
+    ImageLayout, ImageSubresource, ImageSubresourceLayers, ImageSubresourceRange, ImageUsageFlags,
+    ImageView, ImageViewInfo, ImageViewType, IndexType, LoadOp, LogicOp, MakeImageView,
+    MemoryBlockMut, MemoryUsage, MipmapMode, Pipeline, PipelineBindPoint, PipelineLayout,
+    PipelineLayoutInfo, PipelineStageFlags, PolygonMode, PrimitiveTopology, PushConstant,
+    Rasterizer, Rect, ReductionMode, RenderPass, RenderPassInfo, Sampler, SamplerAddressMode,
+    SamplerInfo, Samples, Semaphore, ShaderModule, ShaderModuleInfo, ShaderStageFlags, ShaderType,
+    StencilOp, StencilTest, StencilTests, StoreOp, Subpass, SubpassDependency, Swizzle,
+    UpdateDescriptorSet, VertexFormat, VertexInputAttribute, VertexInputBinding, VertexInputRate,
+    VertexShader, Viewport,
 };
 pub use self::surface::{
-    CreateSurfaceError, PresentMode, Surface, SurfaceError, SurfaceImage, SwapchainSupport,
+    ColorSpace, CreateSurfaceError, PresentMode, Surface, SurfaceError, SurfaceImage,
+    SwapchainSupport,
 };
 pub use self::types::{DeviceAddress, DeviceLost, OutOfDeviceMemory, State};
 
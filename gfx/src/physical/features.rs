@@ -112,6 +112,18 @@ pub enum DeviceFeature {
     /// Adds ability to query the frame presentation timing.
     DisplayTiming,
 
+    /// Allows drawing more than one command with a single indirect draw call.
+    MultiDrawIndirect,
+
+    /// Allows using non-zero `first_instance` in indirect draw commands.
+    DrawIndirectFirstInstance,
+
+    /// Allows rasterizing polygons with [`PolygonMode::Line`] and [`PolygonMode::Point`].
+    ///
+    /// [`PolygonMode::Line`]: crate::PolygonMode::Line
+    /// [`PolygonMode::Point`]: crate::PolygonMode::Point
+    FillModeNonSolid,
+
     /// Adds [`Min`] and [`Max`] reduction modes to the [`SamplerInfo`].
     ///
     /// [`Min`]: crate::ReductionMode::Min
@@ -199,6 +211,10 @@ impl VulkanExtension for BaseExtension {
             extension_features.shader_uniform_buffer_array_dynamic_indexing;
         core_features.shader_storage_buffer_array_dynamic_indexing =
             extension_features.shader_storage_buffer_array_dynamic_indexing;
+        core_features.multi_draw_indirect = extension_features.multi_draw_indirect;
+        core_features.draw_indirect_first_instance =
+            extension_features.draw_indirect_first_instance;
+        core_features.fill_mode_non_solid = extension_features.fill_mode_non_solid;
     }
 
     fn process_features(
@@ -212,6 +228,9 @@ impl VulkanExtension for BaseExtension {
             ShaderStorageImageDynamicIndexing => shader_storage_image_array_dynamic_indexing,
             ShaderUniformBufferDynamicIndexing => shader_uniform_buffer_array_dynamic_indexing,
             ShaderStorageBufferDynamicIndexing => shader_storage_buffer_array_dynamic_indexing,
+            MultiDrawIndirect => multi_draw_indirect,
+            DrawIndirectFirstInstance => draw_indirect_first_instance,
+            FillModeNonSolid => fill_mode_non_solid,
         )
     }
 }
@@ -222,6 +241,9 @@ pub struct BaseFeatures {
     shader_storage_image_array_dynamic_indexing: vk::Bool32,
     shader_uniform_buffer_array_dynamic_indexing: vk::Bool32,
     shader_storage_buffer_array_dynamic_indexing: vk::Bool32,
+    multi_draw_indirect: vk::Bool32,
+    draw_indirect_first_instance: vk::Bool32,
+    fill_mode_non_solid: vk::Bool32,
 }
 
 unsafe impl vk::Cast for BaseFeatures {
@@ -223,9 +223,31 @@ pub struct ImageInfo {
     pub mip_levels: u32,
     pub samples: Samples,
     pub array_layers: u32,
+    pub flags: ImageFlags,
     pub usage: ImageUsageFlags,
 }
 
+bitflags::bitflags! {
+    /// Bitmask specifying additional parameters of an image.
+    #[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+    pub struct ImageFlags: u32 {
+        /// Allows creating cube and cube array views of the image.
+        ///
+        /// The image must be 2D with equal width and height and at least 6 array layers.
+        const CUBE_COMPATIBLE = 1;
+    }
+}
+
+impl FromGfx<ImageFlags> for vk::ImageCreateFlags {
+    fn from_gfx(value: ImageFlags) -> Self {
+        let mut res = Self::empty();
+        if value.contains(ImageFlags::CUBE_COMPATIBLE) {
+            res |= Self::CUBE_COMPATIBLE;
+        }
+        res
+    }
+}
+
 bitflags::bitflags! {
     /// Bitmask specifying intended usage of an image.
     #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -32,7 +32,12 @@ pub enum ImageViewType {
     D1,
     D2,
     D3,
+    /// Six layers of an image created with [`ImageFlags::CUBE_COMPATIBLE`](crate::ImageFlags::CUBE_COMPATIBLE).
     Cube,
+    D1Array,
+    D2Array,
+    /// Multiple of six layers of an image created with [`ImageFlags::CUBE_COMPATIBLE`](crate::ImageFlags::CUBE_COMPATIBLE).
+    CubeArray,
 }
 
 impl FromGfx<ImageViewType> for vk::ImageViewType {
@@ -42,6 +47,9 @@ impl FromGfx<ImageViewType> for vk::ImageViewType {
             ImageViewType::D2 => vk::ImageViewType::_2D,
             ImageViewType::D3 => vk::ImageViewType::_3D,
             ImageViewType::Cube => vk::ImageViewType::CUBE,
+            ImageViewType::D1Array => vk::ImageViewType::_1D_ARRAY,
+            ImageViewType::D2Array => vk::ImageViewType::_2D_ARRAY,
+            ImageViewType::CubeArray => vk::ImageViewType::CUBE_ARRAY,
         }
     }
 }
@@ -114,10 +122,12 @@ impl ImageViewInfo {
         let image_info = image.info();
 
         Self {
-            ty: match &image_info.extent {
-                ImageExtent::D1 { .. } => ImageViewType::D1,
-                ImageExtent::D2 { .. } => ImageViewType::D2,
-                ImageExtent::D3 { .. } => ImageViewType::D3,
+            ty: match (&image_info.extent, image_info.array_layers > 1) {
+                (ImageExtent::D1 { .. }, false) => ImageViewType::D1,
+                (ImageExtent::D1 { .. }, true) => ImageViewType::D1Array,
+                (ImageExtent::D2 { .. }, false) => ImageViewType::D2,
+                (ImageExtent::D2 { .. }, true) => ImageViewType::D2Array,
+                (ImageExtent::D3 { .. }, _) => ImageViewType::D3,
             },
             range: ImageSubresourceRange::whole(image_info),
             image,
@@ -129,12 +139,7 @@ impl ImageViewInfo {
         self.image == *image
             && self.range == ImageSubresourceRange::whole(image.info())
             && self.mapping == ComponentMapping::default()
-            && matches!(
-                (self.ty, &image.info().extent),
-                (ImageViewType::D1, ImageExtent::D1 { .. })
-                    | (ImageViewType::D2, ImageExtent::D2 { .. })
-                    | (ImageViewType::D3, ImageExtent::D3 { .. })
-            )
+            && self.ty == Self::new(image.clone()).ty
     }
 }
 
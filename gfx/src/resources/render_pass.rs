@@ -55,35 +55,35 @@ pub enum ClearValue {
 impl ClearValue {
     pub fn try_to_vk(self, format: Format) -> Option<vk::ClearValue> {
         fn to_uint8(color: f32) -> u8 {
-            color.min(0f32).max(u8::MAX as f32) as u8
+            color.clamp(0f32, u8::MAX as f32) as u8
         }
 
         fn to_sint8(color: f32) -> i8 {
-            color.min(i8::MIN as f32).max(i8::MAX as f32) as i8
+            color.clamp(i8::MIN as f32, i8::MAX as f32) as i8
         }
 
         fn to_uint16(color: f32) -> u16 {
-            color.min(0f32).max(u16::MAX as f32) as u16
+            color.clamp(0f32, u16::MAX as f32) as u16
         }
 
         fn to_sint16(color: f32) -> i16 {
-            color.min(i16::MIN as f32).max(i16::MAX as f32) as i16
+            color.clamp(i16::MIN as f32, i16::MAX as f32) as i16
         }
 
         fn to_uint32(color: f32) -> u32 {
-            color.min(0f32).max(u32::MAX as f32) as u32
+            color.clamp(0f32, u32::MAX as f32) as u32
         }
 
         fn to_sint32(color: f32) -> i32 {
-            color.min(i32::MIN as f32).max(i32::MAX as f32) as i32
+            color.clamp(i32::MIN as f32, i32::MAX as f32) as i32
         }
 
         fn to_uint64(color: f32) -> u64 {
-            color.min(0f32).max(u64::MAX as f32) as u64
+            color.clamp(0f32, u64::MAX as f32) as u64
         }
 
         fn to_sint64(color: f32) -> i64 {
-            color.min(i64::MIN as f32).max(i64::MAX as f32) as i64
+            color.clamp(i64::MIN as f32, i64::MAX as f32) as i64
         }
 
         match self {
@@ -234,6 +234,12 @@ pub struct Subpass {
     pub colors: Vec<(u32, ImageLayout)>,
     // Depth attachment index and layout.
     pub depth: Option<(u32, ImageLayout)>,
+    /// List of resolve attachment indices and their layouts.
+    ///
+    /// Must be either empty or have the same length as `colors`. Each multisampled
+    /// color attachment is resolved into the corresponding single-sampled one at
+    /// the end of the subpass.
+    pub resolves: Vec<(u32, ImageLayout)>,
 }
 
 /// Structure specifying a subpass dependency.
@@ -63,6 +63,31 @@ impl TryFromVk<vk::PresentModeKHR> for PresentMode {
     }
 }
 
+/// Color space in which the presentation engine interprets swapchain images.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB color space with the sRGB transfer function.
+    SrgbNonlinear,
+    /// Extended sRGB color space with a linear transfer function.
+    ExtendedSrgbLinear,
+    /// Extended sRGB color space with the sRGB transfer function.
+    ExtendedSrgbNonlinear,
+    /// Color components are used as is.
+    PassThrough,
+}
+
+impl TryFromVk<vk::ColorSpaceKHR> for ColorSpace {
+    fn try_from_vk(color_space: vk::ColorSpaceKHR) -> Option<Self> {
+        match color_space {
+            vk::ColorSpaceKHR::SRGB_NONLINEAR => Some(Self::SrgbNonlinear),
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => Some(Self::ExtendedSrgbLinear),
+            vk::ColorSpaceKHR::EXTENDED_SRGB_NONLINEAR_EXT => Some(Self::ExtendedSrgbNonlinear),
+            vk::ColorSpaceKHR::PASS_THROUGH_EXT => Some(Self::PassThrough),
+            _ => None,
+        }
+    }
+}
+
 impl FromGfx<PresentMode> for vk::PresentModeKHR {
     fn from_gfx(mode: PresentMode) -> Self {
         match mode {
@@ -180,12 +205,15 @@ impl Surface {
             return Err(SurfaceError::UsageNotSupported { usage });
         }
 
+        // NOTE: The same format can be listed with several color spaces
         let surface_format = self
             .swapchain_support
             .surface_formats
             .iter()
-            .find(|item| Format::from_vk(item.format) == Some(format))
+            .filter(|item| Format::from_vk(item.format) == Some(format))
+            .min_by_key(|item| item.color_space != vk::ColorSpaceKHR::SRGB_NONLINEAR)
             .ok_or(SurfaceError::FormatNotSupported { format })?;
+        let color_space = ColorSpace::try_from_vk(surface_format.color_space);
 
         if self
             .swapchain_support
@@ -268,6 +296,7 @@ impl Surface {
                     mip_levels: 1,
                     samples: Samples::_1,
                     array_layers: 1,
+                    flags: Default::default(),
                     usage,
                 };
                 let id = IMAGE_ID.fetch_add(1, Ordering::Relaxed).try_into().unwrap();
@@ -286,6 +315,7 @@ impl Surface {
         self.swapchain = Some(Swapchain {
             handle,
             format,
+            color_space,
             usage,
             mode,
             images,
@@ -371,6 +401,7 @@ impl Surface {
             supported_families: &self.swapchain_support.supported_families,
             total_image_count,
             image: &image_state.image,
+            color_space: swapchain.color_space,
             index,
             acquired_count: &mut swapchain.acquired_count,
             wait: &mut image_state.acquire,
@@ -409,6 +440,7 @@ pub struct SurfaceImage<'a> {
     supported_families: &'a [bool],
     total_image_count: usize,
     image: &'a Image,
+    color_space: Option<ColorSpace>,
     index: u32,
     acquired_count: &'a mut u32,
     wait: &'a mut Semaphore,
@@ -428,6 +460,11 @@ impl<'a> SurfaceImage<'a> {
         self.image
     }
 
+    /// Returns the color space of the swapchain, or `None` if it is not known to `gfx`.
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.color_space
+    }
+
     /// Returns the index of the image in the swapchain.
     pub fn index(&self) -> u32 {
         self.index
@@ -481,6 +518,7 @@ impl Window for winit::window::Window {
 struct Swapchain {
     handle: vk::SwapchainKHR,
     format: Format,
+    color_space: Option<ColorSpace>,
     usage: ImageUsageFlags,
     mode: PresentMode,
     images: Vec<SwapchainImageState>,
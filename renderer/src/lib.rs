@@ -4,23 +4,37 @@ use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use glam::Mat4;
-use shared::Embed;
+use shared::{Embed, FastHashSet};
 use winit::window::Window;
 
 pub use self::render_graph::materials;
 pub use crate::types::{
-    CameraProjection, Color, CubeMeshGenerator, DynamicObjectHandle, MaterialInstance,
+    AmbientOcclusionQuality, AmbientOcclusionSettings, AntiAliasing, BloomSettings,
+    CameraProjection, CapsuleMeshGenerator, Color, Color1, ConeMeshGenerator, CubeMeshGenerator,
+    Custom0, Custom1, Custom2, Custom3, CustomVertexAttribute, CylinderMeshGenerator, DebugDraw,
+    DebugDrawOptions, DebugView, DynamicObjectHandle, EnvironmentMap, Exposure, GridMeshGenerator,
+    Heightmap, HeightmapMeshGenerator, IcosphereMeshGenerator, Joints, MaterialInstance,
     MaterialInstanceHandle, MaterialInstanceTag, Mesh, MeshBuilder, MeshGenerator, MeshHandle,
-    Normal, PlaneMeshGenerator, Position, Sorting, SortingOrder, SortingReason, StaticObjectHandle,
-    Tangent, VertexAttribute, VertexAttributeData, VertexAttributeKind, UV0,
+    MeshOptimizationStats, MorphNormals, MorphPositions, MorphTarget, Msaa, Normal, ObjMaterial,
+    ObjMesh, ObjScene, ObjectFlags, ObjectMigrationPolicy, ObjectStorage, ObjectVisibility,
+    PickRequest, PickResult, PickedObject, PlaneMeshGenerator, Position, PostAntiAliasing,
+    PostProcessSettings, RenderLayers, RenderPassKind, Sorting, SortingOrder, SortingReason,
+    StaticObjectHandle, TaaSettings, Tangent, Tonemapping, TorusMeshGenerator,
+    UvSphereMeshGenerator, VertexAttribute, VertexAttributeData, VertexAttributeEncoding,
+    VertexAttributeEncodings, VertexAttributeKind, Weights, UV0, UV1,
+};
+pub use crate::util::{
+    BindlessSlotUsage, BoundingBox, BoundingSphere, Frustum, LiveHandle, Plane, ResourceReport,
 };
 
 use crate::managers::{MaterialManager, MeshManager, ObjectManager, TimeManager};
-use crate::types::{RawMaterialInstanceHandle, RawMeshHandle, RawStaticObjectHandle};
+use crate::types::{
+    ObjectKey, PendingPick, PickingId, RawMaterialInstanceHandle, RawMeshHandle,
+    RawStaticObjectHandle,
+};
 use crate::util::{
     BindlessResources, FrameResources, FreelistHandleAllocator, HandleAllocator, HandleData,
     HandleDeleter, MultiBufferArena, RawResourceHandle, ScatterCopy, ShaderPreprocessor,
-    SimpleHandleAllocator,
 };
 use crate::worker::RendererWorker;
 
@@ -38,6 +52,7 @@ pub struct RendererBuilder {
     validation_layer: bool,
     optimize_shaders: bool,
     shaders_debug_info_enabled: bool,
+    custom_vertex_attributes: Vec<CustomVertexAttribute>,
 }
 
 impl RendererBuilder {
@@ -51,7 +66,7 @@ impl RendererBuilder {
         });
 
         let graphics = gfx::Graphics::get_or_init()?;
-        let (device, queue) = graphics
+        let mut selected = graphics
             .get_physical_devices()?
             .with_required_features(&[
                 gfx::DeviceFeature::SurfacePresentation,
@@ -61,8 +76,32 @@ impl RendererBuilder {
                 gfx::DeviceFeature::DescriptorBindingSampledImageUpdateAfterBind,
                 gfx::DeviceFeature::DescriptorBindingPartiallyBound,
             ])
-            .find_best()?
-            .create_logical_device(gfx::SingleQueueQuery::GRAPHICS)?;
+            .find_best()?;
+
+        // NOTE: Optional features are core ones, which are enabled when supported
+        let core_features = &selected.physical_device.features().v1_0;
+        for (feature, supported) in [
+            (
+                gfx::DeviceFeature::MultiDrawIndirect,
+                core_features.multi_draw_indirect,
+            ),
+            (
+                gfx::DeviceFeature::DrawIndirectFirstInstance,
+                core_features.draw_indirect_first_instance,
+            ),
+            (
+                gfx::DeviceFeature::FillModeNonSolid,
+                core_features.fill_mode_non_solid,
+            ),
+        ] {
+            if supported != 0 {
+                selected.supported_features.insert(feature);
+            } else {
+                tracing::warn!(?feature, "optional device feature is not supported");
+            }
+        }
+        let enabled_features = selected.supported_features.clone();
+        let (device, queue) = selected.create_logical_device(gfx::SingleQueueQuery::GRAPHICS)?;
 
         let mut shader_preprocessor = ShaderPreprocessor::new();
         shader_preprocessor.set_optimizations_enabled(self.optimize_shaders);
@@ -72,6 +111,10 @@ impl RendererBuilder {
                 .with_context(|| anyhow::anyhow!("invalid shader {path}"))?;
             shader_preprocessor.add_file(path, contents)?;
         }
+        shader_preprocessor.add_file(
+            "uniforms/vertex.glsl",
+            types::generate_vertex_glsl(&self.custom_vertex_attributes)?,
+        )?;
 
         let frame_resources = FrameResources::new(&device)?;
         let bindless_resources = BindlessResources::new(&device)?;
@@ -91,10 +134,16 @@ impl RendererBuilder {
             synced_managers: Default::default(),
             handles: Default::default(),
             frame_resources,
+            post_process: Default::default(),
+            pending_environment: Default::default(),
+            debug_draw: Default::default(),
+            debug_view: Default::default(),
+            pending_picks: Default::default(),
             bindless_resources,
             multi_buffer_arena,
             scatter_copy,
             shader_preprocessor,
+            enabled_features,
             window: self.window,
             queue,
             device,
@@ -113,6 +162,7 @@ impl RendererBuilder {
                     state.worker_barrier.wait();
                     worker.draw().unwrap();
                 }
+                worker.release();
 
                 tracing::debug!("rendering thread stopped");
             }
@@ -143,6 +193,14 @@ impl RendererBuilder {
         self.shaders_debug_info_enabled = shaders_debug_info_enabled;
         self
     }
+
+    /// Registers an application-defined vertex attribute in one of the custom slots.
+    ///
+    /// [`RendererBuilder::build`] fails if the slot or the name is already used.
+    pub fn custom_vertex_attribute(mut self, attribute: CustomVertexAttribute) -> Self {
+        self.custom_vertex_attributes.push(attribute);
+        self
+    }
 }
 
 pub struct Renderer {
@@ -158,6 +216,7 @@ impl Renderer {
             validation_layer: false,
             optimize_shaders: true,
             shaders_debug_info_enabled: false,
+            custom_vertex_attributes: Vec::new(),
         }
     }
 
@@ -170,9 +229,23 @@ impl Renderer {
             self.state.set_running(false);
             worker_thread.join().unwrap();
             self.state.device.wait_idle()?;
+
+            self.state.release_pending_handles();
+            let report = self.state.resource_report();
+            if report.has_leaks() {
+                tracing::warn!("renderer resources are still alive at shutdown:\n{report}");
+            } else {
+                tracing::debug!("renderer shutdown report:\n{report}");
+            }
         }
         Ok(())
     }
+
+    /// Stops the renderer and returns resources which are still alive.
+    pub fn shutdown(mut self) -> Result<ResourceReport> {
+        self.cleanup()?;
+        Ok(self.state.resource_report())
+    }
 }
 
 impl Drop for Renderer {
@@ -193,10 +266,19 @@ pub struct RendererState {
     handles: RendererStateHandles,
 
     frame_resources: FrameResources,
+    post_process: Mutex<PostProcessSettings>,
+    /// Environment to convert at the beginning of the next frame, `Some(None)` removes it.
+    pending_environment: Mutex<Option<Option<EnvironmentMap>>>,
+    debug_draw: DebugDraw,
+    debug_view: Mutex<DebugView>,
+    /// Pick requests to submit in the next frame.
+    pending_picks: Mutex<Vec<PendingPick>>,
     bindless_resources: BindlessResources,
     multi_buffer_arena: MultiBufferArena,
     shader_preprocessor: ShaderPreprocessor,
     scatter_copy: ScatterCopy,
+    /// Required device features and the supported optional ones.
+    enabled_features: FastHashSet<gfx::DeviceFeature>,
 
     window: Arc<Window>,
     queue: gfx::Queue,
@@ -210,6 +292,11 @@ impl RendererState {
         &self.window
     }
 
+    /// Returns whether a device feature is enabled, optional ones are enabled when supported.
+    pub fn is_feature_enabled(&self, feature: gfx::DeviceFeature) -> bool {
+        self.enabled_features.contains(&feature)
+    }
+
     pub fn set_running(&self, is_running: bool) {
         self.is_running.store(is_running, Ordering::Release);
         self.worker_barrier.notify();
@@ -219,10 +306,155 @@ impl RendererState {
         self.worker_barrier.notify();
     }
 
+    /// Collects live handles, bindless slots and device objects.
+    pub fn resource_report(&self) -> ResourceReport {
+        ResourceReport {
+            meshes: self.handles.mesh_handle_allocator.live_handles(),
+            materials: self.handles.material_handle_allocator.live_handles(),
+            static_objects: self.handles.static_object_handle_allocator.live_handles(),
+            dynamic_objects: self.handles.dynamic_object_handle_allocator.live_handles(),
+            bindless: self.bindless_resources.slots_in_use(),
+            device_objects: self.device.live_object_counts(),
+        }
+    }
+
     pub fn update_camera(&self, view: &Mat4, projection: &CameraProjection) {
         self.frame_resources.set_camera(view, projection);
     }
 
+    /// Sets render layers of the main camera. Objects without any of these layers are skipped.
+    pub fn set_camera_render_layers(&self, render_layers: RenderLayers) {
+        self.frame_resources.set_camera_render_layers(render_layers);
+    }
+
+    /// Sets render layers of a pass, which are combined with the layers of the camera.
+    ///
+    /// All passes use all layers by default.
+    pub fn set_pass_render_layers(&self, pass: RenderPassKind, render_layers: RenderLayers) {
+        self.frame_resources
+            .set_pass_render_layers(pass, render_layers);
+    }
+
+    /// Immediate-mode debug shapes drawn on top of the next frames.
+    pub fn debug_draw(&self) -> &DebugDraw {
+        &self.debug_draw
+    }
+
+    pub fn debug_view(&self) -> DebugView {
+        *self.debug_view.lock().unwrap()
+    }
+
+    /// Returns whether the device can display the debug view.
+    ///
+    /// The wireframe requires non-solid fill modes, which are optional.
+    pub fn is_debug_view_supported(&self, debug_view: DebugView) -> bool {
+        match debug_view {
+            DebugView::Wireframe => self.is_feature_enabled(gfx::DeviceFeature::FillModeNonSolid),
+            _ => true,
+        }
+    }
+
+    /// Returns the supported debug view after the current one, for cycling through them.
+    pub fn next_debug_view(&self) -> DebugView {
+        self.debug_view()
+            .next_supported(|view| self.is_debug_view_supported(view))
+    }
+
+    /// Replaces the regular shading of materials with a debug visualization.
+    ///
+    /// Material pipelines are recompiled in the next frame when the view changes.
+    /// Unsupported views are ignored, see [`RendererState::is_debug_view_supported`].
+    pub fn set_debug_view(&self, debug_view: DebugView) {
+        if !self.is_debug_view_supported(debug_view) {
+            tracing::warn!(?debug_view, "debug view is not supported by the device");
+            return;
+        }
+        *self.debug_view.lock().unwrap() = debug_view;
+    }
+
+    /// Finds the object at `(x, y)` in physical pixels of the surface.
+    ///
+    /// The request is resolved a few frames later. Objects with
+    /// [`ObjectFlags::EXCLUDE_FROM_PICKING`] are ignored.
+    pub fn pick(&self, x: u32, y: u32) -> PickRequest {
+        let request = PickRequest::default();
+        self.pending_picks.lock().unwrap().push(PendingPick {
+            position: glam::uvec2(x, y),
+            request: request.clone(),
+        });
+        request
+    }
+
+    pub(crate) fn take_pending_picks(&self) -> Vec<PendingPick> {
+        std::mem::take(&mut *self.pending_picks.lock().unwrap())
+    }
+
+    /// Returns the handle of a live object from an id read from the picking buffer.
+    pub(crate) fn find_picked_object(&self, id: u32) -> Option<PickedObject> {
+        let id = PickingId::decode(id)?;
+        if id.dynamic {
+            let handle = self
+                .handles
+                .dynamic_object_handle_allocator
+                .find_alive(id.index)?;
+            id.matches_generation(handle.generation())
+                .then_some(PickedObject::Dynamic(handle))
+        } else {
+            let handle = self
+                .handles
+                .static_object_handle_allocator
+                .find_alive(id.index)?;
+            id.matches_generation(handle.generation())
+                .then_some(PickedObject::Static(handle))
+        }
+    }
+
+    pub fn post_process(&self) -> PostProcessSettings {
+        *self.post_process.lock().unwrap()
+    }
+
+    pub fn set_tonemapping(&self, tonemapping: Tonemapping) {
+        self.post_process.lock().unwrap().tonemapping = tonemapping;
+    }
+
+    pub fn set_exposure(&self, exposure: Exposure) {
+        self.post_process.lock().unwrap().exposure = exposure;
+    }
+
+    /// Enables bloom with the specified settings or disables it with `None`.
+    pub fn set_bloom(&self, bloom: Option<BloomSettings>) {
+        self.post_process.lock().unwrap().bloom = bloom;
+    }
+
+    /// Enables ambient occlusion with the specified settings or disables it with `None`.
+    pub fn set_ambient_occlusion(&self, ambient_occlusion: Option<AmbientOcclusionSettings>) {
+        self.post_process.lock().unwrap().ambient_occlusion = ambient_occlusion;
+    }
+
+    pub fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) {
+        self.post_process.lock().unwrap().anti_aliasing = anti_aliasing;
+    }
+
+    /// Sets the sample count of the main pass.
+    ///
+    /// Falls back to the highest sample count supported by the device.
+    pub fn set_msaa(&self, msaa: Msaa) {
+        self.post_process.lock().unwrap().msaa = msaa;
+    }
+
+    /// Sets the anti-aliasing applied after tonemapping.
+    pub fn set_post_anti_aliasing(&self, post_anti_aliasing: PostAntiAliasing) {
+        self.post_process.lock().unwrap().post_anti_aliasing = post_anti_aliasing;
+    }
+
+    /// Sets the environment rendered as the skybox and used for image-based
+    /// lighting, or removes it with `None`.
+    ///
+    /// The environment is converted on the GPU at the beginning of the next frame.
+    pub fn set_environment(&self, environment: Option<EnvironmentMap>) {
+        *self.pending_environment.lock().unwrap() = Some(environment);
+    }
+
     pub fn add_mesh(self: &Arc<Self>, mesh: &Mesh) -> Result<MeshHandle> {
         let mesh = self.mesh_manager.upload_mesh(&self.queue, mesh)?;
 
@@ -284,6 +516,7 @@ impl RendererState {
                 mesh: mesh_handle,
                 material: material_handle,
                 global_transform: *global_transform,
+                visibility: ObjectVisibility::default(),
             }),
         });
         handle
@@ -307,11 +540,177 @@ impl RendererState {
                 mesh: mesh_handle,
                 material: material_handle,
                 global_transform: *global_transform,
+                visibility: ObjectVisibility::default(),
             }),
         });
         handle
     }
 
+    pub fn add_skinned_object(
+        self: &Arc<Self>,
+        mesh_handle: MeshHandle,
+        material_handle: MaterialInstanceHandle,
+        global_transform: &Mat4,
+        joint_matrices: Vec<Mat4>,
+    ) -> DynamicObjectHandle {
+        let handle = self.add_dynamic_object(mesh_handle, material_handle, global_transform);
+        self.update_dynamic_object_joints(&handle, joint_matrices);
+        handle
+    }
+
+    /// Replaces the mesh of a static object, keeping its handle.
+    pub fn set_static_object_mesh(self: &Arc<Self>, handle: &StaticObjectHandle, mesh: MeshHandle) {
+        self.instructions.send(Instruction::SetStaticObjectMesh {
+            handle: handle.raw(),
+            mesh,
+        });
+    }
+
+    /// Replaces the material of a static object, keeping its handle.
+    ///
+    /// The new material may have a different type.
+    pub fn set_static_object_material(
+        self: &Arc<Self>,
+        handle: &StaticObjectHandle,
+        material: MaterialInstanceHandle,
+    ) {
+        self.instructions
+            .send(Instruction::SetStaticObjectMaterial {
+                handle: handle.raw(),
+                material,
+            });
+    }
+
+    /// Replaces the mesh of a dynamic object, keeping its handle.
+    pub fn set_dynamic_object_mesh(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        mesh: MeshHandle,
+    ) {
+        self.instructions.send(Instruction::SetDynamicObjectMesh {
+            handle: handle.raw(),
+            mesh,
+        });
+    }
+
+    /// Replaces the material of a dynamic object, keeping its handle.
+    ///
+    /// The new material may have a different type.
+    pub fn set_dynamic_object_material(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        material: MaterialInstanceHandle,
+    ) {
+        self.instructions
+            .send(Instruction::SetDynamicObjectMaterial {
+                handle: handle.raw(),
+                material,
+            });
+    }
+
+    /// Hides or shows a static object without removing it.
+    pub fn set_static_object_visible(self: &Arc<Self>, handle: &StaticObjectHandle, visible: bool) {
+        self.update_static_object_visibility(handle, VisibilityUpdate::Visible(visible));
+    }
+
+    pub fn set_static_object_flags(
+        self: &Arc<Self>,
+        handle: &StaticObjectHandle,
+        flags: ObjectFlags,
+    ) {
+        self.update_static_object_visibility(handle, VisibilityUpdate::Flags(flags));
+    }
+
+    pub fn set_static_object_render_layers(
+        self: &Arc<Self>,
+        handle: &StaticObjectHandle,
+        layers: RenderLayers,
+    ) {
+        self.update_static_object_visibility(handle, VisibilityUpdate::Layers(layers));
+    }
+
+    /// Hides or shows a dynamic object without removing it.
+    pub fn set_dynamic_object_visible(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        visible: bool,
+    ) {
+        self.update_dynamic_object_visibility(handle, VisibilityUpdate::Visible(visible));
+    }
+
+    pub fn set_dynamic_object_flags(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        flags: ObjectFlags,
+    ) {
+        self.update_dynamic_object_visibility(handle, VisibilityUpdate::Flags(flags));
+    }
+
+    pub fn set_dynamic_object_render_layers(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        layers: RenderLayers,
+    ) {
+        self.update_dynamic_object_visibility(handle, VisibilityUpdate::Layers(layers));
+    }
+
+    fn update_static_object_visibility(
+        self: &Arc<Self>,
+        handle: &StaticObjectHandle,
+        update: VisibilityUpdate,
+    ) {
+        self.instructions
+            .send(Instruction::UpdateStaticObjectVisibility {
+                handle: handle.raw(),
+                update,
+            });
+    }
+
+    fn update_dynamic_object_visibility(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        update: VisibilityUpdate,
+    ) {
+        self.instructions
+            .send(Instruction::UpdateDynamicObjectVisibility {
+                handle: handle.raw(),
+                update,
+            });
+    }
+
+    /// Moves a static object into another storage, keeping its handle.
+    pub fn set_static_object_storage(
+        self: &Arc<Self>,
+        handle: &StaticObjectHandle,
+        storage: ObjectStorage,
+    ) {
+        self.instructions.send(Instruction::SetStaticObjectStorage {
+            handle: handle.raw(),
+            storage,
+        });
+    }
+
+    /// Moves a dynamic object into another storage, keeping its handle.
+    ///
+    /// Objects with joints or morph weights always stay in dynamic storage.
+    pub fn set_dynamic_object_storage(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        storage: ObjectStorage,
+    ) {
+        self.instructions
+            .send(Instruction::SetDynamicObjectStorage {
+                handle: handle.raw(),
+                storage,
+            });
+    }
+
+    /// Enables automatic migration of objects between static and dynamic storage.
+    pub fn set_object_migration_policy(self: &Arc<Self>, policy: ObjectMigrationPolicy) {
+        self.instructions
+            .send(Instruction::SetObjectMigrationPolicy { policy });
+    }
+
     pub fn update_static_object(self: &Arc<Self>, handle: &StaticObjectHandle, transform: Mat4) {
         self.instructions.send(Instruction::UpdateStaticObject {
             handle: handle.raw(),
@@ -332,6 +731,38 @@ impl RendererState {
         });
     }
 
+    /// Updates joint matrices of a skinned object.
+    ///
+    /// Each matrix transforms a vertex from the mesh space into the
+    /// object space (i.e. it already includes the inverse bind matrix).
+    pub fn update_dynamic_object_joints(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        joint_matrices: Vec<Mat4>,
+    ) {
+        self.instructions
+            .send(Instruction::UpdateDynamicObjectJoints {
+                handle: handle.raw(),
+                joint_matrices: joint_matrices.into_boxed_slice(),
+            });
+    }
+
+    /// Updates morph target weights of a dynamic object.
+    ///
+    /// The number of weights is expected to match the number of
+    /// morph targets of the object mesh.
+    pub fn update_dynamic_object_morph_weights(
+        self: &Arc<Self>,
+        handle: &DynamicObjectHandle,
+        weights: Vec<f32>,
+    ) {
+        self.instructions
+            .send(Instruction::UpdateDynamicObjectMorphWeights {
+                handle: handle.raw(),
+                weights: weights.into_boxed_slice(),
+            });
+    }
+
     pub fn finish_fixed_update(self: &Arc<Self>, updated_at: Instant, duration: Duration) {
         self.instructions.send(Instruction::FinishFixedUpdate {
             updated_at,
@@ -339,6 +770,42 @@ impl RendererState {
         });
     }
 
+    /// Deallocates handles which were dropped but not evaluated by the worker.
+    ///
+    /// Must only be called after the worker thread has stopped.
+    fn release_pending_handles(&self) {
+        // NOTE: objects hold mesh and material handles, so they are dropped first
+        drop(std::mem::take(&mut *self.synced_managers.lock().unwrap()));
+
+        loop {
+            self.instructions.swap();
+            let instructions = std::mem::take(&mut *self.instructions.consumer.lock().unwrap());
+            if instructions.is_empty() {
+                break;
+            }
+
+            // NOTE: dropping the remaining instructions may send new removals
+            for instruction in instructions {
+                match instruction {
+                    Instruction::RemoveMesh { handle } => {
+                        self.handles.mesh_handle_allocator.dealloc(handle);
+                        self.mesh_manager.remove(handle);
+                    }
+                    Instruction::RemoveMaterial { handle } => {
+                        self.handles.material_handle_allocator.dealloc(handle);
+                    }
+                    Instruction::RemoveStaticObject { handle } => {
+                        self.handles.static_object_handle_allocator.dealloc(handle);
+                    }
+                    Instruction::RemoveDynamicObject { handle } => {
+                        self.handles.dynamic_object_handle_allocator.dealloc(handle);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
     #[tracing::instrument(level = "debug", name = "eval_instructions", skip_all)]
     pub(crate) fn eval_instructions<'a>(
         &'a self,
@@ -355,11 +822,21 @@ impl RendererState {
         let mut mesh_manager_data = None;
 
         for instruction in instructions.drain(..) {
+            // NOTE: the remaining instructions must still be applied, so an
+            // invalid one is skipped instead of aborting the drain
+            #[cfg(debug_assertions)]
+            if let Err(e) = self.handles.validate_instruction(&instruction) {
+                tracing::error!("skipping invalid instruction: {e:?}");
+                continue;
+            }
+
             let synced_managers = &mut *synced_managers;
             match instruction {
                 Instruction::RemoveMesh { handle } => {
                     tracing::trace!(?handle, "remove_mesh");
                     self.handles.mesh_handle_allocator.dealloc(handle);
+                    // NOTE: registry lock must be released before removing the mesh
+                    mesh_manager_data = None;
                     self.mesh_manager.remove(handle);
                 }
                 Instruction::AddMaterialInstance { handle, on_add } => {
@@ -399,11 +876,105 @@ impl RendererState {
                         &mut synced_managers.material_manager,
                     );
                 }
-                Instruction::UpdateStaticObject { handle, transform } => {
-                    tracing::trace!(?handle, "update_static_object");
+                Instruction::SetStaticObjectMesh { handle, mesh } => {
+                    tracing::trace!(?handle, "set_static_object_mesh");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.modify_object(
+                        ObjectKey::Static(handle),
+                        |object| object.mesh = mesh,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::SetStaticObjectMaterial { handle, material } => {
+                    tracing::trace!(?handle, "set_static_object_material");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.modify_object(
+                        ObjectKey::Static(handle),
+                        |object| object.material = material,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::SetDynamicObjectMesh { handle, mesh } => {
+                    tracing::trace!(?handle, "set_dynamic_object_mesh");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.modify_object(
+                        ObjectKey::Dynamic(handle),
+                        |object| object.mesh = mesh,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::SetDynamicObjectMaterial { handle, material } => {
+                    tracing::trace!(?handle, "set_dynamic_object_material");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.modify_object(
+                        ObjectKey::Dynamic(handle),
+                        |object| object.material = material,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::UpdateStaticObjectVisibility { handle, update } => {
+                    tracing::trace!(?handle, ?update, "update_static_object_visibility");
                     synced_managers
                         .object_manager
-                        .update_static_object(handle, transform.as_ref());
+                        .update_object_visibility(ObjectKey::Static(handle), |visibility| {
+                            update.apply(visibility)
+                        });
+                }
+                Instruction::UpdateDynamicObjectVisibility { handle, update } => {
+                    tracing::trace!(?handle, ?update, "update_dynamic_object_visibility");
+                    synced_managers
+                        .object_manager
+                        .update_object_visibility(ObjectKey::Dynamic(handle), |visibility| {
+                            update.apply(visibility)
+                        });
+                }
+                Instruction::SetStaticObjectStorage { handle, storage } => {
+                    tracing::trace!(?handle, ?storage, "set_static_object_storage");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.set_object_storage(
+                        ObjectKey::Static(handle),
+                        storage,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::SetDynamicObjectStorage { handle, storage } => {
+                    tracing::trace!(?handle, ?storage, "set_dynamic_object_storage");
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+
+                    synced_managers.object_manager.set_object_storage(
+                        ObjectKey::Dynamic(handle),
+                        storage,
+                        inner_meshes,
+                        &mut synced_managers.material_manager,
+                    );
+                }
+                Instruction::SetObjectMigrationPolicy { policy } => {
+                    tracing::trace!(?policy, "set_object_migration_policy");
+                    synced_managers.object_manager.set_migration_policy(policy);
+                }
+                Instruction::UpdateStaticObject { handle, transform } => {
+                    tracing::trace!(?handle, "update_static_object");
+                    synced_managers.object_manager.update_object_transform(
+                        ObjectKey::Static(handle),
+                        transform.as_ref(),
+                        false,
+                    );
                 }
                 Instruction::UpdateDynamicObject {
                     handle,
@@ -411,21 +982,40 @@ impl RendererState {
                     teleport,
                 } => {
                     tracing::trace!(?handle, "update_dynamic_object");
-                    synced_managers.object_manager.update_dynamic_object(
-                        handle,
+                    synced_managers.object_manager.update_object_transform(
+                        ObjectKey::Dynamic(handle),
                         transform.as_ref(),
                         teleport,
                     );
                 }
+                Instruction::UpdateDynamicObjectJoints {
+                    handle,
+                    joint_matrices,
+                } => {
+                    tracing::trace!(?handle, "update_dynamic_object_joints");
+                    synced_managers
+                        .object_manager
+                        .update_dynamic_object_joints(handle, &joint_matrices);
+                }
+                Instruction::UpdateDynamicObjectMorphWeights { handle, weights } => {
+                    tracing::trace!(?handle, "update_dynamic_object_morph_weights");
+                    synced_managers
+                        .object_manager
+                        .update_dynamic_object_morph_weights(handle, &weights);
+                }
                 Instruction::RemoveStaticObject { handle } => {
                     tracing::trace!(?handle, "remove_static_object");
                     self.handles.static_object_handle_allocator.dealloc(handle);
-                    synced_managers.object_manager.remove_static_object(handle);
+                    synced_managers
+                        .object_manager
+                        .remove_object(ObjectKey::Static(handle));
                 }
                 Instruction::RemoveDynamicObject { handle } => {
                     tracing::trace!(?handle, "remove_dynamic_object");
                     self.handles.dynamic_object_handle_allocator.dealloc(handle);
-                    synced_managers.object_manager.remove_dynamic_object(handle);
+                    synced_managers
+                        .object_manager
+                        .remove_object(ObjectKey::Dynamic(handle));
                 }
                 Instruction::FinishFixedUpdate {
                     updated_at,
@@ -437,6 +1027,12 @@ impl RendererState {
                         .object_manager
                         .finalize_dynamic_object_transforms();
 
+                    let inner_meshes =
+                        mesh_manager_data.get_or_insert_with(|| self.mesh_manager.lock_data());
+                    synced_managers
+                        .object_manager
+                        .migrate_objects(inner_meshes, &mut synced_managers.material_manager);
+
                     synced_managers
                         .time_manager
                         .updated_fixed_time(updated_at, duration);
@@ -484,9 +1080,77 @@ struct RendererStateSyncedManagers {
 #[derive(Default)]
 struct RendererStateHandles {
     mesh_handle_allocator: FreelistHandleAllocator<Mesh>,
-    material_handle_allocator: SimpleHandleAllocator<MaterialInstanceTag>,
-    static_object_handle_allocator: SimpleHandleAllocator<StaticObjectTag>,
-    dynamic_object_handle_allocator: SimpleHandleAllocator<DynamicObjectTag>,
+    material_handle_allocator: FreelistHandleAllocator<MaterialInstanceTag>,
+    static_object_handle_allocator: FreelistHandleAllocator<StaticObjectTag>,
+    dynamic_object_handle_allocator: FreelistHandleAllocator<DynamicObjectTag>,
+}
+
+impl RendererStateHandles {
+    /// Checks that the instruction only refers to live handles.
+    fn validate_instruction(&self, instruction: &Instruction) -> Result<()> {
+        fn check<T: HandleData>(
+            allocator: &FreelistHandleAllocator<T>,
+            handle: RawResourceHandle<T>,
+        ) -> Result<()> {
+            anyhow::ensure!(
+                allocator.is_alive(handle),
+                "use after free: {} {handle:?} is no longer valid",
+                std::any::type_name::<T>()
+            );
+            Ok(())
+        }
+
+        match instruction {
+            Instruction::RemoveMesh { handle } => check(&self.mesh_handle_allocator, *handle),
+            Instruction::AddMaterialInstance { handle, .. }
+            | Instruction::UpdateMaterial { handle, .. }
+            | Instruction::RemoveMaterial { handle } => {
+                check(&self.material_handle_allocator, *handle)
+            }
+            Instruction::AddStaticObject { handle, object } => {
+                check(&self.static_object_handle_allocator, *handle)?;
+                check(&self.mesh_handle_allocator, object.mesh.raw())?;
+                check(&self.material_handle_allocator, object.material.raw())
+            }
+            Instruction::AddDynamicObject { handle, object } => {
+                check(&self.dynamic_object_handle_allocator, *handle)?;
+                check(&self.mesh_handle_allocator, object.mesh.raw())?;
+                check(&self.material_handle_allocator, object.material.raw())
+            }
+            Instruction::SetStaticObjectMesh { handle, mesh } => {
+                check(&self.static_object_handle_allocator, *handle)?;
+                check(&self.mesh_handle_allocator, mesh.raw())
+            }
+            Instruction::SetStaticObjectMaterial { handle, material } => {
+                check(&self.static_object_handle_allocator, *handle)?;
+                check(&self.material_handle_allocator, material.raw())
+            }
+            Instruction::SetDynamicObjectMesh { handle, mesh } => {
+                check(&self.dynamic_object_handle_allocator, *handle)?;
+                check(&self.mesh_handle_allocator, mesh.raw())
+            }
+            Instruction::SetDynamicObjectMaterial { handle, material } => {
+                check(&self.dynamic_object_handle_allocator, *handle)?;
+                check(&self.material_handle_allocator, material.raw())
+            }
+            Instruction::UpdateStaticObject { handle, .. }
+            | Instruction::SetStaticObjectStorage { handle, .. }
+            | Instruction::UpdateStaticObjectVisibility { handle, .. }
+            | Instruction::RemoveStaticObject { handle } => {
+                check(&self.static_object_handle_allocator, *handle)
+            }
+            Instruction::UpdateDynamicObject { handle, .. }
+            | Instruction::SetDynamicObjectStorage { handle, .. }
+            | Instruction::UpdateDynamicObjectVisibility { handle, .. }
+            | Instruction::UpdateDynamicObjectJoints { handle, .. }
+            | Instruction::UpdateDynamicObjectMorphWeights { handle, .. }
+            | Instruction::RemoveDynamicObject { handle } => {
+                check(&self.dynamic_object_handle_allocator, *handle)
+            }
+            Instruction::SetObjectMigrationPolicy { .. }
+            | Instruction::FinishFixedUpdate { .. } => Ok(()),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -530,6 +1194,41 @@ enum Instruction {
         handle: RawDynamicObjectHandle,
         object: Box<ObjectData>,
     },
+    SetStaticObjectMesh {
+        handle: RawStaticObjectHandle,
+        mesh: MeshHandle,
+    },
+    SetStaticObjectMaterial {
+        handle: RawStaticObjectHandle,
+        material: MaterialInstanceHandle,
+    },
+    SetDynamicObjectMesh {
+        handle: RawDynamicObjectHandle,
+        mesh: MeshHandle,
+    },
+    SetDynamicObjectMaterial {
+        handle: RawDynamicObjectHandle,
+        material: MaterialInstanceHandle,
+    },
+    SetStaticObjectStorage {
+        handle: RawStaticObjectHandle,
+        storage: ObjectStorage,
+    },
+    SetDynamicObjectStorage {
+        handle: RawDynamicObjectHandle,
+        storage: ObjectStorage,
+    },
+    SetObjectMigrationPolicy {
+        policy: ObjectMigrationPolicy,
+    },
+    UpdateStaticObjectVisibility {
+        handle: RawStaticObjectHandle,
+        update: VisibilityUpdate,
+    },
+    UpdateDynamicObjectVisibility {
+        handle: RawDynamicObjectHandle,
+        update: VisibilityUpdate,
+    },
     UpdateStaticObject {
         handle: RawStaticObjectHandle,
         transform: Box<Mat4>,
@@ -539,6 +1238,14 @@ enum Instruction {
         transform: Box<Mat4>,
         teleport: bool,
     },
+    UpdateDynamicObjectJoints {
+        handle: RawDynamicObjectHandle,
+        joint_matrices: Box<[Mat4]>,
+    },
+    UpdateDynamicObjectMorphWeights {
+        handle: RawDynamicObjectHandle,
+        weights: Box<[f32]>,
+    },
     RemoveStaticObject {
         handle: RawStaticObjectHandle,
     },
@@ -551,6 +1258,25 @@ enum Instruction {
     },
 }
 
+#[derive(Debug, Clone, Copy)]
+enum VisibilityUpdate {
+    Visible(bool),
+    Flags(ObjectFlags),
+    Layers(RenderLayers),
+}
+
+impl VisibilityUpdate {
+    fn apply(self, visibility: &mut ObjectVisibility) {
+        match self {
+            Self::Visible(visible) => {
+                visibility.flags = visibility.flags.with(ObjectFlags::VISIBLE, visible);
+            }
+            Self::Flags(flags) => visibility.flags = flags,
+            Self::Layers(layers) => visibility.layers = layers,
+        }
+    }
+}
+
 type FnOnAddMaterial = dyn FnOnce(&mut MaterialManager, RawMaterialInstanceHandle) + Send + Sync;
 type FnOnUpdateMaterial = dyn FnOnce(&mut MaterialManager, RawMaterialInstanceHandle) + Send + Sync;
 
@@ -639,15 +1365,50 @@ impl LoopBarrier {
 
 shared::embed!(
     Shaders("../../assets/shaders") = [
+        "math/aabb.glsl",
         "math/color.glsl",
         "math/const.glsl",
+        "math/cubemap.glsl",
+        "math/debug_view.glsl",
         "math/frustum.glsl",
+        "math/sh.glsl",
         "math/sphere.glsl",
+        "math/tonemap.glsl",
+        "math/velocity.glsl",
         "uniforms/bindless.glsl",
+        "uniforms/environment.glsl",
         "uniforms/globals.glsl",
         "uniforms/object.glsl",
+        "uniforms/morph.glsl",
+        "uniforms/skin.glsl",
+        "ambient_occlusion.comp",
+        "ambient_occlusion_blur.comp",
+        "auto_exposure.comp",
+        "bloom_downsample.comp",
+        "bloom_upsample.comp",
+        "depth_pyramid.comp",
+        "environment_irradiance.comp",
+        "environment_prefilter.comp",
+        "environment_to_cube.comp",
+        "luminance_histogram.comp",
+        "occlusion_cull.comp",
         "scatter_copy.comp",
+        "taa_resolve.comp",
         "opaque_mesh.vert",
-        "opaque_mesh.frag"
+        "opaque_mesh.frag",
+        "debug_line.vert",
+        "fullscreen.vert",
+        "skybox.vert",
+        "debug_line.frag",
+        "depth_resolve.frag",
+        "fxaa.frag",
+        "picking.frag",
+        "smaa_blend.frag",
+        "smaa_edges.frag",
+        "smaa_weights.frag",
+        "skybox.frag",
+        "tonemap.frag"
     ]
 );
+
+shared::embed!(Textures("../../assets/textures") = ["smaa/area.bin", "smaa/search.bin"]);
@@ -4,14 +4,14 @@ use std::sync::{Mutex, MutexGuard};
 use anyhow::Result;
 use range_alloc::RangeAllocator;
 
-use crate::types::{Mesh, RawMeshHandle, VertexAttributeKind};
+use crate::types::{Mesh, RawMeshHandle, VertexAttributeEncoding, VertexAttributeKind};
 use crate::util::{
-    AtomicStorageBufferHandle, BindlessResources, BoundingSphere, StorageBufferHandle,
+    AtomicStorageBufferHandle, BindlessResources, BoundingBox, BoundingSphere, StorageBufferHandle,
 };
 
 pub struct MeshManager {
     state: Mutex<MeshManagerState>,
-    registry: Mutex<Vec<Option<GpuMesh>>>,
+    registry: Mutex<Vec<Option<(RawMeshHandle, GpuMesh)>>>,
     vertex_buffer_handle: AtomicStorageBufferHandle,
 }
 
@@ -68,9 +68,9 @@ impl MeshManager {
         state.encoder.take()
     }
 
-    pub fn bind_index_buffer(&self, encoder: &mut gfx::Encoder) {
+    pub fn bind_index_buffer(&self, encoder: &mut gfx::EncoderCommon, index_type: gfx::IndexType) {
         let state = self.state.lock().unwrap();
-        state.buffers.bind_index_buffer(encoder);
+        state.buffers.bind_index_buffer(encoder, index_type);
     }
 
     #[tracing::instrument(level = "debug", name = "upload_mesh", skip_all)]
@@ -96,7 +96,24 @@ impl MeshManager {
             .iter()
             .map(|a| a.byte_len())
             .sum::<usize>();
-        let total_index_size = index_count * (INDEX_SIZE as usize);
+
+        // NOTE: Index ranges are allocated in 32-bit units,
+        // so 16-bit indices are packed in pairs.
+        let packed_indices;
+        let index_type = mesh.index_type();
+        let index_data = match index_type {
+            gfx::IndexType::U16 => {
+                packed_indices = mesh
+                    .indices()
+                    .iter()
+                    .map(|&index| index as u16)
+                    .chain((index_count % 2 == 1).then_some(0))
+                    .collect::<Vec<_>>();
+                bytemuck::cast_slice::<_, u8>(&packed_indices)
+            }
+            gfx::IndexType::U32 => bytemuck::cast_slice(mesh.indices()),
+        };
+        let total_index_size = index_data.len();
 
         let staging_buffer = device.create_mappable_buffer(
             gfx::BufferInfo {
@@ -133,15 +150,18 @@ impl MeshManager {
                     );
                 }
 
-                let range = state.alloc_range_for_vertices(queue, len as _)?;
+                // NOTE: Ranges are aligned so that the lowest bits of the
+                // offset are free to store the attribute encoding.
+                let aligned_len = (len + VERTEX_ALIGN_MASK) & !VERTEX_ALIGN_MASK;
+                let range = state.alloc_range_for_vertices(queue, aligned_len as _)?;
                 tracing::debug!(?range, len, "allocated vertex attribute range");
 
                 vertex_attribute_copies.push(gfx::BufferCopy {
                     src_offset: staging_buffer_offset,
                     dst_offset: range.start as usize,
-                    size: (range.end - range.start) as usize,
+                    size: len,
                 });
-                vertex_attribute_ranges.push((attribute.kind(), range));
+                vertex_attribute_ranges.push((attribute.kind(), attribute.encoding(), range));
 
                 staging_buffer_offset += len;
             }
@@ -149,16 +169,17 @@ impl MeshManager {
             // Allocate range for indices
 
             // SAFETY: `staging_buffer_data` is a valid pointer to a slice with
-            // the exact remaining capacity required for `mesh.indices`.
+            // the exact remaining capacity required for `index_data`.
             unsafe {
                 std::ptr::copy_nonoverlapping(
-                    mesh.indices().as_ptr().cast::<u8>(),
+                    index_data.as_ptr(),
                     staging_buffer_data.add(staging_buffer_offset).cast(),
-                    std::mem::size_of_val::<[u32]>(mesh.indices()),
+                    total_index_size,
                 );
             }
 
-            indices_range = state.alloc_range_for_indices(queue, index_count as _)?;
+            indices_range = state
+                .alloc_range_for_indices(queue, (total_index_size / INDEX_SIZE as usize) as _)?;
             tracing::debug!(range = ?indices_range, "allocated indices range");
 
             indices_copy = gfx::BufferCopy {
@@ -187,9 +208,14 @@ impl MeshManager {
 
         // Done
         Ok(GpuMesh {
+            vertex_count,
+            morph_target_count: mesh.morph_target_count(),
             vertex_attribute_ranges,
             indices_range,
+            index_count: index_count as u32,
+            index_type,
             bounding_sphere: *mesh.bounding_sphere(),
+            bounding_box: *mesh.bounding_box(),
         })
     }
 
@@ -199,7 +225,7 @@ impl MeshManager {
         if index >= registry.len() {
             registry.resize_with(index + 1, || None);
         }
-        registry[index] = Some(mesh);
+        registry[index] = Some((handle, mesh));
     }
 
     #[tracing::instrument(level = "debug", name = "remove_mesh", skip_all, fields(index = %handle.index))]
@@ -207,12 +233,15 @@ impl MeshManager {
         let index = handle.index;
         let mesh = {
             let mut registry = self.registry.lock().unwrap();
-            registry[index].take().expect("handle must be valid")
+            match registry[index].take() {
+                Some((registered, mesh)) if registered == handle => mesh,
+                _ => panic!("mesh handle {handle:?} is not valid"),
+            }
         };
 
         let mut state = self.state.lock().unwrap();
 
-        for (_, range) in mesh.vertex_attribute_ranges {
+        for (_, _, range) in mesh.vertex_attribute_ranges {
             if !range.is_empty() {
                 state.vertex_alloc.free_range(range.clone());
                 tracing::debug!(?range, "freed vertex attribute range");
@@ -227,22 +256,16 @@ impl MeshManager {
 }
 
 pub struct MeshManagerDataGuard<'a> {
-    registry: MutexGuard<'a, Vec<Option<GpuMesh>>>,
+    registry: MutexGuard<'a, Vec<Option<(RawMeshHandle, GpuMesh)>>>,
 }
 
-impl std::ops::Deref for MeshManagerDataGuard<'_> {
-    type Target = Vec<Option<GpuMesh>>;
-
-    #[inline]
-    fn deref(&self) -> &Self::Target {
-        self.registry.deref()
-    }
-}
-
-impl std::ops::DerefMut for MeshManagerDataGuard<'_> {
-    #[inline]
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.registry.deref_mut()
+impl MeshManagerDataGuard<'_> {
+    /// Returns the mesh if the handle is still valid (including its generation).
+    pub fn get(&self, handle: RawMeshHandle) -> Option<&GpuMesh> {
+        match self.registry.get(handle.index)? {
+            Some((registered, mesh)) if *registered == handle => Some(mesh),
+            _ => None,
+        }
     }
 }
 
@@ -386,39 +409,78 @@ impl MeshManagerState {
 }
 
 pub struct GpuMesh {
-    vertex_attribute_ranges: Vec<(VertexAttributeKind, Range<u32>)>,
+    vertex_count: u32,
+    morph_target_count: u32,
+    vertex_attribute_ranges: Vec<(VertexAttributeKind, VertexAttributeEncoding, Range<u32>)>,
+    /// Allocated range in 32-bit units.
     indices_range: Range<u32>,
+    index_count: u32,
+    index_type: gfx::IndexType,
     bounding_sphere: BoundingSphere,
+    bounding_box: BoundingBox,
 }
 
 impl GpuMesh {
     pub fn new_empty() -> Self {
         Self {
+            vertex_count: 0,
+            morph_target_count: 0,
             vertex_attribute_ranges: Default::default(),
             indices_range: 0..0,
+            index_count: 0,
+            index_type: INDEX_TYPE,
             bounding_sphere: BoundingSphere::compute_from_positions(&[]),
+            bounding_box: BoundingBox::compute_from_positions(&[]),
         }
     }
 
+    pub fn vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    pub fn morph_target_count(&self) -> u32 {
+        self.morph_target_count
+    }
+
     pub fn attributes(&self) -> impl Iterator<Item = VertexAttributeKind> + '_ {
         self.vertex_attribute_ranges
             .iter()
-            .map(|(component, _)| *component)
+            .map(|(component, _, _)| *component)
     }
 
     pub fn get_attribute_range(&self, attribute: VertexAttributeKind) -> Option<Range<u32>> {
         self.vertex_attribute_ranges
             .iter()
-            .find_map(|(c, range)| (*c == attribute).then_some(range.clone()))
+            .find_map(|(c, _, range)| (*c == attribute).then_some(range.clone()))
     }
 
+    pub fn get_attribute_encoding(
+        &self,
+        attribute: VertexAttributeKind,
+    ) -> Option<VertexAttributeEncoding> {
+        self.vertex_attribute_ranges
+            .iter()
+            .find_map(|(c, encoding, _)| (*c == attribute).then_some(*encoding))
+    }
+
+    /// Range of indices to draw (in units of [`GpuMesh::index_type`]).
     pub fn indices(&self) -> Range<u32> {
-        self.indices_range.clone()
+        let first_index =
+            self.indices_range.start * INDEX_SIZE / self.index_type.index_size() as u32;
+        first_index..first_index + self.index_count
+    }
+
+    pub fn index_type(&self) -> gfx::IndexType {
+        self.index_type
     }
 
     pub fn bounding_sphere(&self) -> &BoundingSphere {
         &self.bounding_sphere
     }
+
+    pub fn bounding_box(&self) -> &BoundingBox {
+        &self.bounding_box
+    }
 }
 
 struct MeshBuffers {
@@ -434,8 +496,8 @@ impl MeshBuffers {
         })
     }
 
-    fn bind_index_buffer(&self, encoder: &mut gfx::Encoder) {
-        encoder.bind_index_buffer(&self.indices, 0, INDEX_TYPE);
+    fn bind_index_buffer(&self, encoder: &mut gfx::EncoderCommon, index_type: gfx::IndexType) {
+        encoder.bind_index_buffer(&self.indices, 0, index_type);
     }
 }
 
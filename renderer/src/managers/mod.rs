@@ -1,6 +1,6 @@
 pub use self::material_manager::MaterialManager;
 pub use self::mesh_manager::{GpuMesh, MeshManager, MeshManagerDataGuard};
-pub use self::object_manager::{ObjectManager, GpuObject};
+pub use self::object_manager::{GpuJointMatrix, GpuObject, ObjectManager};
 pub use self::time_manager::TimeManager;
 
 mod material_manager;
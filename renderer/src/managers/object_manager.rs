@@ -10,20 +10,21 @@ use shared::FastHashMap;
 
 use crate::managers::{GpuMesh, MaterialManager, MeshManagerDataGuard};
 use crate::types::{
-    MaterialInstance, MaterialInstanceHandle, MeshHandle, ObjectData, RawDynamicObjectHandle,
-    RawStaticObjectHandle, VertexAttributeArray, VertexAttributeKind,
+    MaterialInstance, MaterialInstanceHandle, MeshHandle, ObjectData, ObjectKey,
+    ObjectMigrationPolicy, ObjectStorage, ObjectVisibility, PickingId, RawDynamicObjectHandle,
+    RawStaticObjectHandle, VertexAttributeArray, VertexAttributeEncodings, VertexAttributeKind,
 };
 use crate::util::{
-    BindlessResources, BoundingSphere, FreelistDoubleBuffer, MultiBufferArena, ScatterCopy,
-    StorageBufferHandle,
+    BindlessResources, BoundingBox, BoundingSphere, BufferArena, FreelistDoubleBuffer,
+    MultiBufferArena, ScatterCopy, StorageBufferHandle,
 };
 
 #[derive(Default)]
 pub struct ObjectManager {
-    static_handles: FastHashMap<RawStaticObjectHandle, HandleData>,
+    handles: FastHashMap<ObjectKey, HandleData>,
     static_archetypes: FastHashMap<TypeId, StaticObjectArchetype>,
-    dynamic_handles: FastHashMap<RawDynamicObjectHandle, HandleData>,
     dynamic_archetypes: FastHashMap<TypeId, DynamicObjectArchetype>,
+    migration_policy: ObjectMigrationPolicy,
 }
 
 impl ObjectManager {
@@ -75,18 +76,12 @@ impl ObjectManager {
         mesh_manager_data: &MeshManagerDataGuard,
         material_manager: &mut MaterialManager,
     ) {
-        let mesh = mesh_manager_data[object.mesh.index()]
-            .as_ref()
-            .expect("invalid mesh handle");
-
-        material_manager.write_static_object(
-            object.material.raw(),
-            WriteStaticObject {
-                mesh,
-                handle,
-                object,
-                object_manager: Some(self),
-            },
+        self.write_object(
+            ObjectKey::Static(handle),
+            ObjectStorage::Static,
+            object,
+            mesh_manager_data,
+            material_manager,
         );
     }
 
@@ -98,72 +93,177 @@ impl ObjectManager {
         mesh_manager_data: &MeshManagerDataGuard,
         material_manager: &mut MaterialManager,
     ) {
-        let mesh = mesh_manager_data[object.mesh.index()]
-            .as_ref()
-            .expect("invalid mesh handle");
-
-        material_manager.write_dynamic_object(
-            object.material.raw(),
-            WriteDynamicObject {
-                mesh,
-                handle,
-                object,
-                object_manager: Some(self),
-            },
+        self.write_object(
+            ObjectKey::Dynamic(handle),
+            ObjectStorage::Dynamic,
+            object,
+            mesh_manager_data,
+            material_manager,
         );
     }
 
-    #[tracing::instrument(level = "debug", name = "update_static_object", skip_all)]
-    pub fn update_static_object(&mut self, handle: RawStaticObjectHandle, transform: &Mat4) {
-        let HandleData { archetype, slot } = &self.static_handles[&handle];
+    /// Replaces parts of an existing object (e.g. its mesh or material).
+    ///
+    /// The object is moved into another archetype if the material type changes,
+    /// but keeps its handle, storage and transform. Dynamic objects also keep
+    /// their interpolated transforms, joints and morph weights.
+    #[tracing::instrument(level = "debug", name = "modify_object", skip_all)]
+    pub fn modify_object(
+        &mut self,
+        key: ObjectKey,
+        modify: impl FnOnce(&mut ObjectData),
+        mesh_manager_data: &MeshManagerDataGuard,
+        material_manager: &mut MaterialManager,
+    ) {
+        let (storage, mut object, state) = self.take_object(key);
+        modify(&mut object);
 
-        let archetype = self
-            .static_archetypes
-            .get_mut(archetype)
-            .expect("invalid handle archetype");
+        self.write_object(key, storage, object, mesh_manager_data, material_manager);
 
-        (archetype.update_transform)(archetype, *slot, transform);
+        if let Some(state) = state {
+            let ObjectSlotMut::Dynamic(archetype, slot) = self.object_slot_mut(key) else {
+                unreachable!("object storage must not change");
+            };
+            (archetype.restore)(archetype, slot, state);
+        }
     }
 
-    #[tracing::instrument(level = "debug", name = "update_dynamic_object", skip_all)]
-    pub fn update_dynamic_object(
+    /// Moves an object between static and dynamic storage, keeping its handle.
+    ///
+    /// Objects with joints or morph weights are never moved into static storage.
+    #[tracing::instrument(level = "debug", name = "set_object_storage", skip_all)]
+    pub fn set_object_storage(
         &mut self,
-        handle: RawDynamicObjectHandle,
-        transform: &Mat4,
-        teleport: bool,
+        key: ObjectKey,
+        storage: ObjectStorage,
+        mesh_manager_data: &MeshManagerDataGuard,
+        material_manager: &mut MaterialManager,
     ) {
-        let HandleData { archetype, slot } = &self.dynamic_handles[&handle];
+        match self.object_slot_mut(key) {
+            ObjectSlotMut::Static(..) if storage == ObjectStorage::Static => return,
+            ObjectSlotMut::Dynamic(..) if storage == ObjectStorage::Dynamic => return,
+            ObjectSlotMut::Dynamic(archetype, slot) if (archetype.is_animated)(archetype, slot) => {
+                tracing::warn!(
+                    ?key,
+                    "animated objects can not be moved into static storage"
+                );
+                return;
+            }
+            _ => {}
+        }
 
-        let archetype = self
-            .dynamic_archetypes
-            .get_mut(archetype)
-            .expect("invalid handle archetype");
+        // NOTE: interpolation starts over from the latest transform
+        let (_, object, _) = self.take_object(key);
+        self.write_object(key, storage, object, mesh_manager_data, material_manager);
+        tracing::debug!(?key, ?storage, "moved object");
+    }
 
-        (archetype.update_transform)(archetype, *slot, transform, teleport);
+    pub fn set_migration_policy(&mut self, policy: ObjectMigrationPolicy) {
+        self.migration_policy = policy;
     }
 
-    #[tracing::instrument(level = "debug", name = "remove_static_object", skip_all)]
-    pub fn remove_static_object(&mut self, handle: RawStaticObjectHandle) {
-        let HandleData { archetype, slot } = &self.static_handles[&handle];
+    /// Moves objects between static and dynamic storage according to the migration policy.
+    ///
+    /// Must be called once per fixed update after [`Self::finalize_dynamic_object_transforms`].
+    #[tracing::instrument(level = "debug", name = "migrate_objects", skip_all)]
+    pub fn migrate_objects(
+        &mut self,
+        mesh_manager_data: &MeshManagerDataGuard,
+        material_manager: &mut MaterialManager,
+    ) {
+        let policy = self.migration_policy;
+
+        let mut migrations = Vec::new();
+        // NOTE: static objects are always visited to reset their update tracking,
+        // promotions are only collected if `promote_after` is set
+        for archetype in self.static_archetypes.values_mut() {
+            (archetype.collect_promotions)(archetype, policy.promote_after, &mut migrations);
+        }
+        if let Some(demote_after) = policy.demote_after {
+            for archetype in self.dynamic_archetypes.values_mut() {
+                (archetype.collect_demotions)(archetype, demote_after, &mut migrations);
+            }
+        }
+
+        for (key, storage) in migrations {
+            self.set_object_storage(key, storage, mesh_manager_data, material_manager);
+        }
+    }
 
-        let archetype = self
-            .static_archetypes
-            .get_mut(archetype)
-            .expect("invalid handle archetype");
+    #[tracing::instrument(level = "debug", name = "update_object_visibility", skip_all)]
+    pub fn update_object_visibility(
+        &mut self,
+        key: ObjectKey,
+        update: impl FnOnce(&mut ObjectVisibility),
+    ) {
+        match self.object_slot_mut(key) {
+            ObjectSlotMut::Static(archetype, slot) => {
+                update((archetype.visibility_mut)(archetype, slot));
+                archetype.buffer.update_slot(slot);
+            }
+            ObjectSlotMut::Dynamic(archetype, slot) => {
+                update((archetype.visibility_mut)(archetype, slot));
+            }
+        }
+    }
 
-        (archetype.remove)(archetype, *slot);
+    /// Updates the object transform.
+    ///
+    /// `teleport` disables interpolation of objects in dynamic storage.
+    #[tracing::instrument(level = "debug", name = "update_object_transform", skip_all)]
+    pub fn update_object_transform(&mut self, key: ObjectKey, transform: &Mat4, teleport: bool) {
+        match self.object_slot_mut(key) {
+            ObjectSlotMut::Static(archetype, slot) => {
+                (archetype.update_transform)(archetype, slot, transform);
+            }
+            ObjectSlotMut::Dynamic(archetype, slot) => {
+                (archetype.update_transform)(archetype, slot, transform, teleport);
+            }
+        }
     }
 
-    #[tracing::instrument(level = "debug", name = "remove_dynamic_object", skip_all)]
-    pub fn remove_dynamic_object(&mut self, handle: RawDynamicObjectHandle) {
-        let HandleData { archetype, slot } = &self.dynamic_handles[&handle];
+    #[tracing::instrument(level = "debug", name = "update_dynamic_object_joints", skip_all)]
+    pub fn update_dynamic_object_joints(
+        &mut self,
+        handle: RawDynamicObjectHandle,
+        joint_matrices: &[Mat4],
+    ) {
+        match self.object_slot_mut(ObjectKey::Dynamic(handle)) {
+            ObjectSlotMut::Dynamic(archetype, slot) => {
+                (archetype.update_joints)(archetype, slot, joint_matrices);
+            }
+            ObjectSlotMut::Static(..) => {
+                tracing::warn!(?handle, "joints of objects in static storage are ignored");
+            }
+        }
+    }
 
-        let archetype = self
-            .dynamic_archetypes
-            .get_mut(archetype)
-            .expect("invalid handle archetype");
+    #[tracing::instrument(
+        level = "debug",
+        name = "update_dynamic_object_morph_weights",
+        skip_all
+    )]
+    pub fn update_dynamic_object_morph_weights(
+        &mut self,
+        handle: RawDynamicObjectHandle,
+        weights: &[f32],
+    ) {
+        match self.object_slot_mut(ObjectKey::Dynamic(handle)) {
+            ObjectSlotMut::Dynamic(archetype, slot) => {
+                (archetype.update_morph_weights)(archetype, slot, weights);
+            }
+            ObjectSlotMut::Static(..) => {
+                tracing::warn!(
+                    ?handle,
+                    "morph weights of objects in static storage are ignored"
+                );
+            }
+        }
+    }
 
-        (archetype.remove)(archetype, *slot);
+    #[tracing::instrument(level = "debug", name = "remove_object", skip_all)]
+    pub fn remove_object(&mut self, key: ObjectKey) {
+        drop(self.take_object(key));
     }
 
     #[tracing::instrument(level = "debug", name = "flush_static_objects", skip_all)]
@@ -197,6 +297,79 @@ impl ObjectManager {
         }
     }
 
+    fn write_object(
+        &mut self,
+        key: ObjectKey,
+        storage: ObjectStorage,
+        object: Box<ObjectData>,
+        mesh_manager_data: &MeshManagerDataGuard,
+        material_manager: &mut MaterialManager,
+    ) {
+        let mesh = mesh_manager_data
+            .get(object.mesh.raw())
+            .expect("invalid mesh handle");
+        let material = object.material.raw();
+
+        match storage {
+            ObjectStorage::Static => material_manager.write_static_object(
+                material,
+                WriteStaticObject {
+                    mesh,
+                    key,
+                    object,
+                    object_manager: Some(self),
+                },
+            ),
+            ObjectStorage::Dynamic => material_manager.write_dynamic_object(
+                material,
+                WriteDynamicObject {
+                    mesh,
+                    key,
+                    object,
+                    object_manager: Some(self),
+                },
+            ),
+        }
+    }
+
+    fn take_object(
+        &mut self,
+        key: ObjectKey,
+    ) -> (ObjectStorage, Box<ObjectData>, Option<DynamicObjectState>) {
+        let (object, state) = match self.object_slot_mut(key) {
+            ObjectSlotMut::Static(archetype, slot) => ((archetype.take)(archetype, slot), None),
+            ObjectSlotMut::Dynamic(archetype, slot) => {
+                let (object, state) = (archetype.take)(archetype, slot);
+                (object, Some(state))
+            }
+        };
+        let HandleData { storage, .. } = self.handles.remove(&key).expect("invalid object handle");
+        (storage, object, state)
+    }
+
+    fn object_slot_mut(&mut self, key: ObjectKey) -> ObjectSlotMut<'_> {
+        let HandleData {
+            storage,
+            archetype,
+            slot,
+        } = self.handles.get(&key).expect("invalid object handle");
+
+        match storage {
+            ObjectStorage::Static => ObjectSlotMut::Static(
+                self.static_archetypes
+                    .get_mut(archetype)
+                    .expect("invalid handle archetype"),
+                *slot,
+            ),
+            ObjectStorage::Dynamic => ObjectSlotMut::Dynamic(
+                self.dynamic_archetypes
+                    .get_mut(archetype)
+                    .expect("invalid handle archetype"),
+                *slot,
+            ),
+        }
+    }
+
     fn get_or_create_static_object_archetype<M: MaterialInstance>(
         &mut self,
     ) -> &mut StaticObjectArchetype {
@@ -211,7 +384,9 @@ impl ObjectManager {
                 free_slots: Vec::new(),
                 flush: flush_static_object::<M::SupportedAttributes>,
                 update_transform: update_static_object_transform::<M::SupportedAttributes>,
-                remove: remove_static_object::<M::SupportedAttributes>,
+                visibility_mut: static_object_visibility_mut::<M::SupportedAttributes>,
+                collect_promotions: collect_static_object_promotions::<M::SupportedAttributes>,
+                take: take_static_object::<M::SupportedAttributes>,
             }),
         }
     }
@@ -229,7 +404,13 @@ impl ObjectManager {
                 free_slots: Vec::new(),
                 finalize_transforms: finalize_dynamic_object_transforms::<M::SupportedAttributes>,
                 update_transform: update_dynamic_object_transform::<M::SupportedAttributes>,
-                remove: remove_dynamic_object::<M::SupportedAttributes>,
+                update_joints: update_dynamic_object_joints::<M::SupportedAttributes>,
+                update_morph_weights: update_dynamic_object_morph_weights::<M::SupportedAttributes>,
+                visibility_mut: dynamic_object_visibility_mut::<M::SupportedAttributes>,
+                is_animated: is_dynamic_object_animated::<M::SupportedAttributes>,
+                collect_demotions: collect_dynamic_object_demotions::<M::SupportedAttributes>,
+                take: take_dynamic_object::<M::SupportedAttributes>,
+                restore: restore_dynamic_object_state::<M::SupportedAttributes>,
             }),
         }
     }
@@ -238,10 +419,16 @@ impl ObjectManager {
 const INITIAL_BUFFER_CAPACITY: u32 = 16;
 
 struct HandleData {
+    storage: ObjectStorage,
     archetype: TypeId,
     slot: u32,
 }
 
+enum ObjectSlotMut<'a> {
+    Static(&'a mut StaticObjectArchetype, u32),
+    Dynamic(&'a mut DynamicObjectArchetype, u32),
+}
+
 struct StaticObjectArchetype {
     data: AnyVec,
     buffer: FreelistDoubleBuffer,
@@ -250,7 +437,9 @@ struct StaticObjectArchetype {
     free_slots: Vec<u32>,
     flush: fn(&mut StaticObjectArchetype, FlushStaticObject) -> Result<()>,
     update_transform: fn(&mut StaticObjectArchetype, u32, &Mat4),
-    remove: fn(&mut StaticObjectArchetype, u32),
+    visibility_mut: fn(&mut StaticObjectArchetype, u32) -> &mut ObjectVisibility,
+    collect_promotions: fn(&mut StaticObjectArchetype, Option<u32>, &mut Vec<ObjectMigration>),
+    take: fn(&mut StaticObjectArchetype, u32) -> Box<ObjectData>,
 }
 
 struct DynamicObjectArchetype {
@@ -260,7 +449,50 @@ struct DynamicObjectArchetype {
     free_slots: Vec<u32>,
     finalize_transforms: fn(&mut DynamicObjectArchetype),
     update_transform: fn(&mut DynamicObjectArchetype, u32, &Mat4, bool),
-    remove: fn(&mut DynamicObjectArchetype, u32),
+    update_joints: fn(&mut DynamicObjectArchetype, u32, &[Mat4]),
+    update_morph_weights: fn(&mut DynamicObjectArchetype, u32, &[f32]),
+    visibility_mut: fn(&mut DynamicObjectArchetype, u32) -> &mut ObjectVisibility,
+    is_animated: fn(&DynamicObjectArchetype, u32) -> bool,
+    collect_demotions: fn(&mut DynamicObjectArchetype, u32, &mut Vec<ObjectMigration>),
+    take: fn(&mut DynamicObjectArchetype, u32) -> (Box<ObjectData>, DynamicObjectState),
+    restore: fn(&mut DynamicObjectArchetype, u32, DynamicObjectState),
+}
+
+type ObjectMigration = (ObjectKey, ObjectStorage);
+
+/// Dynamic object data which is preserved when the object changes its archetype.
+struct DynamicObjectState {
+    prev_global_transform: GlobalTransform,
+    next_global_transform: GlobalTransform,
+    updated: bool,
+    skin: Option<Box<InterpolatedSlice<Mat4>>>,
+    morph_weights: Option<Box<InterpolatedSlice<f32>>>,
+}
+
+/// Number of consecutive fixed updates during which a static object was moved.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateStreak {
+    updated: bool,
+    consecutive_updates: u32,
+}
+
+impl UpdateStreak {
+    pub fn mark_updated(&mut self) {
+        self.updated = true;
+    }
+
+    /// Ends the current fixed update and returns whether the object must be promoted.
+    ///
+    /// Must be called on every fixed update, otherwise a stale update
+    /// counts towards the streak once promotions are enabled.
+    pub fn end_fixed_update(&mut self, promote_after: Option<u32>) -> bool {
+        if std::mem::take(&mut self.updated) {
+            self.consecutive_updates = self.consecutive_updates.saturating_add(1);
+        } else {
+            self.consecutive_updates = 0;
+        }
+        matches!(promote_after, Some(n) if self.consecutive_updates >= n)
+    }
 }
 
 type StaticSlotData<A> = Option<InternalStaticObject<<A as VertexAttributeArray>::U32Array>>;
@@ -271,13 +503,21 @@ pub struct InternalStaticObject<A> {
     // This is used to drop handles when the object is removed,
     // but allows to sync the GPU data with `enabled: false`.
     pub enabled_object_data: Option<EnabledObjectData>,
+    pub key: ObjectKey,
+    pub visibility: ObjectVisibility,
     pub mesh_bounding_sphere: BoundingSphere,
+    pub mesh_bounding_box: BoundingBox,
+
+    // NOTE: used to move frequently updated objects into dynamic storage
+    pub update_streak: UpdateStreak,
 
     pub global_transform: Mat4,
     pub global_bounding_sphere: BoundingSphere,
+    pub global_bounding_box: BoundingBox,
     pub vertex_attribute_offsets: A,
     pub first_index: u32,
     pub index_count: u32,
+    pub index_type: gfx::IndexType,
     pub material_slot: u32,
 }
 
@@ -287,7 +527,11 @@ impl<A> InternalStaticObject<A> {
             self.first_index,
             self.index_count,
             self.material_slot,
-            self.enabled_object_data.is_some() as _,
+            // NOTE: removed objects have no flags, so they are never visible
+            match self.enabled_object_data {
+                Some(_) => self.visibility.gpu_bits(),
+                None => 0,
+            },
         )
     }
 }
@@ -302,8 +546,14 @@ where
         GpuObject {
             transform: self.global_transform,
             transform_inverse_transpose: self.global_transform.inverse().transpose(),
+            // NOTE: static objects are not interpolated, so their updates are not tracked in motion vectors
+            previous_transform: self.global_transform,
             bounding_sphere: self.global_bounding_sphere.into(),
+            bounding_box: self.global_bounding_box.into(),
             data: self.make_data(),
+            skin: UVec4::ZERO,
+            morph: UVec4::ZERO,
+            id: PickingId::from_key(self.key).encode(),
             vertex_attribute_offsets: self.vertex_attribute_offsets,
         }
     }
@@ -311,15 +561,26 @@ where
     fn write_as_std430(&self, dst: &mut Self::Output) {
         dst.transform = self.global_transform;
         dst.transform_inverse_transpose = self.global_transform.inverse().transpose();
+        dst.previous_transform = self.global_transform;
         dst.bounding_sphere = self.global_bounding_sphere.into();
+        dst.bounding_box = self.global_bounding_box.into();
         dst.data = self.make_data();
+        dst.skin = UVec4::ZERO;
+        dst.morph = UVec4::ZERO;
+        dst.id = PickingId::from_key(self.key).encode();
         dst.vertex_attribute_offsets = self.vertex_attribute_offsets;
     }
 }
 
 pub struct InternalDynamicObject<A> {
     pub enabled_object_data: EnabledObjectData,
+    pub key: ObjectKey,
+    pub visibility: ObjectVisibility,
     pub mesh_bounding_sphere: BoundingSphere,
+    pub mesh_bounding_box: BoundingBox,
+
+    // NOTE: used to move idle objects into static storage
+    pub idle_updates: u32,
 
     pub prev_global_transform: GlobalTransform,
     pub next_global_transform: GlobalTransform,
@@ -329,7 +590,14 @@ pub struct InternalDynamicObject<A> {
     // NOTE: `updated` flag is stored here to reduce the object size.
     // Index is unlikely to be greater than 2^31.
     pub index_count_and_updated: U32WithBool,
+    pub index_type: gfx::IndexType,
     pub material_slot: u32,
+
+    pub vertex_count: u32,
+    pub morph_target_count: u32,
+
+    pub skin: Option<Box<InterpolatedSlice<Mat4>>>,
+    pub morph_weights: Option<Box<InterpolatedSlice<f32>>>,
 }
 
 impl<A> InternalDynamicObject<A> {
@@ -343,7 +611,53 @@ impl<A> InternalDynamicObject<A> {
         self.index_count_and_updated.get_u32()
     }
 
-    pub fn as_interpolated_std430(&self, t: f32) -> GpuObject<A>
+    #[inline]
+    pub fn joint_count(&self) -> u32 {
+        match &self.skin {
+            Some(skin) => skin.len() as u32,
+            None => 0,
+        }
+    }
+
+    #[inline]
+    pub fn morph_weight_count(&self) -> u32 {
+        match &self.morph_weights {
+            Some(weights) => weights.len() as u32,
+            None => 0,
+        }
+    }
+
+    /// Returns whether skinning or morph targets can move vertices outside of
+    /// the bounding volumes of the mesh.
+    #[inline]
+    pub fn is_deformed(&self) -> bool {
+        self.skin.is_some() || self.morph_weights.is_some()
+    }
+
+    /// Computes global bounding volumes for the interpolation factor `t`.
+    pub fn interpolated_bounds(&self, t: f32) -> (BoundingSphere, BoundingBox) {
+        let transform = self
+            .prev_global_transform
+            .as_interpolated_matrix(&self.next_global_transform, t);
+        (
+            self.mesh_bounding_sphere.transformed(&transform),
+            self.mesh_bounding_box.transformed(&transform),
+        )
+    }
+
+    /// Computes GPU object data.
+    ///
+    /// `previous_t` is the interpolation factor of the previous frame relative
+    /// to the current fixed update interval, it is used for motion vectors.
+    /// `joints` and `morph_weights` are buffers with per-object data and
+    /// the index of the first item of this object in them.
+    pub fn as_interpolated_std430(
+        &self,
+        t: f32,
+        previous_t: f32,
+        joints: Option<(StorageBufferHandle, u32)>,
+        morph_weights: Option<(StorageBufferHandle, u32)>,
+    ) -> GpuObject<A>
     where
         A: gfx::Std430,
     {
@@ -353,9 +667,29 @@ impl<A> InternalDynamicObject<A> {
 
         GpuObject {
             transform_inverse_transpose: transform.inverse().transpose(),
+            previous_transform: self
+                .prev_global_transform
+                .as_interpolated_matrix(&self.next_global_transform, previous_t),
             bounding_sphere: self.mesh_bounding_sphere.transformed(&transform).into(),
+            bounding_box: self.mesh_bounding_box.transformed(&transform).into(),
             transform,
             data: self.make_data(),
+            skin: match joints {
+                Some((buffer, first_joint)) if self.skin.is_some() => {
+                    glam::uvec4(buffer.index(), first_joint, self.joint_count(), 0)
+                }
+                _ => UVec4::ZERO,
+            },
+            morph: match morph_weights {
+                Some((buffer, first_weight)) if self.morph_weights.is_some() => glam::uvec4(
+                    buffer.index(),
+                    first_weight,
+                    self.morph_weight_count().min(self.morph_target_count),
+                    self.vertex_count,
+                ),
+                _ => UVec4::ZERO,
+            },
+            id: PickingId::from_key(self.key).encode(),
             vertex_attribute_offsets: self.vertex_attribute_offsets,
         }
     }
@@ -365,17 +699,105 @@ impl<A> InternalDynamicObject<A> {
             self.first_index,
             self.index_count(),
             self.material_slot,
-            true as _, // NOTE: dynamic objects are always enabled if they exist
+            self.visibility.gpu_bits(),
         )
     }
 }
 
+pub type GpuJointMatrix = <Mat4 as gfx::AsStd430>::Output;
+
+/// Per-object array which is interpolated between fixed updates
+/// in the same way as transforms.
+pub struct InterpolatedSlice<T> {
+    prev: Box<[T]>,
+    next: Box<[T]>,
+    updated: bool,
+}
+
+impl<T: Copy> InterpolatedSlice<T> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    pub fn interpolated<'a>(
+        &'a self,
+        t: f32,
+        lerp: fn(&T, &T, f32) -> T,
+    ) -> impl Iterator<Item = T> + 'a {
+        let t = t.clamp(0.0, 1.0);
+        std::iter::zip(&*self.prev, &*self.next).map(move |(prev, next)| lerp(prev, next, t))
+    }
+
+    fn update(this: &mut Option<Box<Self>>, values: &[T]) {
+        match this {
+            Some(this) if this.next.len() == values.len() => {
+                if !this.updated {
+                    // Update the previous values on the first update.
+                    std::mem::swap(&mut this.prev, &mut this.next);
+                }
+                this.next.copy_from_slice(values);
+                this.updated = true;
+            }
+            // New values (or values with a different length) are not interpolated
+            this => {
+                *this = Some(Box::new(Self {
+                    prev: values.into(),
+                    next: values.into(),
+                    updated: true,
+                }));
+            }
+        }
+    }
+
+    fn finalize(&mut self) {
+        if self.updated {
+            self.updated = false;
+        } else {
+            self.prev.copy_from_slice(&self.next);
+        }
+    }
+}
+
+impl InterpolatedSlice<Mat4> {
+    pub fn write_interpolated(&self, t: f32, arena: &mut BufferArena<GpuJointMatrix>) {
+        // NOTE: Component-wise interpolation is not correct for rotations,
+        // but the difference is negligible for small time steps.
+        let lerp = |prev: &Mat4, next: &Mat4, t: f32| {
+            Mat4::from_cols(
+                prev.x_axis.lerp(next.x_axis, t),
+                prev.y_axis.lerp(next.y_axis, t),
+                prev.z_axis.lerp(next.z_axis, t),
+                prev.w_axis.lerp(next.w_axis, t),
+            )
+        };
+        for matrix in self.interpolated(t, lerp) {
+            arena.write(&matrix.as_std430());
+        }
+    }
+}
+
+impl InterpolatedSlice<f32> {
+    pub fn write_interpolated(&self, t: f32, arena: &mut BufferArena<f32>) {
+        let lerp = |prev: &f32, next: &f32, t: f32| prev + (next - prev) * t;
+        for weight in self.interpolated(t, lerp) {
+            arena.write(&weight);
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct GpuObject<A> {
     transform: Mat4,
     transform_inverse_transpose: Mat4,
+    previous_transform: Mat4,
     bounding_sphere: Vec4,
+    bounding_box: [Vec4; 2],
     data: UVec4,
+    skin: UVec4,
+    morph: UVec4,
+    /// Encoded [`PickingId`] of the object handle.
+    id: u32,
     vertex_attribute_offsets: A,
 }
 
@@ -390,8 +812,8 @@ unsafe impl<A: gfx::Std430> gfx::Std430 for GpuObject<A> {
 }
 
 pub struct EnabledObjectData {
-    pub _mesh_handle: MeshHandle,
-    pub _material_handle: MaterialInstanceHandle,
+    pub mesh_handle: MeshHandle,
+    pub material_handle: MaterialInstanceHandle,
 }
 
 #[derive(Clone, Copy)]
@@ -402,6 +824,10 @@ pub struct GlobalTransform {
 }
 
 impl GlobalTransform {
+    fn to_matrix(self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
     fn as_interpolated_matrix(&self, other: &Self, t: f32) -> Mat4 {
         Mat4::from_scale_rotation_translation(
             self.scale.lerp(other.scale, t),
@@ -508,7 +934,7 @@ impl<'a, A> ExactSizeIterator for DynamicObjectsIter<'a, A> where A: VertexAttri
 
 pub(crate) struct WriteStaticObject<'a> {
     mesh: &'a GpuMesh,
-    handle: RawStaticObjectHandle,
+    key: ObjectKey,
     object: Box<ObjectData>,
     object_manager: Option<&'a mut ObjectManager>,
 }
@@ -517,18 +943,20 @@ impl WriteStaticObject<'_> {
     pub fn run<M: MaterialInstance>(mut self, material_slot: u32) {
         let object_manager = self.object_manager.take().expect("must always be some");
         let archetype = object_manager.get_or_create_static_object_archetype::<M>();
-        let handle = self.handle;
+        let key = self.key;
 
         let slot = self.fill_slot(
             material_slot,
             M::required_attributes().as_ref(),
             &M::supported_attributes(),
+            &M::vertex_encodings(),
             archetype,
         );
 
-        object_manager.static_handles.insert(
-            handle,
+        object_manager.handles.insert(
+            key,
             HandleData {
+                storage: ObjectStorage::Static,
                 archetype: TypeId::of::<M>(),
                 slot,
             },
@@ -540,34 +968,47 @@ impl WriteStaticObject<'_> {
         material_slot: u32,
         required_attributes: &[VertexAttributeKind],
         supported_attributes: &A,
+        vertex_encodings: &VertexAttributeEncodings,
         archetype: &mut StaticObjectArchetype,
     ) -> u32
     where
         A: VertexAttributeArray,
     {
-        let vertex_attribute_offsets =
-            make_vertex_attribute_offsets(self.mesh, required_attributes, supported_attributes);
+        let vertex_attribute_offsets = make_vertex_attribute_offsets(
+            self.mesh,
+            required_attributes,
+            supported_attributes,
+            vertex_encodings,
+        );
 
         let indices = self.mesh.indices();
         let first_index = indices.start;
         let index_count = indices.end - indices.start;
 
-        // Compute bounding sphere in global space
+        // Compute bounding volumes in global space
         let mesh_bounding_sphere = *self.mesh.bounding_sphere();
         let global_bounding_sphere =
             mesh_bounding_sphere.transformed(&self.object.global_transform);
+        let mesh_bounding_box = *self.mesh.bounding_box();
+        let global_bounding_box = mesh_bounding_box.transformed(&self.object.global_transform);
 
         let gpu_object = InternalStaticObject::<A::U32Array> {
             enabled_object_data: Some(EnabledObjectData {
-                _mesh_handle: self.object.mesh,
-                _material_handle: self.object.material,
+                mesh_handle: self.object.mesh,
+                material_handle: self.object.material,
             }),
+            key: self.key,
+            visibility: self.object.visibility,
             mesh_bounding_sphere,
+            mesh_bounding_box,
+            update_streak: UpdateStreak::default(),
             global_transform: self.object.global_transform,
             global_bounding_sphere,
+            global_bounding_box,
             vertex_attribute_offsets,
             first_index,
             index_count,
+            index_type: self.mesh.index_type(),
             material_slot,
         };
 
@@ -592,7 +1033,7 @@ impl WriteStaticObject<'_> {
 
 pub(crate) struct WriteDynamicObject<'a> {
     mesh: &'a GpuMesh,
-    handle: RawDynamicObjectHandle,
+    key: ObjectKey,
     object: Box<ObjectData>,
     object_manager: Option<&'a mut ObjectManager>,
 }
@@ -601,18 +1042,20 @@ impl WriteDynamicObject<'_> {
     pub fn run<M: MaterialInstance>(mut self, material_slot: u32) {
         let object_manager = self.object_manager.take().expect("must always be some");
         let archetype = object_manager.get_or_create_dynamic_object_archetype::<M>();
-        let handle = self.handle;
+        let key = self.key;
 
         let slot = self.fill_slot(
             material_slot,
             M::required_attributes().as_ref(),
             &M::supported_attributes(),
+            &M::vertex_encodings(),
             archetype,
         );
 
-        object_manager.dynamic_handles.insert(
-            handle,
+        object_manager.handles.insert(
+            key,
             HandleData {
+                storage: ObjectStorage::Dynamic,
                 archetype: TypeId::of::<M>(),
                 slot,
             },
@@ -624,35 +1067,49 @@ impl WriteDynamicObject<'_> {
         material_slot: u32,
         required_attributes: &[VertexAttributeKind],
         supported_attributes: &A,
+        vertex_encodings: &VertexAttributeEncodings,
         archetype: &mut DynamicObjectArchetype,
     ) -> u32
     where
         A: VertexAttributeArray,
     {
-        let vertex_attribute_offsets =
-            make_vertex_attribute_offsets(self.mesh, required_attributes, supported_attributes);
+        let vertex_attribute_offsets = make_vertex_attribute_offsets(
+            self.mesh,
+            required_attributes,
+            supported_attributes,
+            vertex_encodings,
+        );
 
         let indices = self.mesh.indices();
         let first_index = indices.start;
         let index_count = indices.end - indices.start;
 
-        // Compute bounding sphere in global space
         let mesh_bounding_sphere = *self.mesh.bounding_sphere();
+        let mesh_bounding_box = *self.mesh.bounding_box();
 
         let global_transform = GlobalTransform::from(self.object.global_transform);
 
         let gpu_object = InternalDynamicObject::<A::U32Array> {
             enabled_object_data: EnabledObjectData {
-                _mesh_handle: self.object.mesh,
-                _material_handle: self.object.material,
+                mesh_handle: self.object.mesh,
+                material_handle: self.object.material,
             },
+            key: self.key,
+            visibility: self.object.visibility,
             mesh_bounding_sphere,
+            mesh_bounding_box,
+            idle_updates: 0,
             prev_global_transform: global_transform,
             next_global_transform: global_transform,
             vertex_attribute_offsets,
             first_index,
             index_count_and_updated: U32WithBool::new(index_count, false),
+            index_type: self.mesh.index_type(),
             material_slot,
+            vertex_count: self.mesh.vertex_count(),
+            morph_target_count: self.mesh.morph_target_count(),
+            skin: None,
+            morph_weights: None,
         };
 
         let slot = alloc_slot(&mut archetype.next_slot, &mut archetype.free_slots);
@@ -677,28 +1134,46 @@ fn make_vertex_attribute_offsets<A>(
     mesh: &GpuMesh,
     required_attributes: &[VertexAttributeKind],
     supported_attributes: &A,
+    vertex_encodings: &VertexAttributeEncodings,
 ) -> A::U32Array
 where
     A: VertexAttributeArray,
 {
+    // Returns the encoding if the material can decode it.
+    let get_encoding = |attribute| {
+        mesh.get_attribute_encoding(attribute)
+            .filter(|encoding| vertex_encodings.contains(attribute, *encoding))
+    };
+
     let required_attributes_mask = required_attributes
         .iter()
-        .fold(0u8, |mask, attribute| mask | *attribute as u8);
+        .fold(0u32, |mask, attribute| mask | 1 << *attribute as u8);
     let mesh_attributes_mask = mesh
         .attributes()
-        .fold(0u8, |mask, attribute| mask | attribute as u8);
+        .filter(|attribute| get_encoding(*attribute).is_some())
+        .fold(0u32, |mask, attribute| mask | 1 << attribute as u8);
 
     assert_eq!(
         mesh_attributes_mask & required_attributes_mask,
-        required_attributes_mask
+        required_attributes_mask,
+        "mesh must have all required attributes in supported encodings"
     );
 
-    supported_attributes
-        .clone()
-        .map_to_u32(|attribute| match mesh.get_attribute_range(attribute) {
-            Some(range) => range.start,
-            None => u32::MAX,
-        })
+    // NOTE: Ranges are 16-byte aligned, so the encoding is stored in the lowest bits
+    supported_attributes.clone().map_to_u32(|attribute| {
+        match (mesh.get_attribute_range(attribute), get_encoding(attribute)) {
+            (Some(range), Some(encoding)) => range.start | encoding as u32,
+            (Some(_), None) => {
+                tracing::warn!(
+                    ?attribute,
+                    encoding = ?mesh.get_attribute_encoding(attribute),
+                    "vertex attribute encoding is not supported by the material"
+                );
+                u32::MAX
+            }
+            (None, _) => u32::MAX,
+        }
+    })
 }
 
 fn alloc_slot(next_slot: &mut u32, free_slots: &mut Vec<u32>) -> u32 {
@@ -756,12 +1231,22 @@ fn finalize_dynamic_object_transforms<A: VertexAttributeArray>(
         if item.index_count_and_updated.get_bool() {
             // Reset the flag for the next fixed update interval.
             item.index_count_and_updated.set_bool(false);
+            item.idle_updates = 0;
         } else {
+            item.idle_updates = item.idle_updates.saturating_add(1);
+
             // Objects which were not updated during the fixed update
             // interval should have their previous transform same as the
             // next one so that they are not interpolated.
             item.prev_global_transform = item.next_global_transform;
         }
+
+        if let Some(skin) = &mut item.skin {
+            skin.finalize();
+        }
+        if let Some(morph_weights) = &mut item.morph_weights {
+            morph_weights.finalize();
+        }
     }
 }
 
@@ -775,6 +1260,8 @@ fn update_static_object_transform<A: VertexAttributeArray>(
 
     item.global_transform = *transform;
     item.global_bounding_sphere = item.mesh_bounding_sphere.transformed(transform);
+    item.global_bounding_box = item.mesh_bounding_box.transformed(transform);
+    item.update_streak.mark_updated();
 
     archetype.buffer.update_slot(slot);
 }
@@ -805,12 +1292,115 @@ fn update_dynamic_object_transform<A: VertexAttributeArray>(
     item.index_count_and_updated.set_bool(true);
 }
 
-fn remove_static_object<A: VertexAttributeArray>(archetype: &mut StaticObjectArchetype, slot: u32) {
+fn update_dynamic_object_joints<A: VertexAttributeArray>(
+    archetype: &mut DynamicObjectArchetype,
+    slot: u32,
+    joint_matrices: &[Mat4],
+) {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let item = unsafe { expect_data_slot_mut::<DynamicSlotData<A>>(&mut archetype.data, slot) };
+
+    InterpolatedSlice::update(&mut item.skin, joint_matrices);
+}
+
+fn update_dynamic_object_morph_weights<A: VertexAttributeArray>(
+    archetype: &mut DynamicObjectArchetype,
+    slot: u32,
+    weights: &[f32],
+) {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let item = unsafe { expect_data_slot_mut::<DynamicSlotData<A>>(&mut archetype.data, slot) };
+
+    if weights.len() != item.morph_target_count as usize {
+        tracing::warn!(
+            weight_count = weights.len(),
+            morph_target_count = item.morph_target_count,
+            "morph weight count mismatch"
+        );
+    }
+
+    InterpolatedSlice::update(&mut item.morph_weights, weights);
+}
+
+fn static_object_visibility_mut<A: VertexAttributeArray>(
+    archetype: &mut StaticObjectArchetype,
+    slot: u32,
+) -> &mut ObjectVisibility {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let item = unsafe { expect_data_slot_mut::<StaticSlotData<A>>(&mut archetype.data, slot) };
+    &mut item.visibility
+}
+
+fn dynamic_object_visibility_mut<A: VertexAttributeArray>(
+    archetype: &mut DynamicObjectArchetype,
+    slot: u32,
+) -> &mut ObjectVisibility {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let item = unsafe { expect_data_slot_mut::<DynamicSlotData<A>>(&mut archetype.data, slot) };
+    &mut item.visibility
+}
+
+fn collect_static_object_promotions<A: VertexAttributeArray>(
+    archetype: &mut StaticObjectArchetype,
+    promote_after: Option<u32>,
+    migrations: &mut Vec<ObjectMigration>,
+) {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let data = unsafe { archetype.data.typed_data_mut::<StaticSlotData<A>>() };
+
+    for item in data.iter_mut().flatten() {
+        if item.enabled_object_data.is_none() {
+            continue;
+        }
+
+        if item.update_streak.end_fixed_update(promote_after) {
+            migrations.push((item.key, ObjectStorage::Dynamic));
+        }
+    }
+}
+
+fn is_dynamic_object_animated<A: VertexAttributeArray>(
+    archetype: &DynamicObjectArchetype,
+    slot: u32,
+) -> bool {
+    // SAFETY: `typed_data` template parameter is the same as the one used to construct `data`.
+    let data = unsafe { archetype.data.typed_data::<DynamicSlotData<A>>() };
+    let item = data[slot as usize]
+        .as_ref()
+        .expect("value was not initialized");
+    item.skin.is_some() || item.morph_weights.is_some()
+}
+
+fn collect_dynamic_object_demotions<A: VertexAttributeArray>(
+    archetype: &mut DynamicObjectArchetype,
+    demote_after: u32,
+    migrations: &mut Vec<ObjectMigration>,
+) {
+    // SAFETY: `typed_data` template parameter is the same as the one used to construct `data`.
+    let data = unsafe { archetype.data.typed_data::<DynamicSlotData<A>>() };
+
+    for item in data.iter().flatten() {
+        let is_animated = item.skin.is_some() || item.morph_weights.is_some();
+        if !is_animated && item.idle_updates >= demote_after {
+            migrations.push((item.key, ObjectStorage::Static));
+        }
+    }
+}
+
+fn take_static_object<A: VertexAttributeArray>(
+    archetype: &mut StaticObjectArchetype,
+    slot: u32,
+) -> Box<ObjectData> {
     // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
     let item = unsafe { expect_data_slot_mut::<StaticSlotData<A>>(&mut archetype.data, slot) };
 
     // Set item as disabled and mark it as updated to flush the data to the GPU.
-    item.enabled_object_data = None;
+    let enabled_object_data = item
+        .enabled_object_data
+        .take()
+        .expect("object is already removed");
+    let global_transform = item.global_transform;
+    let visibility = item.visibility;
     archetype.buffer.update_slot(slot);
 
     // It is ok to add this slot to available, since an inserted object will
@@ -818,18 +1408,66 @@ fn remove_static_object<A: VertexAttributeArray>(archetype: &mut StaticObjectArc
     // and this slot was already marked as updated.
     archetype.free_slots.push(slot);
     archetype.active_object_count -= 1;
+
+    Box::new(ObjectData {
+        mesh: enabled_object_data.mesh_handle,
+        material: enabled_object_data.material_handle,
+        global_transform,
+        visibility,
+    })
 }
 
-fn remove_dynamic_object<A: VertexAttributeArray>(
+fn take_dynamic_object<A: VertexAttributeArray>(
     archetype: &mut DynamicObjectArchetype,
     slot: u32,
-) {
+) -> (Box<ObjectData>, DynamicObjectState) {
     // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
     let data = unsafe { archetype.data.typed_data_mut::<DynamicSlotData<A>>() };
     let item = data.get_mut(slot as usize).expect("invalid handle slot");
-    std::mem::take(item).expect("value was not initialized");
+    let item = std::mem::take(item).expect("value was not initialized");
 
     archetype.free_slots.push(slot);
+    archetype.active_object_count -= 1;
+
+    let object = Box::new(ObjectData {
+        mesh: item.enabled_object_data.mesh_handle,
+        material: item.enabled_object_data.material_handle,
+        global_transform: item.next_global_transform.to_matrix(),
+        visibility: item.visibility,
+    });
+    let state = DynamicObjectState {
+        prev_global_transform: item.prev_global_transform,
+        next_global_transform: item.next_global_transform,
+        updated: item.index_count_and_updated.get_bool(),
+        skin: item.skin,
+        morph_weights: item.morph_weights,
+    };
+    (object, state)
+}
+
+fn restore_dynamic_object_state<A: VertexAttributeArray>(
+    archetype: &mut DynamicObjectArchetype,
+    slot: u32,
+    state: DynamicObjectState,
+) {
+    // SAFETY: `typed_data_mut` template parameter is the same as the one used to construct `data`.
+    let item = unsafe { expect_data_slot_mut::<DynamicSlotData<A>>(&mut archetype.data, slot) };
+
+    if let Some(morph_weights) = &state.morph_weights {
+        if morph_weights.len() != item.morph_target_count as usize {
+            tracing::warn!(
+                weight_count = morph_weights.len(),
+                morph_target_count = item.morph_target_count,
+                "morph weight count mismatch"
+            );
+        }
+    }
+
+    item.prev_global_transform = state.prev_global_transform;
+    item.next_global_transform = state.next_global_transform;
+    item.index_count_and_updated.set_bool(state.updated);
+    item.skin = state.skin;
+    item.morph_weights = state.morph_weights;
 }
 
 // SAFETY: `T` must be the same type as used to construct `data`.
@@ -856,3 +1494,24 @@ impl<T> SlotDataExt for Option<T> {
         Option::as_mut(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enabling_promotions_ignores_earlier_updates() {
+        let mut streak = UpdateStreak::default();
+
+        // Updated once while promotions are disabled
+        streak.mark_updated();
+        assert!(!streak.end_fixed_update(None));
+        assert!(!streak.end_fixed_update(None));
+
+        // Promotions are enabled without further updates
+        assert!(!streak.end_fixed_update(Some(1)));
+
+        streak.mark_updated();
+        assert!(streak.end_fixed_update(Some(1)));
+    }
+}
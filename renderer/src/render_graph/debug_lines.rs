@@ -0,0 +1,140 @@
+use anyhow::Result;
+use glam::Vec3;
+
+use crate::render_graph::post_process::needs_srgb_encoding;
+use crate::types::{DebugLine, RenderPassKind};
+use crate::util::{CachedGraphicsPipeline, RenderPassEncoderExt, ShaderPreprocessor};
+use crate::RendererState;
+
+/// Draws the lines accumulated by [`DebugDraw`](crate::DebugDraw).
+pub struct DebugLines {
+    depth_tested_pipeline: CachedGraphicsPipeline,
+    overlay_pipeline: CachedGraphicsPipeline,
+    lines: Vec<DebugLine>,
+}
+
+impl DebugLines {
+    pub fn new(
+        device: &gfx::Device,
+        pipeline_layout: &gfx::PipelineLayout,
+        shaders: &ShaderPreprocessor,
+    ) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "debug_line.vert", "main")?;
+        let fragment_shader = shaders.make_fragment_shader(device, "debug_line.frag", "main")?;
+
+        let make_pipeline = |depth_test: Option<gfx::DepthTest>| {
+            CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+                vertex_bindings: Vec::new(),
+                vertex_attributes: Vec::new(),
+                primitive_topology: gfx::PrimitiveTopology::LineList,
+                primitive_restart_enable: false,
+                vertex_shader: vertex_shader.clone(),
+                rasterizer: Some(gfx::Rasterizer {
+                    fragment_shader: Some(fragment_shader.clone()),
+                    depth_test,
+                    ..Default::default()
+                }),
+                layout: pipeline_layout.clone(),
+            })
+        };
+
+        Ok(Self {
+            depth_tested_pipeline: make_pipeline(Some(gfx::DepthTest {
+                compare: gfx::CompareOp::LessOrEqual,
+                write: false,
+            })),
+            overlay_pipeline: make_pipeline(None),
+            lines: Vec::new(),
+        })
+    }
+
+    /// Takes the lines of the current frame, returns whether there is anything to draw.
+    pub fn prepare(&mut self, state: &RendererState) -> bool {
+        self.lines.clear();
+        state.debug_draw.collect_frame(&mut self.lines);
+
+        let render_layers = state
+            .frame_resources
+            .pass_render_layers(RenderPassKind::Debug);
+        self.lines
+            .retain(|line| line.layers.intersects(render_layers));
+        !self.lines.is_empty()
+    }
+
+    pub fn execute(
+        &mut self,
+        state: &RendererState,
+        globals_offset: u32,
+        pipeline_layout: &gfx::PipelineLayout,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        color_space: Option<gfx::ColorSpace>,
+    ) -> Result<()> {
+        if self.lines.is_empty() {
+            return Ok(());
+        }
+
+        // NOTE: Depth tested lines go first, the rest is drawn on top of them
+        self.lines.sort_by_key(|line| !line.depth_test);
+        let depth_tested = self.lines.partition_point(|line| line.depth_test) as u32 * 2;
+        let total = self.lines.len() as u32 * 2;
+
+        let mut arena = state.multi_buffer_arena.begin::<GpuDebugVertex>(
+            &state.device,
+            total as usize,
+            gfx::BufferUsage::STORAGE,
+        )?;
+        for line in &self.lines {
+            for position in [line.start, line.end] {
+                arena.write(&gfx::AsStd430::as_std430(&DebugVertex {
+                    position,
+                    color: line.color,
+                }));
+            }
+        }
+        let vertices =
+            state
+                .multi_buffer_arena
+                .end(&state.device, &state.bindless_resources, arena);
+
+        encoder.bind_graphics_descriptor_sets(
+            pipeline_layout,
+            0,
+            &[
+                state.frame_resources.descriptor_set(),
+                state.bindless_resources.descriptor_set(),
+            ],
+            &[globals_offset],
+        );
+
+        // NOTE: Line colors are sRGB encoded
+        let decode_srgb = (!needs_srgb_encoding(encoder, color_space)) as u32;
+        for (pipeline, range) in [
+            (&mut self.depth_tested_pipeline, 0..depth_tested),
+            (&mut self.overlay_pipeline, depth_tested..total),
+        ] {
+            if range.is_empty() {
+                continue;
+            }
+            encoder.bind_cached_graphics_pipeline(pipeline, &state.device)?;
+            encoder.push_constants(
+                pipeline_layout,
+                gfx::ShaderStageFlags::ALL,
+                0,
+                &[vertices.index(), decode_srgb, 0],
+            );
+            encoder.draw(range, 0..1);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(gfx::AsStd430)]
+struct DebugVertex {
+    position: Vec3,
+    /// sRGB encoded RGBA8 color.
+    color: u32,
+}
+
+type GpuDebugVertex = <DebugVertex as gfx::AsStd430>::Output;
@@ -1,14 +1,33 @@
+use std::ops::Range;
+
 use anyhow::Result;
 use glam::Vec3;
 
-use crate::managers::GpuObject;
+use crate::managers::{GpuJointMatrix, GpuObject};
 use crate::render_graph::render_passes::MainPass;
-use crate::render_graph::{RenderGraphNode, RenderGraphNodeContext};
-use crate::types::{MaterialInstance, Sorting, VertexAttributeArray, VertexAttributeKind};
-use crate::util::{CachedGraphicsPipeline, RenderPassEncoderExt, ShaderPreprocessor};
+use crate::render_graph::{RenderGraphCullContext, RenderGraphNode, RenderGraphNodeContext};
+use crate::types::{
+    DebugView, MaterialInstance, ObjMaterial, Sorting, VertexAttributeArray,
+    VertexAttributeEncodings, VertexAttributeKind,
+};
+use crate::util::{
+    CachedGraphicsPipeline, OcclusionCandidates, OcclusionDraws, OcclusionPass,
+    RenderPassEncoderExt, ShaderPreprocessor,
+};
 
 pub struct DebugMaterial {
-    pipeline: CachedGraphicsPipeline,
+    debug_view: DebugView,
+    pipelines: DebugMaterialPipelines,
+    static_candidates: OcclusionCandidates,
+    static_draws: OcclusionDraws,
+    dynamic_candidates: OcclusionCandidates,
+    dynamic_draws: OcclusionDraws,
+    /// Skinned and morphed dynamic objects, which are drawn without occlusion culling.
+    deformed_draws: Vec<DynamicDraw>,
+    /// Bindless index of the dynamic objects uploaded in the current frame.
+    dynamic_objects_buffer: Option<u32>,
+    /// Compiled on the first pick request.
+    picking_pipeline: Option<CachedGraphicsPipeline>,
 }
 
 impl DebugMaterial {
@@ -17,37 +36,272 @@ impl DebugMaterial {
         pipeline_layout: &gfx::PipelineLayout,
         shaders: &ShaderPreprocessor,
     ) -> Result<Self> {
-        let shaders = shaders.begin();
+        let debug_view = DebugView::None;
+        Ok(Self {
+            debug_view,
+            pipelines: DebugMaterialPipelines::new(device, pipeline_layout, shaders, debug_view)?,
+            static_candidates: OcclusionCandidates::default(),
+            static_draws: OcclusionDraws::default(),
+            dynamic_candidates: OcclusionCandidates::default(),
+            dynamic_draws: OcclusionDraws::default(),
+            deformed_draws: Vec::new(),
+            dynamic_objects_buffer: None,
+            picking_pipeline: None,
+        })
+    }
+
+    /// Recompiles the pipelines when the debug view changes.
+    pub fn set_debug_view(
+        &mut self,
+        device: &gfx::Device,
+        shaders: &ShaderPreprocessor,
+        debug_view: DebugView,
+    ) -> Result<()> {
+        if self.debug_view != debug_view {
+            let pipeline_layout = self.pipelines.early.descr().layout.clone();
+            self.pipelines =
+                DebugMaterialPipelines::new(device, &pipeline_layout, shaders, debug_view)?;
+            self.debug_view = debug_view;
+        }
+        Ok(())
+    }
+}
 
-        let vertex_shader = shaders.make_vertex_shader(device, "opaque_mesh.vert", "main")?;
-        let fragment_shader = shaders.make_fragment_shader(device, "opaque_mesh.frag", "main")?;
+struct DebugMaterialPipelines {
+    early: CachedGraphicsPipeline,
+    // NOTE: the late pass uses a different render pass
+    late: CachedGraphicsPipeline,
+    /// Pipeline for deformed dynamic objects, if it differs from the early one.
+    dynamic: Option<CachedGraphicsPipeline>,
+    /// Edges drawn over the shaded objects in the early and late passes.
+    wireframe: Option<[CachedGraphicsPipeline; 2]>,
+}
 
-        Ok(Self {
-            pipeline: CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
-                vertex_bindings: Vec::new(),
-                vertex_attributes: Vec::new(),
-                primitive_topology: Default::default(),
-                primitive_restart_enable: false,
-                vertex_shader,
-                rasterizer: Some(gfx::Rasterizer {
-                    fragment_shader: Some(fragment_shader),
-                    front_face: gfx::FrontFace::CCW,
-                    cull_mode: Some(gfx::CullMode::Back),
-                    depth_test: Some(gfx::DepthTest {
-                        compare: gfx::CompareOp::Less,
-                        write: true,
-                    }),
-                    ..Default::default()
-                }),
-                layout: pipeline_layout.clone(),
-            }),
+impl DebugMaterialPipelines {
+    fn new(
+        device: &gfx::Device,
+        pipeline_layout: &gfx::PipelineLayout,
+        shaders: &ShaderPreprocessor,
+        debug_view: DebugView,
+    ) -> Result<Self> {
+        let make_descr = |debug_view: DebugView, culling_status: Option<u32>| {
+            make_pipeline_descr(device, pipeline_layout, shaders, debug_view, culling_status)
+        };
+
+        Ok(match debug_view {
+            DebugView::Wireframe => {
+                let shaded = make_descr(DebugView::None, None)?;
+                let wireframe = make_descr(DebugView::Wireframe, None)?;
+                Self {
+                    early: CachedGraphicsPipeline::new(shaded.clone()),
+                    late: CachedGraphicsPipeline::new(shaded),
+                    dynamic: None,
+                    wireframe: Some([
+                        CachedGraphicsPipeline::new(wireframe.clone()),
+                        CachedGraphicsPipeline::new(wireframe),
+                    ]),
+                }
+            }
+            DebugView::CullingStatus => Self {
+                early: CachedGraphicsPipeline::new(make_descr(debug_view, Some(CULLING_EARLY))?),
+                late: CachedGraphicsPipeline::new(make_descr(debug_view, Some(CULLING_LATE))?),
+                dynamic: Some(CachedGraphicsPipeline::new(make_descr(
+                    debug_view,
+                    Some(CULLING_NONE),
+                )?)),
+                wireframe: None,
+            },
+            _ => {
+                let descr = make_descr(debug_view, None)?;
+                Self {
+                    early: CachedGraphicsPipeline::new(descr.clone()),
+                    late: CachedGraphicsPipeline::new(descr),
+                    dynamic: None,
+                    wireframe: None,
+                }
+            }
         })
     }
 }
 
+fn make_pipeline_descr(
+    device: &gfx::Device,
+    pipeline_layout: &gfx::PipelineLayout,
+    shaders: &ShaderPreprocessor,
+    debug_view: DebugView,
+    culling_status: Option<u32>,
+) -> Result<gfx::GraphicsPipelineDescr> {
+    let mut shaders = shaders.begin();
+    shaders.define_vertex_attributes(DebugMaterialInstance::supported_attributes().as_ref());
+    shaders.define_vertex_encodings(&DebugMaterialInstance::vertex_encodings());
+    if debug_view != DebugView::None {
+        shaders.define_expr("DEBUG_VIEW", debug_view.shader_index().to_string());
+    }
+    if let Some(culling_status) = culling_status {
+        shaders.define_expr("DEBUG_CULLING_STATUS", culling_status.to_string());
+    }
+
+    let vertex_shader = shaders.make_vertex_shader(device, "opaque_mesh.vert", "main")?;
+    let fragment_shader = shaders.make_fragment_shader(device, "opaque_mesh.frag", "main")?;
+
+    let mut rasterizer = gfx::Rasterizer {
+        fragment_shader: Some(fragment_shader),
+        front_face: gfx::FrontFace::CCW,
+        cull_mode: Some(gfx::CullMode::Back),
+        depth_test: Some(gfx::DepthTest {
+            compare: gfx::CompareOp::Less,
+            write: true,
+        }),
+        ..Default::default()
+    };
+    match debug_view {
+        DebugView::Wireframe => {
+            rasterizer.polygin_mode = gfx::PolygonMode::Line;
+            rasterizer.depth_test = Some(gfx::DepthTest {
+                compare: gfx::CompareOp::LessOrEqual,
+                write: false,
+            });
+            // NOTE: Edges keep the velocity of the shaded surface
+            rasterizer.color_blend = gfx::ColorBlend::IndependentBlending {
+                blending: vec![
+                    (None, gfx::ComponentMask::RGBA),
+                    (None, gfx::ComponentMask::empty()),
+                ],
+                constants: gfx::State::Static([0.0; 4]),
+            };
+        }
+        DebugView::Overdraw => {
+            // NOTE: Every fragment is counted, including the hidden ones
+            rasterizer.depth_test = None;
+            rasterizer.color_blend = gfx::ColorBlend::Blending {
+                blending: Some(gfx::Blending {
+                    color_src_factor: gfx::BlendFactor::One,
+                    color_dst_factor: gfx::BlendFactor::One,
+                    color_op: gfx::BlendOp::Add,
+                    alpha_src_factor: gfx::BlendFactor::One,
+                    alpha_dst_factor: gfx::BlendFactor::One,
+                    alpha_op: gfx::BlendOp::Add,
+                }),
+                write_mask: gfx::ComponentMask::RGBA,
+                constants: gfx::State::Static([0.0; 4]),
+            };
+        }
+        _ => {}
+    }
+
+    Ok(gfx::GraphicsPipelineDescr {
+        vertex_bindings: Vec::new(),
+        vertex_attributes: Vec::new(),
+        primitive_topology: Default::default(),
+        primitive_restart_enable: false,
+        vertex_shader,
+        rasterizer: Some(rasterizer),
+        layout: pipeline_layout.clone(),
+    })
+}
+
+fn make_picking_pipeline_descr(
+    device: &gfx::Device,
+    pipeline_layout: &gfx::PipelineLayout,
+    shaders: &ShaderPreprocessor,
+) -> Result<gfx::GraphicsPipelineDescr> {
+    let mut shaders = shaders.begin();
+    shaders.define_vertex_attributes(DebugMaterialInstance::supported_attributes().as_ref());
+    shaders.define_vertex_encodings(&DebugMaterialInstance::vertex_encodings());
+    shaders.define("PICKING");
+
+    let vertex_shader = shaders.make_vertex_shader(device, "opaque_mesh.vert", "main")?;
+    let fragment_shader = shaders.make_fragment_shader(device, "picking.frag", "main")?;
+
+    Ok(gfx::GraphicsPipelineDescr {
+        vertex_bindings: Vec::new(),
+        vertex_attributes: Vec::new(),
+        primitive_topology: Default::default(),
+        primitive_restart_enable: false,
+        vertex_shader,
+        rasterizer: Some(gfx::Rasterizer {
+            fragment_shader: Some(fragment_shader),
+            front_face: gfx::FrontFace::CCW,
+            cull_mode: Some(gfx::CullMode::Back),
+            depth_test: Some(gfx::DepthTest {
+                compare: gfx::CompareOp::Less,
+                write: true,
+            }),
+            ..Default::default()
+        }),
+        layout: pipeline_layout.clone(),
+    })
+}
+
+// NOTE: Values of `DEBUG_CULLING_*` in shaders
+const CULLING_EARLY: u32 = 0;
+const CULLING_LATE: u32 = 1;
+const CULLING_NONE: u32 = 2;
+
+struct DynamicDraw {
+    index_type: gfx::IndexType,
+    indices: Range<u32>,
+    slot: u32,
+}
+
 impl RenderGraphNode for DebugMaterial {
     type RenderPass = MainPass;
 
+    fn cull(&mut self, ctx: &mut RenderGraphCullContext<'_>) -> Result<()> {
+        if ctx.occlusion_pass == OcclusionPass::Early {
+            let frustum = &ctx.globals.frustum;
+            let render_layers = ctx.globals.render_layers();
+
+            self.static_candidates.clear();
+            if let Some(static_objects) = ctx
+                .synced_managers
+                .object_manager
+                .iter_static_objects::<DebugMaterialInstance>()
+            {
+                for (slot, object) in static_objects {
+                    if !object.visibility.is_visible_in(render_layers)
+                        || !frustum.intersects_bounds(
+                            &object.global_bounding_sphere,
+                            &object.global_bounding_box,
+                        )
+                    {
+                        continue;
+                    }
+
+                    self.static_candidates.push(
+                        object.index_type,
+                        &object.global_bounding_box,
+                        slot,
+                        object.first_index..object.first_index + object.index_count,
+                    );
+                }
+            }
+
+            ctx.occlusion_culling.prepare(
+                &ctx.state.device,
+                &ctx.state.multi_buffer_arena,
+                ctx.depth_pyramid,
+                &self.static_candidates,
+                &mut self.static_draws,
+            )?;
+
+            self.dynamic_objects_buffer = self.upload_dynamic(ctx)?;
+            ctx.occlusion_culling.prepare(
+                &ctx.state.device,
+                &ctx.state.multi_buffer_arena,
+                ctx.depth_pyramid,
+                &self.dynamic_candidates,
+                &mut self.dynamic_draws,
+            )?;
+        }
+
+        for draws in [&self.static_draws, &self.dynamic_draws] {
+            ctx.occlusion_culling
+                .cull(ctx.encoder, ctx.depth_pyramid, draws, ctx.occlusion_pass);
+        }
+        Ok(())
+    }
+
     fn execute(&mut self, ctx: &mut RenderGraphNodeContext<'_, '_>) -> Result<()> {
         let Some(material_instances_buffer) =
             ctx.synced_managers
@@ -57,39 +311,135 @@ impl RenderGraphNode for DebugMaterial {
             return Ok(());
         };
 
-        let frustum = &ctx.globals.frustum;
+        let material_instances_buffer = material_instances_buffer.index();
+        let static_objects_buffer = ctx
+            .synced_managers
+            .object_manager
+            .iter_static_objects::<DebugMaterialInstance>()
+            .map(|static_objects| static_objects.buffer_handle().index());
 
+        let pipeline = match ctx.occlusion_pass {
+            OcclusionPass::Early => &mut self.pipelines.early,
+            OcclusionPass::Late => &mut self.pipelines.late,
+        };
         ctx.encoder
-            .bind_cached_graphics_pipeline(&mut self.pipeline, &ctx.state.device)?;
+            .bind_cached_graphics_pipeline(pipeline, &ctx.state.device)?;
+        self.draw_culled(
+            ctx,
+            static_objects_buffer,
+            material_instances_buffer,
+            ctx.occlusion_pass,
+        );
 
-        if let Some(static_objects) = ctx
+        if ctx.occlusion_pass == OcclusionPass::Early {
+            if let Some(pipeline) = &mut self.pipelines.dynamic {
+                ctx.encoder
+                    .bind_cached_graphics_pipeline(pipeline, &ctx.state.device)?;
+            }
+            self.draw_deformed(ctx, material_instances_buffer);
+        }
+
+        if let Some([early, late]) = &mut self.pipelines.wireframe {
+            let pipeline = match ctx.occlusion_pass {
+                OcclusionPass::Early => early,
+                OcclusionPass::Late => late,
+            };
+            ctx.encoder
+                .bind_cached_graphics_pipeline(pipeline, &ctx.state.device)?;
+
+            self.draw_culled(
+                ctx,
+                static_objects_buffer,
+                material_instances_buffer,
+                ctx.occlusion_pass,
+            );
+            if ctx.occlusion_pass == OcclusionPass::Early {
+                self.draw_deformed(ctx, material_instances_buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute_picking(&mut self, ctx: &mut RenderGraphNodeContext<'_, '_>) -> Result<()> {
+        let Some(material_instances_buffer) =
+            ctx.synced_managers
+                .material_manager
+                .materials_data_buffer_handle::<DebugMaterialInstance>()
+        else {
+            return Ok(());
+        };
+
+        let pipeline = match &mut self.picking_pipeline {
+            Some(pipeline) => pipeline,
+            None => self.picking_pipeline.insert(CachedGraphicsPipeline::new(
+                make_picking_pipeline_descr(
+                    &ctx.state.device,
+                    ctx.graphics_pipeline_layout,
+                    &ctx.state.shader_preprocessor,
+                )?,
+            )),
+        };
+        ctx.encoder
+            .bind_cached_graphics_pipeline(pipeline, &ctx.state.device)?;
+
+        let material_instances_buffer = material_instances_buffer.index();
+        let static_objects_buffer = ctx
             .synced_managers
             .object_manager
             .iter_static_objects::<DebugMaterialInstance>()
-        {
-            ctx.encoder.push_constants(
-                ctx.graphics_pipeline_layout,
-                gfx::ShaderStageFlags::ALL,
-                0,
-                &[
-                    ctx.state.mesh_manager.vertex_buffer_handle().index(),
-                    static_objects.buffer_handle().index(),
-                    material_instances_buffer.index(),
-                ],
-            );
+            .map(|static_objects| static_objects.buffer_handle().index());
 
-            for (slot, object) in static_objects {
-                if !frustum.contains_sphere(&object.global_bounding_sphere) {
-                    continue;
-                }
+        for pass in [OcclusionPass::Early, OcclusionPass::Late] {
+            self.draw_culled(ctx, static_objects_buffer, material_instances_buffer, pass);
+        }
+        self.draw_deformed(ctx, material_instances_buffer);
+
+        Ok(())
+    }
+}
+
+impl DebugMaterial {
+    /// Draws static and dynamic objects which passed the occlusion culling in the specified pass.
+    fn draw_culled(
+        &self,
+        ctx: &mut RenderGraphNodeContext<'_, '_>,
+        static_objects_buffer: Option<u32>,
+        material_instances_buffer: u32,
+        pass: OcclusionPass,
+    ) {
+        for (objects_buffer, draws) in [
+            (static_objects_buffer, &self.static_draws),
+            (self.dynamic_objects_buffer, &self.dynamic_draws),
+        ] {
+            if let Some(objects_buffer) = objects_buffer {
+                push_constants(ctx, objects_buffer, material_instances_buffer);
+                draws.draw(&mut ctx.encoder, &ctx.state.mesh_manager, pass);
+            }
+        }
+    }
 
-                ctx.encoder.draw_indexed(
-                    object.first_index..object.first_index + object.index_count,
-                    0,
-                    slot..slot + 1,
-                );
+    fn draw_deformed(
+        &self,
+        ctx: &mut RenderGraphNodeContext<'_, '_>,
+        material_instances_buffer: u32,
+    ) {
+        if let Some(dynamic_objects_buffer) = self.dynamic_objects_buffer {
+            if !self.deformed_draws.is_empty() {
+                push_constants(ctx, dynamic_objects_buffer, material_instances_buffer);
+                draw_dynamic_objects(ctx, &self.deformed_draws);
             }
         }
+    }
+
+    /// Uploads the dynamic objects and collects the visible ones,
+    /// returns the index of their buffer.
+    fn upload_dynamic(&mut self, ctx: &RenderGraphCullContext<'_>) -> Result<Option<u32>> {
+        let frustum = &ctx.globals.frustum;
+        let render_layers = ctx.globals.render_layers();
+
+        self.dynamic_candidates.clear();
+        self.deformed_draws.clear();
 
         if let Some(dynamic_objects) = ctx
             .synced_managers
@@ -103,9 +453,72 @@ impl RenderGraphNode for DebugMaterial {
                 gfx::BufferUsage::STORAGE,
             )?;
 
+            // Upload joint matrices of all skinned objects into a single buffer
+            let joint_count = dynamic_objects
+                .clone()
+                .map(|object| object.joint_count() as usize)
+                .sum::<usize>();
+
+            let joints_buffer_handle = if joint_count > 0 {
+                let mut joints_arena = ctx.state.multi_buffer_arena.begin::<GpuJointMatrix>(
+                    &ctx.state.device,
+                    joint_count,
+                    gfx::BufferUsage::STORAGE,
+                )?;
+                for skin in dynamic_objects
+                    .clone()
+                    .filter_map(|object| object.skin.as_ref())
+                {
+                    skin.write_interpolated(ctx.interpolation_factor, &mut joints_arena);
+                }
+                Some(ctx.state.multi_buffer_arena.end(
+                    &ctx.state.device,
+                    &ctx.state.bindless_resources,
+                    joints_arena,
+                ))
+            } else {
+                None
+            };
+
+            // Upload morph target weights
+            let morph_weight_count = dynamic_objects
+                .clone()
+                .map(|object| object.morph_weight_count() as usize)
+                .sum::<usize>();
+
+            let morph_weights_buffer_handle = if morph_weight_count > 0 {
+                let mut weights_arena = ctx.state.multi_buffer_arena.begin::<f32>(
+                    &ctx.state.device,
+                    morph_weight_count,
+                    gfx::BufferUsage::STORAGE,
+                )?;
+                for weights in dynamic_objects
+                    .clone()
+                    .filter_map(|object| object.morph_weights.as_ref())
+                {
+                    weights.write_interpolated(ctx.interpolation_factor, &mut weights_arena);
+                }
+                Some(ctx.state.multi_buffer_arena.end(
+                    &ctx.state.device,
+                    &ctx.state.bindless_resources,
+                    weights_arena,
+                ))
+            } else {
+                None
+            };
+
             // TODO: make it one iteration
+            let mut first_joint = 0;
+            let mut first_morph_weight = 0;
             for object in dynamic_objects.clone() {
-                arena.write(&object.as_interpolated_std430(ctx.interpolation_factor));
+                arena.write(&object.as_interpolated_std430(
+                    ctx.interpolation_factor,
+                    ctx.previous_interpolation_factor,
+                    joints_buffer_handle.map(|handle| (handle, first_joint)),
+                    morph_weights_buffer_handle.map(|handle| (handle, first_morph_weight)),
+                ));
+                first_joint += object.joint_count();
+                first_morph_weight += object.morph_weight_count();
             }
 
             let objects_buffer_handle = ctx.state.multi_buffer_arena.end(
@@ -114,27 +527,66 @@ impl RenderGraphNode for DebugMaterial {
                 arena,
             );
 
-            ctx.encoder.push_constants(
-                ctx.graphics_pipeline_layout,
-                gfx::ShaderStageFlags::ALL,
-                0,
-                &[
-                    ctx.state.mesh_manager.vertex_buffer_handle().index(),
-                    objects_buffer_handle.index(),
-                    material_instances_buffer.index(),
-                ],
-            );
-
             for (slot, object) in dynamic_objects.enumerate() {
-                ctx.encoder.draw_indexed(
-                    object.first_index..object.first_index + object.index_count(),
-                    0,
-                    slot as u32..slot as u32 + 1,
-                );
+                if !object.visibility.is_visible_in(render_layers) {
+                    continue;
+                }
+
+                let slot = slot as u32;
+                let indices = object.first_index..object.first_index + object.index_count();
+                // NOTE: Deformed objects can leave their bounding volumes, so they are always drawn
+                if object.is_deformed() {
+                    self.deformed_draws.push(DynamicDraw {
+                        index_type: object.index_type,
+                        indices,
+                        slot,
+                    });
+                    continue;
+                }
+
+                let (bounding_sphere, bounding_box) =
+                    object.interpolated_bounds(ctx.interpolation_factor);
+                if frustum.intersects_bounds(&bounding_sphere, &bounding_box) {
+                    self.dynamic_candidates
+                        .push(object.index_type, &bounding_box, slot, indices);
+                }
             }
+
+            return Ok(Some(objects_buffer_handle.index()));
         }
 
-        Ok(())
+        Ok(None)
+    }
+}
+
+fn push_constants(
+    ctx: &mut RenderGraphNodeContext<'_, '_>,
+    objects_buffer: u32,
+    material_instances_buffer: u32,
+) {
+    ctx.encoder.push_constants(
+        ctx.graphics_pipeline_layout,
+        gfx::ShaderStageFlags::ALL,
+        0,
+        &[
+            ctx.state.mesh_manager.vertex_buffer_handle().index(),
+            objects_buffer,
+            material_instances_buffer,
+        ],
+    );
+}
+
+fn draw_dynamic_objects(ctx: &mut RenderGraphNodeContext<'_, '_>, draws: &[DynamicDraw]) {
+    let mut index_type = None;
+    for draw in draws {
+        if index_type != Some(draw.index_type) {
+            index_type = Some(draw.index_type);
+            ctx.state
+                .mesh_manager
+                .bind_index_buffer(&mut ctx.encoder, draw.index_type);
+        }
+        ctx.encoder
+            .draw_indexed(draw.indices.clone(), 0, draw.slot..draw.slot + 1);
     }
 }
 
@@ -147,10 +599,18 @@ pub struct DebugMaterialInstance {
     pub color: Vec3,
 }
 
+impl From<&ObjMaterial> for DebugMaterialInstance {
+    fn from(material: &ObjMaterial) -> Self {
+        Self {
+            color: material.diffuse,
+        }
+    }
+}
+
 impl MaterialInstance for DebugMaterialInstance {
     type ShaderDataType = <Vec3 as gfx::AsStd430>::Output;
     type RequiredAttributes = [VertexAttributeKind; 1];
-    type SupportedAttributes = [VertexAttributeKind; 5];
+    type SupportedAttributes = [VertexAttributeKind; 9];
 
     fn required_attributes() -> Self::RequiredAttributes {
         [VertexAttributeKind::Position]
@@ -162,9 +622,23 @@ impl MaterialInstance for DebugMaterialInstance {
             VertexAttributeKind::Tangent,
             VertexAttributeKind::UV0,
             VertexAttributeKind::Color,
+            VertexAttributeKind::Joints,
+            VertexAttributeKind::Weights,
+            VertexAttributeKind::MorphPositions,
+            VertexAttributeKind::MorphNormals,
         ]
     }
 
+    fn vertex_encodings() -> VertexAttributeEncodings {
+        VertexAttributeEncodings::FLOAT.with_compressed(&[
+            VertexAttributeKind::Position,
+            VertexAttributeKind::Normal,
+            VertexAttributeKind::Tangent,
+            VertexAttributeKind::UV0,
+            VertexAttributeKind::Color,
+        ])
+    }
+
     fn key(&self) -> u64 {
         0
     }
@@ -1,9 +1,15 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use glam::UVec2;
 
-use crate::render_graph::render_passes::MainPassInput;
-use crate::util::{EncoderExt, FlushFrameResources, FrameGlobals, RenderPass};
+use crate::render_graph::render_passes::{FullscreenPassInput, MainPassInput, MainPassResolve};
+use crate::types::{AntiAliasing, DebugView, Msaa, PostAntiAliasing};
+use crate::util::{
+    AmbientOcclusion, AutoExposure, Bloom, DepthPyramid, DepthResolve, EncoderExt, Environment,
+    FlushFrameResources, FrameGlobals, OcclusionCulling, OcclusionDrawMode, OcclusionPass,
+    RenderPass, TaaInput, TemporalAntiAliasing,
+};
 use crate::{RendererState, RendererStateSyncedManagers};
 
 pub mod materials {
@@ -12,19 +18,102 @@ pub mod materials {
     mod debug_material;
 }
 
+mod post_process {
+    pub use self::fxaa::Fxaa;
+    pub use self::smaa::Smaa;
+    pub use self::tonemap::{Tonemap, TonemapInput};
+
+    mod fxaa;
+    mod smaa;
+    mod tonemap;
+
+    /// Returns whether shaders must encode colors to sRGB when writing them
+    /// to the render pass target displayed in the given color space.
+    ///
+    /// sRGB formats encode colors on write, other formats store values as is,
+    /// so they are only encoded if the color space expects nonlinear values.
+    pub(super) fn needs_srgb_encoding(
+        encoder: &gfx::RenderPassEncoder<'_, '_>,
+        color_space: Option<gfx::ColorSpace>,
+    ) -> bool {
+        let target_format = encoder.framebuffer().info().attachments[0]
+            .info()
+            .image
+            .info()
+            .format;
+        target_format.description().ty != gfx::FormatType::Srgb
+            && matches!(
+                color_space,
+                Some(gfx::ColorSpace::SrgbNonlinear | gfx::ColorSpace::ExtendedSrgbNonlinear)
+            )
+    }
+}
+
+mod debug_lines;
+mod picking;
+mod skybox;
+
 mod render_passes {
-    pub use self::main_pass::{MainPass, MainPassInput};
+    pub use self::debug_pass::{DebugPass, DebugPassInput};
+    pub use self::fullscreen_pass::{FullscreenPass, FullscreenPassInput};
+    pub use self::main_pass::{
+        make_depth_attachment, make_hdr_attachment, make_ldr_attachment, make_velocity_attachment,
+        MainPass, MainPassInput, MainPassResolve, DEPTH_FORMAT,
+    };
+    pub use self::picking_pass::{PickingPass, PickingPassInput, PICKING_ID_FORMAT};
 
+    mod debug_pass;
+    mod fullscreen_pass;
     mod main_pass;
+    mod picking_pass;
 }
 
 // NOTE: This is a "fixed-function" stub for now.
 pub struct RenderGraph {
     graphics_pipeline_layout: gfx::PipelineLayout,
+    targets: Option<RenderTargets>,
+    msaa: Msaa,
+    depth_resolve: DepthResolve,
+    ambient_occlusion: AmbientOcclusion,
+    environment: Environment,
+    depth_pyramid: DepthPyramid,
+    occlusion_culling: OcclusionCulling,
+    temporal_aa: TemporalAntiAliasing,
+    auto_exposure: AutoExposure,
+    bloom: Bloom,
+    tonemap: post_process::Tonemap,
+    fxaa: post_process::Fxaa,
+    smaa: post_process::Smaa,
 
     // TEMP
     main_pass: render_passes::MainPass,
+    main_pass_late: render_passes::MainPass,
+    ldr_pass: render_passes::FullscreenPass,
+    output_pass: render_passes::FullscreenPass,
     debug_material: materials::DebugMaterial,
+    skybox: skybox::Skybox,
+    debug_pass: render_passes::DebugPass,
+    debug_lines: debug_lines::DebugLines,
+    picking_pass: render_passes::PickingPass,
+    picking: picking::Picking,
+}
+
+#[derive(Clone)]
+struct RenderTargets {
+    hdr: gfx::ImageView,
+    velocity: gfx::ImageView,
+    depth: gfx::ImageView,
+    /// Tonemapped scene when anti-aliasing is applied after tonemapping.
+    ldr: gfx::ImageView,
+    /// Main pass attachments when MSAA is enabled, resolved into the ones above.
+    multisampled: Option<MultisampledTargets>,
+}
+
+#[derive(Clone)]
+struct MultisampledTargets {
+    color: gfx::ImageView,
+    velocity: gfx::ImageView,
+    depth: gfx::ImageView,
 }
 
 impl RenderGraph {
@@ -44,20 +133,85 @@ impl RenderGraph {
                     }],
                 })?;
 
-        let main_pass = render_passes::MainPass::default();
+        let depth_resolve = DepthResolve::new(&state.device, &state.shader_preprocessor)?;
+        let ambient_occlusion = AmbientOcclusion::new(
+            &state.device,
+            &state.shader_preprocessor,
+            &state.frame_resources,
+        )?;
+        let environment = Environment::new(&state.device, &state.shader_preprocessor)?;
+        let depth_pyramid = DepthPyramid::new(&state.device, &state.shader_preprocessor)?;
+        let occlusion_culling = OcclusionCulling::new(
+            &state.device,
+            &state.shader_preprocessor,
+            OcclusionDrawMode::from_features(
+                state.is_feature_enabled(gfx::DeviceFeature::MultiDrawIndirect),
+                state.is_feature_enabled(gfx::DeviceFeature::DrawIndirectFirstInstance),
+            ),
+        )?;
+        let temporal_aa = TemporalAntiAliasing::new(&state.device, &state.shader_preprocessor)?;
+        let auto_exposure = AutoExposure::new(&state.device, &state.shader_preprocessor)?;
+        let bloom = Bloom::new(&state.device, &state.shader_preprocessor)?;
+        let tonemap = post_process::Tonemap::new(&state.device, &state.shader_preprocessor)?;
+        let fxaa = post_process::Fxaa::new(&state.device, &state.shader_preprocessor)?;
+        let smaa = post_process::Smaa::new(&state.device, &state.shader_preprocessor)?;
+
+        let main_pass = render_passes::MainPass::clear();
+        let main_pass_late = render_passes::MainPass::load();
+        let ldr_pass = render_passes::FullscreenPass::new(gfx::ImageLayout::ShaderReadOnlyOptimal);
+        let output_pass =
+            render_passes::FullscreenPass::new(gfx::ImageLayout::ColorAttachmentOptimal);
         let debug_material = materials::DebugMaterial::new(
             &state.device,
             &graphics_pipeline_layout,
             &state.shader_preprocessor,
         )?;
+        let skybox = skybox::Skybox::new(
+            &state.device,
+            &graphics_pipeline_layout,
+            &state.shader_preprocessor,
+        )?;
+
+        let debug_lines = debug_lines::DebugLines::new(
+            &state.device,
+            &graphics_pipeline_layout,
+            &state.shader_preprocessor,
+        )?;
 
         Ok(Self {
             graphics_pipeline_layout,
+            targets: None,
+            msaa: Msaa::Off,
+            depth_resolve,
+            ambient_occlusion,
+            environment,
+            depth_pyramid,
+            occlusion_culling,
+            temporal_aa,
+            auto_exposure,
+            bloom,
+            tonemap,
+            fxaa,
+            smaa,
             main_pass,
+            main_pass_late,
+            ldr_pass,
+            output_pass,
             debug_material,
+            skybox,
+            debug_pass: Default::default(),
+            debug_lines,
+            picking_pass: Default::default(),
+            picking: Default::default(),
         })
     }
 
+    /// Frees the bindless handles owned by the graph, e.g. at shutdown.
+    pub fn release(&mut self, state: &RendererState) {
+        self.ambient_occlusion.release(&state.bindless_resources);
+        self.environment.release(&state.bindless_resources);
+    }
+
     pub fn execute(&mut self, ctx: &mut RenderGraphContext<'_>) -> Result<()> {
         profiling::scope!("render_graph");
 
@@ -65,25 +219,67 @@ impl RenderGraph {
             .synced_managers
             .time_manager
             .compute_interpolation_factor(ctx.now);
+        // NOTE: Used to compute the motion of dynamic objects since the previous frame
+        let previous_interpolation_factor = ctx
+            .now
+            .checked_sub(Duration::from_secs_f32(ctx.delta_time.max(0.0)))
+            .map_or(interpolation_factor, |previous_now| {
+                ctx.synced_managers
+                    .time_manager
+                    .compute_interpolation_factor(previous_now)
+            })
+            .min(interpolation_factor);
+
+        self.picking.resolve(ctx.state, ctx.frame)?;
+
+        let debug_view = ctx.state.debug_view();
+        let mut post_process = ctx.state.post_process();
+        if debug_view != DebugView::None {
+            // NOTE: Debug views are displayed as is
+            post_process.anti_aliasing = AntiAliasing::None;
+            post_process.ambient_occlusion = None;
+            post_process.bloom = None;
+        }
+        self.debug_material.set_debug_view(
+            &ctx.state.device,
+            &ctx.state.shader_preprocessor,
+            debug_view,
+        )?;
+        let msaa = self.validate_msaa(&ctx.state.device, post_process.msaa);
+        let targets =
+            self.get_or_init_targets(&ctx.state.device, ctx.surface_image, msaa.samples())?;
+
+        let pending_environment = ctx.state.pending_environment.lock().unwrap().take();
+        if let Some(environment) = pending_environment {
+            profiling::scope!("environment");
+            self.environment.update(
+                &ctx.state.device,
+                &ctx.state.bindless_resources,
+                ctx.encoder,
+                environment.as_ref(),
+            )?;
+        }
+
+        // NOTE: Prepared before the globals are written since materials sample
+        // the occlusion of the previous frame, which is discarded on resize.
+        match &post_process.ambient_occlusion {
+            Some(_) => self.ambient_occlusion.prepare(
+                &ctx.state.device,
+                &ctx.state.bindless_resources,
+                &targets.depth,
+            )?,
+            None => self.ambient_occlusion.reset(),
+        }
 
         let globals = ctx.state.frame_resources.flush(FlushFrameResources {
             render_resolution: ctx.surface_image.image().info().extent.into(),
             delta_time: ctx.delta_time,
             frame: ctx.frame,
+            jitter: matches!(post_process.anti_aliasing, AntiAliasing::Taa(_)),
+            ambient_occlusion: self.ambient_occlusion.history(),
+            environment: self.environment.handles(),
         });
 
-        ctx.encoder.bind_graphics_descriptor_sets(
-            &self.graphics_pipeline_layout,
-            0,
-            &[
-                ctx.state.frame_resources.descriptor_set(),
-                ctx.state.bindless_resources.descriptor_set(),
-            ],
-            &[globals.dynamic_offset()],
-        );
-
-        ctx.state.mesh_manager.bind_index_buffer(ctx.encoder);
-
         ctx.encoder.memory_barrier(
             gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::TRANSFER,
             gfx::AccessFlags::SHADER_WRITE | gfx::AccessFlags::TRANSFER_WRITE,
@@ -91,33 +287,385 @@ impl RenderGraph {
             gfx::AccessFlags::SHADER_READ,
         );
 
-        {
+        self.depth_pyramid
+            .prepare(&ctx.state.device, &targets.depth)?;
+
+        // NOTE: Objects visible in the previous frame are drawn first, then the
+        // depth pyramid is rebuilt and the rest of the objects are tested again.
+        for pass in [OcclusionPass::Early, OcclusionPass::Late] {
+            if pass == OcclusionPass::Late && self.occlusion_culling.is_enabled() {
+                profiling::scope!("depth_pyramid");
+                let view_projection = globals.camera_projection * globals.camera_view;
+                self.depth_pyramid.build(ctx.encoder, &view_projection);
+            }
+
+            {
+                profiling::scope!("occlusion_culling");
+
+                self.debug_material.cull(&mut RenderGraphCullContext {
+                    state: ctx.state,
+                    synced_managers: ctx.synced_managers,
+                    globals: &globals,
+                    encoder: ctx.encoder,
+                    occlusion_culling: &self.occlusion_culling,
+                    depth_pyramid: &self.depth_pyramid,
+                    interpolation_factor,
+                    previous_interpolation_factor,
+                    occlusion_pass: pass,
+                })?;
+            }
+
             profiling::scope!("main_pass");
 
-            let encoder = ctx.encoder.with_render_pass(
-                &mut self.main_pass,
-                &MainPassInput {
+            // NOTE: Rebound for each pass since the depth resolve uses its own layout.
+            ctx.encoder.bind_graphics_descriptor_sets(
+                &self.graphics_pipeline_layout,
+                0,
+                &[
+                    ctx.state.frame_resources.descriptor_set(),
+                    ctx.state.bindless_resources.descriptor_set(),
+                ],
+                &[globals.dynamic_offset()],
+            );
+
+            let main_pass = match pass {
+                OcclusionPass::Early => &mut self.main_pass,
+                OcclusionPass::Late => &mut self.main_pass_late,
+            };
+            let main_pass_input = match &targets.multisampled {
+                Some(multisampled) => MainPassInput {
+                    max_image_count: 1,
+                    target: multisampled.color.info().image.clone(),
+                    velocity: multisampled.velocity.clone(),
+                    depth: multisampled.depth.clone(),
+                    resolve: Some(MainPassResolve {
+                        target: targets.hdr.clone(),
+                        velocity: targets.velocity.clone(),
+                    }),
+                },
+                None => MainPassInput {
+                    max_image_count: 1,
+                    target: targets.hdr.info().image.clone(),
+                    velocity: targets.velocity.clone(),
+                    depth: targets.depth.clone(),
+                    resolve: None,
+                },
+            };
+            {
+                let encoder =
+                    ctx.encoder
+                        .with_render_pass(main_pass, &main_pass_input, &ctx.state.device)?;
+
+                let mut node_ctx = RenderGraphNodeContext {
+                    graphics_pipeline_layout: &self.graphics_pipeline_layout,
+                    state: ctx.state,
+                    synced_managers: ctx.synced_managers,
+                    encoder,
+                    now: ctx.now,
+                    delta_time: ctx.delta_time,
+                    frame: ctx.frame,
+                    occlusion_pass: pass,
+                };
+                self.debug_material.execute(&mut node_ctx)?;
+                if self.environment.handles().is_some() && debug_view == DebugView::None {
+                    self.skybox.execute(&mut node_ctx)?;
+                }
+            }
+
+            if let Some(multisampled) = &targets.multisampled {
+                profiling::scope!("depth_resolve");
+                self.depth_resolve.prepare(
+                    &ctx.state.device,
+                    &multisampled.depth,
+                    &targets.depth,
+                )?;
+                self.depth_resolve.execute(&ctx.state.device, ctx.encoder)?;
+            }
+        }
+
+        let render_resolution = globals.render_resolution;
+        if self.picking.prepare(ctx.state, render_resolution) {
+            profiling::scope!("picking");
+            let picking_input = self
+                .picking
+                .get_or_init_targets(&ctx.state.device, render_resolution)?;
+            {
+                let mut encoder = ctx.encoder.with_render_pass(
+                    &mut self.picking_pass,
+                    &picking_input,
+                    &ctx.state.device,
+                )?;
+                encoder.bind_graphics_descriptor_sets(
+                    &self.graphics_pipeline_layout,
+                    0,
+                    &[
+                        ctx.state.frame_resources.descriptor_set(),
+                        ctx.state.bindless_resources.descriptor_set(),
+                    ],
+                    &[globals.dynamic_offset()],
+                );
+
+                let mut node_ctx = RenderGraphNodeContext {
+                    graphics_pipeline_layout: &self.graphics_pipeline_layout,
+                    state: ctx.state,
+                    synced_managers: ctx.synced_managers,
+                    encoder,
+                    now: ctx.now,
+                    delta_time: ctx.delta_time,
+                    frame: ctx.frame,
+                    occlusion_pass: OcclusionPass::Late,
+                };
+                self.debug_material.execute_picking(&mut node_ctx)?;
+            }
+            self.picking.read_back(
+                &ctx.state.device,
+                ctx.encoder,
+                &picking_input,
+                &globals,
+                ctx.frame,
+            )?;
+        }
+
+        fn color_barrier(view: &gfx::ImageView) -> gfx::ImageMemoryBarrier<'_> {
+            gfx::ImageMemoryBarrier {
+                image: &view.info().image,
+                src_access: gfx::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::ColorAttachmentOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: gfx::ImageSubresourceRange::whole(view.info().image.info()),
+            }
+        }
+        ctx.encoder.image_barriers(
+            gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[
+                color_barrier(&targets.hdr),
+                color_barrier(&targets.velocity),
+                gfx::ImageMemoryBarrier {
+                    image: &targets.depth.info().image,
+                    src_access: gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                    dst_access: gfx::AccessFlags::SHADER_READ,
+                    old_layout: Some(gfx::ImageLayout::DepthStencilAttachmentOptimal),
+                    new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::whole(
+                        targets.depth.info().image.info(),
+                    ),
+                },
+            ],
+        );
+
+        if let Some(settings) = &post_process.ambient_occlusion {
+            profiling::scope!("ambient_occlusion");
+            self.ambient_occlusion.execute(
+                ctx.encoder,
+                &ctx.state.frame_resources,
+                globals.dynamic_offset(),
+                settings,
+            );
+        }
+
+        let hdr = match &post_process.anti_aliasing {
+            AntiAliasing::Taa(settings) => {
+                profiling::scope!("temporal_aa");
+                self.temporal_aa.prepare(
+                    &ctx.state.device,
+                    &TaaInput {
+                        color: &targets.hdr,
+                        velocity: &targets.velocity,
+                        depth: &targets.depth,
+                    },
+                )?;
+                self.temporal_aa.execute(ctx.encoder, settings);
+                self.temporal_aa.output().clone()
+            }
+            AntiAliasing::None => {
+                self.temporal_aa.reset();
+                targets.hdr.clone()
+            }
+        };
+
+        {
+            profiling::scope!("auto_exposure");
+            self.auto_exposure.execute(
+                &ctx.state.device,
+                ctx.encoder,
+                &hdr,
+                &post_process.exposure,
+                ctx.delta_time,
+            )?;
+        }
+
+        {
+            profiling::scope!("bloom");
+            self.bloom.prepare(
+                &ctx.state.device,
+                &hdr,
+                self.auto_exposure.exposure_buffer(),
+            )?;
+            self.bloom.execute(ctx.encoder, post_process.bloom.as_ref());
+        }
+
+        let post_anti_aliasing = post_process.post_anti_aliasing;
+        let color_space = ctx.surface_image.color_space();
+        let surface_input = FullscreenPassInput {
+            max_image_count: ctx.surface_image.total_image_count(),
+            target: ctx.surface_image.image().clone(),
+        };
+
+        {
+            profiling::scope!("tonemap");
+            let ldr_input;
+            let (pass, input, color_space) = match post_anti_aliasing {
+                PostAntiAliasing::None => (&mut self.output_pass, &surface_input, color_space),
+                PostAntiAliasing::Fxaa | PostAntiAliasing::Smaa => {
+                    ldr_input = FullscreenPassInput {
+                        max_image_count: 1,
+                        target: targets.ldr.info().image.clone(),
+                    };
+                    // NOTE: Post anti-aliasing expects sRGB encoded values
+                    let ldr_color_space = Some(gfx::ColorSpace::SrgbNonlinear);
+                    (&mut self.ldr_pass, &ldr_input, ldr_color_space)
+                }
+            };
+            let mut encoder = ctx
+                .encoder
+                .with_render_pass(pass, input, &ctx.state.device)?;
+            self.tonemap.execute(
+                &ctx.state.device,
+                &mut encoder,
+                &post_process::TonemapInput {
+                    hdr_target: &hdr,
+                    exposure_buffer: self.auto_exposure.exposure_buffer(),
+                    bloom: self.bloom.view(),
+                    tonemapping: post_process.tonemapping,
+                    bloom_intensity: post_process.bloom.map_or(0.0, |bloom| {
+                        bloom.intensity.max(0.0) / self.bloom.mip_levels() as f32
+                    }),
+                    debug_view,
+                    color_space,
+                },
+            )?;
+        }
+
+        if post_anti_aliasing != PostAntiAliasing::None {
+            ctx.encoder.memory_barrier(
+                gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                gfx::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                gfx::PipelineStageFlags::FRAGMENT_SHADER,
+                gfx::AccessFlags::SHADER_READ,
+            );
+        }
+
+        match post_anti_aliasing {
+            PostAntiAliasing::None => {}
+            PostAntiAliasing::Fxaa => {
+                profiling::scope!("fxaa");
+                let mut encoder = ctx.encoder.with_render_pass(
+                    &mut self.output_pass,
+                    &surface_input,
+                    &ctx.state.device,
+                )?;
+                self.fxaa
+                    .execute(&ctx.state.device, &mut encoder, &targets.ldr, color_space)?;
+            }
+            PostAntiAliasing::Smaa => {
+                profiling::scope!("smaa");
+                self.smaa.prepare(&ctx.state.device, &targets.ldr)?;
+                self.smaa.execute(&ctx.state.device, ctx.encoder)?;
+                let mut encoder = ctx.encoder.with_render_pass(
+                    &mut self.output_pass,
+                    &surface_input,
+                    &ctx.state.device,
+                )?;
+                self.smaa
+                    .blend(&ctx.state.device, &mut encoder, color_space)?;
+            }
+        }
+
+        if self.debug_lines.prepare(ctx.state) {
+            profiling::scope!("debug_lines");
+            let mut encoder = ctx.encoder.with_render_pass(
+                &mut self.debug_pass,
+                &render_passes::DebugPassInput {
                     max_image_count: ctx.surface_image.total_image_count(),
                     target: ctx.surface_image.image().clone(),
+                    depth: targets.depth.clone(),
                 },
                 &ctx.state.device,
             )?;
-
-            self.debug_material.execute(&mut RenderGraphNodeContext {
-                graphics_pipeline_layout: &self.graphics_pipeline_layout,
-                state: ctx.state,
-                globals: &globals,
-                synced_managers: ctx.synced_managers,
-                encoder,
-                now: ctx.now,
-                delta_time: ctx.delta_time,
-                frame: ctx.frame,
-                interpolation_factor,
-            })?;
+            self.debug_lines.execute(
+                ctx.state,
+                globals.dynamic_offset(),
+                &self.graphics_pipeline_layout,
+                &mut encoder,
+                color_space,
+            )?;
         }
 
         Ok(())
     }
+
+    /// Returns the requested MSAA mode, lowered to the highest one supported by the device.
+    fn validate_msaa(&mut self, device: &gfx::Device, requested: Msaa) -> Msaa {
+        let limits = device.limits();
+        // NOTE: The multisampled depth is also sampled when resolved.
+        let supported_counts = limits.framebuffer_color_sample_counts.bits()
+            & limits.framebuffer_depth_sample_counts.bits()
+            & limits.sampled_image_color_sample_counts.bits()
+            & limits.sampled_image_depth_sample_counts.bits();
+
+        let msaa = requested.clamp_to_supported(supported_counts);
+        if self.msaa != requested && msaa != requested {
+            tracing::warn!(?requested, supported = ?msaa, "unsupported MSAA sample count");
+        }
+        self.msaa = requested;
+        msaa
+    }
+
+    /// Returns the scene attachments matching the surface extent and sample count.
+    fn get_or_init_targets(
+        &mut self,
+        device: &gfx::Device,
+        surface_image: &gfx::SurfaceImage<'_>,
+        samples: gfx::Samples,
+    ) -> Result<RenderTargets> {
+        let extent = surface_image.image().info().extent;
+        if let Some(targets) = &self.targets {
+            let target_samples = match &targets.multisampled {
+                Some(multisampled) => multisampled.depth.info().image.info().samples,
+                None => gfx::Samples::_1,
+            };
+            if UVec2::from(targets.depth.info().image.info().extent) == UVec2::from(extent)
+                && target_samples == samples
+            {
+                return Ok(targets.clone());
+            }
+        }
+
+        let multisampled = if samples != gfx::Samples::_1 {
+            Some(MultisampledTargets {
+                color: render_passes::make_hdr_attachment(device, extent, samples)?,
+                velocity: render_passes::make_velocity_attachment(device, extent, samples)?,
+                depth: render_passes::make_depth_attachment(device, extent, samples)?,
+            })
+        } else {
+            None
+        };
+
+        let single = gfx::Samples::_1;
+        let targets = self.targets.insert(RenderTargets {
+            hdr: render_passes::make_hdr_attachment(device, extent, single)?,
+            velocity: render_passes::make_velocity_attachment(device, extent, single)?,
+            depth: render_passes::make_depth_attachment(device, extent, single)?,
+            ldr: render_passes::make_ldr_attachment(device, extent)?,
+            multisampled,
+        });
+        Ok(targets.clone())
+    }
 }
 
 pub struct RenderGraphContext<'a> {
@@ -133,17 +681,40 @@ pub struct RenderGraphContext<'a> {
 trait RenderGraphNode {
     type RenderPass: RenderPass;
 
+    /// Prepares draws of the node before its render pass begins.
+    fn cull(&mut self, _ctx: &mut RenderGraphCullContext<'_>) -> Result<()> {
+        Ok(())
+    }
+
     fn execute(&mut self, ctx: &mut RenderGraphNodeContext<'_, '_>) -> Result<()>;
+
+    /// Draws the objects of the current frame into the picking pass.
+    ///
+    /// Called after all main passes, so draws of both occlusion passes are ready.
+    fn execute_picking(&mut self, _ctx: &mut RenderGraphNodeContext<'_, '_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct RenderGraphCullContext<'a> {
+    pub state: &'a RendererState,
+    pub synced_managers: &'a RendererStateSyncedManagers,
+    pub globals: &'a FrameGlobals,
+    pub encoder: &'a mut gfx::Encoder,
+    pub occlusion_culling: &'a OcclusionCulling,
+    pub depth_pyramid: &'a DepthPyramid,
+    pub interpolation_factor: f32,
+    pub previous_interpolation_factor: f32,
+    pub occlusion_pass: OcclusionPass,
 }
 
 struct RenderGraphNodeContext<'a, 'pass> {
     pub graphics_pipeline_layout: &'a gfx::PipelineLayout,
     pub state: &'a RendererState,
     pub synced_managers: &'a RendererStateSyncedManagers,
-    pub globals: &'a FrameGlobals,
     pub encoder: gfx::RenderPassEncoder<'a, 'pass>,
     pub now: Instant,
     pub delta_time: f32,
     pub frame: u32,
-    pub interpolation_factor: f32,
+    pub occlusion_pass: OcclusionPass,
 }
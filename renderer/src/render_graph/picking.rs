@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use gfx::MakeImageView;
+use glam::{Mat4, UVec2, Vec3, Vec4};
+
+use crate::render_graph::render_passes::{PickingPassInput, DEPTH_FORMAT, PICKING_ID_FORMAT};
+use crate::types::{PendingPick, PickRequest, PickResult};
+use crate::util::FrameGlobals;
+use crate::worker::FRAMES_IN_FLIGHT;
+use crate::RendererState;
+
+/// Width and height of the region read back around the picked position.
+const PICK_REGION_SIZE: u32 = 5;
+
+/// Object picking through a buffer with ids of the visible objects.
+///
+/// The buffer is only rendered in frames with pick requests. A small region
+/// around each requested position is copied into a host visible buffer,
+/// which is read once the frame is complete.
+#[derive(Default)]
+pub struct Picking {
+    targets: Option<PickingPassInput>,
+    pending: Vec<PendingPick>,
+    in_flight: VecDeque<InFlightPick>,
+}
+
+struct InFlightPick {
+    frame: u32,
+    request: PickRequest,
+    region: PickRegion,
+    /// Ids of the region followed by their depth.
+    buffer: gfx::Buffer,
+    render_resolution: UVec2,
+    /// Transforms NDC of the picked frame into world space.
+    inverse_view_projection: Mat4,
+}
+
+impl Picking {
+    /// Resolves the requests of completed frames.
+    pub fn resolve(&mut self, state: &RendererState, frame: u32) -> Result<()> {
+        while let Some(pick) = self.in_flight.front() {
+            if (frame.wrapping_sub(pick.frame) as usize) < FRAMES_IN_FLIGHT {
+                break;
+            }
+            let pick = self.in_flight.pop_front().unwrap();
+
+            let texel_count = pick.region.texel_count();
+            let mut ids = vec![0u32; texel_count];
+            let mut depths = vec![0f32; texel_count];
+            let mut memory = pick.buffer.as_mappable();
+            state
+                .device
+                .download_from_memory(&mut memory, 0, &mut ids)?;
+            state
+                .device
+                .download_from_memory(&mut memory, texel_count * 4, &mut depths)?;
+
+            let result = pick.region.nearest_hit(&ids).and_then(|index| {
+                let object = state.find_picked_object(ids[index])?;
+                let texel = pick.region.texel_position(index);
+                Some(PickResult {
+                    object,
+                    position: unproject(
+                        &pick.inverse_view_projection,
+                        pick.render_resolution,
+                        texel,
+                        depths[index],
+                    ),
+                })
+            });
+            pick.request.resolve(result);
+        }
+        Ok(())
+    }
+
+    /// Takes new pick requests, returns whether the picking pass is needed.
+    pub fn prepare(&mut self, state: &RendererState, render_resolution: UVec2) -> bool {
+        self.pending.extend(state.take_pending_picks());
+        self.pending.retain(|pick| {
+            let inside = pick.position.cmplt(render_resolution).all();
+            if !inside {
+                pick.request.resolve(None);
+            }
+            inside
+        });
+        !self.pending.is_empty()
+    }
+
+    /// Returns the attachments of the picking pass matching the render resolution.
+    pub fn get_or_init_targets(
+        &mut self,
+        device: &gfx::Device,
+        render_resolution: UVec2,
+    ) -> Result<PickingPassInput> {
+        if let Some(targets) = &self.targets {
+            if UVec2::from(targets.ids.info().image.info().extent) == render_resolution {
+                return Ok(PickingPassInput {
+                    ids: targets.ids.clone(),
+                    depth: targets.depth.clone(),
+                });
+            }
+        }
+
+        let make_attachment = |format: gfx::Format, usage: gfx::ImageUsageFlags| {
+            device
+                .create_image(gfx::ImageInfo {
+                    extent: gfx::ImageExtent::D2 {
+                        width: render_resolution.x,
+                        height: render_resolution.y,
+                    },
+                    format,
+                    mip_levels: 1,
+                    samples: gfx::Samples::_1,
+                    array_layers: 1,
+                    flags: Default::default(),
+                    usage: usage | gfx::ImageUsageFlags::TRANSFER_SRC,
+                })?
+                .make_image_view(device)
+        };
+
+        let targets = self.targets.insert(PickingPassInput {
+            ids: make_attachment(PICKING_ID_FORMAT, gfx::ImageUsageFlags::COLOR_ATTACHMENT)?,
+            depth: make_attachment(DEPTH_FORMAT, gfx::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)?,
+        });
+        Ok(PickingPassInput {
+            ids: targets.ids.clone(),
+            depth: targets.depth.clone(),
+        })
+    }
+
+    /// Copies the regions around the pending requests out of the picking pass attachments.
+    pub fn read_back(
+        &mut self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        targets: &PickingPassInput,
+        globals: &FrameGlobals,
+        frame: u32,
+    ) -> Result<()> {
+        let inverse_view_projection = (globals.camera_projection * globals.camera_view).inverse();
+
+        for pick in self.pending.drain(..) {
+            let region = PickRegion::new(pick.position, globals.render_resolution);
+            let texel_count = region.texel_count();
+
+            let buffer = device.create_mappable_buffer(
+                gfx::BufferInfo {
+                    align_mask: 0b11,
+                    size: texel_count * 8,
+                    usage: gfx::BufferUsage::TRANSFER_DST,
+                },
+                gfx::MemoryUsage::DOWNLOAD,
+            )?;
+
+            for (view, aspect, buffer_offset) in [
+                (&targets.ids, gfx::ImageAspectFlags::COLOR, 0),
+                (
+                    &targets.depth,
+                    gfx::ImageAspectFlags::DEPTH,
+                    texel_count * 4,
+                ),
+            ] {
+                encoder.copy_image_to_buffer(
+                    &view.info().image,
+                    gfx::ImageLayout::TransferSrcOptimal,
+                    &buffer,
+                    &[gfx::BufferImageCopy {
+                        buffer_offset,
+                        buffer_row_length: 0,
+                        buffer_image_height: 0,
+                        image_subresource: gfx::ImageSubresourceLayers::new(aspect, 0, 0..1),
+                        image_offset: region.offset.as_ivec2().extend(0),
+                        image_extent: region.extent.extend(1),
+                    }],
+                );
+            }
+
+            self.in_flight.push_back(InFlightPick {
+                frame,
+                request: pick.request,
+                region,
+                buffer,
+                render_resolution: globals.render_resolution,
+                inverse_view_projection,
+            });
+        }
+
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::TRANSFER,
+            gfx::AccessFlags::TRANSFER_WRITE,
+            gfx::PipelineStageFlags::HOST,
+            gfx::AccessFlags::HOST_READ,
+        );
+        Ok(())
+    }
+}
+
+/// Region of the picking buffer around the requested position, clamped to its bounds.
+#[derive(Debug, Clone, Copy)]
+struct PickRegion {
+    offset: UVec2,
+    extent: UVec2,
+    /// Requested position.
+    center: UVec2,
+}
+
+impl PickRegion {
+    fn new(center: UVec2, render_resolution: UVec2) -> Self {
+        let half_size = UVec2::splat(PICK_REGION_SIZE / 2);
+        let offset = center.saturating_sub(half_size);
+        let end = (center + half_size + 1).min(render_resolution);
+        Self {
+            offset,
+            extent: end - offset,
+            center,
+        }
+    }
+
+    fn texel_count(&self) -> usize {
+        (self.extent.x * self.extent.y) as usize
+    }
+
+    fn texel_position(&self, index: usize) -> UVec2 {
+        let index = index as u32;
+        self.offset + glam::uvec2(index % self.extent.x, index / self.extent.x)
+    }
+
+    /// Returns the index of the closest texel to the center with an object.
+    fn nearest_hit(&self, ids: &[u32]) -> Option<usize> {
+        ids.iter()
+            .enumerate()
+            .filter(|(_, id)| **id != 0)
+            .min_by_key(|(index, _)| {
+                let delta = self.texel_position(*index).as_ivec2() - self.center.as_ivec2();
+                delta.length_squared()
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+/// Returns the world space position at the center of a texel with the specified depth.
+fn unproject(
+    inverse_view_projection: &Mat4,
+    render_resolution: UVec2,
+    texel: UVec2,
+    depth: f32,
+) -> Vec3 {
+    let uv = (texel.as_vec2() + 0.5) / render_resolution.as_vec2();
+    // NOTE: The viewport is flipped
+    let ndc = Vec4::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let position = *inverse_view_projection * ndc;
+    position.truncate() / position.w
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_nearest_hit_in_clamped_region() {
+        let region = PickRegion::new(glam::uvec2(1, 0), glam::uvec2(100, 100));
+        assert_eq!(region.offset, glam::uvec2(0, 0));
+        assert_eq!(region.extent, glam::uvec2(4, 3));
+
+        let mut ids = vec![0; region.texel_count()];
+        assert_eq!(region.nearest_hit(&ids), None);
+
+        ids[11] = 7;
+        ids[4] = 3;
+        assert_eq!(region.nearest_hit(&ids), Some(4));
+        assert_eq!(region.texel_position(4), glam::uvec2(0, 1));
+
+        ids[1] = 5;
+        assert_eq!(region.nearest_hit(&ids), Some(1));
+    }
+
+    #[test]
+    fn unprojects_texel_center() {
+        let projection = Mat4::perspective_infinite_rh(1.0, 1.0, 0.1);
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let inverse_view_projection = (projection * view).inverse();
+
+        let point = Vec3::new(0.0, 0.0, 1.0);
+        let clip = projection * view * point.extend(1.0);
+        let depth = clip.z / clip.w;
+
+        let position = unproject(
+            &inverse_view_projection,
+            glam::uvec2(2, 2),
+            glam::uvec2(1, 1),
+            depth,
+        );
+        // NOTE: Texel centers are offset by a quarter of the target from the origin
+        assert!((position.z - point.z).abs() < 1e-3);
+        assert!(position.x > 0.0 && position.y < 0.0);
+    }
+}
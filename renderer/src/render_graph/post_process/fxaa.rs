@@ -0,0 +1,119 @@
+use anyhow::Result;
+
+use crate::util::{CachedGraphicsPipeline, RenderPassEncoderExt, ShaderPreprocessor};
+
+/// Fast approximate anti-aliasing of the tonemapped scene.
+pub struct Fxaa {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: CachedGraphicsPipeline,
+    sampler: gfx::Sampler,
+    source: Option<(gfx::ImageView, gfx::DescriptorSet)>,
+}
+
+impl Fxaa {
+    #[tracing::instrument(level = "debug", name = "create_fxaa", skip_all)]
+    pub fn new(device: &gfx::Device, shaders: &ShaderPreprocessor) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "fullscreen.vert", "main")?;
+        let fragment_shader = shaders.make_fragment_shader(device, "fxaa.frag", "main")?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![gfx::DescriptorSetLayoutBinding {
+                    binding: 0,
+                    ty: gfx::DescriptorType::CombinedImageSampler,
+                    count: 1,
+                    stages: gfx::ShaderStageFlags::FRAGMENT,
+                    flags: Default::default(),
+                }],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::FRAGMENT,
+                offset: 0,
+                size: std::mem::size_of::<u32>() as u32,
+            }],
+        })?;
+
+        let pipeline = CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
+            primitive_topology: Default::default(),
+            primitive_restart_enable: false,
+            vertex_shader,
+            rasterizer: Some(gfx::Rasterizer {
+                fragment_shader: Some(fragment_shader),
+                ..Default::default()
+            }),
+            layout,
+        });
+
+        let sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            sampler,
+            source: None,
+        })
+    }
+
+    /// Draws the anti-aliased `ldr_target` into the current render pass.
+    ///
+    /// The source must contain sRGB encoded colors in the `ShaderReadOnlyOptimal` layout.
+    pub fn execute(
+        &mut self,
+        device: &gfx::Device,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        ldr_target: &gfx::ImageView,
+        color_space: Option<gfx::ColorSpace>,
+    ) -> Result<()> {
+        if !matches!(&self.source, Some((source, _)) if source == ldr_target) {
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: self.descriptor_set_layout.clone(),
+            })?;
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &[gfx::DescriptorSetWrite {
+                    binding: 0,
+                    element: 0,
+                    data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                        gfx::CombinedImageSampler {
+                            view: ldr_target.clone(),
+                            layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                            sampler: self.sampler.clone(),
+                        },
+                    ]),
+                }],
+            }]);
+            self.source = Some((ldr_target.clone(), descriptor_set));
+        }
+        let (_, descriptor_set) = self.source.as_ref().unwrap();
+
+        // NOTE: The LDR target is sRGB encoded
+        let decode_srgb = !super::needs_srgb_encoding(encoder, color_space);
+
+        encoder.bind_cached_graphics_pipeline(&mut self.pipeline, device)?;
+        let layout = &self.pipeline.descr().layout;
+        encoder.bind_graphics_descriptor_sets(layout, 0, &[descriptor_set], &[]);
+        encoder.push_constants(
+            layout,
+            gfx::ShaderStageFlags::FRAGMENT,
+            0,
+            &[decode_srgb as u32],
+        );
+        encoder.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
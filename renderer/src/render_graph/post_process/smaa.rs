@@ -0,0 +1,398 @@
+use anyhow::{Context, Result};
+use gfx::MakeImageView;
+use glam::{IVec3, UVec2};
+use shared::Embed;
+
+use crate::render_graph::render_passes::{FullscreenPass, FullscreenPassInput};
+use crate::util::{CachedGraphicsPipeline, EncoderExt, RenderPassEncoderExt, ShaderPreprocessor};
+use crate::Textures;
+
+/// Subpixel morphological anti-aliasing (SMAA 1x) of the tonemapped scene.
+///
+/// Edges are detected from luma and searched along orthogonal directions,
+/// diagonal and corner detection are disabled as in the medium preset.
+pub struct Smaa {
+    edges_layout: gfx::DescriptorSetLayout,
+    weights_layout: gfx::DescriptorSetLayout,
+    blend_layout: gfx::DescriptorSetLayout,
+    edges_pipeline: CachedGraphicsPipeline,
+    weights_pipeline: CachedGraphicsPipeline,
+    blend_pipeline: CachedGraphicsPipeline,
+    point_sampler: gfx::Sampler,
+    linear_sampler: gfx::Sampler,
+    area_texture: LookupTexture,
+    search_texture: LookupTexture,
+    edges_pass: FullscreenPass,
+    weights_pass: FullscreenPass,
+    target: Option<SmaaTarget>,
+}
+
+struct LookupTexture {
+    view: gfx::ImageView,
+    data: &'static [u8],
+    uploaded: bool,
+}
+
+struct SmaaTarget {
+    color: gfx::ImageView,
+    edges: gfx::ImageView,
+    weights: gfx::ImageView,
+    edges_descriptor_set: gfx::DescriptorSet,
+    weights_descriptor_set: gfx::DescriptorSet,
+    blend_descriptor_set: gfx::DescriptorSet,
+}
+
+impl Smaa {
+    #[tracing::instrument(level = "debug", name = "create_smaa", skip_all)]
+    pub fn new(device: &gfx::Device, shaders: &ShaderPreprocessor) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "fullscreen.vert", "main")?;
+
+        let make_layout = |binding_count: u32| {
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: (0..binding_count)
+                    .map(|binding| gfx::DescriptorSetLayoutBinding {
+                        binding,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::FRAGMENT,
+                        flags: Default::default(),
+                    })
+                    .collect(),
+                flags: Default::default(),
+            })
+        };
+        let edges_layout = make_layout(1)?;
+        let weights_layout = make_layout(3)?;
+        let blend_layout = make_layout(2)?;
+
+        let make_pipeline = |fragment_shader: &str,
+                             descriptor_set_layout: &gfx::DescriptorSetLayout,
+                             push_constants: Vec<gfx::PushConstant>|
+         -> Result<CachedGraphicsPipeline> {
+            let fragment_shader = shaders.make_fragment_shader(device, fragment_shader, "main")?;
+            let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+                sets: vec![descriptor_set_layout.clone()],
+                push_constants,
+            })?;
+            Ok(CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+                vertex_bindings: Vec::new(),
+                vertex_attributes: Vec::new(),
+                primitive_topology: Default::default(),
+                primitive_restart_enable: false,
+                vertex_shader: vertex_shader.clone(),
+                rasterizer: Some(gfx::Rasterizer {
+                    fragment_shader: Some(fragment_shader),
+                    ..Default::default()
+                }),
+                layout,
+            }))
+        };
+        let edges_pipeline = make_pipeline("smaa_edges.frag", &edges_layout, Vec::new())?;
+        let weights_pipeline = make_pipeline("smaa_weights.frag", &weights_layout, Vec::new())?;
+        let blend_pipeline = make_pipeline(
+            "smaa_blend.frag",
+            &blend_layout,
+            vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::FRAGMENT,
+                offset: 0,
+                size: std::mem::size_of::<u32>() as u32,
+            }],
+        )?;
+
+        let point_sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+        let linear_sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        let area_texture = LookupTexture::new(
+            device,
+            "smaa/area.bin",
+            gfx::Format::RG8Unorm,
+            AREA_TEXTURE_SIZE,
+        )?;
+        let search_texture = LookupTexture::new(
+            device,
+            "smaa/search.bin",
+            gfx::Format::R8Unorm,
+            SEARCH_TEXTURE_SIZE,
+        )?;
+
+        Ok(Self {
+            edges_layout,
+            weights_layout,
+            blend_layout,
+            edges_pipeline,
+            weights_pipeline,
+            blend_pipeline,
+            point_sampler,
+            linear_sampler,
+            area_texture,
+            search_texture,
+            edges_pass: FullscreenPass::new(gfx::ImageLayout::ShaderReadOnlyOptimal),
+            weights_pass: FullscreenPass::new(gfx::ImageLayout::ShaderReadOnlyOptimal),
+            target: None,
+        })
+    }
+
+    /// Recreates the intermediate targets if the source has changed (e.g. on resize).
+    pub fn prepare(&mut self, device: &gfx::Device, ldr_target: &gfx::ImageView) -> Result<()> {
+        if matches!(&self.target, Some(target) if &target.color == ldr_target) {
+            return Ok(());
+        }
+        self.target = None;
+
+        let extent = ldr_target.info().image.info().extent;
+        let make_target = |format| {
+            device
+                .create_image(gfx::ImageInfo {
+                    extent,
+                    format,
+                    mip_levels: 1,
+                    samples: gfx::Samples::_1,
+                    array_layers: 1,
+                    flags: Default::default(),
+                    usage: gfx::ImageUsageFlags::COLOR_ATTACHMENT | gfx::ImageUsageFlags::SAMPLED,
+                })?
+                .make_image_view(device)
+        };
+        let edges = make_target(gfx::Format::RG8Unorm)?;
+        let weights = make_target(gfx::Format::RGBA8Unorm)?;
+
+        let sampled = |view: &gfx::ImageView, sampler: &gfx::Sampler| gfx::CombinedImageSampler {
+            view: view.clone(),
+            layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+            sampler: sampler.clone(),
+        };
+        let make_descriptor_set = |layout: &gfx::DescriptorSetLayout,
+                                   images: &[gfx::CombinedImageSampler]|
+         -> Result<gfx::DescriptorSet> {
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: layout.clone(),
+            })?;
+            let writes = images
+                .iter()
+                .enumerate()
+                .map(|(binding, image)| gfx::DescriptorSetWrite {
+                    binding: binding as u32,
+                    element: 0,
+                    data: gfx::DescriptorSlice::CombinedImageSampler(std::slice::from_ref(image)),
+                })
+                .collect::<Vec<_>>();
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &writes,
+            }]);
+            Ok(descriptor_set)
+        };
+
+        let edges_descriptor_set = make_descriptor_set(
+            &self.edges_layout,
+            &[sampled(ldr_target, &self.point_sampler)],
+        )?;
+        let weights_descriptor_set = make_descriptor_set(
+            &self.weights_layout,
+            &[
+                sampled(&edges, &self.linear_sampler),
+                sampled(&self.area_texture.view, &self.linear_sampler),
+                sampled(&self.search_texture.view, &self.point_sampler),
+            ],
+        )?;
+        let blend_descriptor_set = make_descriptor_set(
+            &self.blend_layout,
+            &[
+                sampled(ldr_target, &self.linear_sampler),
+                sampled(&weights, &self.linear_sampler),
+            ],
+        )?;
+
+        self.target = Some(SmaaTarget {
+            color: ldr_target.clone(),
+            edges,
+            weights,
+            edges_descriptor_set,
+            weights_descriptor_set,
+            blend_descriptor_set,
+        });
+        Ok(())
+    }
+
+    /// Detects edges and computes the blending weights.
+    ///
+    /// The source must contain sRGB encoded colors in the `ShaderReadOnlyOptimal` layout.
+    pub fn execute(&mut self, device: &gfx::Device, encoder: &mut gfx::Encoder) -> Result<()> {
+        self.area_texture.upload(device, encoder)?;
+        self.search_texture.upload(device, encoder)?;
+
+        let target = self.target.as_ref().expect("smaa must be prepared");
+
+        for (pass, pipeline, image, descriptor_set) in [
+            (
+                &mut self.edges_pass,
+                &mut self.edges_pipeline,
+                &target.edges,
+                &target.edges_descriptor_set,
+            ),
+            (
+                &mut self.weights_pass,
+                &mut self.weights_pipeline,
+                &target.weights,
+                &target.weights_descriptor_set,
+            ),
+        ] {
+            {
+                let mut encoder = encoder.with_render_pass(
+                    pass,
+                    &FullscreenPassInput {
+                        max_image_count: 1,
+                        target: image.info().image.clone(),
+                    },
+                    device,
+                )?;
+                encoder.bind_cached_graphics_pipeline(pipeline, device)?;
+                encoder.bind_graphics_descriptor_sets(
+                    &pipeline.descr().layout,
+                    0,
+                    &[descriptor_set],
+                    &[],
+                );
+                encoder.draw(0..3, 0..1);
+            }
+
+            encoder.memory_barrier(
+                gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                gfx::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                gfx::PipelineStageFlags::FRAGMENT_SHADER,
+                gfx::AccessFlags::SHADER_READ,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Draws the anti-aliased source into the current render pass.
+    pub fn blend(
+        &mut self,
+        device: &gfx::Device,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        color_space: Option<gfx::ColorSpace>,
+    ) -> Result<()> {
+        let target = self.target.as_ref().expect("smaa must be prepared");
+
+        // NOTE: The LDR target is sRGB encoded
+        let decode_srgb = !super::needs_srgb_encoding(encoder, color_space);
+
+        encoder.bind_cached_graphics_pipeline(&mut self.blend_pipeline, device)?;
+        let layout = &self.blend_pipeline.descr().layout;
+        encoder.bind_graphics_descriptor_sets(layout, 0, &[&target.blend_descriptor_set], &[]);
+        encoder.push_constants(
+            layout,
+            gfx::ShaderStageFlags::FRAGMENT,
+            0,
+            &[decode_srgb as u32],
+        );
+        encoder.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+impl LookupTexture {
+    fn new(device: &gfx::Device, name: &str, format: gfx::Format, extent: UVec2) -> Result<Self> {
+        let data = Textures::iter()
+            .find_map(|(path, data)| (path == name).then_some(data))
+            .with_context(|| format!("texture {name} is not embedded"))?;
+
+        let view = device
+            .create_image(gfx::ImageInfo {
+                extent: gfx::ImageExtent::D2 {
+                    width: extent.x,
+                    height: extent.y,
+                },
+                format,
+                mip_levels: 1,
+                samples: gfx::Samples::_1,
+                array_layers: 1,
+                flags: Default::default(),
+                usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::TRANSFER_DST,
+            })?
+            .make_image_view(device)?;
+
+        Ok(Self {
+            view,
+            data,
+            uploaded: false,
+        })
+    }
+
+    fn upload(&mut self, device: &gfx::Device, encoder: &mut gfx::Encoder) -> Result<()> {
+        if self.uploaded {
+            return Ok(());
+        }
+
+        let staging = device.create_mappable_buffer(
+            gfx::BufferInfo {
+                align_mask: 0b11,
+                size: self.data.len(),
+                usage: gfx::BufferUsage::TRANSFER_SRC,
+            },
+            gfx::MemoryUsage::UPLOAD | gfx::MemoryUsage::TRANSIENT,
+        )?;
+        device.upload_to_memory(&mut staging.as_mappable(), 0, self.data)?;
+
+        let image = &self.view.info().image;
+        let range = gfx::ImageSubresourceRange::whole(image.info());
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TOP_OF_PIPE,
+            gfx::PipelineStageFlags::TRANSFER,
+            &[gfx::ImageMemoryBarrier {
+                image,
+                src_access: gfx::AccessFlags::empty(),
+                dst_access: gfx::AccessFlags::TRANSFER_WRITE,
+                old_layout: None,
+                new_layout: gfx::ImageLayout::TransferDstOptimal,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+        encoder.copy_buffer_to_image(
+            &staging,
+            image,
+            gfx::ImageLayout::TransferDstOptimal,
+            &[gfx::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: gfx::ImageSubresourceLayers::all_layers(image.info(), 0),
+                image_offset: IVec3::ZERO,
+                image_extent: UVec2::from(image.info().extent).extend(1),
+            }],
+        );
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TRANSFER,
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image,
+                src_access: gfx::AccessFlags::TRANSFER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::TransferDstOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+
+        self.uploaded = true;
+        Ok(())
+    }
+}
+
+/// Size of the orthogonal part of the reference area texture.
+const AREA_TEXTURE_SIZE: UVec2 = UVec2::new(80, 80);
+/// Size of the uncropped reference search texture.
+const SEARCH_TEXTURE_SIZE: UVec2 = UVec2::new(66, 33);
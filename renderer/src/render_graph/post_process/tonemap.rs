@@ -0,0 +1,207 @@
+use anyhow::Result;
+
+use crate::types::{DebugView, Tonemapping};
+use crate::util::{CachedGraphicsPipeline, RenderPassEncoderExt, ShaderPreprocessor, SourceCache};
+
+/// Applies exposure and tonemapping to the HDR target.
+pub struct Tonemap {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: CachedGraphicsPipeline,
+    sampler: gfx::Sampler,
+    bloom_sampler: gfx::Sampler,
+    /// Bloom view referenced by the cached descriptor sets.
+    bloom: Option<gfx::ImageView>,
+    descriptor_sets: SourceCache<gfx::DescriptorSet>,
+}
+
+pub struct TonemapInput<'a> {
+    /// Scene color in the `ShaderReadOnlyOptimal` layout.
+    pub hdr_target: &'a gfx::ImageView,
+    /// Exposure in EV100, see [`AutoExposure`](crate::util::AutoExposure).
+    pub exposure_buffer: &'a gfx::Buffer,
+    /// Accumulated bloom in the `General` layout.
+    pub bloom: &'a gfx::ImageView,
+    pub tonemapping: Tonemapping,
+    /// Zero if bloom is disabled.
+    pub bloom_intensity: f32,
+    /// Debug views are displayed without exposure and tonemapping.
+    pub debug_view: DebugView,
+    /// Color space in which the render pass target is displayed.
+    pub color_space: Option<gfx::ColorSpace>,
+}
+
+impl Tonemap {
+    #[tracing::instrument(level = "debug", name = "create_tonemap", skip_all)]
+    pub fn new(device: &gfx::Device, shaders: &ShaderPreprocessor) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "fullscreen.vert", "main")?;
+        let fragment_shader = shaders.make_fragment_shader(device, "tonemap.frag", "main")?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::FRAGMENT,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::FRAGMENT,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 2,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::FRAGMENT,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::FRAGMENT,
+                offset: 0,
+                size: std::mem::size_of::<TonemapPushConstants>() as u32,
+            }],
+        })?;
+
+        let pipeline = CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
+            primitive_topology: Default::default(),
+            primitive_restart_enable: false,
+            vertex_shader,
+            rasterizer: Some(gfx::Rasterizer {
+                fragment_shader: Some(fragment_shader),
+                ..Default::default()
+            }),
+            layout,
+        });
+
+        let sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+        let bloom_sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            sampler,
+            bloom_sampler,
+            bloom: None,
+            descriptor_sets: SourceCache::new(),
+        })
+    }
+
+    /// Draws the tonemapped HDR target into the current render pass.
+    pub fn execute(
+        &mut self,
+        device: &gfx::Device,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        input: &TonemapInput<'_>,
+    ) -> Result<()> {
+        let TonemapInput {
+            hdr_target,
+            exposure_buffer,
+            bloom,
+            tonemapping,
+            bloom_intensity,
+            debug_view,
+            color_space,
+        } = *input;
+
+        if self.bloom.as_ref() != Some(bloom) {
+            self.descriptor_sets.clear();
+            self.bloom = Some(bloom.clone());
+        }
+        let descriptor_set = self
+            .descriptor_sets
+            .get_or_try_insert_with(hdr_target, || {
+                let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                    layout: self.descriptor_set_layout.clone(),
+                })?;
+                device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                    set: &descriptor_set,
+                    writes: &[
+                        gfx::DescriptorSetWrite {
+                            binding: 0,
+                            element: 0,
+                            data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                                gfx::CombinedImageSampler {
+                                    view: hdr_target.clone(),
+                                    layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                                    sampler: self.sampler.clone(),
+                                },
+                            ]),
+                        },
+                        gfx::DescriptorSetWrite {
+                            binding: 1,
+                            element: 0,
+                            data: gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(
+                                exposure_buffer.clone(),
+                            )]),
+                        },
+                        gfx::DescriptorSetWrite {
+                            binding: 2,
+                            element: 0,
+                            data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                                gfx::CombinedImageSampler {
+                                    view: bloom.clone(),
+                                    layout: gfx::ImageLayout::General,
+                                    sampler: self.bloom_sampler.clone(),
+                                },
+                            ]),
+                        },
+                    ],
+                }]);
+                Ok(descriptor_set)
+            })?;
+
+        let encode_srgb = super::needs_srgb_encoding(encoder, color_space);
+
+        encoder.bind_cached_graphics_pipeline(&mut self.pipeline, device)?;
+        let layout = &self.pipeline.descr().layout;
+        encoder.bind_graphics_descriptor_sets(layout, 0, &[descriptor_set], &[]);
+        encoder.push_constants(
+            layout,
+            gfx::ShaderStageFlags::FRAGMENT,
+            0,
+            &[TonemapPushConstants {
+                tonemapping: tonemapping.shader_index(),
+                encode_srgb: encode_srgb as u32,
+                bloom_intensity,
+                debug_view: debug_view.shader_index(),
+            }],
+        );
+        encoder.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TonemapPushConstants {
+    tonemapping: u32,
+    encode_srgb: u32,
+    bloom_intensity: f32,
+    debug_view: u32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for TonemapPushConstants {}
+unsafe impl bytemuck::Zeroable for TonemapPushConstants {}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use gfx::MakeImageView;
+
+use crate::render_graph::render_passes::main_pass::DEPTH_FORMAT;
+use crate::util::RenderPass;
+
+pub struct DebugPassInput {
+    pub max_image_count: usize,
+    pub target: gfx::Image,
+    /// Single-sampled scene depth, sampled by the post-processing.
+    pub depth: gfx::ImageView,
+}
+
+/// Pass which draws on top of the final image, testing against the scene depth.
+///
+/// The target must be in the `ColorAttachmentOptimal` layout and is left in it.
+#[derive(Default)]
+pub struct DebugPass {
+    render_pass: Option<gfx::RenderPass>,
+    framebuffers: Vec<gfx::Framebuffer>,
+}
+
+impl DebugPass {
+    #[tracing::instrument(level = "debug", name = "create_debug_pass", skip_all)]
+    fn get_or_init_framebuffer(
+        &mut self,
+        device: &gfx::Device,
+        input: &DebugPassInput,
+    ) -> Result<&gfx::Framebuffer> {
+        let target_image_info = input.target.info();
+
+        let render_pass = match &self.render_pass {
+            Some(render_pass)
+                if render_pass.info().attachments[0].format == target_image_info.format =>
+            {
+                render_pass.clone()
+            }
+            _ => {
+                self.framebuffers.clear();
+                self.render_pass
+                    .insert(device.create_render_pass(gfx::RenderPassInfo {
+                        attachments: vec![
+                            gfx::AttachmentInfo {
+                                format: target_image_info.format,
+                                samples: gfx::Samples::_1,
+                                load_op: gfx::LoadOp::Load,
+                                store_op: gfx::StoreOp::Store,
+                                initial_layout: Some(gfx::ImageLayout::ColorAttachmentOptimal),
+                                final_layout: gfx::ImageLayout::ColorAttachmentOptimal,
+                            },
+                            // NOTE: The depth stays readable by shaders.
+                            gfx::AttachmentInfo {
+                                format: DEPTH_FORMAT,
+                                samples: gfx::Samples::_1,
+                                load_op: gfx::LoadOp::Load,
+                                store_op: gfx::StoreOp::Store,
+                                initial_layout: Some(gfx::ImageLayout::ShaderReadOnlyOptimal),
+                                final_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                            },
+                        ],
+                        subpasses: vec![gfx::Subpass {
+                            colors: vec![(0, gfx::ImageLayout::ColorAttachmentOptimal)],
+                            depth: Some((1, gfx::ImageLayout::DepthStencilReadOnlyOptimal)),
+                            resolves: Vec::new(),
+                        }],
+                        dependencies: vec![gfx::SubpassDependency {
+                            src: None,
+                            src_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | gfx::PipelineStageFlags::FRAGMENT_SHADER
+                                | gfx::PipelineStageFlags::COMPUTE_SHADER,
+                            dst: Some(0),
+                            dst_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                        }],
+                    })?)
+                    .clone()
+            }
+        };
+
+        match self
+            .framebuffers
+            .iter()
+            .position(|fb| is_framebuffer_compatible(fb, input))
+        {
+            Some(index) => {
+                let framebuffer = self.framebuffers.remove(index);
+                self.framebuffers.push(framebuffer);
+            }
+            None => {
+                let framebuffer = device.create_framebuffer(gfx::FramebufferInfo {
+                    render_pass,
+                    attachments: vec![input.target.make_image_view(device)?, input.depth.clone()],
+                    extent: target_image_info.extent.into(),
+                })?;
+
+                let to_remove = (self.framebuffers.len() + 1).saturating_sub(input.max_image_count);
+                if to_remove > 0 {
+                    self.framebuffers.drain(0..to_remove);
+                }
+                self.framebuffers.push(framebuffer);
+            }
+        }
+
+        Ok(self.framebuffers.last().unwrap())
+    }
+}
+
+impl RenderPass for DebugPass {
+    type Input = DebugPassInput;
+
+    fn begin_render_pass<'a, 'b>(
+        &'b mut self,
+        input: &Self::Input,
+        device: &gfx::Device,
+        encoder: &'a mut gfx::Encoder,
+    ) -> Result<gfx::RenderPassEncoder<'a, 'b>> {
+        let framebuffer = self.get_or_init_framebuffer(device, input)?;
+        Ok(encoder.with_framebuffer(framebuffer, &[]))
+    }
+}
+
+fn is_framebuffer_compatible(framebuffer: &gfx::Framebuffer, input: &DebugPassInput) -> bool {
+    let attachments = &framebuffer.info().attachments;
+    attachments[0].info().image == input.target && attachments[1] == input.depth
+}
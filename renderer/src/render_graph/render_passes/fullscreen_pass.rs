@@ -0,0 +1,116 @@
+use anyhow::Result;
+use gfx::MakeImageView;
+
+use crate::util::RenderPass;
+
+pub struct FullscreenPassInput {
+    pub max_image_count: usize,
+    pub target: gfx::Image,
+}
+
+/// Pass which overwrites the whole target, e.g. with a fullscreen triangle.
+pub struct FullscreenPass {
+    final_layout: gfx::ImageLayout,
+    render_pass: Option<gfx::RenderPass>,
+    framebuffers: Vec<gfx::Framebuffer>,
+}
+
+impl FullscreenPass {
+    pub fn new(final_layout: gfx::ImageLayout) -> Self {
+        Self {
+            final_layout,
+            render_pass: None,
+            framebuffers: Vec::new(),
+        }
+    }
+
+    #[tracing::instrument(level = "debug", name = "create_fullscreen_pass", skip_all)]
+    fn get_or_init_framebuffer(
+        &mut self,
+        device: &gfx::Device,
+        input: &FullscreenPassInput,
+    ) -> Result<&gfx::Framebuffer> {
+        let target_image_info = input.target.info();
+
+        let render_pass = match &self.render_pass {
+            Some(render_pass)
+                if render_pass.info().attachments[0].format == target_image_info.format
+                    && render_pass.info().attachments[0].samples == target_image_info.samples =>
+            {
+                render_pass.clone()
+            }
+            _ => {
+                self.framebuffers.clear();
+                self.render_pass
+                    .insert(device.create_render_pass(gfx::RenderPassInfo {
+                        attachments: vec![gfx::AttachmentInfo {
+                            format: target_image_info.format,
+                            samples: target_image_info.samples,
+                            load_op: gfx::LoadOp::DontCare,
+                            store_op: gfx::StoreOp::Store,
+                            initial_layout: None,
+                            final_layout: self.final_layout,
+                        }],
+                        subpasses: vec![gfx::Subpass {
+                            colors: vec![(0, gfx::ImageLayout::ColorAttachmentOptimal)],
+                            depth: None,
+                            resolves: Vec::new(),
+                        }],
+                        dependencies: vec![gfx::SubpassDependency {
+                            src: None,
+                            src_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | gfx::PipelineStageFlags::FRAGMENT_SHADER
+                                | gfx::PipelineStageFlags::COMPUTE_SHADER,
+                            dst: Some(0),
+                            dst_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                        }],
+                    })?)
+                    .clone()
+            }
+        };
+
+        match self
+            .framebuffers
+            .iter()
+            .position(|fb| is_framebuffer_compatible(fb, input))
+        {
+            Some(index) => {
+                let framebuffer = self.framebuffers.remove(index);
+                self.framebuffers.push(framebuffer);
+            }
+            None => {
+                let framebuffer = device.create_framebuffer(gfx::FramebufferInfo {
+                    render_pass,
+                    attachments: vec![input.target.make_image_view(device)?],
+                    extent: target_image_info.extent.into(),
+                })?;
+
+                let to_remove = (self.framebuffers.len() + 1).saturating_sub(input.max_image_count);
+                if to_remove > 0 {
+                    self.framebuffers.drain(0..to_remove);
+                }
+                self.framebuffers.push(framebuffer);
+            }
+        }
+
+        Ok(self.framebuffers.last().unwrap())
+    }
+}
+
+impl RenderPass for FullscreenPass {
+    type Input = FullscreenPassInput;
+
+    fn begin_render_pass<'a, 'b>(
+        &'b mut self,
+        input: &Self::Input,
+        device: &gfx::Device,
+        encoder: &'a mut gfx::Encoder,
+    ) -> Result<gfx::RenderPassEncoder<'a, 'b>> {
+        let framebuffer = self.get_or_init_framebuffer(device, input)?;
+        Ok(encoder.with_framebuffer(framebuffer, &[]))
+    }
+}
+
+fn is_framebuffer_compatible(framebuffer: &gfx::Framebuffer, input: &FullscreenPassInput) -> bool {
+    framebuffer.info().attachments[0].info().image == input.target
+}
@@ -6,15 +6,43 @@ use crate::util::RenderPass;
 pub struct MainPassInput {
     pub max_image_count: usize,
     pub target: gfx::Image,
+    pub velocity: gfx::ImageView,
+    pub depth: gfx::ImageView,
+    /// Attachments into which multisampled `target` and `velocity` are resolved.
+    pub resolve: Option<MainPassResolve>,
 }
 
-#[derive(Default)]
+pub struct MainPassResolve {
+    pub target: gfx::ImageView,
+    pub velocity: gfx::ImageView,
+}
+
+/// Opaque geometry pass.
+///
+/// The first pass of a frame clears the attachments, the following
+/// ones keep the contents (e.g. for objects drawn after occlusion culling).
 pub struct MainPass {
+    load: bool,
     render_pass: Option<gfx::RenderPass>,
     framebuffers: Vec<gfx::Framebuffer>,
 }
 
 impl MainPass {
+    pub fn clear() -> Self {
+        Self {
+            load: false,
+            render_pass: None,
+            framebuffers: Vec::new(),
+        }
+    }
+
+    pub fn load() -> Self {
+        Self {
+            load: true,
+            ..Self::clear()
+        }
+    }
+
     #[tracing::instrument(level = "debug", name = "create_main_pass", skip_all)]
     fn get_or_init_framebuffer(
         &mut self,
@@ -29,22 +57,18 @@ impl MainPass {
             let target_attachment = &render_pass.info().attachments[0];
             if target_attachment.format != input.target.info().format
                 || target_attachment.samples != input.target.info().samples
+                || render_pass.info().attachments.len() != attachment_count(input)
             {
                 break 'compat;
             }
 
             //
             let target_image_info = input.target.info();
-            match self.framebuffers.iter().position(|fb| {
-                let attachment = fb.info().attachments[0].info();
-                attachment.image == input.target
-                    && attachment.range
-                        == gfx::ImageSubresourceRange::new(
-                            target_image_info.format.aspect_flags(),
-                            0..1,
-                            0..1,
-                        )
-            }) {
+            match self
+                .framebuffers
+                .iter()
+                .position(|fb| is_framebuffer_compatible(fb, input))
+            {
                 Some(index) => {
                     let framebuffer = self.framebuffers.remove(index);
                     self.framebuffers.push(framebuffer);
@@ -52,10 +76,7 @@ impl MainPass {
                 None => {
                     let framebuffer = device.create_framebuffer(gfx::FramebufferInfo {
                         render_pass: render_pass.clone(),
-                        attachments: vec![
-                            input.target.make_image_view(device)?,
-                            make_depth_attachment(device, &input.target)?,
-                        ],
+                        attachments: make_framebuffer_attachments(device, input)?,
                         extent: target_image_info.extent.into(),
                     })?;
 
@@ -81,37 +102,84 @@ impl MainPass {
     ) -> Result<&gfx::Framebuffer> {
         let target_image_info = input.target.info();
 
-        let attachments = vec![
+        let (load_op, color_layout, depth_layout) = if self.load {
+            (
+                gfx::LoadOp::Load,
+                Some(gfx::ImageLayout::ColorAttachmentOptimal),
+                Some(gfx::ImageLayout::DepthStencilAttachmentOptimal),
+            )
+        } else {
+            (gfx::LoadOp::Clear(()), None, None)
+        };
+
+        let samples = target_image_info.samples;
+        let mut attachments = vec![
             gfx::AttachmentInfo {
                 format: target_image_info.format,
-                samples: target_image_info.samples,
-                load_op: gfx::LoadOp::Clear(()),
+                samples,
+                load_op,
                 store_op: gfx::StoreOp::Store,
-                initial_layout: None,
+                initial_layout: color_layout,
                 final_layout: gfx::ImageLayout::ColorAttachmentOptimal,
             },
             gfx::AttachmentInfo {
-                format: gfx::Format::D32Sfloat,
-                samples: gfx::Samples::_1,
-                load_op: gfx::LoadOp::Clear(()),
-                store_op: gfx::StoreOp::DontCare,
-                initial_layout: None,
+                format: VELOCITY_FORMAT,
+                samples,
+                load_op,
+                store_op: gfx::StoreOp::Store,
+                initial_layout: color_layout,
+                final_layout: gfx::ImageLayout::ColorAttachmentOptimal,
+            },
+            gfx::AttachmentInfo {
+                format: DEPTH_FORMAT,
+                samples,
+                load_op,
+                store_op: gfx::StoreOp::Store,
+                initial_layout: depth_layout,
                 final_layout: gfx::ImageLayout::DepthStencilAttachmentOptimal,
             },
         ];
 
+        let mut resolves = Vec::new();
+        if let Some(resolve) = &input.resolve {
+            // NOTE: Resolve attachments are fully overwritten at the end of the subpass.
+            for format in [resolve.target.info().image.info().format, VELOCITY_FORMAT] {
+                resolves.push((
+                    attachments.len() as u32,
+                    gfx::ImageLayout::ColorAttachmentOptimal,
+                ));
+                attachments.push(gfx::AttachmentInfo {
+                    format,
+                    samples: gfx::Samples::_1,
+                    load_op: gfx::LoadOp::DontCare,
+                    store_op: gfx::StoreOp::Store,
+                    initial_layout: None,
+                    final_layout: gfx::ImageLayout::ColorAttachmentOptimal,
+                });
+            }
+        }
+
         let subpasses = vec![gfx::Subpass {
-            colors: vec![(0, gfx::ImageLayout::ColorAttachmentOptimal)],
-            depth: Some((1, gfx::ImageLayout::DepthStencilAttachmentOptimal)),
+            colors: vec![
+                (0, gfx::ImageLayout::ColorAttachmentOptimal),
+                (1, gfx::ImageLayout::ColorAttachmentOptimal),
+            ],
+            depth: Some((2, gfx::ImageLayout::DepthStencilAttachmentOptimal)),
+            resolves,
         }];
 
         let dependencies = vec![gfx::SubpassDependency {
             src: None,
+            // NOTE: Attachments might still be sampled by the previous frame.
             src_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::FRAGMENT_SHADER
+                | gfx::PipelineStageFlags::COMPUTE_SHADER,
             dst: Some(0),
             dst_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
         }];
 
         let render_pass =
@@ -123,16 +191,11 @@ impl MainPass {
                 })?);
 
         //
-        let framebuffer_info = match self.framebuffers.iter().find(|fb| {
-            let attachment = fb.info().attachments[0].info();
-            attachment.image == input.target
-                && attachment.range
-                    == gfx::ImageSubresourceRange::new(
-                        target_image_info.format.aspect_flags(),
-                        0..1,
-                        0..1,
-                    )
-        }) {
+        let framebuffer_info = match self
+            .framebuffers
+            .iter()
+            .find(|fb| is_framebuffer_compatible(fb, input))
+        {
             Some(fb) => gfx::FramebufferInfo {
                 render_pass: render_pass.clone(),
                 attachments: fb.info().attachments.clone(),
@@ -140,10 +203,7 @@ impl MainPass {
             },
             None => gfx::FramebufferInfo {
                 render_pass: render_pass.clone(),
-                attachments: vec![
-                    input.target.make_image_view(device)?,
-                    make_depth_attachment(device, &input.target)?,
-                ],
+                attachments: make_framebuffer_attachments(device, input)?,
                 extent: target_image_info.extent.into(),
             },
         };
@@ -164,29 +224,138 @@ impl RenderPass for MainPass {
         device: &gfx::Device,
         encoder: &'a mut gfx::Encoder,
     ) -> Result<gfx::RenderPassEncoder<'a, 'b>> {
+        let clear_values = [
+            gfx::ClearColor(0.02, 0.02, 0.02, 1.0).into(),
+            gfx::ClearColor(0.0, 0.0, 0.0, 0.0).into(),
+            gfx::ClearDepth(1.0).into(),
+        ];
+        let clear_values = if self.load { &[][..] } else { &clear_values };
+
         let framebuffer = self.get_or_init_framebuffer(device, input)?;
-        Ok(encoder.with_framebuffer(
-            framebuffer,
-            &[
-                gfx::ClearColor(0.02, 0.02, 0.02, 1.0).into(),
-                gfx::ClearDepth(1.0).into(),
-            ],
-        ))
+        Ok(encoder.with_framebuffer(framebuffer, clear_values))
+    }
+}
+
+pub const HDR_FORMAT: gfx::Format = gfx::Format::RGBA16Sfloat;
+pub const VELOCITY_FORMAT: gfx::Format = gfx::Format::RG16Sfloat;
+pub const DEPTH_FORMAT: gfx::Format = gfx::Format::D32Sfloat;
+
+fn is_framebuffer_compatible(framebuffer: &gfx::Framebuffer, input: &MainPassInput) -> bool {
+    let attachments = &framebuffer.info().attachments;
+    let target = attachments[0].info();
+    target.image == input.target
+        && target.range
+            == gfx::ImageSubresourceRange::new(
+                input.target.info().format.aspect_flags(),
+                0..1,
+                0..1,
+            )
+        && attachments[1] == input.velocity
+        && attachments[2] == input.depth
+        && attachments.len() == attachment_count(input)
+        && input.resolve.as_ref().map_or(true, |resolve| {
+            attachments[3] == resolve.target && attachments[4] == resolve.velocity
+        })
+}
+
+fn attachment_count(input: &MainPassInput) -> usize {
+    if input.resolve.is_some() {
+        5
+    } else {
+        3
+    }
+}
+
+fn make_framebuffer_attachments(
+    device: &gfx::Device,
+    input: &MainPassInput,
+) -> Result<Vec<gfx::ImageView>> {
+    let mut attachments = vec![
+        input.target.make_image_view(device)?,
+        input.velocity.clone(),
+        input.depth.clone(),
+    ];
+    if let Some(resolve) = &input.resolve {
+        attachments.extend([resolve.target.clone(), resolve.velocity.clone()]);
     }
+    Ok(attachments)
+}
+
+/// Creates a depth attachment which can also be sampled to build a depth pyramid.
+pub fn make_depth_attachment(
+    device: &gfx::Device,
+    extent: gfx::ImageExtent,
+    samples: gfx::Samples,
+) -> Result<gfx::ImageView, gfx::OutOfDeviceMemory> {
+    make_attachment(
+        device,
+        extent,
+        samples,
+        DEPTH_FORMAT,
+        gfx::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+    )
+}
+
+/// Creates a color attachment for the scene in linear HDR values.
+pub fn make_hdr_attachment(
+    device: &gfx::Device,
+    extent: gfx::ImageExtent,
+    samples: gfx::Samples,
+) -> Result<gfx::ImageView, gfx::OutOfDeviceMemory> {
+    make_attachment(
+        device,
+        extent,
+        samples,
+        HDR_FORMAT,
+        gfx::ImageUsageFlags::COLOR_ATTACHMENT,
+    )
+}
+
+/// Creates a color attachment for the tonemapped scene in sRGB encoded values.
+pub fn make_ldr_attachment(
+    device: &gfx::Device,
+    extent: gfx::ImageExtent,
+) -> Result<gfx::ImageView, gfx::OutOfDeviceMemory> {
+    make_attachment(
+        device,
+        extent,
+        gfx::Samples::_1,
+        gfx::Format::RGBA8Unorm,
+        gfx::ImageUsageFlags::COLOR_ATTACHMENT,
+    )
+}
+
+/// Creates a color attachment for screen space motion since the previous frame.
+pub fn make_velocity_attachment(
+    device: &gfx::Device,
+    extent: gfx::ImageExtent,
+    samples: gfx::Samples,
+) -> Result<gfx::ImageView, gfx::OutOfDeviceMemory> {
+    make_attachment(
+        device,
+        extent,
+        samples,
+        VELOCITY_FORMAT,
+        gfx::ImageUsageFlags::COLOR_ATTACHMENT,
+    )
 }
 
-fn make_depth_attachment(
+fn make_attachment(
     device: &gfx::Device,
-    target: &gfx::Image,
+    extent: gfx::ImageExtent,
+    samples: gfx::Samples,
+    format: gfx::Format,
+    usage: gfx::ImageUsageFlags,
 ) -> Result<gfx::ImageView, gfx::OutOfDeviceMemory> {
     device
         .create_image(gfx::ImageInfo {
-            extent: target.info().extent,
-            format: gfx::Format::D32Sfloat,
+            extent,
+            format,
             mip_levels: 1,
-            samples: gfx::Samples::_1,
+            samples,
             array_layers: 1,
-            usage: gfx::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            flags: Default::default(),
+            usage: usage | gfx::ImageUsageFlags::SAMPLED,
         })?
         .make_image_view(device)
 }
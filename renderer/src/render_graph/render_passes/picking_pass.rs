@@ -0,0 +1,114 @@
+use anyhow::Result;
+
+use crate::render_graph::render_passes::main_pass::DEPTH_FORMAT;
+use crate::util::RenderPass;
+
+pub struct PickingPassInput {
+    pub ids: gfx::ImageView,
+    pub depth: gfx::ImageView,
+}
+
+/// Pass which writes object ids and their depth, both are left
+/// in the `TransferSrcOptimal` layout to be read back.
+#[derive(Default)]
+pub struct PickingPass {
+    render_pass: Option<gfx::RenderPass>,
+    framebuffer: Option<gfx::Framebuffer>,
+}
+
+impl PickingPass {
+    #[tracing::instrument(level = "debug", name = "create_picking_pass", skip_all)]
+    fn get_or_init_framebuffer(
+        &mut self,
+        device: &gfx::Device,
+        input: &PickingPassInput,
+    ) -> Result<&gfx::Framebuffer> {
+        let render_pass = match &self.render_pass {
+            Some(render_pass) => render_pass.clone(),
+            None => self
+                .render_pass
+                .insert(device.create_render_pass(gfx::RenderPassInfo {
+                    attachments: vec![
+                        gfx::AttachmentInfo {
+                            format: PICKING_ID_FORMAT,
+                            samples: gfx::Samples::_1,
+                            load_op: gfx::LoadOp::Clear(()),
+                            store_op: gfx::StoreOp::Store,
+                            initial_layout: None,
+                            final_layout: gfx::ImageLayout::TransferSrcOptimal,
+                        },
+                        gfx::AttachmentInfo {
+                            format: DEPTH_FORMAT,
+                            samples: gfx::Samples::_1,
+                            load_op: gfx::LoadOp::Clear(()),
+                            store_op: gfx::StoreOp::Store,
+                            initial_layout: None,
+                            final_layout: gfx::ImageLayout::TransferSrcOptimal,
+                        },
+                    ],
+                    subpasses: vec![gfx::Subpass {
+                        colors: vec![(0, gfx::ImageLayout::ColorAttachmentOptimal)],
+                        depth: Some((1, gfx::ImageLayout::DepthStencilAttachmentOptimal)),
+                        resolves: Vec::new(),
+                    }],
+                    dependencies: vec![
+                        gfx::SubpassDependency {
+                            // NOTE: Attachments might still be read back by the previous frame.
+                            src: None,
+                            src_stages: gfx::PipelineStageFlags::TRANSFER,
+                            dst: Some(0),
+                            dst_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                        },
+                        gfx::SubpassDependency {
+                            src: Some(0),
+                            src_stages: gfx::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                            dst: None,
+                            dst_stages: gfx::PipelineStageFlags::TRANSFER,
+                        },
+                    ],
+                })?)
+                .clone(),
+        };
+
+        let framebuffer = match self.framebuffer.take() {
+            Some(framebuffer)
+                if framebuffer.info().attachments[0] == input.ids
+                    && framebuffer.info().attachments[1] == input.depth =>
+            {
+                framebuffer
+            }
+            _ => device.create_framebuffer(gfx::FramebufferInfo {
+                render_pass,
+                attachments: vec![input.ids.clone(), input.depth.clone()],
+                extent: input.ids.info().image.info().extent.into(),
+            })?,
+        };
+
+        Ok(self.framebuffer.insert(framebuffer))
+    }
+}
+
+impl RenderPass for PickingPass {
+    type Input = PickingPassInput;
+
+    fn begin_render_pass<'a, 'b>(
+        &'b mut self,
+        input: &Self::Input,
+        device: &gfx::Device,
+        encoder: &'a mut gfx::Encoder,
+    ) -> Result<gfx::RenderPassEncoder<'a, 'b>> {
+        // NOTE: Zero id means that there is no object
+        let clear_values = [
+            gfx::ClearColor(0.0, 0.0, 0.0, 0.0).into(),
+            gfx::ClearDepth(1.0).into(),
+        ];
+
+        let framebuffer = self.get_or_init_framebuffer(device, input)?;
+        Ok(encoder.with_framebuffer(framebuffer, &clear_values))
+    }
+}
+
+pub const PICKING_ID_FORMAT: gfx::Format = gfx::Format::R32Uint;
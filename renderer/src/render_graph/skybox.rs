@@ -0,0 +1,62 @@
+use anyhow::Result;
+
+use crate::render_graph::render_passes::MainPass;
+use crate::render_graph::{RenderGraphNode, RenderGraphNodeContext};
+use crate::util::{
+    CachedGraphicsPipeline, OcclusionPass, RenderPassEncoderExt, ShaderPreprocessor,
+};
+
+/// Draws the environment behind the opaque geometry.
+///
+/// Only drawn in the late main pass, where most of the covered pixels are
+/// already rejected by the depth test.
+pub struct Skybox {
+    pipeline: CachedGraphicsPipeline,
+}
+
+impl Skybox {
+    pub fn new(
+        device: &gfx::Device,
+        pipeline_layout: &gfx::PipelineLayout,
+        shaders: &ShaderPreprocessor,
+    ) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "skybox.vert", "main")?;
+        let fragment_shader = shaders.make_fragment_shader(device, "skybox.frag", "main")?;
+
+        let pipeline = CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
+            primitive_topology: Default::default(),
+            primitive_restart_enable: false,
+            vertex_shader,
+            rasterizer: Some(gfx::Rasterizer {
+                fragment_shader: Some(fragment_shader),
+                // NOTE: Drawn on the far plane where nothing else was drawn
+                depth_test: Some(gfx::DepthTest {
+                    compare: gfx::CompareOp::LessOrEqual,
+                    write: false,
+                }),
+                ..Default::default()
+            }),
+            layout: pipeline_layout.clone(),
+        });
+
+        Ok(Self { pipeline })
+    }
+}
+
+impl RenderGraphNode for Skybox {
+    type RenderPass = MainPass;
+
+    fn execute(&mut self, ctx: &mut RenderGraphNodeContext<'_, '_>) -> Result<()> {
+        if ctx.occlusion_pass != OcclusionPass::Late {
+            return Ok(());
+        }
+
+        ctx.encoder
+            .bind_cached_graphics_pipeline(&mut self.pipeline, &ctx.state.device)?;
+        ctx.encoder.draw(0..3, 0..1);
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::sync::Mutex;
+
+use glam::{Mat4, UVec2, Vec3, Vec4};
+
+use crate::types::RenderLayers;
+use crate::util::{BoundingBox, BoundingSphere, Frustum, Plane};
+
+/// Immediate-mode drawing of lines and wireframe shapes for debugging.
+///
+/// Shapes can be added from any thread. They are drawn on top of the final
+/// image during the next `lifetime` frames, so shapes which must stay visible
+/// are usually added again each frame.
+#[derive(Default)]
+pub struct DebugDraw {
+    lines: Mutex<Vec<DebugLine>>,
+}
+
+/// Parameters shared by all debug shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugDrawOptions {
+    /// Whether the shape is hidden behind the scene geometry.
+    pub depth_test: bool,
+    /// Number of frames in which the shape is drawn, at least one.
+    pub lifetime: u32,
+    /// The shape is drawn if the camera and the debug pass share a layer with it.
+    pub layers: RenderLayers,
+}
+
+impl Default for DebugDrawOptions {
+    fn default() -> Self {
+        Self {
+            depth_test: true,
+            lifetime: 1,
+            layers: RenderLayers::ALL,
+        }
+    }
+}
+
+impl DebugDrawOptions {
+    /// Draws the shape on top of the scene geometry.
+    pub fn overlay() -> Self {
+        Self {
+            depth_test: false,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DebugLine {
+    pub start: Vec3,
+    pub end: Vec3,
+    /// sRGB encoded RGBA8 color.
+    pub color: u32,
+    pub depth_test: bool,
+    pub layers: RenderLayers,
+    pub frames_left: u32,
+}
+
+impl DebugDraw {
+    /// Draws a line segment. Colors are sRGB encoded with a linear alpha.
+    pub fn line(&self, start: Vec3, end: Vec3, color: Vec4, options: DebugDrawOptions) {
+        self.push_lines([(start, end)], color, options);
+    }
+
+    /// Draws an arrow pointing from `start` to `end`.
+    pub fn arrow(&self, start: Vec3, end: Vec3, color: Vec4, options: DebugDrawOptions) {
+        let direction = end - start;
+        let length = direction.length();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let direction = direction / length;
+        let head_length = length * ARROW_HEAD_SIZE;
+        let (side, up) = direction.any_orthonormal_pair();
+        let base = end - direction * head_length;
+        let head_width = head_length * 0.5;
+
+        self.push_lines(
+            [
+                (start, end),
+                (end, base + side * head_width),
+                (end, base - side * head_width),
+                (end, base + up * head_width),
+                (end, base - up * head_width),
+            ],
+            color,
+            options,
+        );
+    }
+
+    /// Draws the edges of an axis-aligned box.
+    pub fn aabb(&self, aabb: &BoundingBox, color: Vec4, options: DebugDrawOptions) {
+        let corner = |i: usize| {
+            Vec3::select(
+                glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                aabb.max,
+                aabb.min,
+            )
+        };
+
+        // NOTE: Each edge connects corners which differ in a single axis
+        let edges = (0..8usize).flat_map(|i| {
+            [1, 2, 4]
+                .into_iter()
+                .filter(move |bit| i & bit == 0)
+                .map(move |bit| (corner(i), corner(i | bit)))
+        });
+        self.push_lines(edges, color, options);
+    }
+
+    /// Draws three great circles of a sphere.
+    pub fn sphere(&self, sphere: &BoundingSphere, color: Vec4, options: DebugDrawOptions) {
+        let axes = [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)];
+        let lines = axes.into_iter().flat_map(|(u, v)| {
+            let point = move |i: u32| {
+                let angle = i as f32 / SPHERE_SEGMENTS as f32 * std::f32::consts::TAU;
+                sphere.center + (u * angle.cos() + v * angle.sin()) * sphere.radius
+            };
+            (0..SPHERE_SEGMENTS).map(move |i| (point(i), point(i + 1)))
+        });
+        self.push_lines(lines, color, options);
+    }
+
+    /// Draws the near plane and the side edges of a frustum.
+    ///
+    /// The far plane is infinite, so the side edges are cut at `length` from the near plane.
+    pub fn frustum(&self, frustum: &Frustum, length: f32, color: Vec4, options: DebugDrawOptions) {
+        // NOTE: Side planes in the order of the frustum corners
+        let sides = [&frustum.left, &frustum.top, &frustum.right, &frustum.bottom];
+
+        let mut lines = Vec::with_capacity(8);
+        for (i, side) in sides.iter().enumerate() {
+            let next = sides[(i + 1) % sides.len()];
+            let next_corner = sides[(i + 2) % sides.len()];
+            let (Some(corner), Some(other_corner)) = (
+                intersect_planes(&frustum.near, side, next),
+                intersect_planes(&frustum.near, next, next_corner),
+            ) else {
+                return;
+            };
+
+            // Edges point away from the camera, inside of the near plane
+            let mut direction = side.normal.cross(next.normal).normalize_or_zero();
+            if direction.dot(frustum.near.normal) < 0.0 {
+                direction = -direction;
+            }
+
+            lines.push((corner, other_corner));
+            lines.push((corner, corner + direction * length));
+        }
+        self.push_lines(lines, color, options);
+    }
+
+    /// Draws the X, Y and Z axes of a transform in red, green and blue.
+    pub fn axes(&self, transform: &Mat4, length: f32, options: DebugDrawOptions) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [
+            (Vec3::X, Vec4::new(1.0, 0.0, 0.0, 1.0)),
+            (Vec3::Y, Vec4::new(0.0, 1.0, 0.0, 1.0)),
+            (Vec3::Z, Vec4::new(0.0, 0.0, 1.0, 1.0)),
+        ] {
+            let end = transform.transform_point3(axis * length);
+            self.push_lines([(origin, end)], color, options);
+        }
+    }
+
+    /// Draws a grid of `cells` in the XZ plane of a transform, centered at its origin.
+    pub fn grid(
+        &self,
+        transform: &Mat4,
+        cells: UVec2,
+        cell_size: f32,
+        color: Vec4,
+        options: DebugDrawOptions,
+    ) {
+        let half_extent = cells.as_vec2() * cell_size * 0.5;
+        let point = |x: f32, z: f32| transform.transform_point3(Vec3::new(x, 0.0, z));
+
+        let along_z = (0..=cells.x).map(|i| {
+            let x = i as f32 * cell_size - half_extent.x;
+            (point(x, -half_extent.y), point(x, half_extent.y))
+        });
+        let along_x = (0..=cells.y).map(|i| {
+            let z = i as f32 * cell_size - half_extent.y;
+            (point(-half_extent.x, z), point(half_extent.x, z))
+        });
+        self.push_lines(along_z.chain(along_x), color, options);
+    }
+
+    fn push_lines(
+        &self,
+        lines: impl IntoIterator<Item = (Vec3, Vec3)>,
+        color: Vec4,
+        options: DebugDrawOptions,
+    ) {
+        let color = pack_color(color);
+        let mut queued = self.lines.lock().unwrap();
+        queued.extend(lines.into_iter().map(|(start, end)| DebugLine {
+            start,
+            end,
+            color,
+            depth_test: options.depth_test,
+            layers: options.layers,
+            frames_left: options.lifetime.max(1),
+        }));
+    }
+
+    /// Appends the lines to draw in the current frame and removes the expired ones.
+    pub(crate) fn collect_frame(&self, lines: &mut Vec<DebugLine>) {
+        let mut queued = self.lines.lock().unwrap();
+        lines.extend_from_slice(&queued);
+        queued.retain_mut(|line| {
+            line.frames_left -= 1;
+            line.frames_left > 0
+        });
+    }
+}
+
+/// Returns the point shared by three planes, if they are not parallel.
+fn intersect_planes(a: &Plane, b: &Plane, c: &Plane) -> Option<Vec3> {
+    let bc = b.normal.cross(c.normal);
+    let denominator = a.normal.dot(bc);
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let point = -(a.distance * bc
+        + b.distance * c.normal.cross(a.normal)
+        + c.distance * a.normal.cross(b.normal));
+    Some(point / denominator)
+}
+
+fn pack_color(color: Vec4) -> u32 {
+    let [r, g, b, a] = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.0)
+        .round()
+        .to_array()
+        .map(|value| value as u32);
+    r | (g << 8) | (b << 16) | (a << 24)
+}
+
+const SPHERE_SEGMENTS: u32 = 24;
+/// Length of the arrow head relative to the whole arrow.
+const ARROW_HEAD_SIZE: f32 = 0.2;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(debug_draw: &DebugDraw) -> Vec<DebugLine> {
+        let mut lines = Vec::new();
+        debug_draw.collect_frame(&mut lines);
+        lines
+    }
+
+    #[test]
+    fn lines_expire_after_lifetime() {
+        let debug_draw = DebugDraw::default();
+        let options = DebugDrawOptions {
+            lifetime: 2,
+            ..Default::default()
+        };
+        debug_draw.line(Vec3::ZERO, Vec3::X, Vec4::ONE, options);
+        debug_draw.line(Vec3::ZERO, Vec3::Y, Vec4::ONE, Default::default());
+
+        assert_eq!(collect(&debug_draw).len(), 2);
+        assert_eq!(collect(&debug_draw).len(), 1);
+        assert!(collect(&debug_draw).is_empty());
+    }
+
+    #[test]
+    fn aabb_has_twelve_edges() {
+        let debug_draw = DebugDraw::default();
+        let aabb = BoundingBox {
+            min: Vec3::splat(-1.0),
+            max: Vec3::splat(1.0),
+        };
+        debug_draw.aabb(&aabb, Vec4::ONE, Default::default());
+
+        let lines = collect(&debug_draw);
+        assert_eq!(lines.len(), 12);
+        for line in lines {
+            assert_eq!((line.end - line.start).length(), 2.0);
+        }
+    }
+
+    #[test]
+    fn frustum_starts_at_near_plane() {
+        let projection = Mat4::perspective_infinite_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.5);
+        let frustum = Frustum::new(projection);
+        let debug_draw = DebugDraw::default();
+        debug_draw.frustum(&frustum, 10.0, Vec4::ONE, Default::default());
+
+        let lines = collect(&debug_draw);
+        assert_eq!(lines.len(), 8);
+        for line in lines {
+            assert!(frustum.near.distance_to_point(line.start).abs() < 1e-4);
+            for side in [&frustum.left, &frustum.right, &frustum.top, &frustum.bottom] {
+                assert!(side.distance_to_point(line.start) > -1e-4);
+                assert!(side.distance_to_point(line.end) > -1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn packs_colors() {
+        assert_eq!(pack_color(Vec4::new(1.0, 0.0, 0.5, 2.0)), 0xff80_00ff);
+    }
+}
@@ -0,0 +1,109 @@
+/// Visualization which replaces the regular shading of materials.
+///
+/// Debug views are displayed as is, so temporal anti-aliasing, ambient
+/// occlusion, bloom and tonemapping are skipped while one is active.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugView {
+    #[default]
+    None,
+    /// Shaded scene with the triangle edges drawn on top.
+    Wireframe,
+    /// World space normals mapped to colors.
+    Normals,
+    /// Checkerboard pattern of the first texture coordinates.
+    UvCheckerboard,
+    /// Number of fragments drawn per pixel, from blue to red.
+    Overdraw,
+    /// Random color per object.
+    ObjectColor,
+    /// Objects visible in the previous frame are green, objects revealed by the
+    /// occlusion culling in the current frame are red and objects which are never
+    /// occlusion culled are blue.
+    CullingStatus,
+    /// Random color per material slot.
+    MaterialSlot,
+}
+
+impl DebugView {
+    pub const ALL: [Self; 8] = [
+        Self::None,
+        Self::Wireframe,
+        Self::Normals,
+        Self::UvCheckerboard,
+        Self::Overdraw,
+        Self::ObjectColor,
+        Self::CullingStatus,
+        Self::MaterialSlot,
+    ];
+
+    /// Returns the view after this one, wrapping around to [`DebugView::None`].
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|view| *view == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Returns the next view accepted by `is_supported`, skipping the rest.
+    ///
+    /// [`DebugView::None`] is always accepted, so the cycle stops there at the latest.
+    pub fn next_supported(self, is_supported: impl Fn(Self) -> bool) -> Self {
+        let mut view = self.next();
+        while view != Self::None && !is_supported(view) {
+            view = view.next();
+        }
+        view
+    }
+
+    /// Returns the view before this one, wrapping around to the last view.
+    pub fn previous(self) -> Self {
+        let index = Self::ALL.iter().position(|view| *view == self).unwrap();
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Value of `DEBUG_VIEW_*` defines in shaders.
+    pub(crate) fn shader_index(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Wireframe => 1,
+            Self::Normals => 2,
+            Self::UvCheckerboard => 3,
+            Self::Overdraw => 4,
+            Self::ObjectColor => 5,
+            Self::CullingStatus => 6,
+            Self::MaterialSlot => 7,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_through_all_views() {
+        let mut view = DebugView::None;
+        for expected in DebugView::ALL.iter().skip(1) {
+            view = view.next();
+            assert_eq!(view, *expected);
+            assert_eq!(view.previous().next(), view);
+        }
+        assert_eq!(view.next(), DebugView::None);
+        assert_eq!(DebugView::None.previous(), DebugView::MaterialSlot);
+    }
+
+    #[test]
+    fn skips_unsupported_views() {
+        let is_supported = |view| view != DebugView::Wireframe;
+        assert_eq!(
+            DebugView::None.next_supported(is_supported),
+            DebugView::Normals
+        );
+        assert_eq!(
+            DebugView::MaterialSlot.next_supported(is_supported),
+            DebugView::None
+        );
+        assert_eq!(
+            DebugView::Normals.next_supported(|_| false),
+            DebugView::None
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+
+/// An HDR panorama of the surroundings in the equirectangular projection.
+///
+/// Rendered as the skybox and used as the source of image-based lighting.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl EnvironmentMap {
+    /// Creates an environment map from row-major linear RGB pixels, starting
+    /// from the top row (looking up).
+    ///
+    /// Negative and non-finite values are replaced with zeros.
+    pub fn from_rgb32f(width: u32, height: u32, pixels: &[f32]) -> Result<Self> {
+        anyhow::ensure!(
+            width >= 2 && height >= 1,
+            "environment map must have at least 2x1 pixels"
+        );
+        anyhow::ensure!(
+            pixels.len() == width as usize * height as usize * 3,
+            "environment map pixel count mismatch"
+        );
+
+        let sanitize = |value: f32| {
+            if value.is_finite() {
+                value.max(0.0)
+            } else {
+                0.0
+            }
+        };
+        let pixels = pixels
+            .chunks_exact(3)
+            .map(|rgb| [sanitize(rgb[0]), sanitize(rgb[1]), sanitize(rgb[2]), 1.0])
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Linear RGBA pixels with an opaque alpha.
+    pub fn pixels(&self) -> &[[f32; 4]] {
+        &self.pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_pixels() {
+        let map = EnvironmentMap::from_rgb32f(2, 1, &[1.0, -1.0, f32::NAN, 0.5, 2.0, 0.0]).unwrap();
+        assert_eq!(map.pixels(), &[[1.0, 0.0, 0.0, 1.0], [0.5, 2.0, 0.0, 1.0]]);
+
+        assert!(EnvironmentMap::from_rgb32f(2, 2, &[0.0; 6]).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use glam::{Vec2, Vec3};
+
+use crate::types::{MeshBuilder, MeshGenerator, Normal, Position, UV0};
+
+/// A regular grid of height samples.
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    width: u32,
+    depth: u32,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Creates a heightmap from row-major samples (`width` samples along X
+    /// for each of `depth` rows along Z).
+    pub fn new(width: u32, depth: u32, heights: Vec<f32>) -> Result<Self> {
+        anyhow::ensure!(
+            width >= 2 && depth >= 2,
+            "heightmap must have at least 2x2 samples"
+        );
+        anyhow::ensure!(
+            heights.len() == width as usize * depth as usize,
+            "heightmap sample count mismatch"
+        );
+        Ok(Self {
+            width,
+            depth,
+            heights,
+        })
+    }
+
+    /// Creates a heightmap from 16-bit grayscale pixels, mapping them to `[0, 1]`.
+    pub fn from_luma16(width: u32, depth: u32, pixels: &[u16]) -> Result<Self> {
+        let heights = pixels
+            .iter()
+            .map(|&value| value as f32 / u16::MAX as f32)
+            .collect();
+        Self::new(width, depth, heights)
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    /// Returns a height sample, clamping coordinates to the heightmap bounds.
+    pub fn get(&self, x: i64, z: i64) -> f32 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let z = z.clamp(0, self.depth as i64 - 1) as usize;
+        self.heights[z * self.width as usize + x]
+    }
+
+    /// Computes a normal from the height gradient at the sample.
+    pub fn normal(&self, x: i64, z: i64, spacing: Vec2, height_scale: f32) -> Vec3 {
+        let dx = (self.get(x + 1, z) - self.get(x - 1, z)) * height_scale / (2.0 * spacing.x);
+        let dz = (self.get(x, z + 1) - self.get(x, z - 1)) * height_scale / (2.0 * spacing.y);
+        Vec3::new(-dx, 1.0, -dz).normalize()
+    }
+}
+
+/// Builds a grid from a region of a heightmap.
+///
+/// Vertex positions are in the heightmap space, so chunks of the same
+/// heightmap can share the same transform. Normals are computed from
+/// the whole heightmap to keep them continuous across chunk borders.
+#[derive(Debug, Clone, Copy)]
+pub struct HeightmapMeshGenerator<'a> {
+    pub heightmap: &'a Heightmap,
+    /// First sample of the region.
+    pub offset: [u32; 2],
+    /// Region size in samples intervals (clamped to the heightmap size).
+    pub size: [u32; 2],
+    /// Distance between vertices in samples (used for LOD).
+    pub step: u32,
+    /// Distance between samples along X and Z.
+    pub spacing: Vec2,
+    pub height_scale: f32,
+    /// Depth of the vertical strips around the region which hide
+    /// cracks between chunks with different LODs. Zero disables them.
+    pub skirt_depth: f32,
+}
+
+impl<'a> HeightmapMeshGenerator<'a> {
+    pub fn new(heightmap: &'a Heightmap) -> Self {
+        Self {
+            heightmap,
+            offset: [0, 0],
+            size: [heightmap.width - 1, heightmap.depth - 1],
+            step: 1,
+            spacing: Vec2::ONE,
+            height_scale: 1.0,
+            skirt_depth: 0.0,
+        }
+    }
+
+    fn has_skirts(&self) -> bool {
+        self.skirt_depth > 0.0
+    }
+
+    fn axis_samples(&self, axis: usize) -> Vec<u32> {
+        let max = [self.heightmap.width, self.heightmap.depth][axis] - 1;
+        let start = self.offset[axis].min(max);
+        let end = (start + self.size[axis]).min(max);
+        let step = self.step.max(1) as usize;
+
+        let mut samples = (start..end).step_by(step).collect::<Vec<_>>();
+        samples.push(end);
+        samples
+    }
+}
+
+impl MeshGenerator for HeightmapMeshGenerator<'_> {
+    fn generate_mesh(self) -> MeshBuilder {
+        let xs = self.axis_samples(0);
+        let zs = self.axis_samples(1);
+        let (nx, nz) = (xs.len() as u32, zs.len() as u32);
+
+        let uv_scale = Vec2::new(
+            1.0 / (self.heightmap.width - 1) as f32,
+            1.0 / (self.heightmap.depth - 1) as f32,
+        );
+
+        let vertex_count = (nx * nz + self.has_skirts() as u32 * 2 * (nx + nz)) as usize;
+        let mut positions = Vec::with_capacity(vertex_count);
+        let mut normals = Vec::with_capacity(vertex_count);
+        let mut uv0 = Vec::with_capacity(vertex_count);
+
+        for &z in &zs {
+            for &x in &xs {
+                let (x, z) = (x as i64, z as i64);
+                let height = self.heightmap.get(x, z) * self.height_scale;
+                positions.push(Position(Vec3::new(
+                    x as f32 * self.spacing.x,
+                    height,
+                    z as f32 * self.spacing.y,
+                )));
+                normals.push(Normal(self.heightmap.normal(
+                    x,
+                    z,
+                    self.spacing,
+                    self.height_scale,
+                )));
+                uv0.push(UV0(Vec2::new(x as f32, z as f32) * uv_scale));
+            }
+        }
+
+        let mut indices = Vec::with_capacity(
+            ((nx - 1) * (nz - 1) * 6 + self.has_skirts() as u32 * 2 * (nx + nz - 2) * 6) as usize,
+        );
+        for j in 0..nz - 1 {
+            for i in 0..nx - 1 {
+                let a = j * nx + i;
+                let (b, c, d) = (a + 1, a + nx, a + nx + 1);
+                indices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+
+        if self.has_skirts() {
+            // Edges go around the region so that skirts face outwards.
+            let edges = [
+                (0..nx).collect::<Vec<_>>(),
+                (0..nz).map(|j| j * nx + nx - 1).collect(),
+                (0..nx).rev().map(|i| (nz - 1) * nx + i).collect(),
+                (0..nz).rev().map(|j| j * nx).collect(),
+            ];
+
+            for edge in edges {
+                let first = positions.len() as u32;
+                for &index in &edge {
+                    let index = index as usize;
+                    let mut position = positions[index];
+                    position.y -= self.skirt_depth;
+                    positions.push(position);
+                    normals.push(normals[index]);
+                    uv0.push(uv0[index]);
+                }
+
+                for (k, pair) in edge.windows(2).enumerate() {
+                    let (top0, top1) = (pair[0], pair[1]);
+                    let (bottom0, bottom1) = (first + k as u32, first + k as u32 + 1);
+                    indices.extend_from_slice(&[top0, top1, bottom0, top1, bottom1, bottom0]);
+                }
+            }
+        }
+
+        MeshBuilder::new(positions)
+            .with_normals(normals)
+            .with_uv0(uv0)
+            .with_indices(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_utils::{assert_triangles_face_up, attribute};
+    use crate::types::Mesh;
+
+    #[test]
+    fn flat_heightmap() {
+        let heightmap = Heightmap::new(5, 3, vec![0.5; 15]).unwrap();
+        let mesh = Mesh::builder(HeightmapMeshGenerator::new(&heightmap))
+            .build()
+            .unwrap();
+
+        assert_eq!(mesh.vertex_count(), 15);
+        assert_eq!(mesh.indices().len(), 4 * 2 * 6);
+
+        let normals = attribute::<Normal>(&mesh);
+        assert!(normals.iter().all(|n| n.abs_diff_eq(Vec3::Y, 1e-6)));
+        assert_triangles_face_up(&mesh);
+    }
+
+    #[test]
+    fn slope_normals() {
+        // Height grows along X by one unit per sample
+        let heights = (0..9).map(|i| (i % 3) as f32).collect();
+        let heightmap = Heightmap::new(3, 3, heights).unwrap();
+
+        let normal = heightmap.normal(1, 1, Vec2::ONE, 1.0);
+        assert!(normal.abs_diff_eq(Vec3::new(-1.0, 1.0, 0.0).normalize(), 1e-6));
+    }
+
+    #[test]
+    fn chunks_with_lod_and_skirts() {
+        let heightmap = Heightmap::from_luma16(9, 9, &[u16::MAX; 81]).unwrap();
+        assert_eq!(heightmap.get(100, -100), 1.0);
+
+        let mesh = Mesh::builder(HeightmapMeshGenerator {
+            offset: [4, 0],
+            size: [4, 4],
+            step: 2,
+            skirt_depth: 1.0,
+            ..HeightmapMeshGenerator::new(&heightmap)
+        })
+        .build()
+        .unwrap();
+
+        // 3x3 grid and 4 skirts with 3 vertices each
+        assert_eq!(mesh.vertex_count(), 9 + 4 * 3);
+        assert_eq!(mesh.indices().len(), 2 * 2 * 6 + 4 * 2 * 6);
+
+        let positions = attribute::<Position>(&mesh);
+        assert_eq!(positions[0].0, Vec3::new(4.0, 1.0, 0.0));
+        assert_eq!(positions[8].0, Vec3::new(8.0, 1.0, 4.0));
+        assert!(positions[9..].iter().all(|p| p.y == 0.0));
+
+        // Skirts must face outwards from the chunk center
+        let center = Vec3::new(6.0, 0.5, 2.0);
+        for triangle in mesh.indices()[2 * 2 * 6..].chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize].0);
+            let normal = (b - a).cross(c - a);
+            assert!(normal.dot((a + b + c) / 3.0 - center) > 0.0);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::VertexAttributeKind;
+use crate::types::{VertexAttributeEncodings, VertexAttributeKind};
 use crate::util::{RawResourceHandle, ResourceHandle};
 
 pub type MaterialInstanceHandle = ResourceHandle<MaterialInstanceTag>;
@@ -14,6 +14,12 @@ pub trait MaterialInstance: Send + Sync + 'static {
     fn required_attributes() -> Self::RequiredAttributes;
     fn supported_attributes() -> Self::SupportedAttributes;
 
+    /// Vertex attribute encodings which the material shader can decode.
+    /// Attributes in other encodings are treated as missing.
+    fn vertex_encodings() -> VertexAttributeEncodings {
+        VertexAttributeEncodings::FLOAT
+    }
+
     fn key(&self) -> u64;
     fn sorting(&self) -> Sorting;
 
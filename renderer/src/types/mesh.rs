@@ -1,17 +1,25 @@
 use anyhow::Result;
 use glam::{Vec2, Vec3};
 
-use crate::types::{Color, Normal, Position, Tangent, VertexAttributeData, UV0};
-use crate::util::{BoundingSphere, RawResourceHandle, ResourceHandle};
+use crate::types::{
+    Color, Color1, Joints, MorphNormals, MorphPositions, Normal, Position, Tangent,
+    VertexAttribute, VertexAttributeData, VertexAttributeEncoding, VertexAttributeKind, Weights,
+    UV0, UV1,
+};
+use crate::util::{mesh_optimizer, BoundingBox, BoundingSphere, RawResourceHandle, ResourceHandle};
 
 pub type MeshHandle = ResourceHandle<Mesh>;
 pub(crate) type RawMeshHandle = RawResourceHandle<Mesh>;
 
 pub struct Mesh {
     vertex_count: u32,
+    morph_target_count: u32,
     attribute_data: Vec<VertexAttributeData>,
     indices: Vec<u32>,
+    index_type: gfx::IndexType,
     bounding_sphere: BoundingSphere,
+    bounding_box: BoundingBox,
+    optimization_stats: Option<MeshOptimizationStats>,
 }
 
 impl Mesh {
@@ -23,6 +31,10 @@ impl Mesh {
         self.vertex_count
     }
 
+    pub fn morph_target_count(&self) -> u32 {
+        self.morph_target_count
+    }
+
     pub fn attribute_data(&self) -> &[VertexAttributeData] {
         &self.attribute_data
     }
@@ -31,9 +43,38 @@ impl Mesh {
         &self.indices
     }
 
+    /// Format in which indices are stored on the GPU.
+    pub fn index_type(&self) -> gfx::IndexType {
+        self.index_type
+    }
+
     pub fn bounding_sphere(&self) -> &BoundingSphere {
         &self.bounding_sphere
     }
+
+    /// Local space bounding box (including morph target displacements).
+    pub fn bounding_box(&self) -> &BoundingBox {
+        &self.bounding_box
+    }
+
+    /// Statistics of the optimization pass (if [`MeshBuilder::optimize`] was used).
+    pub fn optimization_stats(&self) -> Option<&MeshOptimizationStats> {
+        self.optimization_stats.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshOptimizationStats {
+    pub vertex_count_before: u32,
+    pub vertex_count_after: u32,
+    /// Average number of vertex shader invocations per triangle
+    /// (for a FIFO cache of [`MeshOptimizationStats::ACMR_CACHE_SIZE`] entries).
+    pub acmr_before: f32,
+    pub acmr_after: f32,
+}
+
+impl MeshOptimizationStats {
+    pub const ACMR_CACHE_SIZE: usize = mesh_optimizer::ACMR_CACHE_SIZE;
 }
 
 pub trait MeshGenerator: Sized {
@@ -212,9 +253,15 @@ pub struct MeshBuilder {
     tangents: Option<ComputableData<Vec<Tangent>>>,
     uv0: Option<Vec<UV0>>,
     colors: Option<Vec<Color>>,
+    joints: Option<Vec<Joints>>,
+    weights: Option<Vec<Weights>>,
+    morph_targets: Vec<MorphTarget>,
+    attributes: Vec<Box<dyn ExtraVertexStream>>,
 
     indices: Option<Vec<u32>>,
     double_sided: bool,
+    optimize: bool,
+    encodings: Vec<(VertexAttributeKind, VertexAttributeEncoding)>,
 }
 
 impl MeshBuilder {
@@ -256,6 +303,37 @@ impl MeshBuilder {
         self
     }
 
+    pub fn with_joints(mut self, joints: Vec<Joints>) -> Self {
+        self.joints = Some(joints);
+        self
+    }
+
+    pub fn with_weights(mut self, weights: Vec<Weights>) -> Self {
+        self.weights = Some(weights);
+        self
+    }
+
+    pub fn with_morph_targets(mut self, morph_targets: Vec<MorphTarget>) -> Self {
+        self.morph_targets = morph_targets;
+        self
+    }
+
+    pub fn with_uv1(self, uv1: Vec<UV1>) -> Self {
+        self.with_attribute(uv1)
+    }
+
+    pub fn with_colors1(self, colors: Vec<Color1>) -> Self {
+        self.with_attribute(colors)
+    }
+
+    /// Adds an attribute which has no dedicated builder method
+    /// (e.g. [`UV1`], [`Color1`] or custom attributes like [`Custom0`]).
+    pub fn with_attribute<T: VertexAttribute>(mut self, data: Vec<T>) -> Self {
+        self.attributes.retain(|stream| stream.kind() != T::KIND);
+        self.attributes.push(Box::new(data));
+        self
+    }
+
     pub fn with_indices(mut self, indices: Vec<u32>) -> Self {
         self.indices = Some(indices);
         self
@@ -266,17 +344,97 @@ impl MeshBuilder {
         self
     }
 
+    /// Welds identical vertices and reorders triangles and vertices to
+    /// improve the vertex cache and fetch efficiency. Allows storing
+    /// indices as 16-bit values if the vertex count is small enough.
+    pub fn optimize(mut self) -> Self {
+        self.optimize = true;
+        self
+    }
+
+    /// Stores the attribute in the specified encoding.
+    ///
+    /// Materials which don't support the encoding treat the attribute as missing.
+    pub fn with_attribute_encoding(
+        mut self,
+        kind: VertexAttributeKind,
+        encoding: VertexAttributeEncoding,
+    ) -> Self {
+        self.encodings.retain(|(k, _)| *k != kind);
+        self.encodings.push((kind, encoding));
+        self
+    }
+
+    /// Stores all attributes in their most compact encodings
+    /// (see [`VertexAttributeEncoding::compressed`]).
+    pub fn quantized(mut self) -> Self {
+        self.encodings = VertexAttributeKind::ALL
+            .iter()
+            .map(|&kind| (kind, VertexAttributeEncoding::compressed(kind)))
+            .filter(|(_, encoding)| *encoding != VertexAttributeEncoding::Float)
+            .collect();
+        self
+    }
+
     pub fn build(self) -> Result<Mesh> {
         let len = self.vertex_count;
 
+        for (kind, encoding) in &self.encodings {
+            anyhow::ensure!(
+                encoding.supports(*kind),
+                "{encoding:?} encoding is not supported for {kind:?}"
+            );
+        }
+
         if matches!(&self.normals, Some(ComputableData::Known(v)) if v.len() != len)
             || matches!(&self.tangents, Some(ComputableData::Known(v)) if v.len() != len)
             || matches!(&self.uv0, Some(v) if v.len() != len)
             || matches!(&self.colors, Some(v) if v.len() != len)
+            || matches!(&self.joints, Some(v) if v.len() != len)
+            || matches!(&self.weights, Some(v) if v.len() != len)
         {
             anyhow::bail!("component length mismatch");
         }
 
+        for stream in &self.attributes {
+            anyhow::ensure!(
+                !matches!(
+                    stream.kind(),
+                    VertexAttributeKind::Position
+                        | VertexAttributeKind::Normal
+                        | VertexAttributeKind::Tangent
+                        | VertexAttributeKind::UV0
+                        | VertexAttributeKind::Color
+                        | VertexAttributeKind::Joints
+                        | VertexAttributeKind::Weights
+                        | VertexAttributeKind::MorphPositions
+                        | VertexAttributeKind::MorphNormals
+                ),
+                "{:?} must be specified with its dedicated builder method",
+                stream.kind()
+            );
+            anyhow::ensure!(stream.vertex_count() == len, "component length mismatch");
+        }
+
+        anyhow::ensure!(
+            self.joints.is_some() == self.weights.is_some(),
+            "joints and weights must be specified together"
+        );
+
+        let morph_target_normals = self
+            .morph_targets
+            .first()
+            .map(|target| target.normals.is_some())
+            .unwrap_or_default();
+        for target in &self.morph_targets {
+            anyhow::ensure!(
+                target.positions.len() == len
+                    && target.normals.is_some() == morph_target_normals
+                    && !matches!(&target.normals, Some(v) if v.len() != len),
+                "morph target length mismatch"
+            );
+        }
+
         let mut indices = self.indices.unwrap_or_else(|| (0..len as u32).collect());
 
         anyhow::ensure!(len <= indices.len(), "index count mismatch");
@@ -321,38 +479,250 @@ impl MeshBuilder {
             _ => unreachable!(),
         };
 
-        let bounding_sphere = BoundingSphere::compute_from_positions(&self.positions);
+        let mut streams = VertexStreams {
+            positions: self.positions,
+            normals,
+            tangents,
+            uv0: self.uv0,
+            colors: self.colors,
+            joints: self.joints,
+            weights: self.weights,
+            morph_targets: self.morph_targets,
+            attributes: self.attributes,
+        };
+
+        let optimization_stats = self.optimize.then(|| {
+            let stats = streams.optimize(&mut indices);
+            tracing::debug!(?stats, "optimized mesh");
+            stats
+        });
+
+        let len = streams.positions.len();
+        let index_type = match optimization_stats {
+            Some(_) if len <= u16::MAX as usize + 1 => gfx::IndexType::U16,
+            _ => gfx::IndexType::U32,
+        };
+
+        let mut bounding_sphere = BoundingSphere::compute_from_positions(&streams.positions);
+
+        // NOTE: Weights are expected to be in the range [0, 1], so the sum
+        // of the largest deltas is enough to cover all displaced vertices.
+        bounding_sphere.radius += streams
+            .morph_targets
+            .iter()
+            .map(|target| {
+                target
+                    .positions
+                    .iter()
+                    .fold(0.0f32, |acc, delta| acc.max(delta.length()))
+            })
+            .sum::<f32>();
+
+        let bounding_box = BoundingBox::compute_from_positions(&streams.positions).expanded(
+            streams
+                .morph_targets
+                .iter()
+                .map(|target| {
+                    target
+                        .positions
+                        .iter()
+                        .fold(Vec3::ZERO, |acc, delta| acc.max(delta.abs()))
+                })
+                .sum::<Vec3>(),
+        );
 
         let mut attribute_data = Vec::with_capacity(
-            1 + normals.is_some() as usize
-                + tangents.is_some() as usize
-                + self.uv0.is_some() as usize
-                + self.colors.is_some() as usize,
+            1 + streams.normals.is_some() as usize
+                + streams.tangents.is_some() as usize
+                + streams.uv0.is_some() as usize
+                + streams.colors.is_some() as usize
+                + streams.joints.is_some() as usize
+                + streams.weights.is_some() as usize
+                + streams.attributes.len()
+                + (!streams.morph_targets.is_empty()) as usize
+                + morph_target_normals as usize,
         );
 
-        attribute_data.push(VertexAttributeData::new(self.positions));
-        if let Some(normals) = normals {
+        attribute_data.push(VertexAttributeData::new(streams.positions));
+        if let Some(normals) = streams.normals {
             attribute_data.push(VertexAttributeData::new(normals));
         }
-        if let Some(tangents) = tangents {
+        if let Some(tangents) = streams.tangents {
             attribute_data.push(VertexAttributeData::new(tangents));
         }
-        if let Some(uv0) = self.uv0 {
+        if let Some(uv0) = streams.uv0 {
             attribute_data.push(VertexAttributeData::new(uv0));
         }
-        if let Some(colors) = self.colors {
+        if let Some(colors) = streams.colors {
             attribute_data.push(VertexAttributeData::new(colors));
         }
+        if let Some(joints) = streams.joints {
+            attribute_data.push(VertexAttributeData::new(joints));
+        }
+        if let Some(weights) = streams.weights {
+            attribute_data.push(VertexAttributeData::new(weights));
+        }
+        attribute_data.extend(
+            streams
+                .attributes
+                .into_iter()
+                .map(|stream| stream.into_data()),
+        );
+
+        let morph_target_count = streams.morph_targets.len() as u32;
+        if !streams.morph_targets.is_empty() {
+            let mut positions = Vec::with_capacity(len * streams.morph_targets.len());
+            let mut normals = Vec::new();
+            for target in streams.morph_targets {
+                positions.extend(target.positions.into_iter().map(MorphPositions));
+                if let Some(target_normals) = target.normals {
+                    normals.extend(target_normals.into_iter().map(MorphNormals));
+                }
+            }
+
+            attribute_data.push(VertexAttributeData::new(positions));
+            if morph_target_normals {
+                attribute_data.push(VertexAttributeData::new(normals));
+            }
+        }
+
+        // NOTE: Encoding is applied last since all previous steps need float data
+        let attribute_data = attribute_data
+            .into_iter()
+            .map(
+                |data| match self.encodings.iter().find(|(kind, _)| *kind == data.kind()) {
+                    Some((_, encoding)) => data.encode(*encoding),
+                    None => Ok(data),
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Mesh {
             vertex_count: len as u32,
+            morph_target_count,
             attribute_data,
             indices,
+            index_type,
             bounding_sphere,
+            bounding_box,
+            optimization_stats,
         })
     }
 }
 
+/// Vertex deltas of a single blend shape.
+#[derive(Debug, Default, Clone)]
+pub struct MorphTarget {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+}
+
+struct VertexStreams {
+    positions: Vec<Position>,
+    normals: Option<Vec<Normal>>,
+    tangents: Option<Vec<Tangent>>,
+    uv0: Option<Vec<UV0>>,
+    colors: Option<Vec<Color>>,
+    joints: Option<Vec<Joints>>,
+    weights: Option<Vec<Weights>>,
+    morph_targets: Vec<MorphTarget>,
+    attributes: Vec<Box<dyn ExtraVertexStream>>,
+}
+
+impl VertexStreams {
+    fn optimize(&mut self, indices: &mut [u32]) -> MeshOptimizationStats {
+        let vertex_count_before = self.positions.len();
+        let acmr_before = mesh_optimizer::compute_acmr(indices, mesh_optimizer::ACMR_CACHE_SIZE);
+
+        let (weld_remap, unique_count) = {
+            let mut streams = vec![bytemuck::cast_slice::<_, u8>(&self.positions)];
+            macro_rules! push_streams {
+                ($($stream:expr),*) => {
+                    $(if let Some(stream) = &$stream {
+                        streams.push(bytemuck::cast_slice(stream));
+                    })*
+                };
+            }
+            push_streams!(self.normals, self.tangents, self.uv0, self.colors);
+            push_streams!(self.joints, self.weights);
+            streams.extend(self.attributes.iter().map(|stream| stream.bytes()));
+            for target in &self.morph_targets {
+                streams.push(bytemuck::cast_slice(&target.positions));
+                push_streams!(target.normals);
+            }
+            mesh_optimizer::weld_vertices(vertex_count_before, &streams)
+        };
+        for index in indices.iter_mut() {
+            *index = weld_remap[*index as usize];
+        }
+
+        mesh_optimizer::optimize_vertex_cache(indices, unique_count);
+        let (fetch_remap, vertex_count) =
+            mesh_optimizer::optimize_vertex_fetch(indices, unique_count);
+
+        let remap = weld_remap
+            .iter()
+            .map(|&index| fetch_remap[index as usize])
+            .collect::<Vec<_>>();
+
+        mesh_optimizer::remap_vertices(&mut self.positions, &remap, vertex_count);
+        macro_rules! remap_streams {
+            ($($stream:expr),*) => {
+                $(if let Some(stream) = &mut $stream {
+                    mesh_optimizer::remap_vertices(stream, &remap, vertex_count);
+                })*
+            };
+        }
+        remap_streams!(self.normals, self.tangents, self.uv0, self.colors);
+        remap_streams!(self.joints, self.weights);
+        for stream in &mut self.attributes {
+            stream.remap(&remap, vertex_count);
+        }
+        for target in &mut self.morph_targets {
+            mesh_optimizer::remap_vertices(&mut target.positions, &remap, vertex_count);
+            remap_streams!(target.normals);
+        }
+
+        MeshOptimizationStats {
+            vertex_count_before: vertex_count_before as u32,
+            vertex_count_after: vertex_count as u32,
+            acmr_before,
+            acmr_after: mesh_optimizer::compute_acmr(indices, mesh_optimizer::ACMR_CACHE_SIZE),
+        }
+    }
+}
+
+/// Vertex data of an attribute kind without a dedicated builder field.
+trait ExtraVertexStream: Send + Sync {
+    fn kind(&self) -> VertexAttributeKind;
+    fn vertex_count(&self) -> usize;
+    fn bytes(&self) -> &[u8];
+    fn remap(&mut self, remap: &[u32], new_vertex_count: usize);
+    fn into_data(self: Box<Self>) -> VertexAttributeData;
+}
+
+impl<T: VertexAttribute> ExtraVertexStream for Vec<T> {
+    fn kind(&self) -> VertexAttributeKind {
+        T::KIND
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.len()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(self)
+    }
+
+    fn remap(&mut self, remap: &[u32], new_vertex_count: usize) {
+        mesh_optimizer::remap_vertices(self, remap, new_vertex_count);
+    }
+
+    fn into_data(self: Box<Self>) -> VertexAttributeData {
+        VertexAttributeData::new(*self)
+    }
+}
+
 enum ComputableData<T> {
     Known(T),
     Compute,
@@ -485,6 +855,8 @@ mod tests {
     use std::collections::HashMap;
     use std::str::FromStr;
 
+    use crate::types::test_utils::attribute;
+
     const OBJ: &'static str = r#"v -1.000000 -1.000000 1.000000
 v -1.000000 1.000000 1.000000
 v -1.000000 -1.000000 -1.000000
@@ -517,6 +889,200 @@ f 6/4/4 2/1/4 1/3/4
 f 7/4/5 5/2/5 1/3/5
 f 4/1/6 2/3/6 6/2/6"#;
 
+    #[test]
+    fn morph_targets() {
+        use super::*;
+
+        let positions = vec![Position(Vec3::ZERO), Position(Vec3::X), Position(Vec3::Y)];
+        let target = |delta: Vec3| MorphTarget {
+            positions: vec![delta; 3],
+            normals: None,
+        };
+
+        let mesh = MeshBuilder::new(positions.clone())
+            .with_morph_targets(vec![target(Vec3::Z), target(Vec3::Z * 2.0)])
+            .build()
+            .unwrap();
+        assert_eq!(mesh.morph_target_count(), 2);
+
+        let deltas = attribute::<MorphPositions>(&mesh);
+        assert_eq!(deltas.len(), 6);
+        assert_eq!(deltas[2].0, Vec3::Z);
+        assert_eq!(deltas[3].0, Vec3::Z * 2.0);
+
+        let base_radius = BoundingSphere::compute_from_positions(&positions).radius;
+        assert!((mesh.bounding_sphere().radius - base_radius - 3.0).abs() < 1e-6);
+
+        let base_box = BoundingBox::compute_from_positions(&positions);
+        assert_eq!(mesh.bounding_box().max.z, base_box.max.z + 3.0);
+        assert_eq!(mesh.bounding_box().min.x, base_box.min.x);
+
+        let invalid = MeshBuilder::new(positions)
+            .with_morph_targets(vec![MorphTarget {
+                positions: vec![Vec3::Z; 2],
+                normals: None,
+            }])
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn optimize() {
+        use super::*;
+
+        // Non-indexed 8x8 grid with a morph target which moves vertices along X
+        let size = 8;
+        let mut positions = Vec::new();
+        for z in 0..size {
+            for x in 0..size {
+                let corner =
+                    |dx: u32, dz: u32| Position(Vec3::new((x + dx) as f32, 0.0, (z + dz) as f32));
+                let (a, b, c, d) = (corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1));
+                positions.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+        let deltas = positions.iter().map(|p| Vec3::X * p.x).collect::<Vec<_>>();
+
+        let mesh = MeshBuilder::new(positions.clone())
+            .with_morph_targets(vec![MorphTarget {
+                positions: deltas,
+                normals: None,
+            }])
+            .optimize()
+            .build()
+            .unwrap();
+
+        let stats = mesh.optimization_stats().unwrap();
+        assert_eq!(stats.vertex_count_before, size * size * 6);
+        assert_eq!(stats.vertex_count_after, (size + 1) * (size + 1));
+        assert!(stats.acmr_after < stats.acmr_before);
+        assert_eq!(mesh.vertex_count(), stats.vertex_count_after);
+        assert_eq!(mesh.index_type(), gfx::IndexType::U16);
+
+        // Triangles and per-vertex data must be preserved
+        let new_positions = attribute::<Position>(&mesh);
+        let new_deltas = attribute::<MorphPositions>(&mesh);
+        for (position, delta) in std::iter::zip(new_positions, new_deltas) {
+            assert_eq!(delta.0, Vec3::X * position.x);
+        }
+
+        // Rotate triangles to start from the smallest vertex to keep the winding
+        let canonical = |triangle: [Vec3; 3]| {
+            let mut triangle = triangle.map(|v| v.to_array().map(f32::to_bits));
+            let min = (0..3).min_by_key(|&i| triangle[i]).unwrap();
+            triangle.rotate_left(min);
+            triangle
+        };
+        let mut triangles = mesh
+            .indices()
+            .chunks_exact(3)
+            .map(|t| canonical([0, 1, 2].map(|i| new_positions[t[i] as usize].0)))
+            .collect::<Vec<_>>();
+        let mut expected = positions
+            .chunks_exact(3)
+            .map(|t| canonical([t[0].0, t[1].0, t[2].0]))
+            .collect::<Vec<_>>();
+        triangles.sort();
+        expected.sort();
+        assert_eq!(triangles, expected);
+
+        // Unoptimized meshes keep 32-bit indices
+        let mesh = MeshBuilder::new(positions).build().unwrap();
+        assert_eq!(mesh.index_type(), gfx::IndexType::U32);
+        assert!(mesh.optimization_stats().is_none());
+    }
+
+    #[test]
+    fn extra_attributes() {
+        use super::*;
+        use crate::types::Custom0;
+        use glam::Vec4;
+
+        let positions = vec![Position::ZERO, Position(Vec3::X), Position::ZERO];
+        let mesh = MeshBuilder::new(positions.clone())
+            .with_uv1(vec![UV1(Vec2::ZERO), UV1(Vec2::ONE), UV1(Vec2::ZERO)])
+            .with_attribute(vec![Custom0(Vec4::ONE); 3])
+            .with_indices(vec![0, 1, 2])
+            .optimize()
+            .build()
+            .unwrap();
+
+        // Duplicate vertices are welded with extra attributes
+        assert_eq!(mesh.vertex_count(), 2);
+        let kinds = mesh
+            .attribute_data()
+            .iter()
+            .map(|data| data.kind())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                VertexAttributeKind::Position,
+                VertexAttributeKind::UV1,
+                VertexAttributeKind::Custom0
+            ]
+        );
+        let uv1 = mesh.attribute_data()[1].typed_data::<UV1>().unwrap();
+        assert_eq!(uv1, [UV1(Vec2::ZERO), UV1(Vec2::ONE)]);
+
+        let invalid = MeshBuilder::new(positions.clone())
+            .with_attribute(vec![UV0::ZERO; 3])
+            .build();
+        assert!(invalid.is_err());
+        let invalid = MeshBuilder::new(positions)
+            .with_colors1(vec![Color1::ZERO; 2])
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn quantized() {
+        use super::*;
+
+        let mesh = Mesh::builder(PlaneMeshGenerator::from_size(2.0))
+            .with_computed_normals()
+            .with_computed_tangents()
+            .quantized()
+            .build()
+            .unwrap();
+
+        let encodings = mesh
+            .attribute_data()
+            .iter()
+            .map(|data| (data.kind(), data.encoding(), data.byte_len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            encodings,
+            [
+                (
+                    VertexAttributeKind::Position,
+                    VertexAttributeEncoding::Quantized,
+                    32 + 4 * 8
+                ),
+                (
+                    VertexAttributeKind::Normal,
+                    VertexAttributeEncoding::Octahedral,
+                    4 * 4
+                ),
+                (
+                    VertexAttributeKind::Tangent,
+                    VertexAttributeEncoding::Octahedral,
+                    4 * 4
+                ),
+                (
+                    VertexAttributeKind::UV0,
+                    VertexAttributeEncoding::Half,
+                    4 * 4
+                ),
+            ]
+        );
+
+        let invalid = Mesh::builder(PlaneMeshGenerator::default())
+            .with_attribute_encoding(VertexAttributeKind::UV0, VertexAttributeEncoding::Unorm8)
+            .build();
+        assert!(invalid.is_err());
+    }
+
     #[test]
     fn generate_indices() {
         let mut positions = Vec::new();
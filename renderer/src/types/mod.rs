@@ -1,11 +1,31 @@
+pub use self::debug_draw::*;
+pub use self::debug_view::*;
+pub use self::environment::*;
+pub use self::heightmap::*;
 pub use self::material::*;
 pub use self::mesh::*;
+pub use self::obj::*;
 pub use self::object::*;
+pub use self::picking::*;
+pub use self::post_process::*;
 pub use self::projection::*;
+pub use self::shapes::*;
 pub use self::vertex::*;
+pub use self::vertex_encoding::*;
 
+mod debug_draw;
+mod debug_view;
+mod environment;
+mod heightmap;
 mod material;
 mod mesh;
+mod obj;
 mod object;
+mod picking;
+mod post_process;
 mod projection;
+mod shapes;
+#[cfg(test)]
+mod test_utils;
 mod vertex;
+mod vertex_encoding;
@@ -0,0 +1,502 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use glam::{Vec2, Vec3};
+use shared::FastHashMap;
+
+use crate::types::{Mesh, MeshBuilder, Normal, Position, UV0};
+
+/// Meshes and materials loaded from a Wavefront OBJ file.
+pub struct ObjScene {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+/// A single object or group with one material.
+pub struct ObjMesh {
+    pub name: Option<String>,
+    /// Index of the material in [`ObjScene::materials`].
+    pub material: Option<usize>,
+    pub mesh: Mesh,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: Vec3,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub emissive: Vec3,
+    pub shininess: f32,
+    pub opacity: f32,
+    pub diffuse_texture: Option<PathBuf>,
+    pub normal_texture: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ambient: Vec3::ZERO,
+            diffuse: Vec3::ONE,
+            specular: Vec3::ZERO,
+            emissive: Vec3::ZERO,
+            shininess: 0.0,
+            opacity: 1.0,
+            diffuse_texture: None,
+            normal_texture: None,
+        }
+    }
+
+    /// Parses MTL source.
+    pub fn parse_mtl(source: &str) -> Result<Vec<Self>> {
+        let mut materials = Vec::<ObjMaterial>::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = match line.split_once('#') {
+                Some((line, _)) => line,
+                None => line,
+            };
+
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+
+            let context = || format!("invalid MTL statement at line {}", i + 1);
+
+            if keyword == "newmtl" {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                materials.push(ObjMaterial::new(name));
+                continue;
+            }
+
+            let Some(material) = materials.last_mut() else {
+                continue;
+            };
+
+            match keyword {
+                "Ka" => material.ambient = parse_vec3(tokens).with_context(context)?,
+                "Kd" => material.diffuse = parse_vec3(tokens).with_context(context)?,
+                "Ks" => material.specular = parse_vec3(tokens).with_context(context)?,
+                "Ke" => material.emissive = parse_vec3(tokens).with_context(context)?,
+                "Ns" => material.shininess = parse_next(&mut tokens).with_context(context)?,
+                "d" => material.opacity = parse_next(&mut tokens).with_context(context)?,
+                "Tr" => {
+                    material.opacity = 1.0 - parse_next::<f32>(&mut tokens).with_context(context)?
+                }
+                // NOTE: Texture options are skipped, the path is always the last token.
+                "map_Kd" => material.diffuse_texture = tokens.last().map(PathBuf::from),
+                "map_Bump" | "map_bump" | "bump" | "norm" => {
+                    material.normal_texture = tokens.last().map(PathBuf::from)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(materials)
+    }
+}
+
+impl ObjScene {
+    /// Loads an OBJ file with all referenced MTL files.
+    ///
+    /// MTL files and textures are resolved relative to the OBJ file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read OBJ file {}", path.display()))?;
+
+        let mut scene = Self::parse(&source, |name| {
+            let path = base.join(name);
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read MTL file {}", path.display()))
+        })?;
+
+        for material in &mut scene.materials {
+            let textures = [&mut material.diffuse_texture, &mut material.normal_texture];
+            for texture in textures.into_iter().flatten() {
+                *texture = base.join(&*texture);
+            }
+        }
+
+        Ok(scene)
+    }
+
+    /// Parses OBJ source. `load_mtl` is called for each referenced MTL file.
+    ///
+    /// Each object, group or material change produces a separate mesh.
+    /// Polygons are triangulated as fans, so they are expected to be convex.
+    pub fn parse<F>(source: &str, mut load_mtl: F) -> Result<Self>
+    where
+        F: FnMut(&str) -> Result<String>,
+    {
+        let mut parser = ObjParser::default();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = match line.split_once('#') {
+                Some((line, _)) => line,
+                None => line,
+            };
+
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+
+            parser
+                .parse_statement(keyword, tokens, &mut load_mtl)
+                .with_context(|| format!("invalid OBJ statement at line {}", i + 1))?;
+        }
+
+        parser.finish_group()?;
+
+        Ok(Self {
+            meshes: parser.meshes,
+            materials: parser.materials,
+        })
+    }
+}
+
+#[derive(Default)]
+struct ObjParser {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uv: Vec<Vec2>,
+    materials: Vec<ObjMaterial>,
+    meshes: Vec<ObjMesh>,
+    group: ObjGroup,
+}
+
+impl ObjParser {
+    fn parse_statement<'a, F>(
+        &mut self,
+        keyword: &str,
+        mut tokens: impl Iterator<Item = &'a str>,
+        load_mtl: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<String>,
+    {
+        match keyword {
+            "v" => self.positions.push(parse_vec3(tokens)?),
+            "vn" => self.normals.push(parse_vec3(tokens)?),
+            "vt" => {
+                let u = parse_next(&mut tokens)?;
+                let v = tokens.next().map(f32::from_str).transpose()?.unwrap_or(0.0);
+                // OBJ texture coordinates start from the bottom left corner
+                self.uv.push(Vec2::new(u, 1.0 - v));
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|corner| self.parse_corner(corner))
+                    .collect::<Result<Vec<_>>>()?;
+                anyhow::ensure!(corners.len() >= 3, "face must have at least 3 vertices");
+
+                let corners = corners
+                    .into_iter()
+                    .map(|corner| self.group.add_vertex(corner))
+                    .collect::<Vec<_>>();
+
+                for i in 1..corners.len() - 1 {
+                    let indices = [corners[0], corners[i], corners[i + 1]];
+                    self.group.indices.extend_from_slice(&indices);
+                }
+            }
+            "o" | "g" => {
+                self.finish_group()?;
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                self.group.name = (!name.is_empty()).then_some(name);
+            }
+            "usemtl" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                let material = self.materials.iter().position(|item| item.name == name);
+                if material.is_none() {
+                    tracing::warn!(name, "OBJ material not found");
+                }
+
+                if material != self.group.material {
+                    let group_name = self.group.name.clone();
+                    self.finish_group()?;
+                    self.group.name = group_name;
+                    self.group.material = material;
+                }
+            }
+            "mtllib" => {
+                for name in tokens {
+                    match load_mtl(name).and_then(|source| ObjMaterial::parse_mtl(&source)) {
+                        Ok(materials) => self.materials.extend(materials),
+                        Err(e) => tracing::warn!(name, "failed to load MTL file: {e:?}"),
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_corner(&self, corner: &str) -> Result<ObjVertex> {
+        let mut indices = corner.split('/');
+
+        let position = resolve_index(indices.next(), self.positions.len())?
+            .context("vertex position index is required")?;
+        let uv = resolve_index(indices.next(), self.uv.len())?;
+        let normal = resolve_index(indices.next(), self.normals.len())?;
+
+        Ok(ObjVertex {
+            position,
+            uv,
+            normal,
+        })
+    }
+
+    fn finish_group(&mut self) -> Result<()> {
+        let group = std::mem::take(&mut self.group);
+        self.group.material = group.material;
+
+        if group.indices.is_empty() {
+            return Ok(());
+        }
+
+        let positions = group
+            .vertices
+            .iter()
+            .map(|vertex| Position(self.positions[vertex.position as usize]))
+            .collect();
+        let mut builder = MeshBuilder::new(positions);
+
+        if group.vertices.iter().any(|vertex| vertex.uv.is_some()) {
+            let uv0 = group
+                .vertices
+                .iter()
+                .map(|vertex| UV0(vertex.uv.map(|i| self.uv[i as usize]).unwrap_or_default()))
+                .collect();
+            builder = builder.with_uv0(uv0);
+        }
+
+        builder = if group.vertices.iter().all(|vertex| vertex.normal.is_some()) {
+            let normals = group
+                .vertices
+                .iter()
+                .filter_map(|vertex| vertex.normal)
+                .map(|i| Normal(self.normals[i as usize]))
+                .collect();
+            builder.with_normals(normals)
+        } else {
+            builder.with_computed_normals()
+        };
+
+        let mesh = builder
+            .with_indices(group.indices)
+            .build()
+            .with_context(|| format!("failed to build OBJ mesh {:?}", group.name))?;
+
+        self.meshes.push(ObjMesh {
+            name: group.name,
+            material: group.material,
+            mesh,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct ObjGroup {
+    name: Option<String>,
+    material: Option<usize>,
+    vertices: Vec<ObjVertex>,
+    vertex_indices: FastHashMap<ObjVertex, u32>,
+    indices: Vec<u32>,
+}
+
+impl ObjGroup {
+    /// Returns an index of the shared vertex with the same attributes.
+    fn add_vertex(&mut self, vertex: ObjVertex) -> u32 {
+        *self.vertex_indices.entry(vertex).or_insert_with(|| {
+            self.vertices.push(vertex);
+            self.vertices.len() as u32 - 1
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ObjVertex {
+    position: u32,
+    uv: Option<u32>,
+    normal: Option<u32>,
+}
+
+/// Converts a one-based (or negative relative) OBJ index into a zero-based one.
+fn resolve_index(index: Option<&str>, len: usize) -> Result<Option<u32>> {
+    let index = match index {
+        Some(index) if !index.is_empty() => i64::from_str(index)?,
+        _ => return Ok(None),
+    };
+
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => len as i64 + index,
+        0 => anyhow::bail!("OBJ indices must not be zero"),
+    };
+    anyhow::ensure!(
+        (0..len as i64).contains(&resolved),
+        "OBJ index {index} is out of bounds"
+    );
+
+    Ok(Some(resolved as u32))
+}
+
+fn parse_next<'a, T>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let token = tokens.next().context("unexpected end of statement")?;
+    Ok(T::from_str(token)?)
+}
+
+fn parse_vec3<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Vec3> {
+    Ok(Vec3::new(
+        parse_next(&mut tokens)?,
+        parse_next(&mut tokens)?,
+        parse_next(&mut tokens)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_utils::attribute;
+
+    const MTL: &str = r#"
+newmtl red
+Kd 1.0 0.0 0.0
+d 0.5
+map_Kd -s 2 2 2 textures/red.png
+
+newmtl blue
+Kd 0.0 0.0 1.0
+Tr 0.25
+"#;
+
+    const OBJ: &str = r#"
+# Quad with a shared position but different normals
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 0 -1
+v 0 0 -1
+vt 0 0
+vt 1 1
+vn 0 1 0
+
+o first
+usemtl red
+f 1/1/1 2/2/1 3/1/1 4/2/1
+
+o second
+f -4 -3 -2
+usemtl blue
+f 1//1 3//1 4//1
+"#;
+
+    fn parse(source: &str) -> Result<ObjScene> {
+        ObjScene::parse(source, |name| {
+            assert_eq!(name, "scene.mtl");
+            Ok(MTL.to_owned())
+        })
+    }
+
+    #[test]
+    fn materials() {
+        let materials = ObjMaterial::parse_mtl(MTL).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, Vec3::X);
+        assert_eq!(materials[0].opacity, 0.5);
+        assert_eq!(
+            materials[0].diffuse_texture.as_deref(),
+            Some(Path::new("textures/red.png"))
+        );
+        assert_eq!(materials[1].diffuse, Vec3::Z);
+        assert_eq!(materials[1].opacity, 0.75);
+    }
+
+    #[test]
+    fn groups_and_triangulation() {
+        let scene = parse(OBJ).unwrap();
+        assert_eq!(scene.materials.len(), 2);
+
+        let meshes = scene
+            .meshes
+            .iter()
+            .map(|item| (item.name.as_deref(), item.material))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            meshes,
+            [
+                (Some("first"), Some(0)),
+                (Some("second"), Some(0)),
+                (Some("second"), Some(1))
+            ]
+        );
+
+        // Quad is split into two triangles facing +Y
+        let quad = &scene.meshes[0].mesh;
+        assert_eq!(quad.vertex_count(), 4);
+        assert_eq!(quad.indices(), [0, 1, 2, 0, 2, 3]);
+
+        let positions = attribute::<Position>(quad);
+        let uv0 = attribute::<UV0>(quad);
+        assert_eq!(positions[1].0, Vec3::X);
+        assert_eq!(uv0[1].0, Vec2::new(1.0, 0.0));
+        for triangle in quad.indices().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize].0);
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+
+        // Relative indices without normals
+        let relative = &scene.meshes[1].mesh;
+        assert_eq!(relative.vertex_count(), 3);
+        assert_eq!(attribute::<Position>(relative)[0].0, Vec3::ZERO);
+        assert!(attribute::<Normal>(relative)
+            .iter()
+            .all(|normal| normal.abs_diff_eq(Vec3::Y, 1e-6)));
+    }
+
+    #[test]
+    fn shared_vertices() {
+        let scene = parse(
+            r#"
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 0 0 1
+vn 0 0 -1
+f 1//1 2//1 3//1
+f 1//2 3//2 2//2
+"#,
+        )
+        .unwrap();
+        assert_eq!(scene.meshes.len(), 1);
+
+        // Same positions with different normals are not merged
+        let mesh = &scene.meshes[0].mesh;
+        assert_eq!(mesh.vertex_count(), 6);
+        assert_eq!(mesh.indices(), [0, 1, 2, 3, 4, 5]);
+
+        let scene = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 3 2\n").unwrap();
+        assert_eq!(scene.meshes[0].mesh.vertex_count(), 3);
+    }
+
+    #[test]
+    fn invalid_indices() {
+        assert!(parse("v 0 0 0\nf 1 1 2\n").is_err());
+        assert!(parse("v 0 0 0\nf 0 1 1\n").is_err());
+        assert!(parse("v 0 0 0\nf 1 1\n").is_err());
+    }
+}
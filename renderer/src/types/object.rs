@@ -16,4 +16,202 @@ pub struct ObjectData {
     pub mesh: MeshHandle,
     pub material: MaterialInstanceHandle,
     pub global_transform: Mat4,
+    pub visibility: ObjectVisibility,
+}
+
+/// Identifies an object regardless of its current storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ObjectKey {
+    Static(RawStaticObjectHandle),
+    Dynamic(RawDynamicObjectHandle),
+}
+
+/// Where object data is stored.
+///
+/// Static objects are kept on the GPU and only re-uploaded when changed.
+/// Dynamic objects are uploaded every frame and interpolated between fixed updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectStorage {
+    Static,
+    Dynamic,
+}
+
+/// Rules for moving objects between static and dynamic storage automatically.
+///
+/// Counters are measured in fixed updates. `None` disables the migration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectMigrationPolicy {
+    /// Move static objects into dynamic storage after they were updated
+    /// during this many consecutive fixed updates.
+    pub promote_after: Option<u32>,
+    /// Move dynamic objects into static storage after they were not updated
+    /// during this many consecutive fixed updates.
+    pub demote_after: Option<u32>,
+}
+
+/// Per-object rendering flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectFlags(u16);
+
+impl ObjectFlags {
+    pub const EMPTY: Self = Self(0);
+    pub const VISIBLE: Self = Self(1 << 0);
+    pub const CAST_SHADOWS: Self = Self(1 << 1);
+    pub const RECEIVE_SHADOWS: Self = Self(1 << 2);
+    pub const EXCLUDE_FROM_PICKING: Self = Self(1 << 3);
+
+    pub const DEFAULT: Self =
+        Self(Self::VISIBLE.0 | Self::CAST_SHADOWS.0 | Self::RECEIVE_SHADOWS.0);
+
+    #[inline]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the flags with `other` added or removed.
+    #[inline]
+    pub const fn with(self, other: Self, enabled: bool) -> Self {
+        if enabled {
+            Self(self.0 | other.0)
+        } else {
+            Self(self.0 & !other.0)
+        }
+    }
+}
+
+impl Default for ObjectFlags {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl std::ops::BitOr for ObjectFlags {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Bitmask of render layers.
+///
+/// Objects are only drawn by cameras and passes which share at least one layer with them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderLayers(u16);
+
+impl RenderLayers {
+    pub const MAX_LAYERS: u8 = 16;
+
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u16::MAX);
+    pub const DEFAULT: Self = Self::layer(0);
+
+    /// A mask with a single layer.
+    #[inline]
+    pub const fn layer(layer: u8) -> Self {
+        assert!(layer < Self::MAX_LAYERS, "render layer is out of range");
+        Self(1 << layer)
+    }
+
+    #[inline]
+    pub const fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn with(self, layer: u8) -> Self {
+        Self(self.0 | Self::layer(layer).0)
+    }
+
+    #[inline]
+    pub const fn without(self, layer: u8) -> Self {
+        Self(self.0 & !Self::layer(layer).0)
+    }
+
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    #[inline]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl Default for RenderLayers {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Passes with their own render layers, which are combined with the layers of the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderPassKind {
+    /// Opaque geometry, also used for the depth of the other passes.
+    Main,
+    /// Object picking, only objects drawn by the main pass can be picked.
+    Picking,
+    /// Shapes of [`DebugDraw`](crate::DebugDraw).
+    Debug,
+}
+
+impl RenderPassKind {
+    pub(crate) const COUNT: usize = 3;
+}
+
+/// Flags and render layers of an object.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectVisibility {
+    pub flags: ObjectFlags,
+    pub layers: RenderLayers,
+}
+
+impl ObjectVisibility {
+    /// Returns whether the object is visible for a camera or a pass with the specified layers.
+    #[inline]
+    pub fn is_visible_in(&self, layers: RenderLayers) -> bool {
+        self.flags.contains(ObjectFlags::VISIBLE) && self.layers.intersects(layers)
+    }
+
+    /// Packs flags into the lower and layers into the upper half of `data.w`.
+    ///
+    /// See `uniforms/object.glsl`.
+    #[inline]
+    pub(crate) fn gpu_bits(&self) -> u32 {
+        self.flags.bits() as u32 | (self.layers.bits() as u32) << 16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visibility_respects_flags_and_layers() {
+        let visibility = ObjectVisibility::default();
+        assert!(visibility.is_visible_in(RenderLayers::ALL));
+        assert!(visibility.is_visible_in(RenderLayers::DEFAULT));
+        assert!(!visibility.is_visible_in(RenderLayers::layer(1)));
+
+        let visibility = ObjectVisibility {
+            flags: ObjectFlags::DEFAULT.with(ObjectFlags::VISIBLE, false),
+            layers: RenderLayers::layer(1).with(3),
+        };
+        assert!(!visibility.is_visible_in(RenderLayers::ALL));
+        assert_eq!(visibility.gpu_bits(), 0b0110 | 0b1010 << 16);
+    }
 }
@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+use glam::{UVec2, Vec3};
+
+use crate::types::{DynamicObjectHandle, ObjectKey, StaticObjectHandle};
+
+/// Object found by [`RendererState::pick`](crate::RendererState::pick).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickResult {
+    pub object: PickedObject,
+    /// World space position of the picked surface.
+    pub position: Vec3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PickedObject {
+    Static(StaticObjectHandle),
+    Dynamic(DynamicObjectHandle),
+}
+
+/// Pick request which is resolved a few frames after it was made.
+#[derive(Debug, Clone, Default)]
+pub struct PickRequest {
+    result: Arc<Mutex<Option<Option<PickResult>>>>,
+}
+
+impl PickRequest {
+    /// Returns `Poll::Ready(None)` if there is no pickable object at the requested position.
+    pub fn poll(&self) -> Poll<Option<PickResult>> {
+        match &*self.result.lock().unwrap() {
+            Some(result) => Poll::Ready(result.clone()),
+            None => Poll::Pending,
+        }
+    }
+
+    pub(crate) fn resolve(&self, result: Option<PickResult>) {
+        *self.result.lock().unwrap() = Some(result);
+    }
+}
+
+/// Pick request which was not submitted to the GPU yet.
+pub(crate) struct PendingPick {
+    /// Position in physical pixels of the surface.
+    pub position: UVec2,
+    pub request: PickRequest,
+}
+
+/// Object identifier written into the picking buffer.
+///
+/// Bit 31 marks dynamic handles, bits 24..31 hold the lowest bits of the handle
+/// generation and the rest is the handle index plus one, so zero means no object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PickingId {
+    pub dynamic: bool,
+    pub index: usize,
+    /// Lowest bits of the handle generation.
+    pub generation: u32,
+}
+
+impl PickingId {
+    const DYNAMIC_BIT: u32 = 1 << 31;
+    const GENERATION_SHIFT: u32 = 24;
+    const GENERATION_MASK: u32 = 0x7f;
+    const INDEX_MASK: u32 = (1 << Self::GENERATION_SHIFT) - 1;
+
+    pub fn from_key(key: ObjectKey) -> Self {
+        let (dynamic, index, generation) = match key {
+            ObjectKey::Static(handle) => (false, handle.index, handle.generation),
+            ObjectKey::Dynamic(handle) => (true, handle.index, handle.generation),
+        };
+        Self {
+            dynamic,
+            index,
+            generation: generation & Self::GENERATION_MASK,
+        }
+    }
+
+    /// Returns zero for indices which do not fit, such objects can not be picked.
+    pub fn encode(&self) -> u32 {
+        if self.index >= Self::INDEX_MASK as usize {
+            return 0;
+        }
+
+        let dynamic = if self.dynamic { Self::DYNAMIC_BIT } else { 0 };
+        dynamic
+            | (self.generation & Self::GENERATION_MASK) << Self::GENERATION_SHIFT
+            | (self.index as u32 + 1)
+    }
+
+    pub fn decode(id: u32) -> Option<Self> {
+        let index = (id & Self::INDEX_MASK).checked_sub(1)?;
+        Some(Self {
+            dynamic: id & Self::DYNAMIC_BIT != 0,
+            index: index as usize,
+            generation: (id >> Self::GENERATION_SHIFT) & Self::GENERATION_MASK,
+        })
+    }
+
+    /// Returns whether the full generation of a handle matches this identifier.
+    pub fn matches_generation(&self, generation: u32) -> bool {
+        generation & Self::GENERATION_MASK == self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picking_id_round_trip() {
+        for (dynamic, index, generation) in [(false, 0, 0), (true, 41, 300), (false, 0xfffffe, 7)] {
+            let id = PickingId {
+                dynamic,
+                index,
+                generation: generation & PickingId::GENERATION_MASK,
+            };
+            let encoded = id.encode();
+            assert_ne!(encoded, 0);
+
+            let decoded = PickingId::decode(encoded).unwrap();
+            assert_eq!(decoded, id);
+            assert!(decoded.matches_generation(generation));
+            assert!(!decoded.matches_generation(generation + 1));
+        }
+
+        assert_eq!(PickingId::decode(0), None);
+        let too_large = PickingId {
+            dynamic: false,
+            index: 0xffffff,
+            generation: 0,
+        };
+        assert_eq!(too_large.encode(), 0);
+    }
+}
@@ -0,0 +1,240 @@
+/// Operator which maps HDR scene colors into the displayable range.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tonemapping {
+    /// Colors are only clamped.
+    None,
+    /// Fitted ACES reference rendering and output transforms.
+    #[default]
+    Aces,
+    /// Minimal AgX with the default look.
+    AgX,
+    /// Luminance-based Reinhard.
+    Reinhard,
+}
+
+impl Tonemapping {
+    pub(crate) fn shader_index(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Aces => 1,
+            Self::AgX => 2,
+            Self::Reinhard => 3,
+        }
+    }
+}
+
+/// Scene exposure, expressed in EV100.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exposure {
+    Manual {
+        ev100: f32,
+    },
+    /// Exposure computed from a luminance histogram of the rendered scene.
+    Auto {
+        /// Darkest scene exposure to adapt to.
+        min_ev100: f32,
+        /// Brightest scene exposure to adapt to.
+        max_ev100: f32,
+        /// Offset applied to the computed exposure, positive values brighten the image.
+        compensation: f32,
+        /// How fast the exposure follows the scene luminance, per second.
+        adaptation_speed: f32,
+    },
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Self::Auto {
+            min_ev100: -4.0,
+            max_ev100: 16.0,
+            compensation: 0.0,
+            adaptation_speed: 1.5,
+        }
+    }
+}
+
+/// Glow around bright parts of the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// Exposed color brightness from which pixels start to glow.
+    pub threshold: f32,
+    /// Softness of the threshold transition, relative to the threshold in `0..=1`.
+    pub knee: f32,
+    /// Amount of bloom added to the scene.
+    pub intensity: f32,
+    /// Spread of the glow, in texels of each bloom mip.
+    pub radius: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.15,
+            radius: 1.0,
+        }
+    }
+}
+
+/// Number of samples taken per pixel by the ambient occlusion pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmbientOcclusionQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl AmbientOcclusionQuality {
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Self::Low => 8,
+            Self::Medium => 16,
+            Self::High => 32,
+        }
+    }
+}
+
+/// Screen space darkening of creases and contact areas under ambient light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusionSettings {
+    pub quality: AmbientOcclusionQuality,
+    /// Distance around each point in which occluders are searched, in world units.
+    pub radius: f32,
+    /// Exponent applied to the occlusion term, higher values darken the result.
+    pub intensity: f32,
+}
+
+impl Default for AmbientOcclusionSettings {
+    fn default() -> Self {
+        Self {
+            quality: AmbientOcclusionQuality::default(),
+            radius: 0.5,
+            intensity: 1.5,
+        }
+    }
+}
+
+/// Accumulation of jittered frames over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaaSettings {
+    /// Weight of the reprojected history in `0..=1`. Higher values are smoother but
+    /// more prone to ghosting.
+    pub history_weight: f32,
+}
+
+impl Default for TaaSettings {
+    fn default() -> Self {
+        Self {
+            history_weight: 0.9,
+        }
+    }
+}
+
+/// Method used to smooth geometry edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiAliasing {
+    None,
+    /// Temporal anti-aliasing, the camera projection is jittered every frame.
+    Taa(TaaSettings),
+}
+
+impl Default for AntiAliasing {
+    fn default() -> Self {
+        Self::Taa(TaaSettings::default())
+    }
+}
+
+/// Number of samples per pixel of the scene attachments.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Msaa {
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Self::Off => 1,
+            Self::X2 => 2,
+            Self::X4 => 4,
+            Self::X8 => 8,
+        }
+    }
+
+    pub(crate) fn samples(&self) -> gfx::Samples {
+        match self {
+            Self::Off => gfx::Samples::_1,
+            Self::X2 => gfx::Samples::_2,
+            Self::X4 => gfx::Samples::_4,
+            Self::X8 => gfx::Samples::_8,
+        }
+    }
+
+    /// Returns the highest sample count not above `self` which is present
+    /// in the mask of supported sample counts (e.g. from device limits).
+    pub(crate) fn clamp_to_supported(self, supported_counts: u32) -> Self {
+        [Self::X8, Self::X4, Self::X2]
+            .into_iter()
+            .find(|msaa| *msaa <= self && supported_counts & msaa.sample_count() != 0)
+            .unwrap_or(Self::Off)
+    }
+}
+
+/// Anti-aliasing applied to the tonemapped scene.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostAntiAliasing {
+    #[default]
+    None,
+    /// Fast approximate anti-aliasing.
+    Fxaa,
+    /// Subpixel morphological anti-aliasing (SMAA 1x).
+    Smaa,
+}
+
+/// Settings of the passes applied to the rendered scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostProcessSettings {
+    pub tonemapping: Tonemapping,
+    pub exposure: Exposure,
+    /// Bloom is disabled when `None`.
+    pub bloom: Option<BloomSettings>,
+    /// Ambient occlusion is disabled when `None`.
+    pub ambient_occlusion: Option<AmbientOcclusionSettings>,
+    pub anti_aliasing: AntiAliasing,
+    /// Multisampling of the main pass, can be combined with `anti_aliasing`.
+    pub msaa: Msaa,
+    /// Edge smoothing after tonemapping, can be combined with `anti_aliasing`.
+    pub post_anti_aliasing: PostAntiAliasing,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            tonemapping: Tonemapping::default(),
+            exposure: Exposure::default(),
+            bloom: Some(BloomSettings::default()),
+            ambient_occlusion: Some(AmbientOcclusionSettings::default()),
+            anti_aliasing: AntiAliasing::default(),
+            msaa: Msaa::default(),
+            post_anti_aliasing: PostAntiAliasing::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn msaa_falls_back_to_supported_samples() {
+        assert_eq!(Msaa::X8.clamp_to_supported(0b1111), Msaa::X8);
+        assert_eq!(Msaa::X8.clamp_to_supported(0b0101), Msaa::X4);
+        assert_eq!(Msaa::X2.clamp_to_supported(0b1101), Msaa::Off);
+        assert_eq!(Msaa::Off.clamp_to_supported(0b1111), Msaa::Off);
+    }
+}
@@ -0,0 +1,759 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::{Vec2, Vec3};
+use shared::FastHashMap;
+
+use crate::types::{MeshBuilder, MeshGenerator, Normal, Position, UV0};
+
+/// UV sphere with poles on the Y axis.
+#[derive(Debug, Clone, Copy)]
+pub struct UvSphereMeshGenerator {
+    pub radius: f32,
+    /// Number of segments around the Y axis.
+    pub sectors: u32,
+    /// Number of segments from pole to pole.
+    pub stacks: u32,
+}
+
+impl Default for UvSphereMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            sectors: 32,
+            stacks: 16,
+        }
+    }
+}
+
+impl MeshGenerator for UvSphereMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let stacks = self.stacks.max(2);
+        let rows = (0..=stacks)
+            .map(|i| {
+                let v = i as f32 / stacks as f32;
+                Row::latitude(v * PI, 0.0, v)
+            })
+            .collect::<Vec<_>>();
+
+        let mut shape = Shape::default();
+        shape.add_revolution(&rows, self.radius, self.sectors.max(3), true, true);
+        shape.into_builder()
+    }
+}
+
+/// Subdivided icosahedron projected onto a sphere.
+#[derive(Debug, Clone, Copy)]
+pub struct IcosphereMeshGenerator {
+    pub radius: f32,
+    pub subdivisions: u32,
+}
+
+impl Default for IcosphereMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            subdivisions: 3,
+        }
+    }
+}
+
+impl MeshGenerator for IcosphereMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let t = (1.0 + 5f32.sqrt()) / 2.0;
+        let mut points = [
+            (-1.0, t, 0.0),
+            (1.0, t, 0.0),
+            (-1.0, -t, 0.0),
+            (1.0, -t, 0.0),
+            (0.0, -1.0, t),
+            (0.0, 1.0, t),
+            (0.0, -1.0, -t),
+            (0.0, 1.0, -t),
+            (t, 0.0, -1.0),
+            (t, 0.0, 1.0),
+            (-t, 0.0, -1.0),
+            (-t, 0.0, 1.0),
+        ]
+        .into_iter()
+        .map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+        .collect::<Vec<_>>();
+
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        // Make sure that all faces point outwards.
+        for [a, b, c] in &mut triangles {
+            let (pa, pb, pc) = (
+                points[*a as usize],
+                points[*b as usize],
+                points[*c as usize],
+            );
+            if (pb - pa).cross(pc - pa).dot(pa + pb + pc) < 0.0 {
+                std::mem::swap(b, c);
+            }
+        }
+
+        for _ in 0..self.subdivisions {
+            let mut midpoints = FastHashMap::<(u32, u32), u32>::default();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    points.push((points[a as usize] + points[b as usize]).normalize());
+                    points.len() as u32 - 1
+                })
+            };
+
+            triangles = triangles
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let ab = midpoint(a, b);
+                    let bc = midpoint(b, c);
+                    let ca = midpoint(c, a);
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let mut uvs = points.iter().map(|p| spherical_uv(*p)).collect::<Vec<_>>();
+
+        // Fix UVs of triangles which cross the seam or touch the poles
+        // by duplicating their vertices.
+        let mut seam_vertices = FastHashMap::<u32, u32>::default();
+        for triangle in &mut triangles {
+            let u = triangle.map(|i| uvs[i as usize].x);
+            let crosses_seam = u.iter().copied().fold(f32::MIN, f32::max)
+                - u.iter().copied().fold(f32::MAX, f32::min)
+                > 0.5;
+
+            if crosses_seam {
+                for index in triangle.iter_mut() {
+                    if uvs[*index as usize].x < 0.5 {
+                        *index = *seam_vertices.entry(*index).or_insert_with(|| {
+                            points.push(points[*index as usize]);
+                            uvs.push(uvs[*index as usize] + Vec2::X);
+                            points.len() as u32 - 1
+                        });
+                    }
+                }
+            }
+
+            for i in 0..3 {
+                let index = triangle[i] as usize;
+                if points[index].y.abs() < 1.0 - 1e-6 {
+                    continue;
+                }
+
+                let u = (uvs[triangle[(i + 1) % 3] as usize].x
+                    + uvs[triangle[(i + 2) % 3] as usize].x)
+                    / 2.0;
+                points.push(points[index]);
+                uvs.push(Vec2::new(u, uvs[index].y));
+                triangle[i] = points.len() as u32 - 1;
+            }
+        }
+
+        let mut shape = Shape::default();
+        for (point, uv) in std::iter::zip(points, uvs) {
+            shape.push_vertex(point * self.radius, point, uv);
+        }
+        shape.indices = triangles.into_iter().flatten().collect();
+        shape.into_builder()
+    }
+}
+
+/// Cylinder along the Y axis centered at the origin.
+#[derive(Debug, Clone, Copy)]
+pub struct CylinderMeshGenerator {
+    pub radius: f32,
+    pub height: f32,
+    pub segments: u32,
+    pub caps: bool,
+}
+
+impl Default for CylinderMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            segments: 32,
+            caps: true,
+        }
+    }
+}
+
+impl MeshGenerator for CylinderMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let segments = self.segments.max(3);
+        let half_height = self.height * 0.5;
+
+        let mut shape = Shape::default();
+        shape.add_revolution(
+            &[
+                Row::latitude(FRAC_PI_2, half_height, 0.0),
+                Row::latitude(FRAC_PI_2, -half_height, 1.0),
+            ],
+            self.radius,
+            segments,
+            false,
+            false,
+        );
+        if self.caps {
+            shape.add_cap(self.radius, half_height, segments, true);
+            shape.add_cap(self.radius, -half_height, segments, false);
+        }
+        shape.into_builder()
+    }
+}
+
+/// Cone along the Y axis with its apex at `height / 2`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConeMeshGenerator {
+    pub radius: f32,
+    pub height: f32,
+    pub segments: u32,
+    pub cap: bool,
+}
+
+impl Default for ConeMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            segments: 32,
+            cap: true,
+        }
+    }
+}
+
+impl MeshGenerator for ConeMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let segments = self.segments.max(3);
+        let half_height = self.height * 0.5;
+
+        // Side normals are tilted by the slope angle.
+        let slope = self.radius.atan2(self.height);
+        let mut shape = Shape::default();
+        shape.add_revolution(
+            &[
+                Row {
+                    radius: 0.0,
+                    y: 0.0,
+                    y_offset: half_height,
+                    normal_phi: FRAC_PI_2 - slope,
+                    v: 0.0,
+                },
+                Row {
+                    radius: 1.0,
+                    y: 0.0,
+                    y_offset: -half_height,
+                    normal_phi: FRAC_PI_2 - slope,
+                    v: 1.0,
+                },
+            ],
+            self.radius,
+            segments,
+            true,
+            false,
+        );
+        if self.cap {
+            shape.add_cap(self.radius, -half_height, segments, false);
+        }
+        shape.into_builder()
+    }
+}
+
+/// Capsule along the Y axis. Total height is `height + 2 * radius`.
+#[derive(Debug, Clone, Copy)]
+pub struct CapsuleMeshGenerator {
+    pub radius: f32,
+    /// Height of the cylindrical part.
+    pub height: f32,
+    pub segments: u32,
+    /// Number of rings in each hemisphere.
+    pub rings: u32,
+}
+
+impl Default for CapsuleMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            segments: 32,
+            rings: 8,
+        }
+    }
+}
+
+impl MeshGenerator for CapsuleMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let rings = self.rings.max(1);
+        let half_height = self.height * 0.5;
+
+        // Texture coordinates are distributed along the profile length.
+        let arc = FRAC_PI_2 * self.radius;
+        let total_length = 2.0 * arc + self.height;
+        let v = |length: f32| {
+            if total_length > 0.0 {
+                length / total_length
+            } else {
+                0.0
+            }
+        };
+
+        let mut rows = Vec::with_capacity(2 * rings as usize + 2);
+        for i in 0..=rings {
+            let t = i as f32 / rings as f32;
+            rows.push(Row::latitude(t * FRAC_PI_2, half_height, v(t * arc)));
+        }
+        for i in 0..=rings {
+            let t = i as f32 / rings as f32;
+            rows.push(Row::latitude(
+                FRAC_PI_2 + t * FRAC_PI_2,
+                -half_height,
+                v(arc + self.height + t * arc),
+            ));
+        }
+
+        let mut shape = Shape::default();
+        shape.add_revolution(&rows, self.radius, self.segments.max(3), true, true);
+        shape.into_builder()
+    }
+}
+
+/// Torus around the Y axis.
+#[derive(Debug, Clone, Copy)]
+pub struct TorusMeshGenerator {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub major_segments: u32,
+    pub minor_segments: u32,
+}
+
+impl Default for TorusMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            major_radius: 0.5,
+            minor_radius: 0.2,
+            major_segments: 32,
+            minor_segments: 16,
+        }
+    }
+}
+
+impl MeshGenerator for TorusMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let minor_segments = self.minor_segments.max(3);
+
+        // Revolve the tube cross-section, starting from the outer equator
+        // and going down to keep the same winding as other shapes.
+        let rows = (0..=minor_segments)
+            .map(|i| {
+                let v = i as f32 / minor_segments as f32;
+                let angle = -v * TAU;
+                Row {
+                    radius: self.major_radius + self.minor_radius * angle.cos(),
+                    y: self.minor_radius * angle.sin(),
+                    y_offset: 0.0,
+                    normal_phi: FRAC_PI_2 - angle,
+                    v,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut shape = Shape::default();
+        shape.add_revolution(&rows, 1.0, self.major_segments.max(3), false, false);
+        shape.into_builder()
+    }
+}
+
+/// Subdivided plane in the XZ plane facing +Y.
+#[derive(Debug, Clone, Copy)]
+pub struct GridMeshGenerator {
+    pub width: f32,
+    pub depth: f32,
+    pub x_segments: u32,
+    pub z_segments: u32,
+}
+
+impl Default for GridMeshGenerator {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            depth: 1.0,
+            x_segments: 16,
+            z_segments: 16,
+        }
+    }
+}
+
+impl MeshGenerator for GridMeshGenerator {
+    fn generate_mesh(self) -> MeshBuilder {
+        let x_segments = self.x_segments.max(1);
+        let z_segments = self.z_segments.max(1);
+
+        let mut shape = Shape::default();
+        for j in 0..=z_segments {
+            let v = j as f32 / z_segments as f32;
+            for i in 0..=x_segments {
+                let u = i as f32 / x_segments as f32;
+                shape.push_vertex(
+                    Vec3::new((u - 0.5) * self.width, 0.0, (v - 0.5) * self.depth),
+                    Vec3::Y,
+                    Vec2::new(u, v),
+                );
+            }
+        }
+
+        let stride = x_segments + 1;
+        for j in 0..z_segments {
+            for i in 0..x_segments {
+                let k = j * stride + i;
+                shape.push_quad(k, k + 1, k + stride, k + stride + 1);
+            }
+        }
+
+        shape.into_builder()
+    }
+}
+
+/// A ring of vertices in a surface of revolution.
+struct Row {
+    /// Ring radius (multiplied by the shape radius).
+    radius: f32,
+    /// Ring height (multiplied by the shape radius).
+    y: f32,
+    /// Additional ring height.
+    y_offset: f32,
+    /// Normal angle from the +Y axis.
+    normal_phi: f32,
+    v: f32,
+}
+
+impl Row {
+    fn latitude(phi: f32, y_offset: f32, v: f32) -> Self {
+        Self {
+            radius: phi.sin(),
+            y: phi.cos(),
+            y_offset,
+            normal_phi: phi,
+            v,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shape {
+    positions: Vec<Position>,
+    normals: Vec<Normal>,
+    uv0: Vec<UV0>,
+    indices: Vec<u32>,
+}
+
+impl Shape {
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) {
+        self.positions.push(Position(position));
+        self.normals.push(Normal(normal));
+        self.uv0.push(UV0(uv));
+    }
+
+    /// Pushes two triangles with `a -> b` going along +U and `a -> c` along +V.
+    fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend_from_slice(&[a, c, b, b, c, d]);
+    }
+
+    /// Revolves rows (from top to bottom) around the Y axis.
+    ///
+    /// Degenerate triangles are skipped for rows which collapse into a point.
+    fn add_revolution(
+        &mut self,
+        rows: &[Row],
+        radius: f32,
+        sectors: u32,
+        top_pole: bool,
+        bottom_pole: bool,
+    ) {
+        let first = self.positions.len() as u32;
+        let stride = sectors + 1;
+
+        for (r, row) in rows.iter().enumerate() {
+            let is_pole = (r == 0 && top_pole) || (r == rows.len() - 1 && bottom_pole);
+            for s in 0..=sectors {
+                // Pole vertices are shifted to the middle of the sector.
+                let u = if is_pole {
+                    (s as f32 + 0.5) / sectors as f32
+                } else {
+                    s as f32 / sectors as f32
+                };
+                let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+                let direction = Vec3::new(cos_theta, 0.0, -sin_theta);
+
+                let (sin_phi, cos_phi) = row.normal_phi.sin_cos();
+                let normal = direction * sin_phi + Vec3::Y * cos_phi;
+                let position =
+                    direction * row.radius * radius + Vec3::Y * (row.y * radius + row.y_offset);
+
+                self.push_vertex(position, normal, Vec2::new(u, row.v));
+            }
+        }
+
+        for r in 0..rows.len() as u32 - 1 {
+            for s in 0..sectors {
+                let a = first + r * stride + s;
+                let c = a + stride;
+                if r != 0 || !top_pole {
+                    self.indices.extend_from_slice(&[a, c, a + 1]);
+                }
+                if r != rows.len() as u32 - 2 || !bottom_pole {
+                    self.indices.extend_from_slice(&[a + 1, c, c + 1]);
+                }
+            }
+        }
+    }
+
+    /// Adds a flat disk at the specified height.
+    fn add_cap(&mut self, radius: f32, y: f32, segments: u32, facing_up: bool) {
+        let normal = if facing_up { Vec3::Y } else { Vec3::NEG_Y };
+
+        let center = self.positions.len() as u32;
+        self.push_vertex(Vec3::new(0.0, y, 0.0), normal, Vec2::splat(0.5));
+        for s in 0..=segments {
+            let (sin_theta, cos_theta) = (s as f32 / segments as f32 * TAU).sin_cos();
+            self.push_vertex(
+                Vec3::new(cos_theta * radius, y, -sin_theta * radius),
+                normal,
+                Vec2::new(0.5 + cos_theta * 0.5, 0.5 - sin_theta * 0.5),
+            );
+        }
+
+        for s in 0..segments {
+            let (a, b) = (center + 1 + s, center + 2 + s);
+            if facing_up {
+                self.indices.extend_from_slice(&[center, a, b]);
+            } else {
+                self.indices.extend_from_slice(&[center, b, a]);
+            }
+        }
+    }
+
+    fn into_builder(self) -> MeshBuilder {
+        MeshBuilder::new(self.positions)
+            .with_normals(self.normals)
+            .with_uv0(self.uv0)
+            .with_indices(self.indices)
+    }
+}
+
+fn spherical_uv(direction: Vec3) -> Vec2 {
+    let u = (-direction.z).atan2(direction.x) / TAU;
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    Vec2::new(u.rem_euclid(1.0), v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_utils::{assert_triangles_face_up, attribute};
+    use crate::types::Mesh;
+
+    #[test]
+    fn uv_sphere() {
+        let mesh = build(UvSphereMeshGenerator {
+            radius: 2.0,
+            sectors: 12,
+            stacks: 6,
+        });
+        assert_eq!(mesh.vertex_count(), 13 * 7);
+        assert_eq!(mesh.indices().len(), 6 * 12 * 5);
+        check_closed_manifold(&mesh);
+        check_normals(&mesh);
+    }
+
+    #[test]
+    fn icosphere() {
+        for subdivisions in 0..3 {
+            let mesh = build(IcosphereMeshGenerator {
+                radius: 1.0,
+                subdivisions,
+            });
+            let base_vertex_count = 10 * 4u32.pow(subdivisions) + 2;
+            assert!(mesh.vertex_count() >= base_vertex_count);
+            assert_eq!(mesh.indices().len(), 60 * 4usize.pow(subdivisions));
+            check_closed_manifold(&mesh);
+            check_normals(&mesh);
+
+            // No triangle should stretch over the whole texture.
+            let uv0 = attribute::<UV0>(&mesh);
+            for triangle in mesh.indices().chunks_exact(3) {
+                let u = triangle.iter().map(|&i| uv0[i as usize].x);
+                let (min, max) = u.fold((f32::MAX, f32::MIN), |(min, max), u| {
+                    (min.min(u), max.max(u))
+                });
+                assert!(max - min <= 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn cylinder() {
+        let mesh = build(CylinderMeshGenerator {
+            radius: 1.0,
+            height: 3.0,
+            segments: 16,
+            caps: true,
+        });
+        assert_eq!(mesh.vertex_count(), 17 * 2 + 2 * 18);
+        assert_eq!(mesh.indices().len(), 16 * 6 + 2 * 16 * 3);
+        check_closed_manifold(&mesh);
+        check_normals(&mesh);
+
+        let open = build(CylinderMeshGenerator {
+            caps: false,
+            ..Default::default()
+        });
+        assert_eq!(open.indices().len(), 32 * 6);
+    }
+
+    #[test]
+    fn cone() {
+        let mesh = build(ConeMeshGenerator {
+            radius: 1.0,
+            height: 2.0,
+            segments: 10,
+            cap: true,
+        });
+        assert_eq!(mesh.vertex_count(), 11 * 2 + 12);
+        assert_eq!(mesh.indices().len(), 10 * 3 + 10 * 3);
+        check_closed_manifold(&mesh);
+        check_normals(&mesh);
+    }
+
+    #[test]
+    fn capsule() {
+        let mesh = build(CapsuleMeshGenerator {
+            radius: 0.5,
+            height: 2.0,
+            segments: 12,
+            rings: 4,
+        });
+        assert_eq!(mesh.vertex_count(), 13 * 10);
+        assert_eq!(mesh.indices().len(), 6 * 12 * 8);
+        check_closed_manifold(&mesh);
+        check_normals(&mesh);
+    }
+
+    #[test]
+    fn torus() {
+        let mesh = build(TorusMeshGenerator {
+            major_radius: 1.0,
+            minor_radius: 0.25,
+            major_segments: 24,
+            minor_segments: 8,
+        });
+        assert_eq!(mesh.vertex_count(), 25 * 9);
+        assert_eq!(mesh.indices().len(), 24 * 8 * 6);
+        check_closed_manifold(&mesh);
+        check_normals(&mesh);
+    }
+
+    #[test]
+    fn grid() {
+        let mesh = build(GridMeshGenerator {
+            width: 2.0,
+            depth: 1.0,
+            x_segments: 4,
+            z_segments: 3,
+        });
+        assert_eq!(mesh.vertex_count(), 5 * 4);
+        assert_eq!(mesh.indices().len(), 4 * 3 * 6);
+        check_normals(&mesh);
+        assert_triangles_face_up(&mesh);
+    }
+
+    #[test]
+    fn computed_tangents() {
+        let mesh = Mesh::builder(UvSphereMeshGenerator::default())
+            .with_computed_tangents()
+            .build()
+            .unwrap();
+        assert!(mesh
+            .attribute_data()
+            .iter()
+            .any(|data| data.typed_data::<crate::types::Tangent>().is_some()));
+    }
+
+    fn build<T: MeshGenerator>(generator: T) -> Mesh {
+        Mesh::builder(generator).build().unwrap()
+    }
+
+    fn check_normals(mesh: &Mesh) {
+        let normals = attribute::<Normal>(mesh);
+        for normal in normals {
+            assert!((normal.length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    /// Checks that the mesh (with welded positions) is a closed
+    /// consistently oriented surface with faces pointing outwards.
+    fn check_closed_manifold(mesh: &Mesh) {
+        let positions = attribute::<Position>(mesh);
+
+        let mut welded = FastHashMap::<[i32; 3], u32>::default();
+        let ids = positions
+            .iter()
+            .map(|p| {
+                let key = (p.0 * 1e4).round().as_ivec3().to_array();
+                let next_id = welded.len() as u32;
+                *welded.entry(key).or_insert(next_id)
+            })
+            .collect::<Vec<_>>();
+
+        let mut edges = FastHashMap::<(u32, u32), u32>::default();
+        let mut volume = 0.0;
+        for triangle in mesh.indices().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| ids[triangle[i] as usize]);
+            assert!(a != b && b != c && c != a, "degenerate triangle");
+
+            for edge in [(a, b), (b, c), (c, a)] {
+                *edges.entry(edge).or_default() += 1;
+            }
+
+            let [pa, pb, pc] = [0, 1, 2].map(|i| positions[triangle[i] as usize].0);
+            volume += pa.dot(pb.cross(pc)) / 6.0;
+        }
+
+        for (&(a, b), &count) in &edges {
+            assert_eq!(
+                count, 1,
+                "edge is shared by triangles with the same winding"
+            );
+            assert!(edges.contains_key(&(b, a)), "open edge");
+        }
+        assert!(volume > 0.0, "faces point inwards");
+    }
+}
@@ -0,0 +1,18 @@
+use crate::types::{Mesh, Position, VertexAttribute};
+
+/// Returns the data of the first attribute of type `T` in the mesh.
+pub fn attribute<T: VertexAttribute>(mesh: &Mesh) -> &[T] {
+    mesh.attribute_data()
+        .iter()
+        .find_map(|data| data.typed_data::<T>())
+        .unwrap()
+}
+
+/// Checks that all triangles of the mesh face towards +Y.
+pub fn assert_triangles_face_up(mesh: &Mesh) {
+    let positions = attribute::<Position>(mesh);
+    for triangle in mesh.indices().chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize].0);
+        assert!((b - a).cross(c - a).y > 0.0);
+    }
+}
@@ -1,5 +1,8 @@
+use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
-use glam::{Vec2, Vec3, Vec4};
+use glam::{UVec4, Vec2, Vec3, Vec4};
+
+use super::vertex_encoding::{encode_attribute, VertexAttributeEncoding};
 
 pub trait VertexAttribute: std::fmt::Debug + Default + PartialEq + Pod + Send + Sync {
     const FORMAT: gfx::VertexFormat;
@@ -11,7 +14,9 @@ macro_rules! define_vertex_attributes {
         $(#[$kind_meta:meta])* kind: $kind:ident;
         $($(#[$ident_meta:meta])* $ident:ident($inner:ty) {
             format: $format:ident,
-            tag: $tag:literal$(,)?
+            tag: $tag:literal,
+            glsl: $glsl:literal,
+            glsl_type: $glsl_type:literal$(,)?
         })*
     ) => {
         $(#[$kind_meta])*
@@ -21,6 +26,26 @@ macro_rules! define_vertex_attributes {
             $($ident = $tag,)*
         }
 
+        impl $kind {
+            /// All attribute kinds in the order of declaration.
+            pub const ALL: &'static [Self] = &[$(Self::$ident,)*];
+            pub const COUNT: usize = Self::ALL.len();
+
+            /// Attribute name used in shader defines.
+            pub const fn glsl_name(self) -> &'static str {
+                match self {
+                    $(Self::$ident => $glsl,)*
+                }
+            }
+
+            /// Type of the decoded attribute in shaders.
+            pub const fn glsl_type(self) -> &'static str {
+                match self {
+                    $(Self::$ident => $glsl_type,)*
+                }
+            }
+        }
+
         $(
             $(#[$ident_meta])*
             #[derive(Debug, Default, Clone, Copy, PartialEq, Pod, Zeroable)]
@@ -91,31 +116,319 @@ define_vertex_attributes! {
     Position(Vec3) {
         format: Float32x3,
         tag: 0,
+        glsl: "POSITION",
+        glsl_type: "vec3",
     }
     /// A normal vector.
     Normal(Vec3) {
         format: Float32x3,
         tag: 1,
+        glsl: "NORMAL",
+        glsl_type: "vec3",
     }
     /// A tangent vector.
     Tangent(Vec3) {
         format: Float32x3,
         tag: 2,
+        glsl: "TANGENT",
+        glsl_type: "vec3",
     }
     /// A local UV coordinate.
     UV0(Vec2) {
         format: Float32x2,
         tag: 3,
+        glsl: "UV0",
+        glsl_type: "vec2",
     }
     /// RGBA color.
     Color(Vec4) {
         format: Float32x4,
         tag: 4,
+        glsl: "COLOR",
+        glsl_type: "vec4",
+    }
+    /// Indices of up to four joints influencing a vertex.
+    Joints(UVec4) {
+        format: Uint32x4,
+        tag: 5,
+        glsl: "JOINTS",
+        glsl_type: "uvec4",
+    }
+    /// Weights of the joints influencing a vertex.
+    Weights(Vec4) {
+        format: Float32x4,
+        tag: 6,
+        glsl: "WEIGHTS",
+        glsl_type: "vec4",
+    }
+    /// Position deltas of all morph targets (target-major).
+    MorphPositions(Vec3) {
+        format: Float32x3,
+        tag: 7,
+        glsl: "MORPH_POSITIONS",
+        glsl_type: "vec3",
+    }
+    /// Normal deltas of all morph targets (target-major).
+    MorphNormals(Vec3) {
+        format: Float32x3,
+        tag: 8,
+        glsl: "MORPH_NORMALS",
+        glsl_type: "vec3",
+    }
+    /// A secondary UV coordinate (e.g. for lightmaps).
+    UV1(Vec2) {
+        format: Float32x2,
+        tag: 9,
+        glsl: "UV1",
+        glsl_type: "vec2",
+    }
+    /// Secondary RGBA color.
+    Color1(Vec4) {
+        format: Float32x4,
+        tag: 10,
+        glsl: "COLOR1",
+        glsl_type: "vec4",
+    }
+    /// Application-defined per-vertex data (e.g. wind weights or baked AO).
+    ///
+    /// Custom slots hold `vec4` data unless a [`CustomVertexAttribute`] is registered in them.
+    Custom0(Vec4) {
+        format: Float32x4,
+        tag: 11,
+        glsl: "CUSTOM0",
+        glsl_type: "vec4",
+    }
+    /// Application-defined per-vertex data.
+    Custom1(Vec4) {
+        format: Float32x4,
+        tag: 12,
+        glsl: "CUSTOM1",
+        glsl_type: "vec4",
+    }
+    /// Application-defined per-vertex data.
+    Custom2(Vec4) {
+        format: Float32x4,
+        tag: 13,
+        glsl: "CUSTOM2",
+        glsl_type: "vec4",
+    }
+    /// Application-defined per-vertex data.
+    Custom3(Vec4) {
+        format: Float32x4,
+        tag: 14,
+        glsl: "CUSTOM3",
+        glsl_type: "vec4",
+    }
+}
+
+impl VertexAttributeKind {
+    /// Returns whether the attribute has exactly one value per vertex.
+    ///
+    /// Morph target deltas are stored for each target and are not
+    /// a part of the shader `Vertex` struct.
+    pub const fn is_per_vertex(self) -> bool {
+        !matches!(self, Self::MorphPositions | Self::MorphNormals)
+    }
+
+    /// Returns whether the kind is one of the slots for [`CustomVertexAttribute`].
+    pub const fn is_custom(self) -> bool {
+        matches!(
+            self,
+            Self::Custom0 | Self::Custom1 | Self::Custom2 | Self::Custom3
+        )
+    }
+}
+
+/// Application-defined attribute kind stored in one of the custom slots.
+///
+/// There are four slots, [`Custom0`] to [`Custom3`]. A registered attribute
+/// replaces the `vec4` field of its slot in the shader `Vertex` struct with
+/// a field of its own name and type. Shaders can check for it with
+/// `VERTEX_<NAME>`.
+///
+/// Mesh data is provided as any [`VertexAttribute`] whose `KIND` is the slot
+/// and whose `FORMAT` matches the registered one, see
+/// [`RendererBuilder::custom_vertex_attribute`](crate::RendererBuilder::custom_vertex_attribute).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomVertexAttribute {
+    /// One of the custom kinds.
+    pub slot: VertexAttributeKind,
+    /// Uppercase name used in shader defines, the field name is its lowercase form.
+    pub glsl_name: &'static str,
+    /// One to four `f32` components, or one or four `u32` components.
+    pub format: gfx::VertexFormat,
+}
+
+impl CustomVertexAttribute {
+    /// Describes the attribute `T` under the given shader name.
+    pub const fn of<T: VertexAttribute>(glsl_name: &'static str) -> Self {
+        Self {
+            slot: T::KIND,
+            glsl_name,
+            format: T::FORMAT,
+        }
+    }
+
+    /// Type of the attribute in shaders, `None` if the format is not supported.
+    pub const fn glsl_type(&self) -> Option<&'static str> {
+        match self.format {
+            gfx::VertexFormat::Float32 => Some("float"),
+            gfx::VertexFormat::Float32x2 => Some("vec2"),
+            gfx::VertexFormat::Float32x3 => Some("vec3"),
+            gfx::VertexFormat::Float32x4 => Some("vec4"),
+            gfx::VertexFormat::Uint32 => Some("uint"),
+            gfx::VertexFormat::Uint32x4 => Some("uvec4"),
+            _ => None,
+        }
+    }
+
+    fn validate(&self, registered: &[CustomVertexAttribute]) -> Result<()> {
+        let name = self.glsl_name;
+        anyhow::ensure!(
+            self.slot.is_custom(),
+            "custom vertex attribute {name} must use a custom slot, not {:?}",
+            self.slot
+        );
+        anyhow::ensure!(
+            name.starts_with(|c: char| c.is_ascii_uppercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+            "custom vertex attribute name {name:?} must be an uppercase identifier"
+        );
+        anyhow::ensure!(
+            VertexAttributeKind::ALL
+                .iter()
+                .all(|kind| kind.glsl_name() != name),
+            "custom vertex attribute name {name} is already used by a built-in attribute"
+        );
+        anyhow::ensure!(
+            self.glsl_type().is_some(),
+            "{:?} format is not supported for custom vertex attribute {name}",
+            self.format
+        );
+        for other in registered {
+            anyhow::ensure!(
+                other.slot != self.slot,
+                "{:?} is already used by custom vertex attribute {}",
+                self.slot,
+                other.glsl_name
+            );
+            anyhow::ensure!(
+                other.glsl_name != name,
+                "custom vertex attribute {name} is registered twice"
+            );
+        }
+        Ok(())
     }
 }
 
+/// Generates the shader `Vertex` struct and its `vertex_read` function
+/// for all attribute kinds and the custom attributes registered in their slots.
+///
+/// Each field is only present if the material defines its index in the
+/// offsets array (see `ShaderPreprocessorScope::define_vertex_attributes`).
+pub(crate) fn generate_vertex_glsl(custom_attributes: &[CustomVertexAttribute]) -> Result<String> {
+    use std::fmt::Write;
+
+    for (index, attribute) in custom_attributes.iter().enumerate() {
+        attribute.validate(&custom_attributes[..index])?;
+    }
+    let custom_attribute =
+        |kind: VertexAttributeKind| custom_attributes.iter().find(|item| item.slot == kind);
+
+    // Returns the field name and the type of the attribute
+    let field = |kind: VertexAttributeKind| match custom_attribute(kind) {
+        Some(attribute) => (
+            attribute.glsl_name.to_lowercase(),
+            attribute.glsl_type().unwrap(),
+        ),
+        None => (kind.glsl_name().to_lowercase(), kind.glsl_type()),
+    };
+
+    let kinds = VertexAttributeKind::ALL
+        .iter()
+        .copied()
+        .filter(|kind| kind.is_per_vertex());
+
+    let mut res = String::from(
+        "// NOTE: This file is generated from `VertexAttributeKind`.\n\
+         #ifndef UNIFORMS_VERTEX_GLSL\n\
+         #define UNIFORMS_VERTEX_GLSL\n\n",
+    );
+
+    for kind in VertexAttributeKind::ALL {
+        let name = kind.glsl_name();
+        _ = writeln!(
+            res,
+            "#ifndef VERTEX_{name}_ENCODINGS\n#define VERTEX_{name}_ENCODINGS 1u\n#endif"
+        );
+    }
+
+    for attribute in custom_attributes {
+        let slot = attribute.slot.glsl_name();
+        let name = attribute.glsl_name;
+        _ = writeln!(
+            res,
+            "#ifdef VERTEX_{slot}\n#define VERTEX_{name} VERTEX_{slot}\n#endif"
+        );
+    }
+
+    res.push_str("\nstruct Vertex {\n");
+    for kind in kinds.clone() {
+        let name = kind.glsl_name();
+        let (field, ty) = field(kind);
+        _ = writeln!(
+            res,
+            "    #ifdef VERTEX_{name}\n    {ty} {field};\n    #endif"
+        );
+    }
+    res.push_str("};\n\n");
+
+    res.push_str(
+        "Vertex vertex_read(uint buffer_index, uint[VERTEX_ATTR_COUNT] offsets) {\n    \
+         Vertex result;\n",
+    );
+    for kind in kinds {
+        let name = kind.glsl_name();
+        let (field, ty) = field(kind);
+        let read = match VertexAttributeEncoding::compressed(kind) {
+            VertexAttributeEncoding::Float => {
+                format!("vertex_data_read_{ty}(buffer_index, offsets[VERTEX_{name}])")
+            }
+            encoding => {
+                let reader = match encoding {
+                    VertexAttributeEncoding::Quantized => "vertex_read_position",
+                    VertexAttributeEncoding::Octahedral => "vertex_read_direction",
+                    VertexAttributeEncoding::Half => "vertex_read_uv",
+                    _ => "vertex_read_color",
+                };
+                format!("{reader}(buffer_index, offsets[VERTEX_{name}], VERTEX_{name}_ENCODINGS)")
+            }
+        };
+
+        _ = writeln!(res, "    #ifdef VERTEX_{name}");
+        if kind == VertexAttributeKind::Position {
+            // NOTE: Position is always present
+            _ = writeln!(res, "    result.{field} = {read};");
+        } else {
+            _ = writeln!(
+                res,
+                "    result.{field} = offsets[VERTEX_{name}] != VERTEX_ATTR_MISSING\n        \
+                 ? {read}\n        : {ty}(0);"
+            );
+        }
+        _ = writeln!(res, "    #endif");
+    }
+    res.push_str("    return result;\n}\n\n#endif // UNIFORMS_VERTEX_GLSL\n");
+
+    Ok(res)
+}
+
 pub struct VertexAttributeData {
     kind: VertexAttributeKind,
+    format: gfx::VertexFormat,
+    encoding: VertexAttributeEncoding,
     ptr: *mut u8,
     byte_len: usize,
     drop_fn: unsafe fn(*mut u8, usize),
@@ -135,16 +448,50 @@ impl VertexAttributeData {
 
         Self {
             kind: T::KIND,
+            format: T::FORMAT,
+            encoding: VertexAttributeEncoding::Float,
             ptr: ptr.cast(),
             byte_len: bytes,
             drop_fn: drop_vec::<T>,
         }
     }
 
+    /// Converts the data into the specified encoding.
+    ///
+    /// Fails if the data is already encoded or the encoding
+    /// is not supported for the attribute kind.
+    pub fn encode(self, encoding: VertexAttributeEncoding) -> Result<Self> {
+        if encoding == VertexAttributeEncoding::Float && self.encoding == encoding {
+            return Ok(self);
+        }
+
+        let mut words = std::mem::ManuallyDrop::new(encode_attribute(&self, encoding)?);
+        words.shrink_to_fit();
+        debug_assert!(words.len() == words.capacity());
+
+        Ok(Self {
+            kind: self.kind,
+            format: self.format,
+            encoding,
+            ptr: words.as_mut_ptr().cast(),
+            byte_len: words.len() * std::mem::size_of::<u32>(),
+            drop_fn: drop_vec::<u32>,
+        })
+    }
+
     pub fn kind(&self) -> VertexAttributeKind {
         self.kind
     }
 
+    /// Format of the data before encoding.
+    pub fn format(&self) -> gfx::VertexFormat {
+        self.format
+    }
+
+    pub fn encoding(&self) -> VertexAttributeEncoding {
+        self.encoding
+    }
+
     pub fn byte_len(&self) -> usize {
         self.byte_len
     }
@@ -154,8 +501,14 @@ impl VertexAttributeData {
         unsafe { std::slice::from_raw_parts(self.ptr, self.byte_len) }
     }
 
+    /// Returns the data as a slice of attributes.
+    ///
+    /// Returns `None` for encoded data.
     pub fn typed_data<T: VertexAttribute>(&self) -> Option<&[T]> {
-        if self.kind == T::KIND {
+        if self.kind == T::KIND
+            && self.format == T::FORMAT
+            && self.encoding == VertexAttributeEncoding::Float
+        {
             Some(bytemuck::cast_slice(self.untyped_data()))
         } else {
             None
@@ -163,7 +516,10 @@ impl VertexAttributeData {
     }
 
     pub fn typed_data_mut<T: VertexAttribute>(&mut self) -> Option<&mut [T]> {
-        if self.kind == T::KIND {
+        if self.kind == T::KIND
+            && self.format == T::FORMAT
+            && self.encoding == VertexAttributeEncoding::Float
+        {
             // SAFETY: `self.ptr` is a valid pointer to a slice of `self.byte_len` bytes.
             let data = unsafe { std::slice::from_raw_parts_mut(self.ptr, self.byte_len) };
             Some(bytemuck::cast_slice_mut(data))
@@ -190,7 +546,7 @@ impl Drop for VertexAttributeData {
 }
 
 // SAFETY: `VertexAttributeData` can only be constructed from `Vec<T>`
-// where `T: VertexAttribute`, where `VertexAttribute: Send + Sync`.
+// where `T: VertexAttribute` (which is `Send + Sync`) or from `Vec<u32>`.
 unsafe impl Send for VertexAttributeData {}
 unsafe impl Sync for VertexAttributeData {}
 
@@ -232,6 +588,69 @@ mod tests {
         assert_eq!(attribute.typed_data_mut::<UV0>(), None);
     }
 
+    #[test]
+    fn generated_vertex_glsl() {
+        let source = generate_vertex_glsl(&[]).unwrap();
+        assert!(source.contains("    #ifdef VERTEX_UV1\n    vec2 uv1;\n    #endif"));
+        assert!(source.contains("    #ifdef VERTEX_CUSTOM3\n    vec4 custom3;\n    #endif"));
+        assert!(source.contains(
+            "result.position = vertex_read_position(buffer_index, offsets[VERTEX_POSITION], \
+             VERTEX_POSITION_ENCODINGS);"
+        ));
+        assert!(source.contains("vertex_data_read_uvec4(buffer_index, offsets[VERTEX_JOINTS])"));
+        assert!(source.contains("#define VERTEX_COLOR1_ENCODINGS 1u"));
+        assert!(!source.contains("morph_positions;"));
+    }
+
+    #[test]
+    fn custom_vertex_attributes() {
+        #[derive(Debug, Default, Clone, Copy, PartialEq)]
+        #[repr(transparent)]
+        struct WindWeight(f32);
+
+        // SAFETY: `f32` is `Pod`.
+        unsafe impl Pod for WindWeight {}
+        unsafe impl Zeroable for WindWeight {}
+
+        impl VertexAttribute for WindWeight {
+            const FORMAT: gfx::VertexFormat = gfx::VertexFormat::Float32;
+            const KIND: VertexAttributeKind = VertexAttributeKind::Custom1;
+        }
+
+        let wind = CustomVertexAttribute::of::<WindWeight>("WIND_WEIGHT");
+        let source = generate_vertex_glsl(&[wind]).unwrap();
+        assert!(source.contains("    #ifdef VERTEX_CUSTOM1\n    float wind_weight;\n    #endif"));
+        assert!(source
+            .contains("#ifdef VERTEX_CUSTOM1\n#define VERTEX_WIND_WEIGHT VERTEX_CUSTOM1\n#endif"));
+        assert!(source.contains("vertex_data_read_float(buffer_index, offsets[VERTEX_CUSTOM1])"));
+        assert!(source.contains("    #ifdef VERTEX_CUSTOM0\n    vec4 custom0;\n    #endif"));
+
+        let data = VertexAttributeData::new(vec![WindWeight(0.5); 3]);
+        assert_eq!(data.kind(), VertexAttributeKind::Custom1);
+        assert_eq!(
+            data.typed_data::<WindWeight>(),
+            Some(&[WindWeight(0.5); 3][..])
+        );
+        assert_eq!(data.typed_data::<Custom1>(), None);
+
+        let other = CustomVertexAttribute {
+            glsl_name: "AO",
+            ..wind
+        };
+        assert!(generate_vertex_glsl(&[wind, other]).is_err());
+        let builtin = CustomVertexAttribute {
+            slot: VertexAttributeKind::Custom2,
+            glsl_name: "UV1",
+            ..wind
+        };
+        assert!(generate_vertex_glsl(&[builtin]).is_err());
+        let not_custom = CustomVertexAttribute {
+            slot: VertexAttributeKind::UV0,
+            ..wind
+        };
+        assert!(generate_vertex_glsl(&[not_custom]).is_err());
+    }
+
     #[test]
     fn from_vec_with_extra_capacity() {
         const POSITIONS: &[Position] = &[
@@ -0,0 +1,367 @@
+use anyhow::Result;
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::types::{VertexAttributeData, VertexAttributeKind};
+
+/// Storage format of vertex attribute data on the GPU.
+///
+/// All encodings except [`VertexAttributeEncoding::Float`] pack
+/// each vertex into 32-bit words.
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexAttributeEncoding {
+    /// Full `f32` (or `u32`) components.
+    #[default]
+    Float = 0,
+    /// Two half-float components in one word (UVs).
+    Half = 1,
+    /// Four unorm8 components in one word (colors).
+    Unorm8 = 2,
+    /// Octahedral-encoded unit vector as two snorm16 components (normals, tangents).
+    Octahedral = 3,
+    /// Three unorm16 components relative to the mesh bounds (positions).
+    ///
+    /// Data starts with a 32-byte header with the bounds origin and extent.
+    Quantized = 4,
+}
+
+impl VertexAttributeEncoding {
+    /// Number of bytes before per-vertex data.
+    pub const QUANTIZED_HEADER_SIZE: usize = 32;
+
+    /// Returns whether the attribute kind can be stored in this encoding.
+    pub fn supports(self, kind: VertexAttributeKind) -> bool {
+        match self {
+            Self::Float => true,
+            Self::Half => matches!(kind, VertexAttributeKind::UV0 | VertexAttributeKind::UV1),
+            Self::Unorm8 => {
+                matches!(
+                    kind,
+                    VertexAttributeKind::Color | VertexAttributeKind::Color1
+                )
+            }
+            Self::Octahedral => {
+                matches!(
+                    kind,
+                    VertexAttributeKind::Normal | VertexAttributeKind::Tangent
+                )
+            }
+            Self::Quantized => kind == VertexAttributeKind::Position,
+        }
+    }
+
+    /// The most compact encoding for the attribute kind.
+    pub fn compressed(kind: VertexAttributeKind) -> Self {
+        match kind {
+            VertexAttributeKind::Position => Self::Quantized,
+            VertexAttributeKind::Normal | VertexAttributeKind::Tangent => Self::Octahedral,
+            VertexAttributeKind::UV0 | VertexAttributeKind::UV1 => Self::Half,
+            VertexAttributeKind::Color | VertexAttributeKind::Color1 => Self::Unorm8,
+            _ => Self::Float,
+        }
+    }
+}
+
+/// Set of vertex attribute encodings supported by a material shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttributeEncodings {
+    masks: [u8; VertexAttributeKind::COUNT],
+}
+
+impl VertexAttributeEncodings {
+    /// Only [`VertexAttributeEncoding::Float`] for all attributes.
+    pub const FLOAT: Self = Self {
+        masks: [1 << VertexAttributeEncoding::Float as u8; VertexAttributeKind::COUNT],
+    };
+
+    /// Adds support of the encoding for the attribute.
+    pub const fn with(
+        mut self,
+        kind: VertexAttributeKind,
+        encoding: VertexAttributeEncoding,
+    ) -> Self {
+        self.masks[kind as usize] |= 1 << encoding as u8;
+        self
+    }
+
+    /// Adds support of the compressed encoding for the specified attributes.
+    pub fn with_compressed(mut self, kinds: &[VertexAttributeKind]) -> Self {
+        for &kind in kinds {
+            self = self.with(kind, VertexAttributeEncoding::compressed(kind));
+        }
+        self
+    }
+
+    pub fn contains(&self, kind: VertexAttributeKind, encoding: VertexAttributeEncoding) -> bool {
+        self.masks[kind as usize] & (1 << encoding as u8) != 0
+    }
+
+    /// Defines `VERTEX_<KIND>_ENCODINGS` masks which select the
+    /// decoding branches of the shader vertex reader.
+    pub(crate) fn shader_defines(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        VertexAttributeKind::ALL.iter().map(|kind| {
+            (
+                format!("VERTEX_{}_ENCODINGS", kind.glsl_name()),
+                format!("{}u", self.masks[*kind as usize]),
+            )
+        })
+    }
+}
+
+impl Default for VertexAttributeEncodings {
+    fn default() -> Self {
+        Self::FLOAT
+    }
+}
+
+pub(crate) fn encode_attribute(
+    data: &VertexAttributeData,
+    encoding: VertexAttributeEncoding,
+) -> Result<Vec<u32>> {
+    anyhow::ensure!(
+        data.encoding() == VertexAttributeEncoding::Float,
+        "vertex attribute is already encoded"
+    );
+    anyhow::ensure!(
+        encoding.supports(data.kind()),
+        "{encoding:?} encoding is not supported for {:?}",
+        data.kind()
+    );
+
+    // NOTE: Supported kinds of each encoding share the same layout
+    let data = data.untyped_data();
+    Ok(match encoding {
+        VertexAttributeEncoding::Float => bytemuck::cast_slice(data).to_vec(),
+        VertexAttributeEncoding::Half => bytemuck::cast_slice::<u8, Vec2>(data)
+            .iter()
+            .map(|&uv| pack_half2x16(uv))
+            .collect(),
+        VertexAttributeEncoding::Unorm8 => bytemuck::cast_slice::<u8, Vec4>(data)
+            .iter()
+            .map(|&color| pack_unorm4x8(color))
+            .collect(),
+        VertexAttributeEncoding::Octahedral => bytemuck::cast_slice::<u8, Vec3>(data)
+            .iter()
+            .map(|&direction| pack_snorm2x16(octahedral_encode(direction)))
+            .collect(),
+        VertexAttributeEncoding::Quantized => quantize_positions(bytemuck::cast_slice(data)),
+    })
+}
+
+fn quantize_positions(positions: &[Vec3]) -> Vec<u32> {
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), position| (min.min(*position), max.max(*position)),
+    );
+    let (origin, extent) = if positions.is_empty() {
+        (Vec3::ZERO, Vec3::ZERO)
+    } else {
+        (min, max - min)
+    };
+    let scale = Vec3::select(extent.cmpgt(Vec3::ZERO), extent.recip(), Vec3::ZERO);
+
+    let mut result = Vec::with_capacity(8 + positions.len() * 2);
+    result.extend(origin.extend(0.0).to_array().map(f32::to_bits));
+    result.extend(extent.extend(0.0).to_array().map(f32::to_bits));
+    for position in positions {
+        let t = ((*position - origin) * scale).clamp(Vec3::ZERO, Vec3::ONE);
+        let t = (t * u16::MAX as f32).round();
+        result.push(t.x as u32 | (t.y as u32) << 16);
+        result.push(t.z as u32);
+    }
+    result
+}
+
+/// Maps a unit vector onto the `[-1, 1]` square.
+fn octahedral_encode(v: Vec3) -> Vec2 {
+    let sum = v.x.abs() + v.y.abs() + v.z.abs();
+    if sum == 0.0 {
+        return Vec2::ZERO;
+    }
+    let v = v / sum;
+    if v.z >= 0.0 {
+        v.truncate()
+    } else {
+        let sign = Vec2::select(v.truncate().cmpge(Vec2::ZERO), Vec2::ONE, -Vec2::ONE);
+        (Vec2::ONE - Vec2::new(v.y, v.x).abs()) * sign
+    }
+}
+
+fn pack_snorm2x16(v: Vec2) -> u32 {
+    let pack = |x: f32| (x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16 as u16 as u32;
+    pack(v.x) | pack(v.y) << 16
+}
+
+fn pack_unorm4x8(v: Vec4) -> u32 {
+    let [x, y, z, w] = v
+        .clamp(Vec4::ZERO, Vec4::ONE)
+        .to_array()
+        .map(|x| (x * u8::MAX as f32).round() as u32);
+    x | y << 8 | z << 16 | w << 24
+}
+
+fn pack_half2x16(v: Vec2) -> u32 {
+    f32_to_f16_bits(v.x) as u32 | (f32_to_f16_bits(v.y) as u32) << 16
+}
+
+/// Converts `f32` to IEEE 754 half-precision bits (round to nearest even).
+fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        // Overflow to infinity
+        return sign | 0x7c00;
+    }
+
+    if half_exponent <= 0 {
+        // Subnormal or zero
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half_mantissa = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round = (rest > halfway || (rest == halfway && half_mantissa & 1 == 1)) as u32;
+        return sign | (half_mantissa + round) as u16;
+    }
+
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1fff;
+    let round = (rest > 0x1000 || (rest == 0x1000 && half & 1 == 1)) as u32;
+    // NOTE: Rounding can carry into the exponent, which is still correct
+    sign | (half + round) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Color, Position, UV0};
+
+    fn f16_bits_to_f32(bits: u16) -> f32 {
+        let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = ((bits >> 10) & 0x1f) as i32;
+        let mantissa = (bits & 0x3ff) as f32;
+        sign * match exponent {
+            0 => mantissa * 2f32.powi(-24),
+            0x1f => f32::INFINITY,
+            _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    fn octahedral_decode(e: Vec2) -> Vec3 {
+        let mut v = Vec3::new(e.x, e.y, 1.0 - e.x.abs() - e.y.abs());
+        if v.z < 0.0 {
+            let sign = Vec2::select(e.cmpge(Vec2::ZERO), Vec2::ONE, -Vec2::ONE);
+            let xy = (Vec2::ONE - Vec2::new(v.y, v.x).abs()) * sign;
+            v.x = xy.x;
+            v.y = xy.y;
+        }
+        v.normalize()
+    }
+
+    fn unpack_snorm2x16(value: u32) -> Vec2 {
+        let unpack = |x: u32| (x as u16 as i16 as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+        Vec2::new(unpack(value & 0xffff), unpack(value >> 16))
+    }
+
+    #[test]
+    fn half_floats() {
+        for value in [0.0, 1.0, -2.5, 0.333, 1e-5, 65504.0, 6.1035156e-5] {
+            let decoded = f16_bits_to_f32(f32_to_f16_bits(value));
+            assert!(
+                (decoded - value).abs() <= value.abs() * 1e-3 + 1e-7,
+                "{value} -> {decoded}"
+            );
+        }
+        assert_eq!(f32_to_f16_bits(1.0), 0x3c00);
+        assert_eq!(f32_to_f16_bits(-2.0), 0xc000);
+        assert_eq!(f32_to_f16_bits(1e6), 0x7c00);
+    }
+
+    #[test]
+    fn octahedral_normals() {
+        let directions = [
+            Vec3::X,
+            -Vec3::Y,
+            Vec3::Z,
+            -Vec3::Z,
+            Vec3::new(1.0, -2.0, 3.0).normalize(),
+            Vec3::new(-0.3, 0.4, -0.8).normalize(),
+        ];
+        for direction in directions {
+            let decoded = octahedral_decode(unpack_snorm2x16(pack_snorm2x16(octahedral_encode(
+                direction,
+            ))));
+            assert!(
+                decoded.abs_diff_eq(direction, 1e-4),
+                "{direction} -> {decoded}"
+            );
+        }
+    }
+
+    #[test]
+    fn encoded_attributes() {
+        let positions = VertexAttributeData::new(vec![
+            Position(Vec3::new(-1.0, 0.0, 2.0)),
+            Position(Vec3::new(3.0, 0.0, 4.0)),
+            Position(Vec3::new(1.0, 0.0, 3.0)),
+        ]);
+        let encoded = positions
+            .encode(VertexAttributeEncoding::Quantized)
+            .unwrap();
+        assert_eq!(encoded.encoding(), VertexAttributeEncoding::Quantized);
+        assert_eq!(encoded.typed_data::<Position>(), None);
+        assert_eq!(
+            encoded.byte_len(),
+            VertexAttributeEncoding::QUANTIZED_HEADER_SIZE + 3 * 8
+        );
+
+        let words = bytemuck::cast_slice::<u8, u32>(encoded.untyped_data());
+        assert_eq!(f32::from_bits(words[0]), -1.0);
+        assert_eq!(f32::from_bits(words[4]), 4.0);
+        assert_eq!(f32::from_bits(words[5]), 0.0);
+        assert_eq!(words[8], 0);
+        assert_eq!(words[10], 0xffff);
+        assert_eq!(words[11], 0xffff);
+        assert_eq!(words[12], 0x8000);
+
+        let colors = VertexAttributeData::new(vec![Color(Vec4::new(1.0, 0.0, 0.5, 2.0))]);
+        let encoded = colors.encode(VertexAttributeEncoding::Unorm8).unwrap();
+        assert_eq!(encoded.untyped_data(), [255, 0, 128, 255]);
+
+        let uv = VertexAttributeData::new(vec![UV0(Vec2::new(1.0, 0.0))]);
+        assert!(uv.encode(VertexAttributeEncoding::Octahedral).is_err());
+    }
+
+    #[test]
+    fn material_encodings() {
+        let encodings = VertexAttributeEncodings::FLOAT
+            .with_compressed(&[VertexAttributeKind::Normal, VertexAttributeKind::UV0]);
+        assert!(encodings.contains(VertexAttributeKind::Normal, VertexAttributeEncoding::Float));
+        assert!(encodings.contains(
+            VertexAttributeKind::Normal,
+            VertexAttributeEncoding::Octahedral
+        ));
+        assert!(encodings.contains(VertexAttributeKind::UV0, VertexAttributeEncoding::Half));
+        assert!(!encodings.contains(
+            VertexAttributeKind::Position,
+            VertexAttributeEncoding::Quantized
+        ));
+
+        let defines = encodings.shader_defines().collect::<Vec<_>>();
+        assert!(defines.contains(&("VERTEX_NORMAL_ENCODINGS".to_owned(), "9u".to_owned())));
+        assert!(defines.contains(&("VERTEX_POSITION_ENCODINGS".to_owned(), "1u".to_owned())));
+    }
+}
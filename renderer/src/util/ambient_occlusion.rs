@@ -0,0 +1,336 @@
+use anyhow::Result;
+use glam::UVec2;
+
+use crate::types::AmbientOcclusionSettings;
+use crate::util::{BindlessResources, FrameResources, SampledImageHandle, ShaderPreprocessor};
+
+/// Screen space ambient occlusion.
+///
+/// Occlusion is estimated from view space positions reconstructed from the
+/// depth attachment, then smoothed with a depth-aware blur. The result is
+/// sampled by materials in the next frame, reprojected with their motion.
+pub struct AmbientOcclusion {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    blur_descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: gfx::ComputePipeline,
+    blur_pipeline: gfx::ComputePipeline,
+    point_sampler: gfx::Sampler,
+    target: Option<AmbientOcclusionTarget>,
+}
+
+struct AmbientOcclusionTarget {
+    depth: gfx::ImageView,
+    raw: gfx::ImageView,
+    output: gfx::ImageView,
+    output_handle: SampledImageHandle,
+    descriptor_set: gfx::DescriptorSet,
+    blur_descriptor_set: gfx::DescriptorSet,
+    extent: UVec2,
+    initialized: bool,
+    output_valid: bool,
+}
+
+impl AmbientOcclusion {
+    #[tracing::instrument(level = "debug", name = "create_ambient_occlusion", skip_all)]
+    pub fn new(
+        device: &gfx::Device,
+        shader_preprocessor: &ShaderPreprocessor,
+        frame_resources: &FrameResources,
+    ) -> Result<Self> {
+        let shaders = shader_preprocessor.begin();
+        let shader = shaders.make_compute_shader(device, "/ambient_occlusion.comp", "main")?;
+        let blur_shader =
+            shaders.make_compute_shader(device, "/ambient_occlusion_blur.comp", "main")?;
+
+        let make_layout = || {
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageImage,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })
+        };
+        let descriptor_set_layout = make_layout()?;
+        let blur_descriptor_set_layout = make_layout()?;
+
+        let push_constants = vec![gfx::PushConstant {
+            stages: gfx::ShaderStageFlags::COMPUTE,
+            offset: 0,
+            size: std::mem::size_of::<AmbientOcclusionPushConstants>() as u32,
+        }];
+
+        // NOTE: Positions are reconstructed with the camera matrices from the globals.
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![
+                frame_resources.descriptor_set_layout().clone(),
+                descriptor_set_layout.clone(),
+            ],
+            push_constants: push_constants.clone(),
+        })?;
+        let pipeline =
+            device.create_compute_pipeline(gfx::ComputePipelineInfo { shader, layout })?;
+
+        let blur_layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![blur_descriptor_set_layout.clone()],
+            push_constants,
+        })?;
+        let blur_pipeline = device.create_compute_pipeline(gfx::ComputePipelineInfo {
+            shader: blur_shader,
+            layout: blur_layout,
+        })?;
+
+        let point_sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            blur_descriptor_set_layout,
+            pipeline,
+            blur_pipeline,
+            point_sampler,
+            target: None,
+        })
+    }
+
+    /// Recreates the intermediate images if the depth attachment has changed (e.g. on resize).
+    ///
+    /// The occlusion of the previous frame is discarded in that case.
+    pub fn prepare(
+        &mut self,
+        device: &gfx::Device,
+        bindless_resources: &BindlessResources,
+        depth: &gfx::ImageView,
+    ) -> Result<()> {
+        if matches!(&self.target, Some(target) if &target.depth == depth) {
+            return Ok(());
+        }
+        self.release(bindless_resources);
+
+        let depth_info = depth.info().image.info();
+        let extent = UVec2::from(depth_info.extent);
+        let make_image = || {
+            let image = device.create_image(gfx::ImageInfo {
+                extent: depth_info.extent,
+                // NOTE: The second channel stores the clip space `w` to reject
+                // disoccluded pixels when reprojecting.
+                format: gfx::Format::RG32Sfloat,
+                mip_levels: 1,
+                samples: gfx::Samples::_1,
+                array_layers: 1,
+                flags: Default::default(),
+                usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::STORAGE,
+            })?;
+            device.create_image_view(gfx::ImageViewInfo::new(image))
+        };
+        let raw = make_image()?;
+        let output = make_image()?;
+
+        let make_descriptor_set = |layout: &gfx::DescriptorSetLayout,
+                                   source: &gfx::ImageView,
+                                   target: &gfx::ImageView|
+         -> Result<gfx::DescriptorSet> {
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: layout.clone(),
+            })?;
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &[
+                    gfx::DescriptorSetWrite {
+                        binding: 0,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                            gfx::CombinedImageSampler {
+                                view: source.clone(),
+                                layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                                sampler: self.point_sampler.clone(),
+                            },
+                        ]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 1,
+                        element: 0,
+                        data: gfx::DescriptorSlice::StorageImage(&[(
+                            target.clone(),
+                            gfx::ImageLayout::General,
+                        )]),
+                    },
+                ],
+            }]);
+            Ok(descriptor_set)
+        };
+        let descriptor_set = make_descriptor_set(&self.descriptor_set_layout, depth, &raw)?;
+        let blur_descriptor_set =
+            make_descriptor_set(&self.blur_descriptor_set_layout, &raw, &output)?;
+
+        let output_handle =
+            bindless_resources.alloc_image(device, output.clone(), self.point_sampler.clone());
+
+        self.target = Some(AmbientOcclusionTarget {
+            depth: depth.clone(),
+            raw,
+            output,
+            output_handle,
+            descriptor_set,
+            blur_descriptor_set,
+            extent,
+            initialized: false,
+            output_valid: false,
+        });
+        Ok(())
+    }
+
+    /// Bindless handle of the occlusion computed in the previous frame, if it is still valid.
+    pub fn history(&self) -> Option<SampledImageHandle> {
+        self.target
+            .as_ref()
+            .filter(|target| target.output_valid)
+            .map(|target| target.output_handle)
+    }
+
+    /// Frees the intermediate images and the bindless handle of the output.
+    pub fn release(&mut self, bindless_resources: &BindlessResources) {
+        if let Some(target) = self.target.take() {
+            bindless_resources.free_image(target.output_handle);
+        }
+    }
+
+    /// Discards the computed occlusion, e.g. when ambient occlusion is disabled.
+    pub fn reset(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.output_valid = false;
+        }
+    }
+
+    /// Computes the occlusion of the current frame.
+    ///
+    /// The depth attachment must be in the `ShaderReadOnlyOptimal` layout,
+    /// the output is left in the same layout for the fragment shaders of the next frame.
+    pub fn execute(
+        &mut self,
+        encoder: &mut gfx::Encoder,
+        frame_resources: &FrameResources,
+        globals_offset: u32,
+        settings: &AmbientOcclusionSettings,
+    ) {
+        let target = self
+            .target
+            .as_mut()
+            .expect("ambient occlusion must be prepared");
+
+        let raw_image = &target.raw.info().image;
+        let output_image = &target.output.info().image;
+        let raw_range = gfx::ImageSubresourceRange::whole(raw_image.info());
+        let output_range = gfx::ImageSubresourceRange::whole(output_image.info());
+        let previous_layout = target
+            .initialized
+            .then_some(gfx::ImageLayout::ShaderReadOnlyOptimal);
+
+        let push_constants = AmbientOcclusionPushConstants {
+            extent: target.extent,
+            radius: settings.radius.max(0.0),
+            intensity: settings.intensity.max(0.0),
+            sample_count: settings.quality.sample_count(),
+        };
+        let (group_count_x, group_count_y) =
+            (target.extent.x.div_ceil(8), target.extent.y.div_ceil(8));
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: raw_image,
+                src_access: gfx::AccessFlags::SHADER_READ,
+                dst_access: gfx::AccessFlags::SHADER_WRITE,
+                old_layout: previous_layout,
+                new_layout: gfx::ImageLayout::General,
+                family_transfer: None,
+                subresource_range: raw_range,
+            }],
+        );
+
+        let layout = &self.pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.pipeline);
+        encoder.bind_compute_descriptor_sets(
+            layout,
+            0,
+            &[frame_resources.descriptor_set(), &target.descriptor_set],
+            &[globals_offset],
+        );
+        encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+        encoder.dispatch(group_count_x, group_count_y, 1);
+
+        // NOTE: The output was sampled by the main pass of this frame.
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[
+                gfx::ImageMemoryBarrier {
+                    image: raw_image,
+                    src_access: gfx::AccessFlags::SHADER_WRITE,
+                    dst_access: gfx::AccessFlags::SHADER_READ,
+                    old_layout: Some(gfx::ImageLayout::General),
+                    new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                    family_transfer: None,
+                    subresource_range: raw_range,
+                },
+                gfx::ImageMemoryBarrier {
+                    image: output_image,
+                    src_access: gfx::AccessFlags::SHADER_READ,
+                    dst_access: gfx::AccessFlags::SHADER_WRITE,
+                    old_layout: previous_layout,
+                    new_layout: gfx::ImageLayout::General,
+                    family_transfer: None,
+                    subresource_range: output_range,
+                },
+            ],
+        );
+
+        let layout = &self.blur_pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.blur_pipeline);
+        encoder.bind_compute_descriptor_sets(layout, 0, &[&target.blur_descriptor_set], &[]);
+        encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+        encoder.dispatch(group_count_x, group_count_y, 1);
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: output_image,
+                src_access: gfx::AccessFlags::SHADER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::General),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: output_range,
+            }],
+        );
+
+        target.initialized = true;
+        target.output_valid = true;
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct AmbientOcclusionPushConstants {
+    extent: UVec2,
+    radius: f32,
+    intensity: f32,
+    sample_count: u32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for AmbientOcclusionPushConstants {}
+unsafe impl bytemuck::Zeroable for AmbientOcclusionPushConstants {}
@@ -0,0 +1,276 @@
+use anyhow::Result;
+use glam::UVec2;
+
+use crate::types::Exposure;
+use crate::util::{ShaderPreprocessor, SourceCache};
+
+/// Computes the scene exposure on the GPU.
+///
+/// A luminance histogram of the HDR target is built every frame and its average
+/// is used to smoothly adapt the exposure. The result is stored in EV100 in
+/// a single-float storage buffer, see [`AutoExposure::exposure_buffer`].
+pub struct AutoExposure {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    histogram_pipeline: gfx::ComputePipeline,
+    average_pipeline: gfx::ComputePipeline,
+    sampler: gfx::Sampler,
+    histogram: gfx::Buffer,
+    exposure: gfx::Buffer,
+    initialized: bool,
+    sources: SourceCache<gfx::DescriptorSet>,
+}
+
+impl AutoExposure {
+    #[tracing::instrument(level = "debug", name = "create_auto_exposure", skip_all)]
+    pub fn new(device: &gfx::Device, shader_preprocessor: &ShaderPreprocessor) -> Result<Self> {
+        let histogram_shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/luminance_histogram.comp",
+            "main",
+        )?;
+        let average_shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/auto_exposure.comp",
+            "main",
+        )?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 2,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: std::mem::size_of::<ExposurePushConstants>() as u32,
+            }],
+        })?;
+
+        let histogram_pipeline = device.create_compute_pipeline(gfx::ComputePipelineInfo {
+            shader: histogram_shader,
+            layout: layout.clone(),
+        })?;
+        let average_pipeline = device.create_compute_pipeline(gfx::ComputePipelineInfo {
+            shader: average_shader,
+            layout,
+        })?;
+
+        let sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+
+        let histogram = device.create_buffer(gfx::BufferInfo {
+            align_mask: 0b11,
+            size: HISTOGRAM_BINS * 4,
+            usage: gfx::BufferUsage::STORAGE | gfx::BufferUsage::TRANSFER_DST,
+        })?;
+        let exposure = device.create_buffer(gfx::BufferInfo {
+            align_mask: 0b11,
+            size: 4,
+            usage: gfx::BufferUsage::STORAGE,
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            histogram_pipeline,
+            average_pipeline,
+            sampler,
+            histogram,
+            exposure,
+            initialized: false,
+            sources: SourceCache::new(),
+        })
+    }
+
+    /// Buffer with a single float of the current exposure in EV100.
+    pub fn exposure_buffer(&self) -> &gfx::Buffer {
+        &self.exposure
+    }
+
+    /// Updates the exposure buffer.
+    ///
+    /// The HDR target must be in the `ShaderReadOnlyOptimal` layout.
+    pub fn execute(
+        &mut self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        hdr_target: &gfx::ImageView,
+        exposure: &Exposure,
+        delta_time: f32,
+    ) -> Result<()> {
+        let descriptor_set = self.get_or_init_descriptor_set(device, hdr_target)?.clone();
+
+        if !self.initialized {
+            encoder.update_buffer(&self.histogram, 0, &[0u32; HISTOGRAM_BINS]);
+            encoder.memory_barrier(
+                gfx::PipelineStageFlags::TRANSFER,
+                gfx::AccessFlags::TRANSFER_WRITE,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::AccessFlags::SHADER_READ | gfx::AccessFlags::SHADER_WRITE,
+            );
+        }
+
+        let extent = UVec2::from(hdr_target.info().image.info().extent);
+        let pixel_count = extent.x * extent.y;
+
+        let push_constants = match *exposure {
+            Exposure::Manual { ev100 } => ExposurePushConstants {
+                min_ev100: ev100,
+                max_ev100: ev100,
+                manual: 1,
+                ..ExposurePushConstants::new(pixel_count, delta_time, self.initialized)
+            },
+            Exposure::Auto {
+                min_ev100,
+                max_ev100,
+                compensation,
+                adaptation_speed,
+            } => ExposurePushConstants {
+                min_ev100,
+                max_ev100: max_ev100.max(min_ev100 + 1.0),
+                compensation,
+                adaptation_speed,
+                ..ExposurePushConstants::new(pixel_count, delta_time, self.initialized)
+            },
+        };
+        self.initialized = true;
+
+        let layout = &self.average_pipeline.info().layout;
+
+        // NOTE: Previous frame might still use the exposure.
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::AccessFlags::SHADER_READ,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::SHADER_WRITE,
+        );
+
+        if push_constants.manual == 0 {
+            encoder.bind_compute_pipeline(&self.histogram_pipeline);
+            encoder.bind_compute_descriptor_sets(layout, 0, &[&descriptor_set], &[]);
+            encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+            encoder.dispatch(extent.x.div_ceil(16), extent.y.div_ceil(16), 1);
+
+            encoder.memory_barrier(
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::AccessFlags::SHADER_WRITE,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::AccessFlags::SHADER_READ | gfx::AccessFlags::SHADER_WRITE,
+            );
+        }
+
+        encoder.bind_compute_pipeline(&self.average_pipeline);
+        encoder.bind_compute_descriptor_sets(layout, 0, &[&descriptor_set], &[]);
+        encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+        encoder.dispatch(1, 1, 1);
+
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::SHADER_WRITE,
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::AccessFlags::SHADER_READ | gfx::AccessFlags::SHADER_WRITE,
+        );
+
+        Ok(())
+    }
+
+    fn get_or_init_descriptor_set(
+        &mut self,
+        device: &gfx::Device,
+        hdr_target: &gfx::ImageView,
+    ) -> Result<&gfx::DescriptorSet> {
+        self.sources.get_or_try_insert_with(hdr_target, || {
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: self.descriptor_set_layout.clone(),
+            })?;
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &[
+                    gfx::DescriptorSetWrite {
+                        binding: 0,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                            gfx::CombinedImageSampler {
+                                view: hdr_target.clone(),
+                                layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                                sampler: self.sampler.clone(),
+                            },
+                        ]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 1,
+                        element: 0,
+                        data: gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(
+                            self.histogram.clone(),
+                        )]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 2,
+                        element: 0,
+                        data: gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(
+                            self.exposure.clone(),
+                        )]),
+                    },
+                ],
+            }]);
+            Ok(descriptor_set)
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ExposurePushConstants {
+    min_ev100: f32,
+    max_ev100: f32,
+    compensation: f32,
+    adaptation_speed: f32,
+    delta_time: f32,
+    pixel_count: u32,
+    manual: u32,
+    reset: u32,
+}
+
+impl ExposurePushConstants {
+    fn new(pixel_count: u32, delta_time: f32, initialized: bool) -> Self {
+        Self {
+            min_ev100: 0.0,
+            max_ev100: 0.0,
+            compensation: 0.0,
+            adaptation_speed: 0.0,
+            delta_time,
+            pixel_count,
+            manual: 0,
+            reset: !initialized as u32,
+        }
+    }
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for ExposurePushConstants {}
+unsafe impl bytemuck::Zeroable for ExposurePushConstants {}
+
+const HISTOGRAM_BINS: usize = 256;
@@ -68,13 +68,22 @@ impl BindlessResources {
         &self.descriptor_set
     }
 
+    /// Returns the number of allocated slots of each kind
+    /// (including retired ones which are not reusable yet).
+    pub fn slots_in_use(&self) -> BindlessSlotUsage {
+        BindlessSlotUsage {
+            images: self.image_allocator.in_use(),
+            uniform_buffers: self.uniform_buffer_allocator.in_use(),
+            storage_buffers: self.storage_buffer_allocator.in_use(),
+        }
+    }
+
     pub fn flush_retired(&self) {
         self.image_allocator.flush_retired();
         self.uniform_buffer_allocator.flush_retired();
         self.storage_buffer_allocator.flush_retired();
     }
 
-    #[allow(dead_code)]
     pub fn alloc_image(
         &self,
         device: &gfx::Device,
@@ -99,7 +108,6 @@ impl BindlessResources {
         handle
     }
 
-    #[allow(dead_code)]
     pub fn free_image(&self, handle: SampledImageHandle) {
         self.image_allocator.dealloc(handle);
     }
@@ -216,6 +224,12 @@ impl<const KIND: u8> GpuResourceHandleAllocator<KIND> {
             .extend_from_slice(bytemuck::cast_slice(handles));
     }
 
+    fn in_use(&self) -> u32 {
+        let next_index = self.next_index.load(Ordering::Relaxed);
+        let free = self.unused_handles.lock().unwrap().free_list.len() as u32;
+        next_index - free
+    }
+
     fn flush_retired(&self) {
         fn flush_retired_impl(unused_handles: &Mutex<UnusedHandles>) {
             let mut handles = unused_handles.lock().unwrap();
@@ -227,6 +241,14 @@ impl<const KIND: u8> GpuResourceHandleAllocator<KIND> {
     }
 }
 
+/// Number of bindless descriptor slots in use for each resource kind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BindlessSlotUsage {
+    pub images: u32,
+    pub uniform_buffers: u32,
+    pub storage_buffers: u32,
+}
+
 #[derive(Default)]
 struct UnusedHandles {
     free_list: Vec<u32>,
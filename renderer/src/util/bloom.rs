@@ -0,0 +1,424 @@
+use anyhow::Result;
+use glam::{UVec2, Vec4};
+
+use crate::types::BloomSettings;
+use crate::util::{ShaderPreprocessor, SourceCache};
+
+/// Bloom built from a mip chain of the HDR target.
+///
+/// The target is progressively downsampled into a half resolution image,
+/// where the first step keeps only the bright parts of the exposed scene.
+/// Mips are then upsampled and accumulated back into the first one,
+/// so each level contributes a wider part of the glow.
+pub struct Bloom {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    downsample_pipeline: gfx::ComputePipeline,
+    upsample_pipeline: gfx::ComputePipeline,
+    sampler: gfx::Sampler,
+    target: Option<BloomTarget>,
+}
+
+struct BloomTarget {
+    /// Downsample sets of the first mip, by HDR target.
+    prefilter: SourceCache<gfx::DescriptorSet>,
+    image: gfx::Image,
+    view: gfx::ImageView,
+    initialized: bool,
+    mips: Vec<BloomMip>,
+}
+
+struct BloomMip {
+    /// Reads the previous mip (or the HDR target) and writes this one.
+    downsample: gfx::DescriptorSet,
+    /// Reads the next mip and accumulates into this one.
+    upsample: Option<gfx::DescriptorSet>,
+    extent: UVec2,
+}
+
+impl Bloom {
+    #[tracing::instrument(level = "debug", name = "create_bloom", skip_all)]
+    pub fn new(device: &gfx::Device, shader_preprocessor: &ShaderPreprocessor) -> Result<Self> {
+        let downsample_shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/bloom_downsample.comp",
+            "main",
+        )?;
+        let upsample_shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/bloom_upsample.comp",
+            "main",
+        )?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageImage,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 2,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: std::mem::size_of::<BloomPushConstants>() as u32,
+            }],
+        })?;
+
+        let downsample_pipeline = device.create_compute_pipeline(gfx::ComputePipelineInfo {
+            shader: downsample_shader,
+            layout: layout.clone(),
+        })?;
+        let upsample_pipeline = device.create_compute_pipeline(gfx::ComputePipelineInfo {
+            shader: upsample_shader,
+            layout,
+        })?;
+
+        let sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            downsample_pipeline,
+            upsample_pipeline,
+            sampler,
+            target: None,
+        })
+    }
+
+    /// Recreates the mip chain if the HDR target has been resized.
+    ///
+    /// `exposure_buffer` is used to apply the threshold to the exposed scene.
+    pub fn prepare(
+        &mut self,
+        device: &gfx::Device,
+        hdr_target: &gfx::ImageView,
+        exposure_buffer: &gfx::Buffer,
+    ) -> Result<()> {
+        let extent = (UVec2::from(hdr_target.info().image.info().extent) / 2).max(UVec2::ONE);
+        if !matches!(&self.target, Some(target) if target.mips[0].extent == extent) {
+            self.target = None;
+            self.target = Some(self.create_target(device, hdr_target, exposure_buffer, extent)?);
+        }
+
+        // NOTE: The HDR target may alternate between frames (e.g. with TAA)
+        let target = self.target.as_mut().unwrap();
+        let first_mip = &target.view;
+        target.mips[0].downsample = target
+            .prefilter
+            .get_or_try_insert_with(hdr_target, || {
+                create_descriptor_set(
+                    device,
+                    &self.descriptor_set_layout,
+                    gfx::CombinedImageSampler {
+                        view: hdr_target.clone(),
+                        layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                        sampler: self.sampler.clone(),
+                    },
+                    first_mip,
+                    exposure_buffer,
+                )
+            })?
+            .clone();
+        Ok(())
+    }
+
+    /// View of the accumulated bloom in the `General` layout.
+    pub fn view(&self) -> &gfx::ImageView {
+        &self.target().view
+    }
+
+    /// Number of mips accumulated into [`Bloom::view`].
+    pub fn mip_levels(&self) -> u32 {
+        self.target().mips.len() as u32
+    }
+
+    /// Builds the bloom for the current frame. Does nothing if bloom is disabled.
+    ///
+    /// The HDR target must be in the `ShaderReadOnlyOptimal` layout.
+    pub fn execute(&mut self, encoder: &mut gfx::Encoder, settings: Option<&BloomSettings>) {
+        let target = self.target.as_mut().expect("bloom must be prepared");
+
+        // NOTE: The image is always kept in the `General` layout,
+        // so it can be bound even if bloom is disabled.
+        let whole_range = gfx::ImageSubresourceRange::whole(target.image.info());
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &target.image,
+                src_access: gfx::AccessFlags::SHADER_READ,
+                dst_access: gfx::AccessFlags::SHADER_READ | gfx::AccessFlags::SHADER_WRITE,
+                old_layout: target.initialized.then_some(gfx::ImageLayout::General),
+                new_layout: gfx::ImageLayout::General,
+                family_transfer: None,
+                subresource_range: whole_range,
+            }],
+        );
+        target.initialized = true;
+
+        let Some(settings) = settings else {
+            return;
+        };
+
+        let knee = settings.threshold * settings.knee.clamp(0.0, 1.0);
+        let mut push_constants = BloomPushConstants {
+            threshold: Vec4::new(
+                settings.threshold,
+                settings.threshold - knee,
+                knee * 2.0,
+                0.25 / (knee + 1e-5),
+            ),
+            target_extent: UVec2::ZERO,
+            radius: settings.radius.max(0.0),
+            prefilter: 0,
+        };
+
+        let layout = &self.downsample_pipeline.info().layout;
+        let mip_barrier = |encoder: &mut gfx::Encoder, level: usize| {
+            let level = level as u32;
+            encoder.image_barriers(
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                &[gfx::ImageMemoryBarrier {
+                    image: &target.image,
+                    src_access: gfx::AccessFlags::SHADER_WRITE,
+                    dst_access: gfx::AccessFlags::SHADER_READ | gfx::AccessFlags::SHADER_WRITE,
+                    old_layout: Some(gfx::ImageLayout::General),
+                    new_layout: gfx::ImageLayout::General,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::color(level..level + 1, 0..1),
+                }],
+            );
+        };
+
+        encoder.bind_compute_pipeline(&self.downsample_pipeline);
+        for (level, mip) in target.mips.iter().enumerate() {
+            push_constants.target_extent = mip.extent;
+            push_constants.prefilter = (level == 0) as u32;
+
+            encoder.bind_compute_descriptor_sets(layout, 0, &[&mip.downsample], &[]);
+            encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+            encoder.dispatch(mip.extent.x.div_ceil(8), mip.extent.y.div_ceil(8), 1);
+            mip_barrier(encoder, level);
+        }
+
+        encoder.bind_compute_pipeline(&self.upsample_pipeline);
+        for (level, mip) in target.mips.iter().enumerate().rev() {
+            let Some(upsample) = &mip.upsample else {
+                continue;
+            };
+            push_constants.target_extent = mip.extent;
+
+            encoder.bind_compute_descriptor_sets(layout, 0, &[upsample], &[]);
+            encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[push_constants]);
+            encoder.dispatch(mip.extent.x.div_ceil(8), mip.extent.y.div_ceil(8), 1);
+            mip_barrier(encoder, level);
+        }
+
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::SHADER_WRITE,
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::AccessFlags::SHADER_READ,
+        );
+    }
+
+    fn create_target(
+        &self,
+        device: &gfx::Device,
+        hdr_target: &gfx::ImageView,
+        exposure_buffer: &gfx::Buffer,
+        extent: UVec2,
+    ) -> Result<BloomTarget> {
+        let mip_levels = bloom_mip_levels(extent);
+
+        let image = device.create_image(gfx::ImageInfo {
+            extent: gfx::ImageExtent::D2 {
+                width: extent.x,
+                height: extent.y,
+            },
+            format: gfx::Format::RGBA16Sfloat,
+            mip_levels,
+            samples: gfx::Samples::_1,
+            array_layers: 1,
+            flags: Default::default(),
+            usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::STORAGE,
+        })?;
+        let view = device.create_image_view(gfx::ImageViewInfo {
+            range: gfx::ImageSubresourceRange::color(0..1, 0..1),
+            ..gfx::ImageViewInfo::new(image.clone())
+        })?;
+
+        let mip_views = (0..mip_levels)
+            .map(|level| {
+                device.create_image_view(gfx::ImageViewInfo {
+                    range: gfx::ImageSubresourceRange::color(level..level + 1, 0..1),
+                    ..gfx::ImageViewInfo::new(image.clone())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let make_descriptor_set = |source: gfx::CombinedImageSampler, target: &gfx::ImageView| {
+            create_descriptor_set(
+                device,
+                &self.descriptor_set_layout,
+                source,
+                target,
+                exposure_buffer,
+            )
+        };
+
+        let mut mips = Vec::with_capacity(mip_levels as usize);
+        for (level, mip_view) in mip_views.iter().enumerate() {
+            let downsample_source = match level {
+                0 => gfx::CombinedImageSampler {
+                    view: hdr_target.clone(),
+                    layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                    sampler: self.sampler.clone(),
+                },
+                _ => gfx::CombinedImageSampler {
+                    view: mip_views[level - 1].clone(),
+                    layout: gfx::ImageLayout::General,
+                    sampler: self.sampler.clone(),
+                },
+            };
+            let upsample = mip_views
+                .get(level + 1)
+                .map(|next| {
+                    make_descriptor_set(
+                        gfx::CombinedImageSampler {
+                            view: next.clone(),
+                            layout: gfx::ImageLayout::General,
+                            sampler: self.sampler.clone(),
+                        },
+                        mip_view,
+                    )
+                })
+                .transpose()?;
+
+            mips.push(BloomMip {
+                downsample: make_descriptor_set(downsample_source, mip_view)?,
+                upsample,
+                extent: (extent >> level as u32).max(UVec2::ONE),
+            });
+        }
+
+        let mut prefilter = SourceCache::new();
+        prefilter.get_or_try_insert_with(hdr_target, || Ok(mips[0].downsample.clone()))?;
+
+        Ok(BloomTarget {
+            prefilter,
+            image,
+            view,
+            initialized: false,
+            mips,
+        })
+    }
+
+    fn target(&self) -> &BloomTarget {
+        self.target.as_ref().expect("bloom must be prepared")
+    }
+}
+
+fn create_descriptor_set(
+    device: &gfx::Device,
+    layout: &gfx::DescriptorSetLayout,
+    source: gfx::CombinedImageSampler,
+    target: &gfx::ImageView,
+    exposure_buffer: &gfx::Buffer,
+) -> Result<gfx::DescriptorSet> {
+    let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+        layout: layout.clone(),
+    })?;
+    device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+        set: &descriptor_set,
+        writes: &[
+            gfx::DescriptorSetWrite {
+                binding: 0,
+                element: 0,
+                data: gfx::DescriptorSlice::CombinedImageSampler(&[source]),
+            },
+            gfx::DescriptorSetWrite {
+                binding: 1,
+                element: 0,
+                data: gfx::DescriptorSlice::StorageImage(&[(
+                    target.clone(),
+                    gfx::ImageLayout::General,
+                )]),
+            },
+            gfx::DescriptorSetWrite {
+                binding: 2,
+                element: 0,
+                data: gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(
+                    exposure_buffer.clone(),
+                )]),
+            },
+        ],
+    }]);
+    Ok(descriptor_set)
+}
+
+/// Stops at mips of a few texels, where the glow stops spreading anyway.
+fn bloom_mip_levels(extent: UVec2) -> u32 {
+    const MAX_MIP_LEVELS: u32 = 8;
+
+    let smallest_side = extent.min_element().max(1);
+    smallest_side
+        .ilog2()
+        .saturating_sub(2)
+        .clamp(1, MAX_MIP_LEVELS)
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BloomPushConstants {
+    /// Threshold, threshold minus knee, knee * 2 and 0.25 / knee.
+    threshold: Vec4,
+    target_extent: UVec2,
+    radius: f32,
+    prefilter: u32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for BloomPushConstants {}
+unsafe impl bytemuck::Zeroable for BloomPushConstants {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bloom_mip_chain_length() {
+        assert_eq!(bloom_mip_levels(UVec2::new(1, 1)), 1);
+        assert_eq!(bloom_mip_levels(UVec2::new(960, 540)), 7);
+        assert_eq!(bloom_mip_levels(UVec2::new(4096, 4096)), 8);
+    }
+}
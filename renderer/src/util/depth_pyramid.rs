@@ -0,0 +1,383 @@
+use anyhow::Result;
+use glam::{Mat4, UVec2};
+
+use crate::util::ShaderPreprocessor;
+
+/// Hierarchical depth buffer used for occlusion culling.
+///
+/// Each texel of a mip level holds the farthest depth of the texels it covers
+/// in the previous level, so fetching the 2x2 texels under an object from a
+/// level where it spans at most two texels is a conservative occlusion test.
+///
+/// The first level has half of the depth resolution and each next one halves
+/// the previous level rounding down. The last row and column of a level also
+/// cover the texels left over from odd sizes, so depth texel `p` is covered by
+/// texel `min(p >> (level + 1), extent - 1)` of any level.
+pub struct DepthPyramid {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: gfx::ComputePipeline,
+    sampler: gfx::Sampler,
+    target: Option<PyramidTarget>,
+}
+
+struct PyramidTarget {
+    depth: gfx::ImageView,
+    depth_extent: UVec2,
+    image: gfx::Image,
+    view: gfx::ImageView,
+    mips: Vec<PyramidMip>,
+    view_projection: Option<Mat4>,
+}
+
+struct PyramidMip {
+    descriptor_set: gfx::DescriptorSet,
+    source_extent: UVec2,
+    extent: UVec2,
+}
+
+impl DepthPyramid {
+    #[tracing::instrument(level = "debug", name = "create_depth_pyramid", skip_all)]
+    pub fn new(device: &gfx::Device, shader_preprocessor: &ShaderPreprocessor) -> Result<Self> {
+        let shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/depth_pyramid.comp",
+            "main",
+        )?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageImage,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: 16,
+            }],
+        })?;
+
+        let pipeline =
+            device.create_compute_pipeline(gfx::ComputePipelineInfo { shader, layout })?;
+
+        // NOTE: Texels are fetched directly, so no min/max sampler reduction is needed.
+        let sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Nearest,
+            min_filter: gfx::Filter::Nearest,
+            mipmap_mode: gfx::MipmapMode::Nearest,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            max_lod: 16.0,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            sampler,
+            target: None,
+        })
+    }
+
+    /// Recreates the pyramid if the depth attachment has changed.
+    ///
+    /// The pyramid history is discarded in that case.
+    pub fn prepare(&mut self, device: &gfx::Device, depth: &gfx::ImageView) -> Result<()> {
+        if matches!(&self.target, Some(target) if &target.depth == depth) {
+            return Ok(());
+        }
+        self.target = None;
+
+        let depth_extent = UVec2::from(depth.info().image.info().extent);
+        let mip_extents = mip_extents(depth_extent);
+        let extent = mip_extents[0];
+        let mip_levels = mip_extents.len() as u32;
+
+        let image = device.create_image(gfx::ImageInfo {
+            extent: gfx::ImageExtent::D2 {
+                width: extent.x,
+                height: extent.y,
+            },
+            format: gfx::Format::R32Sfloat,
+            mip_levels,
+            samples: gfx::Samples::_1,
+            array_layers: 1,
+            flags: Default::default(),
+            usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::STORAGE,
+        })?;
+        let view = device.create_image_view(gfx::ImageViewInfo::new(image.clone()))?;
+
+        let mut mips = Vec::with_capacity(mip_levels as usize);
+        let mut source = (depth.clone(), gfx::ImageLayout::ShaderReadOnlyOptimal);
+        let mut source_extent = depth_extent;
+        for (level, &mip_extent) in (0..mip_levels).zip(&mip_extents) {
+            let mip_view = device.create_image_view(gfx::ImageViewInfo {
+                range: gfx::ImageSubresourceRange::color(level..level + 1, 0..1),
+                ..gfx::ImageViewInfo::new(image.clone())
+            })?;
+
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: self.descriptor_set_layout.clone(),
+            })?;
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &[
+                    gfx::DescriptorSetWrite {
+                        binding: 0,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                            gfx::CombinedImageSampler {
+                                view: source.0,
+                                layout: source.1,
+                                sampler: self.sampler.clone(),
+                            },
+                        ]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 1,
+                        element: 0,
+                        data: gfx::DescriptorSlice::StorageImage(&[(
+                            mip_view.clone(),
+                            gfx::ImageLayout::General,
+                        )]),
+                    },
+                ],
+            }]);
+
+            mips.push(PyramidMip {
+                descriptor_set,
+                source_extent,
+                extent: mip_extent,
+            });
+            source = (mip_view, gfx::ImageLayout::General);
+            source_extent = mip_extent;
+        }
+
+        self.target = Some(PyramidTarget {
+            depth: depth.clone(),
+            depth_extent,
+            image,
+            view,
+            mips,
+            view_projection: None,
+        });
+        Ok(())
+    }
+
+    pub fn sampler(&self) -> &gfx::Sampler {
+        &self.sampler
+    }
+
+    pub fn view(&self) -> &gfx::ImageView {
+        &self.target().view
+    }
+
+    /// Extent of the depth attachment from which the pyramid is built.
+    pub fn depth_extent(&self) -> UVec2 {
+        self.target().depth_extent
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.target().mips.len() as u32
+    }
+
+    /// Camera matrix of the depth from which the pyramid was built,
+    /// or `None` if it was not built yet.
+    pub fn view_projection(&self) -> Option<&Mat4> {
+        self.target().view_projection.as_ref()
+    }
+
+    /// Reduces the depth attachment into the pyramid.
+    ///
+    /// The depth attachment must be in the `DepthStencilAttachmentOptimal`
+    /// layout and is returned to it afterwards.
+    pub fn build(&mut self, encoder: &mut gfx::Encoder, view_projection: &Mat4) {
+        let target = self
+            .target
+            .as_mut()
+            .expect("depth pyramid must be prepared");
+
+        let depth_image = &target.depth.info().image;
+        let depth_range = gfx::ImageSubresourceRange::whole(depth_image.info());
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: depth_image,
+                src_access: gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::DepthStencilAttachmentOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: depth_range,
+            }],
+        );
+        // NOTE: Previous occlusion tests must finish before the pyramid is overwritten.
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &target.image,
+                src_access: gfx::AccessFlags::SHADER_READ,
+                dst_access: gfx::AccessFlags::SHADER_WRITE,
+                old_layout: target.view_projection.map(|_| gfx::ImageLayout::General),
+                new_layout: gfx::ImageLayout::General,
+                family_transfer: None,
+                subresource_range: gfx::ImageSubresourceRange::whole(target.image.info()),
+            }],
+        );
+
+        encoder.bind_compute_pipeline(&self.pipeline);
+        for (level, mip) in target.mips.iter().enumerate() {
+            encoder.bind_compute_descriptor_sets(
+                &self.pipeline.info().layout,
+                0,
+                &[&mip.descriptor_set],
+                &[],
+            );
+            encoder.push_constants(
+                &self.pipeline.info().layout,
+                gfx::ShaderStageFlags::COMPUTE,
+                0,
+                &[
+                    mip.source_extent.x,
+                    mip.source_extent.y,
+                    mip.extent.x,
+                    mip.extent.y,
+                ],
+            );
+            encoder.dispatch(mip.extent.x.div_ceil(8), mip.extent.y.div_ceil(8), 1);
+
+            let level = level as u32;
+            encoder.image_barriers(
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                &[gfx::ImageMemoryBarrier {
+                    image: &target.image,
+                    src_access: gfx::AccessFlags::SHADER_WRITE,
+                    dst_access: gfx::AccessFlags::SHADER_READ,
+                    old_layout: Some(gfx::ImageLayout::General),
+                    new_layout: gfx::ImageLayout::General,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::color(level..level + 1, 0..1),
+                }],
+            );
+        }
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            &[gfx::ImageMemoryBarrier {
+                image: depth_image,
+                src_access: gfx::AccessFlags::SHADER_READ,
+                dst_access: gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                old_layout: Some(gfx::ImageLayout::ShaderReadOnlyOptimal),
+                new_layout: gfx::ImageLayout::DepthStencilAttachmentOptimal,
+                family_transfer: None,
+                subresource_range: depth_range,
+            }],
+        );
+
+        target.view_projection = Some(*view_projection);
+    }
+
+    fn target(&self) -> &PyramidTarget {
+        self.target
+            .as_ref()
+            .expect("depth pyramid must be prepared")
+    }
+}
+
+/// Returns extents of the pyramid levels, down to a single texel.
+fn mip_extents(depth_extent: UVec2) -> Vec<UVec2> {
+    let mip_levels = depth_extent.max_element().max(2).ilog2();
+    (1..=mip_levels)
+        .map(|shift| (depth_extent >> shift).max(UVec2::ONE))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference of the reduction in `depth_pyramid.comp`.
+    fn reduce(source: &[f32], source_extent: UVec2, extent: UVec2) -> Vec<f32> {
+        let mut target = Vec::with_capacity((extent.x * extent.y) as usize);
+        for y in 0..extent.y {
+            for x in 0..extent.x {
+                let position = UVec2::new(x, y);
+                let first = (position * 2).min(source_extent - 1);
+                let last = UVec2::select(
+                    position.cmpeq(extent - 1),
+                    source_extent - 1,
+                    (first + 1).min(source_extent - 1),
+                );
+                let mut depth = 0.0f32;
+                for sy in first.y..=last.y {
+                    for sx in first.x..=last.x {
+                        depth = depth.max(source[(sy * source_extent.x + sx) as usize]);
+                    }
+                }
+                target.push(depth);
+            }
+        }
+        target
+    }
+
+    #[test]
+    fn pyramid_covers_odd_resolutions() {
+        for depth_extent in [
+            UVec2::new(1, 1),
+            UVec2::new(7, 3),
+            UVec2::new(13, 21),
+            UVec2::new(64, 17),
+        ] {
+            let extents = mip_extents(depth_extent);
+            assert_eq!(*extents.last().unwrap(), UVec2::ONE);
+
+            let texel_count = (depth_extent.x * depth_extent.y) as usize;
+            for far_texel in 0..texel_count {
+                let mut depth = vec![0.5; texel_count];
+                depth[far_texel] = 1.0;
+                let far_position = UVec2::new(
+                    far_texel as u32 % depth_extent.x,
+                    far_texel as u32 / depth_extent.x,
+                );
+
+                let mut source = (depth, depth_extent);
+                for (level, &extent) in extents.iter().enumerate() {
+                    let mip = reduce(&source.0, source.1, extent);
+                    let texel = (far_position >> (level as u32 + 1)).min(extent - 1);
+                    assert_eq!(
+                        mip[(texel.y * extent.x + texel.x) as usize],
+                        1.0,
+                        "{depth_extent} level {level} misses the farthest depth"
+                    );
+                    source = (mip, extent);
+                }
+            }
+        }
+    }
+}
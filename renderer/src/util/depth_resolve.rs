@@ -0,0 +1,213 @@
+use anyhow::Result;
+
+use crate::util::{CachedGraphicsPipeline, RenderPassEncoderExt, ShaderPreprocessor};
+
+/// Resolves a multisampled depth attachment into a single-sampled one.
+///
+/// Subpass resolves only support color attachments, so the farthest sample
+/// of each pixel is written with a fullscreen pass instead.
+pub struct DepthResolve {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: CachedGraphicsPipeline,
+    sampler: gfx::Sampler,
+    render_pass: Option<gfx::RenderPass>,
+    target: Option<ResolveTarget>,
+}
+
+struct ResolveTarget {
+    source: gfx::ImageView,
+    target: gfx::ImageView,
+    framebuffer: gfx::Framebuffer,
+    descriptor_set: gfx::DescriptorSet,
+}
+
+impl DepthResolve {
+    #[tracing::instrument(level = "debug", name = "create_depth_resolve", skip_all)]
+    pub fn new(device: &gfx::Device, shaders: &ShaderPreprocessor) -> Result<Self> {
+        let shaders = shaders.begin();
+        let vertex_shader = shaders.make_vertex_shader(device, "fullscreen.vert", "main")?;
+        let fragment_shader = shaders.make_fragment_shader(device, "depth_resolve.frag", "main")?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![gfx::DescriptorSetLayoutBinding {
+                    binding: 0,
+                    ty: gfx::DescriptorType::CombinedImageSampler,
+                    count: 1,
+                    stages: gfx::ShaderStageFlags::FRAGMENT,
+                    flags: Default::default(),
+                }],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: Vec::new(),
+        })?;
+
+        let pipeline = CachedGraphicsPipeline::new(gfx::GraphicsPipelineDescr {
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
+            primitive_topology: Default::default(),
+            primitive_restart_enable: false,
+            vertex_shader,
+            rasterizer: Some(gfx::Rasterizer {
+                depth_test: Some(gfx::DepthTest {
+                    compare: gfx::CompareOp::Always,
+                    write: true,
+                }),
+                fragment_shader: Some(fragment_shader),
+                ..Default::default()
+            }),
+            layout,
+        });
+
+        let sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            sampler,
+            render_pass: None,
+            target: None,
+        })
+    }
+
+    /// Recreates the framebuffer if the attachments have changed (e.g. on resize).
+    pub fn prepare(
+        &mut self,
+        device: &gfx::Device,
+        source: &gfx::ImageView,
+        target: &gfx::ImageView,
+    ) -> Result<()> {
+        if matches!(&self.target, Some(t) if &t.source == source && &t.target == target) {
+            return Ok(());
+        }
+        self.target = None;
+
+        let target_info = target.info().image.info();
+        let render_pass = match &self.render_pass {
+            Some(render_pass) if render_pass.info().attachments[0].format == target_info.format => {
+                render_pass.clone()
+            }
+            _ => self
+                .render_pass
+                .insert(device.create_render_pass(gfx::RenderPassInfo {
+                    attachments: vec![gfx::AttachmentInfo {
+                        format: target_info.format,
+                        samples: gfx::Samples::_1,
+                        load_op: gfx::LoadOp::DontCare,
+                        store_op: gfx::StoreOp::Store,
+                        initial_layout: None,
+                        final_layout: gfx::ImageLayout::DepthStencilAttachmentOptimal,
+                    }],
+                    subpasses: vec![gfx::Subpass {
+                        colors: Vec::new(),
+                        depth: Some((0, gfx::ImageLayout::DepthStencilAttachmentOptimal)),
+                        resolves: Vec::new(),
+                    }],
+                    dependencies: vec![gfx::SubpassDependency {
+                        src: None,
+                        // NOTE: The previous resolve might still be read by the depth pyramid.
+                        src_stages: gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                            | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS
+                            | gfx::PipelineStageFlags::FRAGMENT_SHADER
+                            | gfx::PipelineStageFlags::COMPUTE_SHADER,
+                        dst: Some(0),
+                        dst_stages: gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                            | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                    }],
+                })?)
+                .clone(),
+        };
+
+        let framebuffer = device.create_framebuffer(gfx::FramebufferInfo {
+            render_pass,
+            attachments: vec![target.clone()],
+            extent: target_info.extent.into(),
+        })?;
+
+        let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+            layout: self.descriptor_set_layout.clone(),
+        })?;
+        device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+            set: &descriptor_set,
+            writes: &[gfx::DescriptorSetWrite {
+                binding: 0,
+                element: 0,
+                data: gfx::DescriptorSlice::CombinedImageSampler(&[gfx::CombinedImageSampler {
+                    view: source.clone(),
+                    layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                    sampler: self.sampler.clone(),
+                }]),
+            }],
+        }]);
+
+        self.target = Some(ResolveTarget {
+            source: source.clone(),
+            target: target.clone(),
+            framebuffer,
+            descriptor_set,
+        });
+        Ok(())
+    }
+
+    /// Writes the resolved depth into the `DepthStencilAttachmentOptimal` layout.
+    ///
+    /// The multisampled depth attachment must be in the `DepthStencilAttachmentOptimal`
+    /// layout and is returned to it afterwards.
+    pub fn execute(&mut self, device: &gfx::Device, encoder: &mut gfx::Encoder) -> Result<()> {
+        let target = self
+            .target
+            .as_ref()
+            .expect("depth resolve must be prepared");
+
+        let source_image = &target.source.info().image;
+        let source_range = gfx::ImageSubresourceRange::whole(source_image.info());
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: source_image,
+                src_access: gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::DepthStencilAttachmentOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: source_range,
+            }],
+        );
+
+        {
+            let mut encoder = encoder.with_framebuffer(&target.framebuffer, &[]);
+            encoder.bind_cached_graphics_pipeline(&mut self.pipeline, device)?;
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline.descr().layout,
+                0,
+                &[&target.descriptor_set],
+                &[],
+            );
+            encoder.draw(0..3, 0..1);
+        }
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | gfx::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            &[gfx::ImageMemoryBarrier {
+                image: source_image,
+                src_access: gfx::AccessFlags::SHADER_READ,
+                dst_access: gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | gfx::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                old_layout: Some(gfx::ImageLayout::ShaderReadOnlyOptimal),
+                new_layout: gfx::ImageLayout::DepthStencilAttachmentOptimal,
+                family_transfer: None,
+                subresource_range: source_range,
+            }],
+        );
+
+        Ok(())
+    }
+}
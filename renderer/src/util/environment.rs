@@ -0,0 +1,614 @@
+use anyhow::Result;
+use glam::{IVec3, UVec2};
+
+use crate::types::EnvironmentMap;
+use crate::util::{BindlessResources, SampledImageHandle, ShaderPreprocessor, StorageBufferHandle};
+
+/// Skybox and image-based lighting resources converted from an equirectangular panorama.
+///
+/// The panorama is projected onto a cubemap with a full mip chain, which is
+/// then prefiltered for rough specular reflections and projected onto
+/// spherical harmonics for diffuse irradiance.
+pub struct Environment {
+    to_cube_layout: gfx::DescriptorSetLayout,
+    prefilter_layout: gfx::DescriptorSetLayout,
+    irradiance_layout: gfx::DescriptorSetLayout,
+    to_cube_pipeline: gfx::ComputePipeline,
+    prefilter_pipeline: gfx::ComputePipeline,
+    irradiance_pipeline: gfx::ComputePipeline,
+    point_sampler: gfx::Sampler,
+    linear_sampler: gfx::Sampler,
+    resources: Option<EnvironmentResources>,
+}
+
+struct EnvironmentResources {
+    // NOTE: Kept alive while the bindless handles are in use.
+    _skybox: gfx::ImageView,
+    _specular: gfx::ImageView,
+    _irradiance: gfx::Buffer,
+    handles: EnvironmentHandles,
+}
+
+/// Bindless handles of the converted environment.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvironmentHandles {
+    pub skybox: SampledImageHandle,
+    /// Prefiltered radiance with the roughness increasing with each mip.
+    pub specular: SampledImageHandle,
+    /// Second order spherical harmonics of the diffuse irradiance.
+    pub irradiance: StorageBufferHandle,
+}
+
+impl Environment {
+    #[tracing::instrument(level = "debug", name = "create_environment", skip_all)]
+    pub fn new(device: &gfx::Device, shader_preprocessor: &ShaderPreprocessor) -> Result<Self> {
+        let shaders = shader_preprocessor.begin();
+        let to_cube_shader =
+            shaders.make_compute_shader(device, "/environment_to_cube.comp", "main")?;
+        let prefilter_shader =
+            shaders.make_compute_shader(device, "/environment_prefilter.comp", "main")?;
+        let irradiance_shader =
+            shaders.make_compute_shader(device, "/environment_irradiance.comp", "main")?;
+
+        let make_layout = |output: gfx::DescriptorType| {
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: output,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })
+        };
+        let to_cube_layout = make_layout(gfx::DescriptorType::StorageImage)?;
+        let prefilter_layout = make_layout(gfx::DescriptorType::StorageImage)?;
+        let irradiance_layout = make_layout(gfx::DescriptorType::StorageBuffer)?;
+
+        let make_pipeline = |shader: gfx::ComputeShader,
+                             descriptor_set_layout: &gfx::DescriptorSetLayout,
+                             push_constants_size: usize|
+         -> Result<gfx::ComputePipeline> {
+            let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+                sets: vec![descriptor_set_layout.clone()],
+                push_constants: vec![gfx::PushConstant {
+                    stages: gfx::ShaderStageFlags::COMPUTE,
+                    offset: 0,
+                    size: push_constants_size as u32,
+                }],
+            })?;
+            let pipeline =
+                device.create_compute_pipeline(gfx::ComputePipelineInfo { shader, layout })?;
+            Ok(pipeline)
+        };
+        let to_cube_pipeline =
+            make_pipeline(to_cube_shader, &to_cube_layout, std::mem::size_of::<u32>())?;
+        let prefilter_pipeline = make_pipeline(
+            prefilter_shader,
+            &prefilter_layout,
+            std::mem::size_of::<PrefilterPushConstants>(),
+        )?;
+        let irradiance_pipeline = make_pipeline(
+            irradiance_shader,
+            &irradiance_layout,
+            std::mem::size_of::<IrradiancePushConstants>(),
+        )?;
+
+        let point_sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+        let linear_sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            mipmap_mode: gfx::MipmapMode::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            max_lod: 16.0,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            to_cube_layout,
+            prefilter_layout,
+            irradiance_layout,
+            to_cube_pipeline,
+            prefilter_pipeline,
+            irradiance_pipeline,
+            point_sampler,
+            linear_sampler,
+            resources: None,
+        })
+    }
+
+    /// Bindless handles of the current environment, if any.
+    pub fn handles(&self) -> Option<EnvironmentHandles> {
+        self.resources.as_ref().map(|resources| resources.handles)
+    }
+
+    /// Removes the current environment and frees its bindless handles.
+    pub fn release(&mut self, bindless_resources: &BindlessResources) {
+        if let Some(resources) = self.resources.take() {
+            bindless_resources.free_image(resources.handles.skybox);
+            bindless_resources.free_image(resources.handles.specular);
+            bindless_resources.free_storage_buffer(resources.handles.irradiance);
+        }
+    }
+
+    /// Replaces the current environment, or removes it with `None`.
+    ///
+    /// The conversion is recorded into the encoder, the results are left
+    /// readable by fragment shaders.
+    pub fn update(
+        &mut self,
+        device: &gfx::Device,
+        bindless_resources: &BindlessResources,
+        encoder: &mut gfx::Encoder,
+        map: Option<&EnvironmentMap>,
+    ) -> Result<()> {
+        self.release(bindless_resources);
+        let Some(map) = map else {
+            return Ok(());
+        };
+
+        let source = self.upload_source(device, encoder, map)?;
+        let skybox = self.convert_to_cube(device, encoder, &source)?;
+        let specular = self.prefilter_specular(device, encoder, &skybox)?;
+        let irradiance = self.compute_irradiance(device, encoder, &skybox)?;
+
+        let handles = EnvironmentHandles {
+            skybox: bindless_resources.alloc_image(
+                device,
+                skybox.clone(),
+                self.linear_sampler.clone(),
+            ),
+            specular: bindless_resources.alloc_image(
+                device,
+                specular.clone(),
+                self.linear_sampler.clone(),
+            ),
+            irradiance: bindless_resources
+                .alloc_storage_buffer(device, gfx::BufferRange::whole(irradiance.clone())),
+        };
+        self.resources = Some(EnvironmentResources {
+            _skybox: skybox,
+            _specular: specular,
+            _irradiance: irradiance,
+            handles,
+        });
+        Ok(())
+    }
+
+    fn upload_source(
+        &self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        map: &EnvironmentMap,
+    ) -> Result<gfx::ImageView> {
+        let data = bytemuck::cast_slice::<_, u8>(map.pixels());
+        let staging = device.create_mappable_buffer(
+            gfx::BufferInfo {
+                align_mask: 0b1111,
+                size: data.len(),
+                usage: gfx::BufferUsage::TRANSFER_SRC,
+            },
+            gfx::MemoryUsage::UPLOAD | gfx::MemoryUsage::TRANSIENT,
+        )?;
+        device.upload_to_memory(&mut staging.as_mappable(), 0, data)?;
+
+        let image = device.create_image(gfx::ImageInfo {
+            extent: gfx::ImageExtent::D2 {
+                width: map.width(),
+                height: map.height(),
+            },
+            format: gfx::Format::RGBA32Sfloat,
+            mip_levels: 1,
+            samples: gfx::Samples::_1,
+            array_layers: 1,
+            flags: Default::default(),
+            usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::TRANSFER_DST,
+        })?;
+        let range = gfx::ImageSubresourceRange::whole(image.info());
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TOP_OF_PIPE,
+            gfx::PipelineStageFlags::TRANSFER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::empty(),
+                dst_access: gfx::AccessFlags::TRANSFER_WRITE,
+                old_layout: None,
+                new_layout: gfx::ImageLayout::TransferDstOptimal,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+        encoder.copy_buffer_to_image(
+            &staging,
+            &image,
+            gfx::ImageLayout::TransferDstOptimal,
+            &[gfx::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: gfx::ImageSubresourceLayers::all_layers(image.info(), 0),
+                image_offset: IVec3::ZERO,
+                image_extent: UVec2::new(map.width(), map.height()).extend(1),
+            }],
+        );
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TRANSFER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::TRANSFER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::TransferDstOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+
+        Ok(device.create_image_view(gfx::ImageViewInfo::new(image))?)
+    }
+
+    /// Projects the panorama onto the cube faces and fills the mip chain.
+    fn convert_to_cube(
+        &self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        source: &gfx::ImageView,
+    ) -> Result<gfx::ImageView> {
+        let mip_levels = SKYBOX_SIZE.ilog2() + 1;
+        let image = make_cube_image(
+            device,
+            SKYBOX_SIZE,
+            mip_levels,
+            gfx::ImageUsageFlags::TRANSFER_SRC | gfx::ImageUsageFlags::TRANSFER_DST,
+        )?;
+        let face_view = make_face_view(device, &image, 0)?;
+
+        let descriptor_set = make_descriptor_set(
+            device,
+            &self.to_cube_layout,
+            gfx::CombinedImageSampler {
+                view: source.clone(),
+                layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                sampler: self.point_sampler.clone(),
+            },
+            gfx::DescriptorSlice::StorageImage(&[(face_view, gfx::ImageLayout::General)]),
+        )?;
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TOP_OF_PIPE,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::empty(),
+                dst_access: gfx::AccessFlags::SHADER_WRITE,
+                old_layout: None,
+                new_layout: gfx::ImageLayout::General,
+                family_transfer: None,
+                subresource_range: gfx::ImageSubresourceRange::whole(image.info()),
+            }],
+        );
+
+        let layout = &self.to_cube_pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.to_cube_pipeline);
+        encoder.bind_compute_descriptor_sets(layout, 0, &[&descriptor_set], &[]);
+        encoder.push_constants(layout, gfx::ShaderStageFlags::COMPUTE, 0, &[SKYBOX_SIZE]);
+        encoder.dispatch(SKYBOX_SIZE.div_ceil(8), SKYBOX_SIZE.div_ceil(8), CUBE_FACES);
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::TRANSFER,
+            &[
+                gfx::ImageMemoryBarrier {
+                    image: &image,
+                    src_access: gfx::AccessFlags::SHADER_WRITE,
+                    dst_access: gfx::AccessFlags::TRANSFER_READ,
+                    old_layout: Some(gfx::ImageLayout::General),
+                    new_layout: gfx::ImageLayout::TransferSrcOptimal,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::color(0..1, 0..CUBE_FACES),
+                },
+                gfx::ImageMemoryBarrier {
+                    image: &image,
+                    src_access: gfx::AccessFlags::empty(),
+                    dst_access: gfx::AccessFlags::TRANSFER_WRITE,
+                    old_layout: Some(gfx::ImageLayout::General),
+                    new_layout: gfx::ImageLayout::TransferDstOptimal,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::color(
+                        1..mip_levels,
+                        0..CUBE_FACES,
+                    ),
+                },
+            ],
+        );
+
+        for level in 1..mip_levels {
+            let src_size = (SKYBOX_SIZE >> (level - 1)) as i32;
+            let dst_size = (SKYBOX_SIZE >> level) as i32;
+            encoder.blit_image(
+                &image,
+                gfx::ImageLayout::TransferSrcOptimal,
+                &image,
+                gfx::ImageLayout::TransferDstOptimal,
+                &[gfx::ImageBlit {
+                    src_subresource: gfx::ImageSubresourceLayers::color(level - 1, 0..CUBE_FACES),
+                    src_offsets: [IVec3::ZERO, IVec3::new(src_size, src_size, 1)],
+                    dst_subresource: gfx::ImageSubresourceLayers::color(level, 0..CUBE_FACES),
+                    dst_offsets: [IVec3::ZERO, IVec3::new(dst_size, dst_size, 1)],
+                }],
+                gfx::Filter::Linear,
+            );
+            encoder.image_barriers(
+                gfx::PipelineStageFlags::TRANSFER,
+                gfx::PipelineStageFlags::TRANSFER,
+                &[gfx::ImageMemoryBarrier {
+                    image: &image,
+                    src_access: gfx::AccessFlags::TRANSFER_WRITE,
+                    dst_access: gfx::AccessFlags::TRANSFER_READ,
+                    old_layout: Some(gfx::ImageLayout::TransferDstOptimal),
+                    new_layout: gfx::ImageLayout::TransferSrcOptimal,
+                    family_transfer: None,
+                    subresource_range: gfx::ImageSubresourceRange::color(
+                        level..level + 1,
+                        0..CUBE_FACES,
+                    ),
+                }],
+            );
+        }
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TRANSFER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::TRANSFER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::TransferSrcOptimal),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: gfx::ImageSubresourceRange::whole(image.info()),
+            }],
+        );
+
+        make_cube_view(device, image)
+    }
+
+    /// Convolves the skybox with the GGX lobe, one roughness value per mip.
+    fn prefilter_specular(
+        &self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        skybox: &gfx::ImageView,
+    ) -> Result<gfx::ImageView> {
+        let image = make_cube_image(
+            device,
+            SPECULAR_SIZE,
+            SPECULAR_MIP_LEVELS,
+            gfx::ImageUsageFlags::empty(),
+        )?;
+        let range = gfx::ImageSubresourceRange::whole(image.info());
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::TOP_OF_PIPE,
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::empty(),
+                dst_access: gfx::AccessFlags::SHADER_WRITE,
+                old_layout: None,
+                new_layout: gfx::ImageLayout::General,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+
+        let layout = &self.prefilter_pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.prefilter_pipeline);
+        for level in 0..SPECULAR_MIP_LEVELS {
+            let descriptor_set = make_descriptor_set(
+                device,
+                &self.prefilter_layout,
+                gfx::CombinedImageSampler {
+                    view: skybox.clone(),
+                    layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                    sampler: self.linear_sampler.clone(),
+                },
+                gfx::DescriptorSlice::StorageImage(&[(
+                    make_face_view(device, &image, level)?,
+                    gfx::ImageLayout::General,
+                )]),
+            )?;
+
+            let size = SPECULAR_SIZE >> level;
+            encoder.bind_compute_descriptor_sets(layout, 0, &[&descriptor_set], &[]);
+            encoder.push_constants(
+                layout,
+                gfx::ShaderStageFlags::COMPUTE,
+                0,
+                &[PrefilterPushConstants {
+                    size,
+                    roughness: level as f32 / (SPECULAR_MIP_LEVELS - 1) as f32,
+                    sample_count: SPECULAR_SAMPLE_COUNT,
+                    source_size: SKYBOX_SIZE as f32,
+                }],
+            );
+            encoder.dispatch(size.div_ceil(8), size.div_ceil(8), CUBE_FACES);
+        }
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: &image,
+                src_access: gfx::AccessFlags::SHADER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::General),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: range,
+            }],
+        );
+
+        make_cube_view(device, image)
+    }
+
+    /// Projects a low resolution mip of the skybox onto spherical harmonics.
+    fn compute_irradiance(
+        &self,
+        device: &gfx::Device,
+        encoder: &mut gfx::Encoder,
+        skybox: &gfx::ImageView,
+    ) -> Result<gfx::Buffer> {
+        let buffer = device.create_buffer(gfx::BufferInfo {
+            align_mask: 0b1111,
+            size: IRRADIANCE_COEFFICIENTS * std::mem::size_of::<[f32; 4]>(),
+            usage: gfx::BufferUsage::STORAGE,
+        })?;
+
+        let descriptor_set = make_descriptor_set(
+            device,
+            &self.irradiance_layout,
+            gfx::CombinedImageSampler {
+                view: skybox.clone(),
+                layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                sampler: self.linear_sampler.clone(),
+            },
+            gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(buffer.clone())]),
+        )?;
+
+        let lod = (SKYBOX_SIZE / IRRADIANCE_SOURCE_SIZE).ilog2();
+        let layout = &self.irradiance_pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.irradiance_pipeline);
+        encoder.bind_compute_descriptor_sets(layout, 0, &[&descriptor_set], &[]);
+        encoder.push_constants(
+            layout,
+            gfx::ShaderStageFlags::COMPUTE,
+            0,
+            &[IrradiancePushConstants {
+                face_size: IRRADIANCE_SOURCE_SIZE,
+                lod: lod as f32,
+            }],
+        );
+        encoder.dispatch(1, 1, 1);
+
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::SHADER_WRITE,
+            gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            gfx::AccessFlags::SHADER_READ,
+        );
+
+        Ok(buffer)
+    }
+}
+
+fn make_cube_image(
+    device: &gfx::Device,
+    size: u32,
+    mip_levels: u32,
+    usage: gfx::ImageUsageFlags,
+) -> Result<gfx::Image> {
+    Ok(device.create_image(gfx::ImageInfo {
+        extent: gfx::ImageExtent::D2 {
+            width: size,
+            height: size,
+        },
+        format: gfx::Format::RGBA16Sfloat,
+        mip_levels,
+        samples: gfx::Samples::_1,
+        array_layers: CUBE_FACES,
+        flags: gfx::ImageFlags::CUBE_COMPATIBLE,
+        usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::STORAGE | usage,
+    })?)
+}
+
+/// All faces of a single mip, written by compute shaders as an array.
+fn make_face_view(device: &gfx::Device, image: &gfx::Image, level: u32) -> Result<gfx::ImageView> {
+    Ok(device.create_image_view(gfx::ImageViewInfo {
+        ty: gfx::ImageViewType::D2Array,
+        range: gfx::ImageSubresourceRange::color(level..level + 1, 0..CUBE_FACES),
+        ..gfx::ImageViewInfo::new(image.clone())
+    })?)
+}
+
+fn make_cube_view(device: &gfx::Device, image: gfx::Image) -> Result<gfx::ImageView> {
+    Ok(device.create_image_view(gfx::ImageViewInfo {
+        ty: gfx::ImageViewType::Cube,
+        ..gfx::ImageViewInfo::new(image)
+    })?)
+}
+
+fn make_descriptor_set(
+    device: &gfx::Device,
+    layout: &gfx::DescriptorSetLayout,
+    source: gfx::CombinedImageSampler,
+    output: gfx::DescriptorSlice<'_>,
+) -> Result<gfx::DescriptorSet> {
+    let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+        layout: layout.clone(),
+    })?;
+    device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+        set: &descriptor_set,
+        writes: &[
+            gfx::DescriptorSetWrite {
+                binding: 0,
+                element: 0,
+                data: gfx::DescriptorSlice::CombinedImageSampler(&[source]),
+            },
+            gfx::DescriptorSetWrite {
+                binding: 1,
+                element: 0,
+                data: output,
+            },
+        ],
+    }]);
+    Ok(descriptor_set)
+}
+
+const CUBE_FACES: u32 = 6;
+const SKYBOX_SIZE: u32 = 512;
+const SPECULAR_SIZE: u32 = 128;
+/// Must match `ENVIRONMENT_SPECULAR_MIPS` in `uniforms/environment.glsl`.
+const SPECULAR_MIP_LEVELS: u32 = 5;
+const SPECULAR_SAMPLE_COUNT: u32 = 256;
+/// Face size of the skybox mip projected onto spherical harmonics.
+const IRRADIANCE_SOURCE_SIZE: u32 = 32;
+const IRRADIANCE_COEFFICIENTS: usize = 9;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PrefilterPushConstants {
+    size: u32,
+    roughness: f32,
+    sample_count: u32,
+    source_size: f32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for PrefilterPushConstants {}
+unsafe impl bytemuck::Zeroable for PrefilterPushConstants {}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IrradiancePushConstants {
+    face_size: u32,
+    lod: f32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for IrradiancePushConstants {}
+unsafe impl bytemuck::Zeroable for IrradiancePushConstants {}
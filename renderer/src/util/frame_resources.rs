@@ -3,10 +3,10 @@ use std::sync::{Mutex, MutexGuard};
 
 use anyhow::Result;
 use gfx::AsStd140;
-use glam::{Mat4, UVec2};
+use glam::{Mat4, UVec2, Vec2};
 
-use crate::types::CameraProjection;
-use crate::util::Frustum;
+use crate::types::{CameraProjection, RenderLayers, RenderPassKind};
+use crate::util::{EnvironmentHandles, Frustum, SampledImageHandle};
 
 pub struct FrameResources {
     descriptor_set_layout: gfx::DescriptorSetLayout,
@@ -77,6 +77,19 @@ impl FrameResources {
         camera.updated = true;
     }
 
+    pub fn set_camera_render_layers(&self, render_layers: RenderLayers) {
+        self.camera_data.lock().unwrap().render_layers = render_layers;
+    }
+
+    pub fn set_pass_render_layers(&self, pass: RenderPassKind, render_layers: RenderLayers) {
+        self.camera_data.lock().unwrap().pass_render_layers[pass as usize] = render_layers;
+    }
+
+    /// Returns layers of the objects drawn by a pass.
+    pub fn pass_render_layers(&self, pass: RenderPassKind) -> RenderLayers {
+        self.camera_data.lock().unwrap().pass_render_layers(pass)
+    }
+
     /// Update the uniform buffer and return the byte offset of the updated data
     pub fn flush(&self, args: FlushFrameResources) -> FrameResourcesGuard<'_> {
         const TIME_ROLLOVER: f32 = 3600.0;
@@ -90,13 +103,38 @@ impl FrameResources {
         globals.time = (globals.time + args.delta_time) % TIME_ROLLOVER;
         globals.delta_time = args.delta_time;
         globals.frame_index = args.frame;
+        globals.camera_render_layers =
+            camera_data.pass_render_layers(RenderPassKind::Main).bits() as u32;
+        globals.picking_render_layers = camera_data
+            .pass_render_layers(RenderPassKind::Picking)
+            .bits() as u32;
+        globals.camera_jitter = if args.jitter {
+            // NOTE: Offset in pixels is converted into NDC, which spans 2 units
+            compute_jitter(args.frame) * 2.0 / args.render_resolution.as_vec2()
+        } else {
+            Vec2::ZERO
+        };
+        globals.ambient_occlusion_texture = args
+            .ambient_occlusion
+            .map_or(NO_RESOURCE, |handle| handle.index());
+        globals.environment_skybox = args
+            .environment
+            .map_or(NO_RESOURCE, |handles| handles.skybox.index());
+        globals.environment_specular = args
+            .environment
+            .map_or(NO_RESOURCE, |handles| handles.specular.index());
+        globals.environment_irradiance = args
+            .environment
+            .map_or(NO_RESOURCE, |handles| handles.irradiance.index());
+
+        // NOTE: Previous matrices are used for motion vectors,
+        // so they must match the previous frame even without camera updates.
+        globals.camera_previous_view = globals.camera_view;
+        globals.camera_previous_projection = globals.camera_projection;
 
         if std::mem::take(&mut camera_data.updated)
             || args.render_resolution != globals.render_resolution
         {
-            globals.camera_previous_view = globals.camera_view;
-            globals.camera_previous_projection = globals.camera_projection;
-
             let aspect_ratio = args.render_resolution.x as f32 / args.render_resolution.y as f32;
             globals.render_resolution = args.render_resolution;
             globals.camera_view = camera_data.view;
@@ -143,6 +181,32 @@ pub struct FlushFrameResources {
     pub render_resolution: UVec2,
     pub delta_time: f32,
     pub frame: u32,
+    /// Whether to offset the projection by a different sub-pixel amount each frame.
+    pub jitter: bool,
+    /// Ambient occlusion computed in the previous frame, if any.
+    pub ambient_occlusion: Option<SampledImageHandle>,
+    /// Converted environment used for the skybox and image-based lighting, if any.
+    pub environment: Option<EnvironmentHandles>,
+}
+
+/// Returns a sub-pixel offset in `-0.5..0.5` from a Halton (2, 3) sequence.
+fn compute_jitter(frame: u32) -> Vec2 {
+    const SEQUENCE_LEN: u32 = 8;
+
+    fn halton(mut index: u32, base: u32) -> f32 {
+        let mut result = 0.0;
+        let mut fraction = 1.0;
+        while index > 0 {
+            fraction /= base as f32;
+            result += fraction * (index % base) as f32;
+            index /= base;
+        }
+        result
+    }
+
+    // NOTE: The sequence starts from 1 to skip the zero offset
+    let index = frame % SEQUENCE_LEN + 1;
+    Vec2::new(halton(index, 2), halton(index, 3)) - 0.5
 }
 
 struct UniformBuffer {
@@ -221,6 +285,26 @@ pub struct FrameGlobals {
     pub time: f32,
     pub delta_time: f32,
     pub frame_index: u32,
+    /// Layers of the camera combined with the layers of the main pass.
+    pub camera_render_layers: u32,
+    /// Sub-pixel offset in NDC which is added to clip space positions.
+    pub camera_jitter: Vec2,
+    /// Bindless index of the ambient occlusion of the previous frame,
+    /// or `u32::MAX` if there is none.
+    pub ambient_occlusion_texture: u32,
+    /// Bindless indices of the environment cubemaps and irradiance buffer,
+    /// or `u32::MAX` if there is no environment.
+    pub environment_skybox: u32,
+    pub environment_specular: u32,
+    pub environment_irradiance: u32,
+    /// Layers of the camera combined with the layers of the picking pass.
+    pub picking_render_layers: u32,
+}
+
+impl FrameGlobals {
+    pub fn render_layers(&self) -> RenderLayers {
+        RenderLayers::from_bits(self.camera_render_layers as u16)
+    }
 }
 
 impl Default for FrameGlobals {
@@ -237,26 +321,85 @@ impl Default for FrameGlobals {
             time: 0.0,
             delta_time: f32::EPSILON,
             frame_index: 0,
+            camera_render_layers: RenderLayers::DEFAULT.bits() as u32,
+            camera_jitter: Vec2::ZERO,
+            ambient_occlusion_texture: NO_RESOURCE,
+            environment_skybox: NO_RESOURCE,
+            environment_specular: NO_RESOURCE,
+            environment_irradiance: NO_RESOURCE,
+            picking_render_layers: RenderLayers::DEFAULT.bits() as u32,
         }
     }
 }
 
+const NO_RESOURCE: u32 = u32::MAX;
+
 type GpuFrameGlobals = <FrameGlobals as AsStd140>::Output;
 
 struct CameraData {
     view: Mat4,
     projection: CameraProjection,
+    render_layers: RenderLayers,
+    pass_render_layers: [RenderLayers; RenderPassKind::COUNT],
     initialized: bool,
     updated: bool,
 }
 
+impl CameraData {
+    fn pass_render_layers(&self, pass: RenderPassKind) -> RenderLayers {
+        self.render_layers
+            .intersection(self.pass_render_layers[pass as usize])
+    }
+}
+
 impl Default for CameraData {
     fn default() -> Self {
         Self {
             view: Mat4::IDENTITY,
             projection: CameraProjection::default(),
+            render_layers: RenderLayers::DEFAULT,
+            pass_render_layers: [RenderLayers::ALL; RenderPassKind::COUNT],
             initialized: false,
             updated: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_is_sub_pixel() {
+        let offsets = (0..8).map(compute_jitter).collect::<Vec<_>>();
+        assert_eq!(offsets[0], Vec2::new(0.0, 1.0 / 3.0 - 0.5));
+        assert_eq!(compute_jitter(8), offsets[0]);
+        for (i, offset) in offsets.iter().enumerate() {
+            assert!(offset.abs().max_element() < 0.5);
+            assert!(!offsets[..i].contains(offset));
+        }
+    }
+
+    #[test]
+    fn pass_layers_are_combined_with_camera_layers() {
+        let mut camera = CameraData {
+            render_layers: RenderLayers::layer(0).with(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            camera.pass_render_layers(RenderPassKind::Debug),
+            camera.render_layers
+        );
+
+        camera.pass_render_layers[RenderPassKind::Picking as usize] =
+            RenderLayers::layer(1).with(2);
+        assert_eq!(
+            camera.pass_render_layers(RenderPassKind::Picking),
+            RenderLayers::layer(1)
+        );
+        assert_eq!(
+            camera.pass_render_layers(RenderPassKind::Main),
+            camera.render_layers
+        );
+    }
+}
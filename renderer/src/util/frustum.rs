@@ -109,6 +109,37 @@ impl Frustum {
             && self.top.distance_to_point(sphere.center) >= neg_radius
             && self.bottom.distance_to_point(sphere.center) >= neg_radius
     }
+
+    /// Returns `true` if the given bounding box is entirely inside the frustum.
+    pub fn contains_aabb(&self, aabb: &BoundingBox) -> bool {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+        // The box is inside if its farthest corner along each normal is behind the plane.
+        self.planes()
+            .iter()
+            .all(|plane| plane.distance_to_point(center) >= plane.projected_radius(half_extents))
+    }
+
+    /// Returns `true` if the given bounding box is at least partially inside the frustum.
+    ///
+    /// This test is conservative, i.e. some boxes near frustum corners may pass it.
+    pub fn intersects_aabb(&self, aabb: &BoundingBox) -> bool {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+        self.planes()
+            .iter()
+            .all(|plane| plane.distance_to_point(center) >= -plane.projected_radius(half_extents))
+    }
+
+    /// Culling test which checks the bounding sphere first and then
+    /// the tighter bounding box.
+    pub fn intersects_bounds(&self, sphere: &BoundingSphere, aabb: &BoundingBox) -> bool {
+        self.contains_sphere(sphere) && self.intersects_aabb(aabb)
+    }
+
+    fn planes(&self) -> [&Plane; 5] {
+        [&self.near, &self.left, &self.right, &self.top, &self.bottom]
+    }
 }
 
 /// Plane in 3D space.
@@ -138,6 +169,11 @@ impl Plane {
         // Project "origin to point" vector onto plane normal and add distance along normal.
         self.normal.dot(point) + self.distance
     }
+
+    /// Returns the extent of a box with the given half extents along the plane normal.
+    fn projected_radius(&self, half_extents: Vec3) -> f32 {
+        self.normal.abs().dot(half_extents)
+    }
 }
 
 impl gfx::AsStd140 for Plane {
@@ -236,6 +272,77 @@ impl BoundingSphere {
     }
 }
 
+/// Axis-aligned bounding box of a mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    /// Computes the bounding box of the given list of positions.
+    pub fn compute_from_positions(positions: &[Position]) -> Self {
+        if positions.is_empty() {
+            return Self {
+                min: Vec3::ZERO,
+                max: Vec3::ZERO,
+            };
+        }
+
+        let (min, max) = positions.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(p.0), max.max(p.0)),
+        );
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Returns `true` if the given point is inside the bounding box.
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Grows the bounding box by the given amount in each direction.
+    pub fn expanded(self, amount: Vec3) -> Self {
+        Self {
+            min: self.min - amount,
+            max: self.max + amount,
+        }
+    }
+
+    /// Transforms the bounding box by the given transform matrix.
+    ///
+    /// The result is the smallest axis-aligned box containing the transformed box,
+    /// so it stays correct for rotated and non-uniformly scaled objects.
+    /// The transform must be affine, a perspective projection gives a wrong box.
+    pub fn transformed(self, transform: &Mat4) -> Self {
+        let center = transform.transform_point3(self.center());
+        let half_extents = self.half_extents();
+        let half_extents = transform.x_axis.xyz().abs() * half_extents.x
+            + transform.y_axis.xyz().abs() * half_extents.y
+            + transform.z_axis.xyz().abs() * half_extents.z;
+
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+}
+
+impl From<BoundingBox> for [Vec4; 2] {
+    #[inline]
+    fn from(value: BoundingBox) -> Self {
+        [value.min.extend(0.0), value.max.extend(0.0)]
+    }
+}
+
 impl gfx::AsStd140 for BoundingSphere {
     type Output = Vec4;
 
@@ -277,3 +384,72 @@ impl From<&BoundingSphere> for Vec4 {
         value.center.extend(value.radius)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_frustum() -> Frustum {
+        let projection = Mat4::perspective_infinite_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1);
+        Frustum::new(projection)
+    }
+
+    #[test]
+    fn rotated_box_stays_tight() {
+        let aabb = BoundingBox {
+            min: Vec3::new(-10.0, -0.5, -0.5),
+            max: Vec3::new(10.0, 0.5, 0.5),
+        };
+
+        let rotation = Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let rotated = aabb.transformed(&rotation);
+        assert!(
+            (rotated.min - Vec3::new(-0.5, -0.5, -10.0))
+                .abs()
+                .max_element()
+                < 1e-5
+        );
+        assert!(
+            (rotated.max - Vec3::new(0.5, 0.5, 10.0))
+                .abs()
+                .max_element()
+                < 1e-5
+        );
+
+        let scaled = aabb.transformed(&Mat4::from_scale(Vec3::new(2.0, 3.0, 1.0)));
+        assert_eq!(scaled.min, Vec3::new(-20.0, -1.5, -0.5));
+        assert_eq!(scaled.max, Vec3::new(20.0, 1.5, 0.5));
+    }
+
+    #[test]
+    fn frustum_aabb_tests() {
+        let frustum = test_frustum();
+
+        // A long thin wall next to the right plane: the sphere passes, the box does not.
+        let wall = BoundingBox {
+            min: Vec3::new(2.0, -0.5, -1.5),
+            max: Vec3::new(12.0, 0.5, -0.5),
+        };
+        let sphere = BoundingSphere {
+            center: wall.center(),
+            radius: wall.half_extents().length(),
+        };
+        assert!(frustum.contains_sphere(&sphere));
+        assert!(!frustum.intersects_aabb(&wall));
+        assert!(!frustum.intersects_bounds(&sphere, &wall));
+
+        let inside = BoundingBox {
+            min: Vec3::new(-1.0, -1.0, -6.0),
+            max: Vec3::new(1.0, 1.0, -4.0),
+        };
+        assert!(frustum.contains_aabb(&inside));
+        assert!(frustum.intersects_aabb(&inside));
+
+        let partial = BoundingBox {
+            min: Vec3::new(-1.0, -1.0, -6.0),
+            max: Vec3::new(10.0, 1.0, -4.0),
+        };
+        assert!(!frustum.contains_aabb(&partial));
+        assert!(frustum.intersects_aabb(&partial));
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use shared::FastHashMap;
+
+/// Size of the FIFO cache used to compute ACMR.
+pub const ACMR_CACHE_SIZE: usize = 16;
+
+/// Computes a remap table which merges vertices with identical data in all streams.
+///
+/// Each stream must contain data for exactly `vertex_count` vertices.
+/// Returns the remap table and the number of unique vertices.
+pub fn weld_vertices(vertex_count: usize, streams: &[&[u8]]) -> (Vec<u32>, usize) {
+    let streams = streams
+        .iter()
+        .map(|data| {
+            debug_assert_eq!(data.len() % vertex_count.max(1), 0);
+            (*data, data.len() / vertex_count.max(1))
+        })
+        .collect::<Vec<_>>();
+
+    let mut remap = Vec::with_capacity(vertex_count);
+    let mut unique =
+        FastHashMap::<VertexKey, u32>::with_capacity_and_hasher(vertex_count, Default::default());
+    for index in 0..vertex_count {
+        let key = VertexKey {
+            streams: &streams,
+            index,
+        };
+        let next = unique.len() as u32;
+        remap.push(*unique.entry(key).or_insert(next));
+    }
+
+    (remap, unique.len())
+}
+
+/// Reorders triangles to improve the post-transform vertex cache utilization.
+///
+/// Uses Tom Forsyth's "Linear-Speed Vertex Cache Optimisation" algorithm.
+pub fn optimize_vertex_cache(indices: &mut [u32], vertex_count: usize) {
+    const CACHE_SIZE: usize = 32;
+
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return;
+    }
+
+    // Build vertex-triangle adjacency
+    let mut live_triangles = vec![0u32; vertex_count];
+    for &index in indices.iter() {
+        live_triangles[index as usize] += 1;
+    }
+
+    let mut adjacency_offsets = Vec::with_capacity(vertex_count);
+    let mut offset = 0;
+    for &count in &live_triangles {
+        adjacency_offsets.push(offset);
+        offset += count as usize;
+    }
+
+    let mut adjacency = vec![0u32; indices.len()];
+    let mut fill = adjacency_offsets.clone();
+    for (triangle, vertices) in indices.chunks_exact(3).enumerate() {
+        for &vertex in vertices {
+            adjacency[fill[vertex as usize]] = triangle as u32;
+            fill[vertex as usize] += 1;
+        }
+    }
+
+    // Compute initial scores
+    let mut cache_positions = vec![None; vertex_count];
+    let mut vertex_scores = live_triangles
+        .iter()
+        .map(|&live| vertex_score(None, live, CACHE_SIZE))
+        .collect::<Vec<_>>();
+    let mut triangle_scores = indices
+        .chunks_exact(3)
+        .map(|vertices| {
+            vertices
+                .iter()
+                .map(|&vertex| vertex_scores[vertex as usize])
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut emitted = vec![false; triangle_count];
+    let mut output = Vec::with_capacity(indices.len());
+    let mut cache = Vec::<u32>::with_capacity(CACHE_SIZE + 3);
+    let mut new_cache = Vec::<u32>::with_capacity(CACHE_SIZE + 3);
+
+    let mut best_triangle = triangle_scores
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(triangle, _)| triangle);
+    let mut cursor = 0;
+
+    while output.len() < indices.len() {
+        let triangle = match best_triangle {
+            Some(triangle) => triangle,
+            None => {
+                // Cache has no triangles left, continue with the next unused one
+                while emitted[cursor] {
+                    cursor += 1;
+                }
+                cursor
+            }
+        };
+
+        emitted[triangle] = true;
+        let vertices = [
+            indices[triangle * 3],
+            indices[triangle * 3 + 1],
+            indices[triangle * 3 + 2],
+        ];
+        output.extend_from_slice(&vertices);
+
+        // Remove the triangle from the adjacency of its vertices
+        for vertex in vertices {
+            let vertex = vertex as usize;
+            let start = adjacency_offsets[vertex];
+            let live = &mut adjacency[start..start + live_triangles[vertex] as usize];
+            if let Some(position) = live.iter().position(|&t| t == triangle as u32) {
+                let last = live.len() - 1;
+                live.swap(position, last);
+                live_triangles[vertex] -= 1;
+            }
+        }
+
+        // Move triangle vertices to the front of the LRU cache
+        new_cache.clear();
+        new_cache.extend_from_slice(&vertices);
+        for &vertex in &cache {
+            if !vertices.contains(&vertex) {
+                new_cache.push(vertex);
+            }
+        }
+
+        // Update scores of all affected vertices and their triangles
+        for (position, &vertex) in new_cache.iter().enumerate() {
+            let vertex = vertex as usize;
+            cache_positions[vertex] = (position < CACHE_SIZE).then_some(position);
+
+            let score = vertex_score(cache_positions[vertex], live_triangles[vertex], CACHE_SIZE);
+            let diff = score - vertex_scores[vertex];
+            vertex_scores[vertex] = score;
+
+            let start = adjacency_offsets[vertex];
+            for &t in &adjacency[start..start + live_triangles[vertex] as usize] {
+                triangle_scores[t as usize] += diff;
+            }
+        }
+        new_cache.truncate(CACHE_SIZE);
+        std::mem::swap(&mut cache, &mut new_cache);
+
+        // Find the best triangle among the cached ones
+        best_triangle = None;
+        let mut best_score = f32::MIN;
+        for &vertex in &cache {
+            let start = adjacency_offsets[vertex as usize];
+            for &t in &adjacency[start..start + live_triangles[vertex as usize] as usize] {
+                let score = triangle_scores[t as usize];
+                if score > best_score {
+                    best_score = score;
+                    best_triangle = Some(t as usize);
+                }
+            }
+        }
+    }
+
+    indices.copy_from_slice(&output);
+}
+
+/// Renumbers vertices in the order of their first use.
+///
+/// Returns the remap table (`u32::MAX` for unused vertices) and the number of used vertices.
+pub fn optimize_vertex_fetch(indices: &mut [u32], vertex_count: usize) -> (Vec<u32>, usize) {
+    let mut remap = vec![u32::MAX; vertex_count];
+    let mut next = 0;
+    for index in indices {
+        let new_index = &mut remap[*index as usize];
+        if *new_index == u32::MAX {
+            *new_index = next;
+            next += 1;
+        }
+        *index = *new_index;
+    }
+    (remap, next as usize)
+}
+
+/// Moves vertex data according to the remap table.
+pub fn remap_vertices<T: Copy>(data: &mut Vec<T>, remap: &[u32], new_vertex_count: usize) {
+    if data.is_empty() {
+        return;
+    }
+
+    let mut result = vec![data[0]; new_vertex_count];
+    for (item, &new_index) in std::iter::zip(data.iter(), remap) {
+        if new_index != u32::MAX {
+            result[new_index as usize] = *item;
+        }
+    }
+    *data = result;
+}
+
+/// Computes the average number of transformed vertices per triangle
+/// for a FIFO cache of the specified size.
+pub fn compute_acmr(indices: &[u32], cache_size: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return 0.0;
+    }
+
+    let mut cache = VecDeque::with_capacity(cache_size);
+    let mut misses = 0usize;
+    for &index in indices {
+        if !cache.contains(&index) {
+            misses += 1;
+            if cache.len() == cache_size {
+                cache.pop_front();
+            }
+            cache.push_back(index);
+        }
+    }
+
+    misses as f32 / triangle_count as f32
+}
+
+fn vertex_score(cache_position: Option<usize>, live_triangles: u32, cache_size: usize) -> f32 {
+    const CACHE_DECAY_POWER: f32 = 1.5;
+    const LAST_TRIANGLE_SCORE: f32 = 0.75;
+    const VALENCE_BOOST_SCALE: f32 = 2.0;
+    const VALENCE_BOOST_POWER: f32 = 0.5;
+
+    if live_triangles == 0 {
+        // No triangles left to emit
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        None => 0.0,
+        // Vertices of the last triangle get a fixed score to avoid
+        // emitting triangles with the same vertices in a row
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (cache_size - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+
+    cache_score + VALENCE_BOOST_SCALE * (live_triangles as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+struct VertexKey<'a> {
+    streams: &'a [(&'a [u8], usize)],
+    index: usize,
+}
+
+impl VertexKey<'_> {
+    fn stream_data(&self) -> impl Iterator<Item = &[u8]> {
+        self.streams
+            .iter()
+            .map(|(data, stride)| &data[self.index * stride..(self.index + 1) * stride])
+    }
+}
+
+impl Hash for VertexKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for data in self.stream_data() {
+            state.write(data);
+        }
+    }
+}
+
+impl PartialEq for VertexKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stream_data().eq(other.stream_data())
+    }
+}
+
+impl Eq for VertexKey<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Triangulated grid of `size` by `size` quads.
+    fn grid(size: u32) -> Vec<u32> {
+        let mut indices = Vec::new();
+        for z in 0..size {
+            for x in 0..size {
+                let a = z * (size + 1) + x;
+                let (b, c, d) = (a + 1, a + size + 1, a + size + 2);
+                indices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+        indices
+    }
+
+    #[test]
+    fn weld_identical_vertices() {
+        let positions: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [0.0, 0.0]];
+        let colors: [u8; 4] = [1, 2, 1, 3];
+
+        let (remap, unique) = weld_vertices(
+            4,
+            &[
+                bytemuck::cast_slice(&positions),
+                bytemuck::cast_slice(&colors),
+            ],
+        );
+        assert_eq!(unique, 3);
+        assert_eq!(remap, [0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn vertex_cache_reduces_acmr() {
+        let size = 32;
+        let vertex_count = ((size + 1) * (size + 1)) as usize;
+
+        // Shuffle triangles to make the initial order bad for the cache
+        let original = grid(size);
+        let triangle_count = original.len() / 3;
+        let mut indices = Vec::with_capacity(original.len());
+        for i in 0..triangle_count {
+            let triangle = (i * 7919) % triangle_count;
+            indices.extend_from_slice(&original[triangle * 3..triangle * 3 + 3]);
+        }
+
+        let before = compute_acmr(&indices, ACMR_CACHE_SIZE);
+        optimize_vertex_cache(&mut indices, vertex_count);
+        let after = compute_acmr(&indices, ACMR_CACHE_SIZE);
+        assert!(after < before * 0.5, "before: {before}, after: {after}");
+        assert!(after < 1.0);
+
+        // All triangles must be preserved
+        let mut sorted = indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect::<Vec<_>>();
+        let mut expected = original
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect::<Vec<_>>();
+        sorted.sort();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn vertex_fetch_order() {
+        let mut indices = vec![5, 3, 1, 3, 5, 6];
+        let (remap, used) = optimize_vertex_fetch(&mut indices, 7);
+        assert_eq!(used, 4);
+        assert_eq!(indices, [0, 1, 2, 1, 0, 3]);
+
+        let mut data = vec![0, 10, 20, 30, 40, 50, 60];
+        remap_vertices(&mut data, &remap, used);
+        assert_eq!(data, [50, 30, 10, 60]);
+    }
+}
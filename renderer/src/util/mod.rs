@@ -1,27 +1,51 @@
+pub use self::ambient_occlusion::AmbientOcclusion;
+pub use self::auto_exposure::AutoExposure;
 pub use self::bindless_resources::{
-    AtomicStorageBufferHandle, BindlessResources, StorageBufferHandle,
+    AtomicStorageBufferHandle, BindlessResources, BindlessSlotUsage, SampledImageHandle,
+    StorageBufferHandle,
 };
+pub use self::bloom::Bloom;
+pub use self::depth_pyramid::DepthPyramid;
+pub use self::depth_resolve::DepthResolve;
 pub use self::encoder::{CachedGraphicsPipeline, EncoderExt, RenderPass, RenderPassEncoderExt};
+pub use self::environment::{Environment, EnvironmentHandles};
 pub use self::frame_resources::{FlushFrameResources, FrameGlobals, FrameResources};
 pub use self::freelist_double_buffer::FreelistDoubleBuffer;
-pub use self::frustum::{BoundingSphere, Frustum};
-pub use self::multi_buffer_arena::MultiBufferArena;
+pub use self::frustum::{BoundingBox, BoundingSphere, Frustum, Plane};
+pub use self::multi_buffer_arena::{BufferArena, MultiBufferArena};
+pub use self::occlusion_culling::{
+    OcclusionCandidates, OcclusionCulling, OcclusionDrawMode, OcclusionDraws, OcclusionPass,
+};
 pub use self::resource_handle::{
-    FreelistHandleAllocator, HandleAllocator, HandleData, HandleDeleter, RawResourceHandle,
-    ResourceHandle, SimpleHandleAllocator,
+    FreelistHandleAllocator, HandleAllocator, HandleData, HandleDeleter, LiveHandle,
+    RawResourceHandle, ResourceHandle,
 };
+pub use self::resource_report::ResourceReport;
 pub use self::scatter_copy::{ScatterCopy, ScatterData};
 pub use self::shader_preprocessor::ShaderPreprocessor;
+pub use self::source_cache::SourceCache;
+pub use self::temporal_aa::{TaaInput, TemporalAntiAliasing};
 pub use self::virtual_fs::{VirtualFs, VirtualPath};
 
+mod ambient_occlusion;
+mod auto_exposure;
 mod bindless_resources;
+mod bloom;
+mod depth_pyramid;
+mod depth_resolve;
 mod device_seletor;
 mod encoder;
+mod environment;
 mod frame_resources;
 mod freelist_double_buffer;
 mod frustum;
+pub mod mesh_optimizer;
 mod multi_buffer_arena;
+mod occlusion_culling;
 mod resource_handle;
+mod resource_report;
 mod scatter_copy;
 mod shader_preprocessor;
+mod source_cache;
+mod temporal_aa;
 mod virtual_fs;
@@ -0,0 +1,402 @@
+use anyhow::Result;
+use glam::{Mat4, UVec2, UVec4, Vec4};
+
+use crate::managers::MeshManager;
+use crate::util::{BoundingBox, DepthPyramid, MultiBufferArena, ShaderPreprocessor};
+
+/// Stage of the two-pass occlusion culling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OcclusionPass {
+    /// Tests objects against the depth pyramid of the previous frame.
+    Early,
+    /// Re-tests objects rejected by the early pass against the depth pyramid
+    /// built after the early pass, so newly visible objects do not pop in.
+    Late,
+}
+
+/// How the draws of the candidates are recorded, depending on the device features.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OcclusionDrawMode {
+    /// A single indirect draw per index type.
+    #[default]
+    MultiDrawIndirect,
+    /// An indirect draw per candidate, without the `MultiDrawIndirect` feature.
+    DrawIndirect,
+    /// Without the `DrawIndirectFirstInstance` feature indirect draws can not
+    /// select the object slot, so all candidates are drawn by the CPU in the
+    /// early pass and nothing is occlusion culled.
+    Direct,
+}
+
+impl OcclusionDrawMode {
+    pub fn from_features(multi_draw_indirect: bool, draw_indirect_first_instance: bool) -> Self {
+        match (multi_draw_indirect, draw_indirect_first_instance) {
+            (_, false) => Self::Direct,
+            (false, true) => Self::DrawIndirect,
+            (true, true) => Self::MultiDrawIndirect,
+        }
+    }
+}
+
+/// Compute-based occlusion culling of objects against a [`DepthPyramid`].
+///
+/// Each candidate produces one indirect draw command per pass with
+/// the instance count set to zero when the object is culled.
+pub struct OcclusionCulling {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: gfx::ComputePipeline,
+    mode: OcclusionDrawMode,
+}
+
+impl OcclusionCulling {
+    #[tracing::instrument(level = "debug", name = "create_occlusion_culling", skip_all)]
+    pub fn new(
+        device: &gfx::Device,
+        shader_preprocessor: &ShaderPreprocessor,
+        mode: OcclusionDrawMode,
+    ) -> Result<Self> {
+        let shader = shader_preprocessor.begin().make_compute_shader(
+            device,
+            "/occlusion_cull.comp",
+            "main",
+        )?;
+
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 1,
+                        ty: gfx::DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 2,
+                        ty: gfx::DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: std::mem::size_of::<CullPushConstants>() as u32,
+            }],
+        })?;
+
+        let pipeline =
+            device.create_compute_pipeline(gfx::ComputePipelineInfo { shader, layout })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            mode,
+        })
+    }
+
+    /// Returns `false` if objects are drawn without occlusion culling.
+    pub fn is_enabled(&self) -> bool {
+        self.mode != OcclusionDrawMode::Direct
+    }
+
+    /// Uploads the candidates and prepares the draw commands for both passes.
+    pub fn prepare(
+        &self,
+        device: &gfx::Device,
+        buffers: &MultiBufferArena,
+        depth_pyramid: &DepthPyramid,
+        candidates: &OcclusionCandidates,
+        draws: &mut OcclusionDraws,
+    ) -> Result<()> {
+        let count = candidates.len();
+        draws.mode = self.mode;
+        draws.u16_count = candidates.u16.len() as u32;
+        draws.count = count as u32;
+        draws.descriptor_set = None;
+        draws.direct.clear();
+        if count == 0 {
+            return Ok(());
+        }
+
+        if self.mode == OcclusionDrawMode::Direct {
+            draws.direct.extend(
+                candidates
+                    .u16
+                    .iter()
+                    .chain(&candidates.u32)
+                    .map(|candidate| candidate.draw),
+            );
+            return Ok(());
+        }
+
+        let candidates_buffer = {
+            let mut arena =
+                buffers.begin::<GpuOcclusionCandidate>(device, count, gfx::BufferUsage::STORAGE)?;
+            for candidate in candidates.u16.iter().chain(&candidates.u32) {
+                arena.write(&gfx::AsStd430::as_std430(candidate));
+            }
+            buffers.end_raw(arena)
+        };
+
+        // NOTE: Commands are only written by the GPU, so they are kept in a
+        // device local buffer which is reused between frames.
+        let commands_size = count * 2 * COMMAND_STRIDE;
+        if !matches!(&draws.commands, Some(buffer) if buffer.info().size >= commands_size) {
+            draws.commands = Some(device.create_buffer(gfx::BufferInfo {
+                align_mask: 0b11,
+                size: commands_size.next_power_of_two(),
+                usage: gfx::BufferUsage::STORAGE | gfx::BufferUsage::INDIRECT,
+            })?);
+        }
+        let commands = draws.commands.clone().unwrap();
+
+        let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+            layout: self.descriptor_set_layout.clone(),
+        })?;
+        device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+            set: &descriptor_set,
+            writes: &[
+                gfx::DescriptorSetWrite {
+                    binding: 0,
+                    element: 0,
+                    data: gfx::DescriptorSlice::StorageBuffer(&[candidates_buffer]),
+                },
+                gfx::DescriptorSetWrite {
+                    binding: 1,
+                    element: 0,
+                    data: gfx::DescriptorSlice::StorageBuffer(&[gfx::BufferRange::whole(commands)]),
+                },
+                gfx::DescriptorSetWrite {
+                    binding: 2,
+                    element: 0,
+                    data: gfx::DescriptorSlice::CombinedImageSampler(&[
+                        gfx::CombinedImageSampler {
+                            view: depth_pyramid.view().clone(),
+                            layout: gfx::ImageLayout::General,
+                            sampler: depth_pyramid.sampler().clone(),
+                        },
+                    ]),
+                },
+            ],
+        }]);
+        draws.descriptor_set = Some(descriptor_set);
+
+        Ok(())
+    }
+
+    /// Fills the draw commands of the specified pass.
+    ///
+    /// The early pass draws everything if the depth pyramid has no history yet.
+    pub fn cull(
+        &self,
+        encoder: &mut gfx::Encoder,
+        depth_pyramid: &DepthPyramid,
+        draws: &OcclusionDraws,
+        pass: OcclusionPass,
+    ) {
+        let Some(descriptor_set) = &draws.descriptor_set else {
+            return;
+        };
+
+        let view_projection = depth_pyramid.view_projection();
+        let push_constants = CullPushConstants {
+            view_projection: view_projection.copied().unwrap_or(Mat4::IDENTITY),
+            depth_extent: depth_pyramid.depth_extent(),
+            pyramid_mip_levels: depth_pyramid.mip_levels(),
+            candidate_count: draws.count,
+            pass: match pass {
+                OcclusionPass::Early => 0,
+                OcclusionPass::Late => 1,
+            },
+            has_history: view_projection.is_some() as u32,
+            _padding: UVec2::ZERO,
+        };
+
+        if pass == OcclusionPass::Early {
+            // NOTE: Commands of the previous frame might still be in use.
+            encoder.memory_barrier(
+                gfx::PipelineStageFlags::DRAW_INDIRECT,
+                gfx::AccessFlags::INDIRECT_COMMAND_READ,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                gfx::AccessFlags::SHADER_WRITE,
+            );
+        }
+
+        encoder.bind_compute_pipeline(&self.pipeline);
+        encoder.bind_compute_descriptor_sets(
+            &self.pipeline.info().layout,
+            0,
+            &[descriptor_set],
+            &[],
+        );
+        encoder.push_constants(
+            &self.pipeline.info().layout,
+            gfx::ShaderStageFlags::COMPUTE,
+            0,
+            &[push_constants],
+        );
+        encoder.dispatch(draws.count.div_ceil(64), 1, 1);
+
+        encoder.memory_barrier(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::SHADER_WRITE,
+            gfx::PipelineStageFlags::DRAW_INDIRECT | gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::AccessFlags::INDIRECT_COMMAND_READ | gfx::AccessFlags::SHADER_READ,
+        );
+    }
+}
+
+/// Objects which passed the CPU-side culling, grouped by index type.
+#[derive(Default)]
+pub struct OcclusionCandidates {
+    u16: Vec<OcclusionCandidate>,
+    u32: Vec<OcclusionCandidate>,
+}
+
+impl OcclusionCandidates {
+    pub fn len(&self) -> usize {
+        self.u16.len() + self.u32.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.u16.clear();
+        self.u32.clear();
+    }
+
+    pub fn push(
+        &mut self,
+        index_type: gfx::IndexType,
+        bounding_box: &BoundingBox,
+        slot: u32,
+        indices: std::ops::Range<u32>,
+    ) {
+        let candidate = OcclusionCandidate {
+            bounding_box_min: bounding_box.min.extend(0.0),
+            bounding_box_max: bounding_box.max.extend(0.0),
+            draw: UVec4::new(slot, indices.start, indices.end - indices.start, 0),
+        };
+        match index_type {
+            gfx::IndexType::U16 => self.u16.push(candidate),
+            gfx::IndexType::U32 => self.u32.push(candidate),
+        }
+    }
+}
+
+/// Indirect draw commands of the objects, filled by [`OcclusionCulling`].
+#[derive(Default)]
+pub struct OcclusionDraws {
+    mode: OcclusionDrawMode,
+    commands: Option<gfx::Buffer>,
+    descriptor_set: Option<gfx::DescriptorSet>,
+    /// Object slot, first index and index count of each candidate in the direct mode.
+    direct: Vec<UVec4>,
+    u16_count: u32,
+    count: u32,
+}
+
+impl OcclusionDraws {
+    /// Draws objects which were not culled in the specified pass.
+    ///
+    /// The pipeline and push constants must already be set up.
+    pub fn draw(
+        &self,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        mesh_manager: &MeshManager,
+        pass: OcclusionPass,
+    ) {
+        if self.mode == OcclusionDrawMode::Direct {
+            if pass == OcclusionPass::Early {
+                self.draw_direct(encoder, mesh_manager);
+            }
+            return;
+        }
+
+        let (Some(commands), Some(_)) = (&self.commands, &self.descriptor_set) else {
+            return;
+        };
+
+        let mut offset = match pass {
+            OcclusionPass::Early => 0,
+            OcclusionPass::Late => self.count as usize * COMMAND_STRIDE,
+        };
+        for (index_type, count) in [
+            (gfx::IndexType::U16, self.u16_count),
+            (gfx::IndexType::U32, self.count - self.u16_count),
+        ] {
+            if count > 0 {
+                mesh_manager.bind_index_buffer(encoder, index_type);
+                if self.mode == OcclusionDrawMode::MultiDrawIndirect {
+                    encoder.draw_indexed_indirect(commands, offset, count, COMMAND_STRIDE as u32);
+                } else {
+                    for i in 0..count as usize {
+                        let offset = offset + i * COMMAND_STRIDE;
+                        encoder.draw_indexed_indirect(commands, offset, 1, COMMAND_STRIDE as u32);
+                    }
+                }
+            }
+            offset += count as usize * COMMAND_STRIDE;
+        }
+    }
+
+    fn draw_direct(
+        &self,
+        encoder: &mut gfx::RenderPassEncoder<'_, '_>,
+        mesh_manager: &MeshManager,
+    ) {
+        let (u16_draws, u32_draws) = self.direct.split_at(self.u16_count as usize);
+        for (index_type, draws) in [
+            (gfx::IndexType::U16, u16_draws),
+            (gfx::IndexType::U32, u32_draws),
+        ] {
+            if draws.is_empty() {
+                continue;
+            }
+            mesh_manager.bind_index_buffer(encoder, index_type);
+            for draw in draws {
+                encoder.draw_indexed(draw.y..draw.y + draw.z, 0, draw.x..draw.x + 1);
+            }
+        }
+    }
+}
+
+#[derive(gfx::AsStd430)]
+struct OcclusionCandidate {
+    bounding_box_min: Vec4,
+    bounding_box_max: Vec4,
+    /// Object slot, first index and index count.
+    draw: UVec4,
+}
+
+type GpuOcclusionCandidate = <OcclusionCandidate as gfx::AsStd430>::Output;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CullPushConstants {
+    view_projection: Mat4,
+    depth_extent: UVec2,
+    pyramid_mip_levels: u32,
+    candidate_count: u32,
+    pass: u32,
+    has_history: u32,
+    _padding: UVec2,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for CullPushConstants {}
+unsafe impl bytemuck::Zeroable for CullPushConstants {}
+
+const COMMAND_STRIDE: usize = std::mem::size_of::<gfx::DrawIndexedIndirectCommand>();
@@ -1,10 +1,16 @@
+use std::backtrace::Backtrace;
+#[cfg(debug_assertions)]
+use std::backtrace::BacktraceStatus;
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex, Weak};
 
 pub trait HandleAllocator<T: HandleData> {
     fn alloc(&self, deleter: Arc<T::Deleter>) -> ResourceHandle<T>;
     fn dealloc(&self, handle: RawResourceHandle<T>);
+
+    /// Returns whether the handle was allocated and not deallocated yet.
+    fn is_alive(&self, handle: RawResourceHandle<T>) -> bool;
 }
 
 pub trait HandleData: Send + Sync + 'static {
@@ -15,70 +21,153 @@ pub trait HandleDeleter<T: ?Sized>: Send + Sync + 'static {
     fn delete(&self, handle: RawResourceHandle<T>);
 }
 
-pub struct SimpleHandleAllocator<T> {
-    next: AtomicUsize,
-    _phantom: PhantomData<T>,
+/// Handle allocator which reuses indices of deallocated handles.
+///
+/// Each reuse bumps the index generation, so stale handles
+/// never compare equal to the new ones.
+pub struct FreelistHandleAllocator<T: HandleData> {
+    state: Mutex<FreelistState<T>>,
 }
 
-impl<T> Default for SimpleHandleAllocator<T> {
-    fn default() -> Self {
-        Self {
-            next: AtomicUsize::new(0),
-            _phantom: PhantomData,
-        }
-    }
+struct FreelistState<T: HandleData> {
+    slots: Vec<FreelistSlot<T>>,
+    free_list: Vec<usize>,
 }
 
-impl<T: HandleData> HandleAllocator<T> for SimpleHandleAllocator<T> {
-    fn alloc(&self, deleter: Arc<T::Deleter>) -> ResourceHandle<T> {
-        ResourceHandle {
-            index: self.next.fetch_add(1, Ordering::Relaxed),
-            refcount: deleter,
-        }
-    }
+struct FreelistSlot<T: HandleData> {
+    generation: u32,
+    alive: bool,
+    /// Shared reference count of the handle, used to find handles by index.
+    refcount: Weak<T::Deleter>,
+    /// Captured only if enabled with `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+    #[cfg(debug_assertions)]
+    backtrace: Option<Arc<Backtrace>>,
+}
 
-    fn dealloc(&self, _handle: RawResourceHandle<T>) {}
+/// A handle which was allocated and not deallocated yet.
+#[derive(Debug, Clone)]
+pub struct LiveHandle {
+    pub index: usize,
+    pub generation: u32,
+    /// Where the handle was allocated (debug builds only).
+    pub backtrace: Option<Arc<Backtrace>>,
 }
 
-pub struct FreelistHandleAllocator<T> {
-    next: AtomicUsize,
-    free_list: Mutex<Vec<usize>>,
-    _phantom: PhantomData<T>,
+impl<T: HandleData> FreelistHandleAllocator<T> {
+    /// Returns a new reference to the live handle at `index`.
+    ///
+    /// Returns `None` if the slot is free or all references to the handle
+    /// were already dropped.
+    pub fn find_alive(&self, index: usize) -> Option<ResourceHandle<T>> {
+        let state = self.state.lock().unwrap();
+        let slot = state.slots.get(index).filter(|slot| slot.alive)?;
+        let refcount = slot.refcount.upgrade()?;
+        Some(ResourceHandle {
+            index,
+            generation: slot.generation,
+            refcount: ManuallyDrop::new(refcount),
+        })
+    }
+
+    pub fn live_handles(&self) -> Vec<LiveHandle> {
+        let state = self.state.lock().unwrap();
+        state
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| LiveHandle {
+                index,
+                generation: slot.generation,
+                #[cfg(debug_assertions)]
+                backtrace: slot.backtrace.clone(),
+                #[cfg(not(debug_assertions))]
+                backtrace: None,
+            })
+            .collect()
+    }
 }
 
-impl<T> Default for FreelistHandleAllocator<T> {
+impl<T: HandleData> Default for FreelistHandleAllocator<T> {
     fn default() -> Self {
         Self {
-            next: AtomicUsize::new(0),
-            free_list: Mutex::new(Vec::new()),
-            _phantom: PhantomData,
+            state: Mutex::new(FreelistState {
+                slots: Vec::new(),
+                free_list: Vec::new(),
+            }),
         }
     }
 }
 
 impl<T: HandleData> HandleAllocator<T> for FreelistHandleAllocator<T> {
     fn alloc(&self, deleter: Arc<T::Deleter>) -> ResourceHandle<T> {
-        let index = self
-            .free_list
-            .lock()
-            .unwrap()
-            .pop()
-            .unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed));
+        let mut state = self.state.lock().unwrap();
+        let (index, generation) = match state.free_list.pop() {
+            Some(index) => {
+                let slot = &mut state.slots[index];
+                slot.generation = slot.generation.wrapping_add(1);
+                slot.alive = true;
+                slot.refcount = Arc::downgrade(&deleter);
+                #[cfg(debug_assertions)]
+                {
+                    slot.backtrace = capture_backtrace();
+                }
+                (index, slot.generation)
+            }
+            None => {
+                state.slots.push(FreelistSlot {
+                    generation: 0,
+                    alive: true,
+                    refcount: Arc::downgrade(&deleter),
+                    #[cfg(debug_assertions)]
+                    backtrace: capture_backtrace(),
+                });
+                (state.slots.len() - 1, 0)
+            }
+        };
 
         ResourceHandle {
             index,
-            refcount: deleter,
+            generation,
+            refcount: ManuallyDrop::new(deleter),
         }
     }
 
     fn dealloc(&self, handle: RawResourceHandle<T>) {
-        self.free_list.lock().unwrap().push(handle.index);
+        let mut state = self.state.lock().unwrap();
+        let slot = &mut state.slots[handle.index];
+        assert!(
+            slot.alive && slot.generation == handle.generation,
+            "double free of {handle:?}"
+        );
+        slot.alive = false;
+        slot.refcount = Weak::new();
+        #[cfg(debug_assertions)]
+        {
+            slot.backtrace = None;
+        }
+        state.free_list.push(handle.index);
+    }
+
+    fn is_alive(&self, handle: RawResourceHandle<T>) -> bool {
+        let state = self.state.lock().unwrap();
+        matches!(
+            state.slots.get(handle.index),
+            Some(slot) if slot.alive && slot.generation == handle.generation
+        )
     }
 }
 
+#[cfg(debug_assertions)]
+fn capture_backtrace() -> Option<Arc<Backtrace>> {
+    let backtrace = Backtrace::capture();
+    (backtrace.status() == BacktraceStatus::Captured).then(|| Arc::new(backtrace))
+}
+
 pub struct ResourceHandle<T: HandleData> {
     index: usize,
-    refcount: Arc<T::Deleter>,
+    generation: u32,
+    refcount: ManuallyDrop<Arc<T::Deleter>>,
 }
 
 impl<T: HandleData> ResourceHandle<T> {
@@ -86,9 +175,15 @@ impl<T: HandleData> ResourceHandle<T> {
         self.index
     }
 
+    /// Number of times the index was reused before this handle.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
     pub(crate) fn raw(&self) -> RawResourceHandle<T> {
         RawResourceHandle {
             index: self.index,
+            generation: self.generation,
             _phantom: Default::default(),
         }
     }
@@ -96,8 +191,13 @@ impl<T: HandleData> ResourceHandle<T> {
 
 impl<T: HandleData> Drop for ResourceHandle<T> {
     fn drop(&mut self) {
-        if Arc::strong_count(&self.refcount) == 1 {
-            self.refcount.delete(self.raw());
+        let raw = self.raw();
+        // SAFETY: `refcount` is not used after this point
+        let refcount = unsafe { ManuallyDrop::take(&mut self.refcount) };
+        // NOTE: `into_inner` is atomic, so exactly one of the concurrently
+        // dropped references (including the ones from `find_alive`) deletes the handle
+        if let Some(deleter) = Arc::into_inner(refcount) {
+            deleter.delete(raw);
         }
     }
 }
@@ -106,7 +206,8 @@ impl<T: HandleData> Clone for ResourceHandle<T> {
     fn clone(&self) -> Self {
         Self {
             index: self.index,
-            refcount: self.refcount.clone(),
+            generation: self.generation,
+            refcount: ManuallyDrop::new(Arc::clone(&self.refcount)),
         }
     }
 }
@@ -114,13 +215,14 @@ impl<T: HandleData> Clone for ResourceHandle<T> {
 impl<T: HandleData> Eq for ResourceHandle<T> {}
 impl<T: HandleData> PartialEq for ResourceHandle<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.index == other.index
+        self.index == other.index && self.generation == other.generation
     }
 }
 
 impl<T: HandleData> std::hash::Hash for ResourceHandle<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.index.hash(state)
+        self.index.hash(state);
+        self.generation.hash(state);
     }
 }
 
@@ -128,13 +230,15 @@ impl<T: HandleData> std::fmt::Debug for ResourceHandle<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ResourceHandle")
             .field("id", &self.index)
-            .field("refcount", &Arc::strong_count(&self.refcount))
+            .field("generation", &self.generation)
+            .field("refcount", &Arc::strong_count(&*self.refcount))
             .finish()
     }
 }
 
 pub struct RawResourceHandle<T: ?Sized> {
     pub index: usize,
+    pub generation: u32,
     _phantom: PhantomData<T>,
 }
 
@@ -150,6 +254,7 @@ impl<T: ?Sized> std::fmt::Debug for RawResourceHandle<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RawResourceHandle")
             .field("id", &self.index)
+            .field("generation", &self.generation)
             .finish()
     }
 }
@@ -158,13 +263,84 @@ impl<T: ?Sized> Eq for RawResourceHandle<T> {}
 impl<T: ?Sized> PartialEq for RawResourceHandle<T> {
     #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
-        self.index == other.index
+        self.index == other.index && self.generation == other.generation
     }
 }
 
 impl<T: ?Sized> std::hash::Hash for RawResourceHandle<T> {
     #[inline(always)]
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        std::hash::Hash::hash(&self.index, state)
+        std::hash::Hash::hash(&self.index, state);
+        std::hash::Hash::hash(&self.generation, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tag;
+
+    struct NoopDeleter;
+
+    impl HandleDeleter<Tag> for NoopDeleter {
+        fn delete(&self, _: RawResourceHandle<Tag>) {}
+    }
+
+    impl HandleData for Tag {
+        type Deleter = NoopDeleter;
+    }
+
+    #[test]
+    fn reused_index_has_new_generation() {
+        let allocator = FreelistHandleAllocator::<Tag>::default();
+
+        let first = allocator.alloc(Arc::new(NoopDeleter)).raw();
+        let second = allocator.alloc(Arc::new(NoopDeleter)).raw();
+        assert_eq!((first.index, first.generation), (0, 0));
+        assert_eq!((second.index, second.generation), (1, 0));
+
+        allocator.dealloc(first);
+        assert!(!allocator.is_alive(first));
+        assert!(allocator.is_alive(second));
+
+        let reused = allocator.alloc(Arc::new(NoopDeleter)).raw();
+        assert_eq!((reused.index, reused.generation), (0, 1));
+        assert_ne!(reused, first);
+        assert!(allocator.is_alive(reused));
+        assert!(!allocator.is_alive(first));
+
+        let live = allocator.live_handles();
+        let live = live
+            .iter()
+            .map(|handle| (handle.index, handle.generation))
+            .collect::<Vec<_>>();
+        assert_eq!(live, [(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn finds_handles_with_references() {
+        let allocator = FreelistHandleAllocator::<Tag>::default();
+
+        let handle = allocator.alloc(Arc::new(NoopDeleter));
+        let found = allocator.find_alive(handle.index()).unwrap();
+        assert_eq!(found, handle);
+        assert!(allocator.find_alive(handle.index() + 1).is_none());
+
+        let raw = handle.raw();
+        drop((handle, found));
+        assert!(allocator.find_alive(raw.index).is_none());
+
+        allocator.dealloc(raw);
+        assert!(allocator.find_alive(raw.index).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn double_free() {
+        let allocator = FreelistHandleAllocator::<Tag>::default();
+        let handle = allocator.alloc(Arc::new(NoopDeleter)).raw();
+        allocator.dealloc(handle);
+        allocator.dealloc(handle);
     }
 }
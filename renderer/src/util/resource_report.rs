@@ -0,0 +1,114 @@
+use std::fmt;
+
+use super::{BindlessSlotUsage, LiveHandle};
+
+/// Resources which are still alive, usually collected at renderer shutdown.
+///
+/// Handles are only reported as leaks when they were not dropped before
+/// the shutdown. Bindless slots and device objects also include resources
+/// owned by the renderer itself, so they are informational.
+#[derive(Debug, Default, Clone)]
+pub struct ResourceReport {
+    pub meshes: Vec<LiveHandle>,
+    pub materials: Vec<LiveHandle>,
+    pub static_objects: Vec<LiveHandle>,
+    pub dynamic_objects: Vec<LiveHandle>,
+    pub bindless: BindlessSlotUsage,
+    pub device_objects: gfx::DeviceObjectCounts,
+}
+
+impl ResourceReport {
+    pub fn live_handle_count(&self) -> usize {
+        self.meshes.len()
+            + self.materials.len()
+            + self.static_objects.len()
+            + self.dynamic_objects.len()
+    }
+
+    pub fn has_leaks(&self) -> bool {
+        self.live_handle_count() > 0
+    }
+
+    /// Panics with the full report if any handle is still alive.
+    #[track_caller]
+    pub fn assert_no_leaks(&self) {
+        assert!(!self.has_leaks(), "renderer resources leaked:\n{self}");
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_handles(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            handles: &[LiveHandle],
+        ) -> fmt::Result {
+            writeln!(f, "live {name}: {}", handles.len())?;
+            for handle in handles {
+                writeln!(f, "  #{} (generation {})", handle.index, handle.generation)?;
+                if let Some(backtrace) = &handle.backtrace {
+                    for line in backtrace.to_string().lines() {
+                        writeln!(f, "    {line}")?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        write_handles(f, "meshes", &self.meshes)?;
+        write_handles(f, "materials", &self.materials)?;
+        write_handles(f, "static objects", &self.static_objects)?;
+        write_handles(f, "dynamic objects", &self.dynamic_objects)?;
+
+        let bindless = &self.bindless;
+        writeln!(
+            f,
+            "bindless slots in use: {} images, {} uniform buffers, {} storage buffers",
+            bindless.images, bindless.uniform_buffers, bindless.storage_buffers
+        )?;
+
+        let device = &self.device_objects;
+        write!(
+            f,
+            "device objects alive: {} buffers, {} images, {} pipelines",
+            device.buffers, device.images, device.pipelines
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_live_handles() {
+        let mut report = ResourceReport::default();
+        assert!(!report.has_leaks());
+        report.assert_no_leaks();
+
+        report.meshes.push(LiveHandle {
+            index: 3,
+            generation: 1,
+            backtrace: None,
+        });
+        assert!(report.has_leaks());
+
+        let text = report.to_string();
+        assert!(text.contains("live meshes: 1\n  #3 (generation 1)"));
+        assert!(text.contains("live materials: 0"));
+    }
+
+    #[test]
+    #[should_panic(expected = "renderer resources leaked")]
+    fn assert_no_leaks_panics() {
+        let report = ResourceReport {
+            dynamic_objects: vec![LiveHandle {
+                index: 0,
+                generation: 0,
+                backtrace: None,
+            }],
+            ..Default::default()
+        };
+        report.assert_no_leaks();
+    }
+}
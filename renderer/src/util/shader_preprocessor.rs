@@ -5,6 +5,7 @@ use once_cell::sync::OnceCell;
 use anyhow::Result;
 use shared::FastHashMap;
 
+use crate::types::{VertexAttributeEncodings, VertexAttributeKind};
 use crate::util::{VirtualFs, VirtualPath};
 
 #[derive(Default)]
@@ -102,6 +103,25 @@ impl<'a> ShaderPreprocessorScope<'a> {
             .add_macro_definition(name.as_ref(), Some(value.as_ref()));
     }
 
+    /// Defines `VERTEX_<KIND>` indices into the object offsets array
+    /// and `VERTEX_ATTR_COUNT` for the material supported attributes.
+    pub fn define_vertex_attributes(&mut self, attributes: &[VertexAttributeKind]) {
+        for (index, attribute) in attributes.iter().enumerate() {
+            self.define_expr(
+                format!("VERTEX_{}", attribute.glsl_name()),
+                index.to_string(),
+            );
+        }
+        self.define_expr("VERTEX_ATTR_COUNT", attributes.len().to_string());
+    }
+
+    /// Enables decoding of the vertex attribute encodings in `vertex_read`.
+    pub fn define_vertex_encodings(&mut self, encodings: &VertexAttributeEncodings) {
+        for (name, value) in encodings.shader_defines() {
+            self.define_expr(name, value);
+        }
+    }
+
     pub fn set_optimizations_enabled(&mut self, enabled: bool) {
         self.options.set_optimization_level(if enabled {
             shaderc::OptimizationLevel::Performance
@@ -0,0 +1,53 @@
+use anyhow::Result;
+
+/// Values created for the most recently used source images.
+///
+/// Keeps more than one entry because sources may alternate between frames,
+/// e.g. the ping-ponged output of [`TemporalAntiAliasing`](crate::util::TemporalAntiAliasing).
+pub struct SourceCache<T> {
+    entries: Vec<(gfx::ImageView, T)>,
+}
+
+impl<T> SourceCache<T> {
+    const CAPACITY: usize = 2;
+
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the value for the source, creating it if it is not cached.
+    ///
+    /// The least recently used entry is evicted when the cache is full.
+    pub fn get_or_try_insert_with(
+        &mut self,
+        source: &gfx::ImageView,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<&T> {
+        match self.entries.iter().position(|(view, _)| view == source) {
+            Some(index) => {
+                let entry = self.entries.remove(index);
+                self.entries.push(entry);
+            }
+            None => {
+                let value = f()?;
+                if self.entries.len() == Self::CAPACITY {
+                    self.entries.remove(0);
+                }
+                self.entries.push((source.clone(), value));
+            }
+        }
+        Ok(&self.entries.last().unwrap().1)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<T> Default for SourceCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::Result;
+use glam::UVec2;
+
+use crate::types::TaaSettings;
+use crate::util::ShaderPreprocessor;
+
+/// Temporal anti-aliasing.
+///
+/// Accumulates jittered frames by reprojecting the history with motion vectors.
+/// The history is clamped to the neighborhood of the current pixel to reject
+/// samples which are no longer visible.
+pub struct TemporalAntiAliasing {
+    descriptor_set_layout: gfx::DescriptorSetLayout,
+    pipeline: gfx::ComputePipeline,
+    point_sampler: gfx::Sampler,
+    linear_sampler: gfx::Sampler,
+    target: Option<TaaTarget>,
+}
+
+/// Inputs of the resolve, all in the `ShaderReadOnlyOptimal` layout.
+pub struct TaaInput<'a> {
+    pub color: &'a gfx::ImageView,
+    pub velocity: &'a gfx::ImageView,
+    pub depth: &'a gfx::ImageView,
+}
+
+/// Two images which swap the output and history roles every frame.
+struct TaaTarget {
+    color: gfx::ImageView,
+    images: [gfx::ImageView; 2],
+    /// The set at index `i` writes `images[i]` and reads the other image as the history.
+    descriptor_sets: [gfx::DescriptorSet; 2],
+    /// Index of the most recent output.
+    current: usize,
+    extent: UVec2,
+    initialized: bool,
+    history_valid: bool,
+}
+
+impl TemporalAntiAliasing {
+    #[tracing::instrument(level = "debug", name = "create_temporal_aa", skip_all)]
+    pub fn new(device: &gfx::Device, shader_preprocessor: &ShaderPreprocessor) -> Result<Self> {
+        let shader =
+            shader_preprocessor
+                .begin()
+                .make_compute_shader(device, "/taa_resolve.comp", "main")?;
+
+        let sampled_image = |binding| gfx::DescriptorSetLayoutBinding {
+            binding,
+            ty: gfx::DescriptorType::CombinedImageSampler,
+            count: 1,
+            stages: gfx::ShaderStageFlags::COMPUTE,
+            flags: Default::default(),
+        };
+        let descriptor_set_layout =
+            device.create_descriptor_set_layout(gfx::DescriptorSetLayoutInfo {
+                bindings: vec![
+                    sampled_image(0),
+                    sampled_image(1),
+                    sampled_image(2),
+                    sampled_image(3),
+                    gfx::DescriptorSetLayoutBinding {
+                        binding: 4,
+                        ty: gfx::DescriptorType::StorageImage,
+                        count: 1,
+                        stages: gfx::ShaderStageFlags::COMPUTE,
+                        flags: Default::default(),
+                    },
+                ],
+                flags: Default::default(),
+            })?;
+
+        let layout = device.create_pipeline_layout(gfx::PipelineLayoutInfo {
+            sets: vec![descriptor_set_layout.clone()],
+            push_constants: vec![gfx::PushConstant {
+                stages: gfx::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: std::mem::size_of::<TaaPushConstants>() as u32,
+            }],
+        })?;
+
+        let pipeline =
+            device.create_compute_pipeline(gfx::ComputePipelineInfo { shader, layout })?;
+
+        let point_sampler = device.create_sampler(gfx::SamplerInfo::simple_nearest())?;
+        let linear_sampler = device.create_sampler(gfx::SamplerInfo {
+            mag_filter: gfx::Filter::Linear,
+            min_filter: gfx::Filter::Linear,
+            address_mode_u: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_v: gfx::SamplerAddressMode::ClampToEdge,
+            address_mode_w: gfx::SamplerAddressMode::ClampToEdge,
+            ..Default::default()
+        })?;
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline,
+            point_sampler,
+            linear_sampler,
+            target: None,
+        })
+    }
+
+    /// Recreates the history if the scene targets have changed (e.g. on resize).
+    ///
+    /// The history is discarded in that case.
+    pub fn prepare(&mut self, device: &gfx::Device, input: &TaaInput<'_>) -> Result<()> {
+        if matches!(&self.target, Some(target) if &target.color == input.color) {
+            return Ok(());
+        }
+        self.target = None;
+
+        let color_info = input.color.info().image.info();
+        let extent = UVec2::from(color_info.extent);
+        let make_image = || {
+            let image = device.create_image(gfx::ImageInfo {
+                extent: color_info.extent,
+                format: color_info.format,
+                mip_levels: 1,
+                samples: gfx::Samples::_1,
+                array_layers: 1,
+                flags: Default::default(),
+                usage: gfx::ImageUsageFlags::SAMPLED | gfx::ImageUsageFlags::STORAGE,
+            })?;
+            device.create_image_view(gfx::ImageViewInfo::new(image))
+        };
+        let images = [make_image()?, make_image()?];
+
+        let sampled = |view: &gfx::ImageView, sampler: &gfx::Sampler| gfx::CombinedImageSampler {
+            view: view.clone(),
+            layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+            sampler: sampler.clone(),
+        };
+
+        let make_descriptor_set = |output: &gfx::ImageView, history: &gfx::ImageView| {
+            let descriptor_set = device.create_descriptor_set(gfx::DescriptorSetInfo {
+                layout: self.descriptor_set_layout.clone(),
+            })?;
+            device.update_descriptor_sets(&[gfx::UpdateDescriptorSet {
+                set: &descriptor_set,
+                writes: &[
+                    gfx::DescriptorSetWrite {
+                        binding: 0,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[sampled(
+                            input.color,
+                            &self.point_sampler,
+                        )]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 1,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[sampled(
+                            history,
+                            &self.linear_sampler,
+                        )]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 2,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[sampled(
+                            input.velocity,
+                            &self.point_sampler,
+                        )]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 3,
+                        element: 0,
+                        data: gfx::DescriptorSlice::CombinedImageSampler(&[sampled(
+                            input.depth,
+                            &self.point_sampler,
+                        )]),
+                    },
+                    gfx::DescriptorSetWrite {
+                        binding: 4,
+                        element: 0,
+                        data: gfx::DescriptorSlice::StorageImage(&[(
+                            output.clone(),
+                            gfx::ImageLayout::General,
+                        )]),
+                    },
+                ],
+            }]);
+            Ok::<_, anyhow::Error>(descriptor_set)
+        };
+        let descriptor_sets = [
+            make_descriptor_set(&images[0], &images[1])?,
+            make_descriptor_set(&images[1], &images[0])?,
+        ];
+
+        self.target = Some(TaaTarget {
+            color: input.color.clone(),
+            images,
+            descriptor_sets,
+            current: 0,
+            extent,
+            initialized: false,
+            history_valid: false,
+        });
+        Ok(())
+    }
+
+    /// Resolved color in the `ShaderReadOnlyOptimal` layout.
+    pub fn output(&self) -> &gfx::ImageView {
+        let target = self.target();
+        &target.images[target.current]
+    }
+
+    /// Discards the accumulated history, e.g. when TAA was disabled for a while.
+    pub fn reset(&mut self) {
+        if let Some(target) = &mut self.target {
+            target.history_valid = false;
+        }
+    }
+
+    /// Blends the current frame with the history into [`TemporalAntiAliasing::output`].
+    ///
+    /// The previous output becomes the history, so no copies are needed.
+    pub fn execute(&mut self, encoder: &mut gfx::Encoder, settings: &TaaSettings) {
+        let target = self.target.as_mut().expect("temporal AA must be prepared");
+
+        let next = 1 - target.current;
+        let output_image = &target.images[next].info().image;
+        let history_image = &target.images[target.current].info().image;
+        let output_range = gfx::ImageSubresourceRange::whole(output_image.info());
+        let history_range = gfx::ImageSubresourceRange::whole(history_image.info());
+
+        let output_barrier = gfx::ImageMemoryBarrier {
+            image: output_image,
+            src_access: gfx::AccessFlags::SHADER_READ,
+            dst_access: gfx::AccessFlags::SHADER_WRITE,
+            old_layout: target
+                .initialized
+                .then_some(gfx::ImageLayout::ShaderReadOnlyOptimal),
+            new_layout: gfx::ImageLayout::General,
+            family_transfer: None,
+            subresource_range: output_range,
+        };
+        if target.initialized {
+            // NOTE: The history is already readable as the output of the previous frame
+            encoder.image_barriers(
+                gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                &[output_barrier],
+            );
+        } else {
+            encoder.image_barriers(
+                gfx::PipelineStageFlags::TOP_OF_PIPE,
+                gfx::PipelineStageFlags::COMPUTE_SHADER,
+                &[
+                    output_barrier,
+                    gfx::ImageMemoryBarrier {
+                        image: history_image,
+                        src_access: gfx::AccessFlags::empty(),
+                        dst_access: gfx::AccessFlags::SHADER_READ,
+                        old_layout: None,
+                        new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                        family_transfer: None,
+                        subresource_range: history_range,
+                    },
+                ],
+            );
+        }
+
+        let layout = &self.pipeline.info().layout;
+        encoder.bind_compute_pipeline(&self.pipeline);
+        encoder.bind_compute_descriptor_sets(layout, 0, &[&target.descriptor_sets[next]], &[]);
+        encoder.push_constants(
+            layout,
+            gfx::ShaderStageFlags::COMPUTE,
+            0,
+            &[TaaPushConstants {
+                extent: target.extent,
+                history_weight: settings.history_weight.clamp(0.0, 1.0),
+                reset: !target.history_valid as u32,
+            }],
+        );
+        encoder.dispatch(target.extent.x.div_ceil(8), target.extent.y.div_ceil(8), 1);
+
+        encoder.image_barriers(
+            gfx::PipelineStageFlags::COMPUTE_SHADER,
+            gfx::PipelineStageFlags::COMPUTE_SHADER | gfx::PipelineStageFlags::FRAGMENT_SHADER,
+            &[gfx::ImageMemoryBarrier {
+                image: output_image,
+                src_access: gfx::AccessFlags::SHADER_WRITE,
+                dst_access: gfx::AccessFlags::SHADER_READ,
+                old_layout: Some(gfx::ImageLayout::General),
+                new_layout: gfx::ImageLayout::ShaderReadOnlyOptimal,
+                family_transfer: None,
+                subresource_range: output_range,
+            }],
+        );
+
+        target.current = next;
+        target.initialized = true;
+        target.history_valid = true;
+    }
+
+    fn target(&self) -> &TaaTarget {
+        self.target.as_ref().expect("temporal AA must be prepared")
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TaaPushConstants {
+    extent: UVec2,
+    history_weight: f32,
+    reset: u32,
+}
+
+// SAFETY: all fields are `Pod` and there is no implicit padding.
+unsafe impl bytemuck::Pod for TaaPushConstants {}
+unsafe impl bytemuck::Zeroable for TaaPushConstants {}
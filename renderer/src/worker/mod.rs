@@ -23,8 +23,6 @@ pub struct RendererWorker {
 
 impl RendererWorker {
     pub fn new(state: Arc<RendererState>, surface: gfx::Surface) -> Result<Self> {
-        const FRAMES_IN_FLIGHT: usize = 2;
-
         let fences = Fences::new(&state.device, FRAMES_IN_FLIGHT)?;
 
         let graph = RenderGraph::new(&state)?;
@@ -41,6 +39,11 @@ impl RendererWorker {
         })
     }
 
+    /// Frees bindless handles of the render graph, must be called once the worker has stopped.
+    pub fn release(&mut self) {
+        self.graph.release(&self.state);
+    }
+
     pub fn draw(&mut self) -> Result<()> {
         let device = &self.state.device;
         let queue = &self.state.queue;
@@ -172,4 +175,7 @@ impl Fences {
     }
 }
 
+/// Number of frames recorded before waiting for the oldest one to complete.
+pub(crate) const FRAMES_IN_FLIGHT: usize = 2;
+
 const NON_OPTIMAL_LIMIT: usize = 100;